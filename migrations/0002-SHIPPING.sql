@@ -0,0 +1,46 @@
+ALTER TABLE products ADD COLUMN product_weight DECIMAL;
+
+CREATE TABLE shipping_methods (
+    id uuid DEFAULT uuid_generate_v4() PRIMARY KEY,
+    shipping_method_name VARCHAR NOT NULL,
+    shipping_method_description VARCHAR,
+
+    -- ISO country codes the method ships to, empty means everywhere
+    shipping_method_countries VARCHAR[] NOT NULL DEFAULT '{}',
+
+    shipping_method_reference VARCHAR NOT NULL UNIQUE
+);
+
+CREATE TABLE shipping_methods_costs (
+    id uuid DEFAULT uuid_generate_v4() PRIMARY KEY,
+
+    currency_code VARCHAR NOT NULL,
+    cost DECIMAL NOT NULL,
+    free_shipping_threshold DECIMAL,
+
+    shipping_method_id uuid,
+    FOREIGN KEY (shipping_method_id) references shipping_methods(id),
+    UNIQUE(shipping_method_id, currency_code)
+);
+
+CREATE TABLE shipping_methods_weight_tiers (
+    id uuid DEFAULT uuid_generate_v4() PRIMARY KEY,
+
+    currency_code VARCHAR NOT NULL,
+    min_weight DECIMAL NOT NULL,
+    cost DECIMAL NOT NULL,
+
+    shipping_method_id uuid,
+    FOREIGN KEY (shipping_method_id) references shipping_methods(id),
+    UNIQUE(shipping_method_id, currency_code, min_weight)
+);
+
+CREATE TABLE shipping_methods_excluded_categories (
+    id uuid DEFAULT uuid_generate_v4() PRIMARY KEY,
+
+    shipping_method_id uuid,
+    category_id uuid,
+    FOREIGN KEY (shipping_method_id) references shipping_methods(id),
+    FOREIGN KEY (category_id) references categories(id),
+    UNIQUE(shipping_method_id, category_id)
+);
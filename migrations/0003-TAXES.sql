@@ -0,0 +1,28 @@
+CREATE TABLE tax_classes (
+    id uuid DEFAULT uuid_generate_v4() PRIMARY KEY,
+    tax_class_name VARCHAR NOT NULL,
+    tax_class_reference VARCHAR NOT NULL UNIQUE
+);
+
+CREATE TABLE tax_rates (
+    id uuid DEFAULT uuid_generate_v4() PRIMARY KEY,
+
+    -- ISO country code and an optional region (state, province) inside of it
+    country_code VARCHAR NOT NULL,
+    region VARCHAR,
+
+    -- fraction of the net price, 0.2 means 20%
+    rate DECIMAL NOT NULL,
+
+    tax_class_id uuid,
+    FOREIGN KEY (tax_class_id) references tax_classes(id),
+    UNIQUE NULLS NOT DISTINCT (tax_class_id, country_code, region)
+);
+
+ALTER TABLE products ADD COLUMN tax_class_id uuid REFERENCES tax_classes(id);
+
+CREATE TYPE pricebookpricemode AS ENUM (
+    'NET',
+    'GROSS'
+);
+ALTER TABLE pricebooks ADD COLUMN pricebook_price_mode pricebookpricemode NOT NULL DEFAULT 'NET';
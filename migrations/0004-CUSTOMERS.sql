@@ -0,0 +1,23 @@
+CREATE TABLE customers (
+    id uuid DEFAULT uuid_generate_v4() PRIMARY KEY,
+    email VARCHAR NOT NULL UNIQUE,
+    first_name VARCHAR NOT NULL,
+    last_name VARCHAR NOT NULL,
+    phone VARCHAR,
+    password VARCHAR NOT NULL
+);
+
+CREATE TABLE customers_addresses (
+    id uuid DEFAULT uuid_generate_v4() PRIMARY KEY,
+    first_name VARCHAR NOT NULL,
+    last_name VARCHAR NOT NULL,
+    country_code VARCHAR NOT NULL,
+    region VARCHAR,
+    city VARCHAR,
+    postal_code VARCHAR,
+    address_line VARCHAR,
+    is_default boolean NOT NULL DEFAULT false,
+
+    customer_id uuid,
+    FOREIGN KEY (customer_id) references customers(id) ON DELETE CASCADE
+);
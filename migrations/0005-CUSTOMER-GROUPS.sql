@@ -0,0 +1,47 @@
+CREATE TYPE customergrouptype AS ENUM (
+    'STATIC',
+    'DYNAMIC'
+);
+CREATE TABLE customer_groups (
+    id uuid DEFAULT uuid_generate_v4() PRIMARY KEY,
+    customer_group_name VARCHAR NOT NULL,
+    customer_group_type customergrouptype NOT NULL,
+    customer_group_reference VARCHAR NOT NULL UNIQUE
+);
+
+-- explicit membership, only used by STATIC groups
+CREATE TABLE customer_groups_customers (
+    id uuid DEFAULT uuid_generate_v4() PRIMARY KEY,
+
+    customer_group_id uuid,
+    customer_id uuid,
+    FOREIGN KEY (customer_group_id) references customer_groups(id),
+    FOREIGN KEY (customer_id) references customers(id) ON DELETE CASCADE,
+    UNIQUE(customer_group_id, customer_id)
+);
+
+-- rule based membership, only used by DYNAMIC groups, every rule has to match
+CREATE TYPE customergroupruletype AS ENUM (
+    'REGISTERED',
+    'DOMAIN',
+    'COUNTRY'
+);
+CREATE TABLE customer_groups_rules (
+    id uuid DEFAULT uuid_generate_v4() PRIMARY KEY,
+    rule_type customergroupruletype NOT NULL,
+    rule_value VARCHAR,
+
+    customer_group_id uuid,
+    FOREIGN KEY (customer_group_id) references customer_groups(id)
+);
+
+-- pricebooks assigned to a group are only used for the group members
+CREATE TABLE customer_groups_pricebooks (
+    id uuid DEFAULT uuid_generate_v4() PRIMARY KEY,
+
+    customer_group_id uuid,
+    pricebook_id uuid,
+    FOREIGN KEY (customer_group_id) references customer_groups(id),
+    FOREIGN KEY (pricebook_id) references pricebooks(id),
+    UNIQUE(customer_group_id, pricebook_id)
+);
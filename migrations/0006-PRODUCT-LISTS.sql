@@ -0,0 +1,26 @@
+CREATE TYPE productlisttype AS ENUM (
+    'WISHLIST',
+    'REGISTRY'
+);
+CREATE TABLE product_lists (
+    id uuid DEFAULT uuid_generate_v4() PRIMARY KEY,
+    product_list_name VARCHAR NOT NULL,
+    product_list_type productlisttype NOT NULL DEFAULT 'WISHLIST',
+    is_public boolean NOT NULL DEFAULT false,
+    share_token VARCHAR NOT NULL UNIQUE,
+
+    customer_id uuid,
+    FOREIGN KEY (customer_id) references customers(id) ON DELETE CASCADE
+);
+
+CREATE TABLE product_lists_items (
+    id uuid DEFAULT uuid_generate_v4() PRIMARY KEY,
+    quantity INT NOT NULL DEFAULT 1,
+    priority INT NOT NULL DEFAULT 0,
+
+    product_list_id uuid,
+    product_id uuid,
+    FOREIGN KEY (product_list_id) references product_lists(id) ON DELETE CASCADE,
+    FOREIGN KEY (product_id) references products(id),
+    UNIQUE(product_list_id, product_id)
+);
@@ -0,0 +1,60 @@
+CREATE TABLE inventory_adjustments (
+    id uuid DEFAULT uuid_generate_v4() PRIMARY KEY,
+    quantity INT NOT NULL,
+    reason VARCHAR NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+
+    inventory_id uuid,
+    product_id uuid,
+    FOREIGN KEY (inventory_id) references inventories(id),
+    FOREIGN KEY (product_id) references products(id)
+);
+
+CREATE TYPE returnstatus AS ENUM (
+    'AUTHORIZED',
+    'INSPECTED'
+);
+CREATE TABLE returns (
+    id uuid DEFAULT uuid_generate_v4() PRIMARY KEY,
+    return_reference VARCHAR NOT NULL UNIQUE,
+
+    -- reference of the order in the system that placed it
+    order_reference VARCHAR NOT NULL,
+    currency_code VARCHAR NOT NULL,
+    status returnstatus NOT NULL DEFAULT 'AUTHORIZED',
+    refund_amount DECIMAL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+
+    customer_id uuid,
+    FOREIGN KEY (customer_id) references customers(id)
+);
+
+CREATE TYPE returnreason AS ENUM (
+    'DAMAGED',
+    'DEFECTIVE',
+    'MISDELIVERED',
+    'MISDESCRIBED',
+    'UNWANTED',
+    'OTHER'
+);
+CREATE TYPE returninspectionoutcome AS ENUM (
+    'RESTOCK',
+    'DISCARD',
+    'REJECT'
+);
+CREATE TABLE returns_lines (
+    id uuid DEFAULT uuid_generate_v4() PRIMARY KEY,
+    order_line_reference VARCHAR,
+    quantity INT NOT NULL,
+    unit_price DECIMAL NOT NULL,
+    reason returnreason NOT NULL,
+    comment VARCHAR,
+    inspection_outcome returninspectionoutcome,
+
+    return_id uuid,
+    product_id uuid,
+    restock_inventory_id uuid,
+    FOREIGN KEY (return_id) references returns(id),
+    FOREIGN KEY (product_id) references products(id),
+    FOREIGN KEY (restock_inventory_id) references inventories(id)
+);
@@ -0,0 +1,15 @@
+-- bumping the version invalidates every access token issued before
+ALTER TABLE portal_users ADD COLUMN session_version INT NOT NULL DEFAULT 0;
+
+CREATE TABLE portal_users_sessions (
+    id uuid DEFAULT uuid_generate_v4() PRIMARY KEY,
+
+    -- argon2 hash of the secret part of the refresh token
+    refresh_token VARCHAR NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    expires_at TIMESTAMPTZ NOT NULL,
+    revoked_at TIMESTAMPTZ,
+
+    portal_user_id uuid,
+    FOREIGN KEY (portal_user_id) references portal_users(id)
+);
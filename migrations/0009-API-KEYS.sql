@@ -0,0 +1,15 @@
+CREATE TABLE api_keys (
+    id uuid DEFAULT uuid_generate_v4() PRIMARY KEY,
+    name VARCHAR NOT NULL,
+
+    -- argon2 hash of the secret part of the key, the key itself is only shown once
+    key_hash VARCHAR NOT NULL,
+    scopes portaluserroles[] NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    expires_at TIMESTAMPTZ,
+    last_used_at TIMESTAMPTZ,
+    revoked_at TIMESTAMPTZ,
+
+    created_by uuid,
+    FOREIGN KEY (created_by) references portal_users(id)
+);
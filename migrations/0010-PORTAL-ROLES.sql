@@ -0,0 +1,16 @@
+-- custom roles are a named set of permissions, they are granted on top of the
+-- built-in READER/EDITOR/ADMIN roles of the portal user
+CREATE TABLE portal_roles (
+    id uuid DEFAULT uuid_generate_v4() PRIMARY KEY,
+    name VARCHAR NOT NULL UNIQUE,
+    permissions VARCHAR[] NOT NULL
+);
+
+CREATE TABLE portal_users_portal_roles (
+    portal_user_id uuid,
+    portal_role_id uuid,
+
+    PRIMARY KEY (portal_user_id, portal_role_id),
+    FOREIGN KEY (portal_user_id) references portal_users(id),
+    FOREIGN KEY (portal_role_id) references portal_roles(id) ON DELETE CASCADE
+);
@@ -0,0 +1,2 @@
+-- deactivated users can not sign in and their existing sessions stop working
+ALTER TABLE portal_users ADD COLUMN active BOOLEAN NOT NULL DEFAULT true;
@@ -0,0 +1,12 @@
+CREATE TABLE portal_users_password_resets (
+    id uuid DEFAULT uuid_generate_v4() PRIMARY KEY,
+
+    -- argon2 hash of the secret part of the reset token
+    token VARCHAR NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    expires_at TIMESTAMPTZ NOT NULL,
+    used_at TIMESTAMPTZ,
+
+    portal_user_id uuid,
+    FOREIGN KEY (portal_user_id) references portal_users(id)
+);
@@ -0,0 +1,24 @@
+CREATE TYPE loginfailurereason AS ENUM (
+  'UNKNOWN',
+  'PASSWORD',
+  'DEACTIVATED',
+  'LOCKED'
+);
+
+-- every portal sign-in attempt, used both as an audit log and to work out
+-- lockouts for accounts and addresses with too many failed attempts
+CREATE TABLE portal_users_login_attempts (
+    id uuid DEFAULT uuid_generate_v4() PRIMARY KEY,
+    email VARCHAR NOT NULL,
+    ip_address VARCHAR NOT NULL,
+    user_agent VARCHAR,
+    successful BOOLEAN NOT NULL,
+    failure_reason loginfailurereason,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+
+    portal_user_id uuid,
+    FOREIGN KEY (portal_user_id) references portal_users(id)
+);
+
+CREATE INDEX portal_users_login_attempts_email_idx ON portal_users_login_attempts (email, created_at);
+CREATE INDEX portal_users_login_attempts_ip_address_idx ON portal_users_login_attempts (ip_address, created_at);
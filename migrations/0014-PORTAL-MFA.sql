@@ -0,0 +1,32 @@
+ALTER TYPE loginfailurereason ADD VALUE 'MFA';
+
+-- the secret is set on enrollment and only takes effect once a first code was
+-- verified, the last step is kept so a code can not be used twice
+ALTER TABLE portal_users ADD COLUMN mfa_secret VARCHAR;
+ALTER TABLE portal_users ADD COLUMN mfa_enabled BOOLEAN NOT NULL DEFAULT false;
+ALTER TABLE portal_users ADD COLUMN mfa_last_step BIGINT;
+
+CREATE TABLE portal_users_recovery_codes (
+    id uuid DEFAULT uuid_generate_v4() PRIMARY KEY,
+
+    -- argon2 hash of the recovery code
+    code VARCHAR NOT NULL,
+    used_at TIMESTAMPTZ,
+
+    portal_user_id uuid,
+    FOREIGN KEY (portal_user_id) references portal_users(id)
+);
+
+CREATE TABLE portal_users_mfa_challenges (
+    id uuid DEFAULT uuid_generate_v4() PRIMARY KEY,
+
+    -- argon2 hash of the secret part of the challenge
+    token VARCHAR NOT NULL,
+    attempts INT NOT NULL DEFAULT 0,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    expires_at TIMESTAMPTZ NOT NULL,
+    used_at TIMESTAMPTZ,
+
+    portal_user_id uuid,
+    FOREIGN KEY (portal_user_id) references portal_users(id)
+);
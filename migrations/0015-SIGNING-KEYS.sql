@@ -0,0 +1,21 @@
+CREATE TYPE signingalgorithm AS ENUM ('RS256', 'EDDSA');
+
+-- the key id is used as the 'kid' of the tokens signed with it, the newest
+-- active key signs new tokens while every key that did not expire yet is
+-- published and accepted. A rotated key is published a while before it becomes
+-- active, so every instance and every service caching the key set knows it
+-- before the first token is signed with it
+CREATE TABLE signing_keys (
+    id uuid DEFAULT uuid_generate_v4() PRIMARY KEY,
+    algorithm signingalgorithm NOT NULL,
+
+    -- base64 encoded DER, PKCS#1 for RS256 and PKCS#8 for EDDSA keys
+    private_key VARCHAR NOT NULL,
+    -- the public part as a JWK without 'kid' and 'alg'
+    public_key VARCHAR NOT NULL,
+
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    activates_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    retired_at TIMESTAMPTZ,
+    expires_at TIMESTAMPTZ
+);
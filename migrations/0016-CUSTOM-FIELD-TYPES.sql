@@ -0,0 +1,15 @@
+ALTER TYPE metadatafieldtype ADD VALUE 'BOOLEAN';
+ALTER TYPE metadatafieldtype ADD VALUE 'DECIMAL';
+ALTER TYPE metadatafieldtype ADD VALUE 'DATE';
+ALTER TYPE metadatafieldtype ADD VALUE 'DATETIME';
+ALTER TYPE metadatafieldtype ADD VALUE 'ENUM';
+ALTER TYPE metadatafieldtype ADD VALUE 'SET';
+ALTER TYPE metadatafieldtype ADD VALUE 'REFERENCE';
+ALTER TYPE metadatafieldtype ADD VALUE 'JSON';
+
+-- decimal type
+ALTER TABLE _metadata_custom_fields ADD COLUMN scale INT;
+-- enum and set types, a set without allowed values takes any string
+ALTER TABLE _metadata_custom_fields ADD COLUMN allowed_values VARCHAR[];
+-- reference type
+ALTER TABLE _metadata_custom_fields ADD COLUMN reference_object metadataobjecttype;
@@ -0,0 +1,5 @@
+ALTER TYPE metadataobjecttype ADD VALUE 'PRODUCTIMAGE';
+ALTER TYPE metadataobjecttype ADD VALUE 'INVENTORYRECORD';
+ALTER TYPE metadataobjecttype ADD VALUE 'PRICEBOOKRECORD';
+ALTER TYPE metadataobjecttype ADD VALUE 'PORTALUSER';
+ALTER TYPE metadataobjecttype ADD VALUE 'CUSTOMER';
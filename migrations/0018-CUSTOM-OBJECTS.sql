@@ -0,0 +1,28 @@
+-- object types defined in the portal, their schema is made of the custom
+-- fields pointing to them and the values of their records are kept in the
+-- unstructured db like the custom fields of the built-in objects
+CREATE TABLE _metadata_custom_objects (
+  id uuid DEFAULT uuid_generate_v4() PRIMARY KEY,
+  -- used in the urls and the permissions of the type
+  name VARCHAR NOT NULL UNIQUE,
+  description VARCHAR,
+  created_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE TABLE _metadata_custom_object_records (
+  id uuid DEFAULT uuid_generate_v4() PRIMARY KEY,
+  custom_object_id uuid NOT NULL REFERENCES _metadata_custom_objects(id) ON DELETE CASCADE,
+  created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+  updated_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+ALTER TYPE metadataobjecttype ADD VALUE 'CUSTOMOBJECT';
+
+ALTER TABLE _metadata_custom_fields
+  ADD COLUMN custom_object_id uuid REFERENCES _metadata_custom_objects(id) ON DELETE CASCADE;
+
+-- names only have to be unique within an object type, the custom object types
+-- all share the same object
+ALTER TABLE _metadata_custom_fields DROP CONSTRAINT _metadata_custom_fields_name_key;
+CREATE UNIQUE INDEX _metadata_custom_fields_name_key
+  ON _metadata_custom_fields (object, COALESCE(custom_object_id, uuid_nil()), name);
@@ -0,0 +1,12 @@
+-- custom field values when UNSTRUCTURED_DB=postgres, extr_ref holds the id of
+-- the object as text so the values can be joined with `extr_ref = id::text`
+CREATE TABLE _unstructured_entries (
+  id uuid DEFAULT uuid_generate_v4() PRIMARY KEY,
+  object metadataobjecttype NOT NULL,
+  extr_ref VARCHAR NOT NULL,
+  field_name VARCHAR NOT NULL,
+  value JSONB NOT NULL,
+  UNIQUE (object, extr_ref, field_name)
+);
+
+CREATE INDEX _unstructured_entries_field_name_idx ON _unstructured_entries (object, field_name);
@@ -0,0 +1,39 @@
+-- every change of a field definition creates a new version, the versions keep
+-- the parts of the definition that can change
+ALTER TABLE _metadata_custom_fields ADD COLUMN version INT NOT NULL DEFAULT 1;
+
+CREATE TABLE _metadata_custom_field_versions (
+  id uuid DEFAULT uuid_generate_v4() PRIMARY KEY,
+  custom_field_id uuid NOT NULL REFERENCES _metadata_custom_fields(id) ON DELETE CASCADE,
+  version INT NOT NULL,
+  name VARCHAR NOT NULL,
+  description VARCHAR,
+  mandatory boolean NOT NULL,
+  max_len bigint,
+  min_len bigint,
+  allowed_values VARCHAR[],
+  created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+  UNIQUE (custom_field_id, version)
+);
+
+INSERT INTO _metadata_custom_field_versions (custom_field_id, version, name, description, mandatory, max_len, min_len, allowed_values)
+  SELECT id, version, name, description, mandatory, max_len, min_len, allowed_values FROM _metadata_custom_fields;
+
+CREATE TYPE customfieldmigrationstatus AS ENUM ('RUNNING', 'COMPLETED', 'FAILED');
+
+-- the jobs moving the stored values of a field to a new version
+CREATE TABLE _metadata_custom_field_migrations (
+  id uuid DEFAULT uuid_generate_v4() PRIMARY KEY,
+  custom_field_id uuid NOT NULL REFERENCES _metadata_custom_fields(id) ON DELETE CASCADE,
+  from_version INT NOT NULL,
+  to_version INT NOT NULL,
+  status customfieldmigrationstatus NOT NULL DEFAULT 'RUNNING',
+  migrated bigint NOT NULL DEFAULT 0,
+  dropped bigint NOT NULL DEFAULT 0,
+  error VARCHAR,
+  created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+  finished_at TIMESTAMPTZ
+);
+
+-- only one migration of a field runs at a time
+CREATE UNIQUE INDEX _metadata_custom_field_migrations_running_idx ON _metadata_custom_field_migrations (custom_field_id) WHERE status = 'RUNNING';
@@ -0,0 +1,635 @@
+pub mod middlewares;
+pub mod models;
+pub mod routes;
+pub mod schemas;
+pub mod services;
+pub mod utils;
+
+use axum::{
+    extract::State,
+    routing::{delete, get, post, put},
+    Router,
+};
+use models::permission::*;
+use routes::{
+    api_key::{create_api_key, get_api_key, get_api_keys, revoke_api_key},
+    base_extensions::{
+        create_extension, create_extension_migration, delete_extension,
+        dry_run_extension_migration, get_extension_migration, get_extension_migrations,
+        get_extension_versions, get_extensions, update_extension,
+    },
+    category::{
+        assign_products_to_category, create_category, delete_category_custom_field, get_categories,
+        get_category, put_category_custom_fields,
+    },
+    custom_object::{
+        create_custom_object_field, create_custom_object_record, create_custom_object_type,
+        delete_custom_object_field, delete_custom_object_record, delete_custom_object_type,
+        get_custom_object_record, get_custom_object_records, get_custom_object_type,
+        get_custom_object_types, update_custom_object_field, update_custom_object_record,
+    },
+    customer::{
+        create_customer_address, delete_customer_address, delete_customer_custom_field,
+        get_customer, get_customer_addresses, get_customer_profile, get_customers,
+        put_customer_custom_fields, register_customer, signin_customer, update_customer_profile,
+    },
+    customer_group::{
+        assign_customers_to_group, assign_pricebooks_to_group, create_customer_group,
+        get_customer_group, get_customer_groups, get_customer_membership,
+        get_customer_product_price,
+    },
+    inventory::{
+        create_inventory, create_inventory_record, delete_inventory_custom_field,
+        delete_inventory_record_custom_field, get_inventories, get_inventory,
+        get_inventory_adjustments, get_inventory_record, put_inventory_custom_fields,
+        put_inventory_record_custom_fields,
+    },
+    logs::{create_log, get_logs},
+    permission::{
+        assign_portal_roles, create_portal_role, delete_portal_role, get_permissions,
+        get_portal_role, get_portal_roles, get_portal_user_roles,
+    },
+    portal::{
+        change_portal_user_password, create_portal_user, deactivate_portal_user,
+        delete_portal_user_custom_field, disable_portal_user_mfa, enroll_portal_user_mfa,
+        forgot_portal_user_password, get_login_attempts, get_portal_user, get_portal_users,
+        put_portal_user_custom_fields, reactivate_portal_user, recover_portal_user_password,
+        refresh_portal_user_token, regenerate_portal_user_recovery_codes, reset_portal_user_mfa,
+        reset_portal_user_password, revoke_portal_user_sessions, signin_portal_user,
+        signin_portal_user_mfa, signout_portal_user, signout_portal_user_everywhere,
+        update_portal_user, verify_portal_user_mfa,
+    },
+    pricebook::{
+        create_pricebook, create_pricebook_record, delete_pricebook_custom_field,
+        delete_pricebook_record_custom_field, get_pricebook, get_pricebook_record, get_pricebooks,
+        put_pricebook_custom_fields, put_pricebook_record_custom_fields,
+    },
+    product::{
+        create_product, create_product_image, delete_product_custom_field,
+        delete_product_image_custom_field, get_product, get_products, put_product_custom_fields,
+        put_product_image_custom_fields,
+    },
+    product_list::{
+        create_product_list, delete_product_list, delete_product_list_item, get_product_list,
+        get_product_lists, get_shared_product_list, put_product_list_item, update_product_list,
+    },
+    returns::{create_return, get_return, get_returns, inspect_return},
+    shipping::{
+        create_shipping_method, get_basket_shipping_methods, get_shipping_method,
+        get_shipping_methods,
+    },
+    signing_key::{get_jwks, get_signing_keys, rotate_signing_key},
+    tax::{create_tax_class, create_tax_rate, get_basket_tax, get_tax_class, get_tax_classes},
+};
+use services::{
+    db::DbService, logger::Logger, mailer::FileMailer, role_validation::RoleValidation,
+    signing_keys::SigningKeys, unstructureddb::UnstructuredDb,
+};
+use std::sync::Arc;
+
+pub struct CommercyfyState {
+    pub db_service: Arc<dyn DbService + Send + Sync>,
+    pub role_service: RoleValidation,
+    pub unstructureddb: Arc<dyn UnstructuredDb + Send + Sync>,
+    pub logger: Box<dyn Logger + Send + Sync>,
+    pub mailer: FileMailer,
+    pub signing_keys: SigningKeys,
+}
+
+type CommercyfyExtrState = State<Arc<CommercyfyState>>;
+
+// the routes of the whole api, main serves them and the integration tests drive
+// them with the in-memory services
+pub fn create_router(commercyfy_state: Arc<CommercyfyState>) -> Router {
+    let categories = Router::new()
+        .route(
+            "/categories",
+            get(get_categories).route_layer(require_permission!(CATEGORY_READ)),
+        )
+        .route(
+            "/categories",
+            post(create_category).route_layer(require_permission!(CATEGORY_WRITE)),
+        )
+        .route(
+            "/categories/:id",
+            get(get_category).route_layer(require_permission!(CATEGORY_READ)),
+        )
+        .route(
+            "/categories/assign/products",
+            post(assign_products_to_category).route_layer(require_permission!(CATEGORY_WRITE)),
+        )
+        .route(
+            "/categories/:id/custom-fields",
+            put(put_category_custom_fields).route_layer(require_permission!(CATEGORY_WRITE)),
+        )
+        .route(
+            "/categories/:id/custom-fields/:field",
+            delete(delete_category_custom_field).route_layer(require_permission!(CATEGORY_WRITE)),
+        );
+
+    let product = Router::new()
+        .route(
+            "/products",
+            get(get_products).route_layer(require_permission!(PRODUCT_READ)),
+        )
+        .route(
+            "/product/:id",
+            get(get_product).route_layer(require_permission!(PRODUCT_READ)),
+        )
+        .route(
+            "/product",
+            post(create_product).route_layer(require_permission!(PRODUCT_WRITE)),
+        )
+        .route(
+            "/product/:id/images",
+            post(create_product_image).route_layer(require_permission!(PRODUCT_WRITE)),
+        )
+        .route(
+            "/product/:id/custom-fields",
+            put(put_product_custom_fields).route_layer(require_permission!(PRODUCT_WRITE)),
+        )
+        .route(
+            "/product/:id/custom-fields/:field",
+            delete(delete_product_custom_field).route_layer(require_permission!(PRODUCT_WRITE)),
+        )
+        .route(
+            "/product/image/:id/custom-fields",
+            put(put_product_image_custom_fields).route_layer(require_permission!(PRODUCT_WRITE)),
+        )
+        .route(
+            "/product/image/:id/custom-fields/:field",
+            delete(delete_product_image_custom_field)
+                .route_layer(require_permission!(PRODUCT_WRITE)),
+        );
+
+    let inventory = Router::new()
+        .route(
+            "/inventories",
+            get(get_inventories).route_layer(require_permission!(INVENTORY_READ)),
+        )
+        .route(
+            "/inventory/:id",
+            get(get_inventory).route_layer(require_permission!(INVENTORY_READ)),
+        )
+        .route(
+            "/inventory",
+            post(create_inventory).route_layer(require_permission!(INVENTORY_WRITE)),
+        )
+        .route(
+            "/inventory/record",
+            post(create_inventory_record).route_layer(require_permission!(INVENTORY_ADJUST)),
+        )
+        .route(
+            "/inventory/:id/adjustments",
+            get(get_inventory_adjustments).route_layer(require_permission!(INVENTORY_READ)),
+        )
+        .route(
+            "/inventory/:inventory/record/:product",
+            get(get_inventory_record).route_layer(require_permission!(INVENTORY_READ)),
+        )
+        .route(
+            "/inventory/:id/custom-fields",
+            put(put_inventory_custom_fields).route_layer(require_permission!(INVENTORY_WRITE)),
+        )
+        .route(
+            "/inventory/:id/custom-fields/:field",
+            delete(delete_inventory_custom_field).route_layer(require_permission!(INVENTORY_WRITE)),
+        )
+        .route(
+            "/inventory/record/:id/custom-fields",
+            put(put_inventory_record_custom_fields)
+                .route_layer(require_permission!(INVENTORY_ADJUST)),
+        )
+        .route(
+            "/inventory/record/:id/custom-fields/:field",
+            delete(delete_inventory_record_custom_field)
+                .route_layer(require_permission!(INVENTORY_ADJUST)),
+        );
+
+    let pricebooks = Router::new()
+        .route(
+            "/pricebooks",
+            get(get_pricebooks).route_layer(require_permission!(PRICEBOOK_READ)),
+        )
+        .route(
+            "/pricebook/:id",
+            get(get_pricebook).route_layer(require_permission!(PRICEBOOK_READ)),
+        )
+        .route(
+            "/pricebook",
+            post(create_pricebook).route_layer(require_permission!(PRICEBOOK_WRITE)),
+        )
+        .route(
+            "/pricebook/record",
+            post(create_pricebook_record).route_layer(require_permission!(PRICEBOOK_WRITE)),
+        )
+        .route(
+            "/pricebook/:pricebook/record/:product",
+            get(get_pricebook_record).route_layer(require_permission!(PRICEBOOK_READ)),
+        )
+        .route(
+            "/pricebook/:id/custom-fields",
+            put(put_pricebook_custom_fields).route_layer(require_permission!(PRICEBOOK_WRITE)),
+        )
+        .route(
+            "/pricebook/:id/custom-fields/:field",
+            delete(delete_pricebook_custom_field).route_layer(require_permission!(PRICEBOOK_WRITE)),
+        )
+        .route(
+            "/pricebook/record/:id/custom-fields",
+            put(put_pricebook_record_custom_fields)
+                .route_layer(require_permission!(PRICEBOOK_WRITE)),
+        )
+        .route(
+            "/pricebook/record/:id/custom-fields/:field",
+            delete(delete_pricebook_record_custom_field)
+                .route_layer(require_permission!(PRICEBOOK_WRITE)),
+        );
+
+    let returns = Router::new()
+        .route(
+            "/returns",
+            get(get_returns).route_layer(require_permission!(RETURN_READ)),
+        )
+        .route(
+            "/returns",
+            post(create_return).route_layer(require_permission!(RETURN_WRITE)),
+        )
+        .route(
+            "/returns/:id",
+            get(get_return).route_layer(require_permission!(RETURN_READ)),
+        )
+        .route(
+            "/returns/:id/inspection",
+            post(inspect_return).route_layer(require_permission!(RETURN_INSPECT)),
+        );
+
+    let shipping = Router::new()
+        .route(
+            "/shipping/methods",
+            get(get_shipping_methods).route_layer(require_permission!(SHIPPING_READ)),
+        )
+        .route(
+            "/shipping/method/:id",
+            get(get_shipping_method).route_layer(require_permission!(SHIPPING_READ)),
+        )
+        .route(
+            "/shipping/method",
+            post(create_shipping_method).route_layer(require_permission!(SHIPPING_WRITE)),
+        )
+        .route(
+            "/shipping/basket",
+            post(get_basket_shipping_methods).route_layer(require_permission!(SHIPPING_READ)),
+        );
+
+    let taxes = Router::new()
+        .route(
+            "/tax/classes",
+            get(get_tax_classes).route_layer(require_permission!(TAX_READ)),
+        )
+        .route(
+            "/tax/class/:id",
+            get(get_tax_class).route_layer(require_permission!(TAX_READ)),
+        )
+        .route(
+            "/tax/class",
+            post(create_tax_class).route_layer(require_permission!(TAX_WRITE)),
+        )
+        .route(
+            "/tax/class/:id/rate",
+            post(create_tax_rate).route_layer(require_permission!(TAX_WRITE)),
+        )
+        .route(
+            "/tax/basket",
+            post(get_basket_tax).route_layer(require_permission!(TAX_READ)),
+        );
+
+    let portal = Router::new()
+        .route(
+            "/portal/users",
+            get(get_portal_users).route_layer(require_permission!(PORTAL_USER_READ)),
+        )
+        .route(
+            "/portal/user/:id",
+            put(update_portal_user).route_layer(require_permission!(PORTAL_USER_WRITE)),
+        )
+        .route(
+            "/portal/user/:id/deactivate",
+            post(deactivate_portal_user).route_layer(require_permission!(PORTAL_USER_WRITE)),
+        )
+        .route(
+            "/portal/user/:id/reactivate",
+            post(reactivate_portal_user).route_layer(require_permission!(PORTAL_USER_WRITE)),
+        )
+        .route(
+            "/portal/user/:id/password",
+            post(reset_portal_user_password).route_layer(require_permission!(PORTAL_USER_WRITE)),
+        )
+        .route("/portal/password", post(change_portal_user_password))
+        .route("/portal/mfa/enroll", post(enroll_portal_user_mfa))
+        .route("/portal/mfa/verify", post(verify_portal_user_mfa))
+        .route("/portal/mfa/disable", post(disable_portal_user_mfa))
+        .route(
+            "/portal/mfa/recovery-codes",
+            post(regenerate_portal_user_recovery_codes),
+        )
+        .route(
+            "/portal/user/:id/mfa/reset",
+            post(reset_portal_user_mfa).route_layer(require_permission!(PORTAL_USER_WRITE)),
+        )
+        .route(
+            "/portal/login-attempts",
+            get(get_login_attempts).route_layer(require_permission!(AUDIT_READ)),
+        )
+        .route(
+            "/portal/user/:id",
+            get(get_portal_user).route_layer(require_permission!(PORTAL_USER_READ)),
+        )
+        .route(
+            "/portal/user",
+            post(create_portal_user).route_layer(require_permission!(PORTAL_USER_WRITE)),
+        )
+        .route(
+            "/portal/user/:id/custom-fields",
+            put(put_portal_user_custom_fields).route_layer(require_permission!(PORTAL_USER_WRITE)),
+        )
+        .route(
+            "/portal/user/:id/custom-fields/:field",
+            delete(delete_portal_user_custom_field)
+                .route_layer(require_permission!(PORTAL_USER_WRITE)),
+        )
+        .route(
+            "/portal/user/:id/sessions/revoke",
+            post(revoke_portal_user_sessions).route_layer(require_permission!(PORTAL_USER_WRITE)),
+        )
+        .route("/portal/signout", post(signout_portal_user))
+        .route("/portal/signout/all", post(signout_portal_user_everywhere))
+        .route(
+            "/portal/api-keys",
+            get(get_api_keys).route_layer(require_permission!(API_KEY_READ)),
+        )
+        .route(
+            "/portal/api-keys",
+            post(create_api_key).route_layer(require_permission!(API_KEY_WRITE)),
+        )
+        .route(
+            "/portal/api-keys/:id",
+            get(get_api_key).route_layer(require_permission!(API_KEY_READ)),
+        )
+        .route(
+            "/portal/api-keys/:id",
+            delete(revoke_api_key).route_layer(require_permission!(API_KEY_WRITE)),
+        )
+        .route(
+            "/portal/permissions",
+            get(get_permissions).route_layer(require_permission!(ROLE_READ)),
+        )
+        .route(
+            "/portal/roles",
+            get(get_portal_roles).route_layer(require_permission!(ROLE_READ)),
+        )
+        .route(
+            "/portal/roles",
+            post(create_portal_role).route_layer(require_permission!(ROLE_WRITE)),
+        )
+        .route(
+            "/portal/roles/:id",
+            get(get_portal_role).route_layer(require_permission!(ROLE_READ)),
+        )
+        .route(
+            "/portal/roles/:id",
+            delete(delete_portal_role).route_layer(require_permission!(ROLE_WRITE)),
+        )
+        .route(
+            "/portal/user/:id/roles",
+            get(get_portal_user_roles).route_layer(require_permission!(ROLE_READ)),
+        )
+        .route(
+            "/portal/user/:id/roles",
+            put(assign_portal_roles).route_layer(require_permission!(ROLE_WRITE)),
+        )
+        .route(
+            "/portal/signing-keys",
+            get(get_signing_keys).route_layer(require_permission!(SIGNING_KEY_READ)),
+        )
+        .route(
+            "/portal/signing-keys/rotate",
+            post(rotate_signing_key).route_layer(require_permission!(SIGNING_KEY_WRITE)),
+        );
+
+    let customers = Router::new()
+        .route(
+            "/customers",
+            get(get_customers).route_layer(require_permission!(CUSTOMER_READ)),
+        )
+        .route(
+            "/customer/:id",
+            get(get_customer).route_layer(require_permission!(CUSTOMER_READ)),
+        )
+        .route(
+            "/customer/:id/custom-fields",
+            put(put_customer_custom_fields).route_layer(require_permission!(CUSTOMER_WRITE)),
+        )
+        .route(
+            "/customer/:id/custom-fields/:field",
+            delete(delete_customer_custom_field).route_layer(require_permission!(CUSTOMER_WRITE)),
+        )
+        .route(
+            "/customer/:id/groups",
+            get(get_customer_membership).route_layer(require_permission!(CUSTOMER_GROUP_READ)),
+        )
+        .route(
+            "/customer-groups",
+            get(get_customer_groups).route_layer(require_permission!(CUSTOMER_GROUP_READ)),
+        )
+        .route(
+            "/customer-groups",
+            post(create_customer_group).route_layer(require_permission!(CUSTOMER_GROUP_WRITE)),
+        )
+        .route(
+            "/customer-groups/:id",
+            get(get_customer_group).route_layer(require_permission!(CUSTOMER_GROUP_READ)),
+        )
+        .route(
+            "/customer-groups/assign/customers",
+            post(assign_customers_to_group).route_layer(require_permission!(CUSTOMER_GROUP_WRITE)),
+        )
+        .route(
+            "/customer-groups/assign/pricebooks",
+            post(assign_pricebooks_to_group).route_layer(require_permission!(CUSTOMER_GROUP_WRITE)),
+        );
+
+    // field definitions shape the data of every object, so only the roles
+    // with the write permission (ADMIN by default) can change them
+    let extensions = Router::new()
+        .route(
+            "/extensions/:object",
+            get(get_extensions).route_layer(require_permission!(EXTENSION_READ)),
+        )
+        .route(
+            "/extensions",
+            post(create_extension).route_layer(require_permission!(EXTENSION_WRITE)),
+        )
+        .route(
+            "/extensions/:object/:id",
+            put(update_extension).route_layer(require_permission!(EXTENSION_WRITE)),
+        )
+        .route(
+            "/extensions/:object/:id",
+            delete(delete_extension).route_layer(require_permission!(EXTENSION_WRITE)),
+        )
+        .route(
+            "/extensions/:object/:id/versions",
+            get(get_extension_versions).route_layer(require_permission!(EXTENSION_READ)),
+        )
+        .route(
+            "/extensions/:object/:id/migrations/dry-run",
+            post(dry_run_extension_migration).route_layer(require_permission!(EXTENSION_WRITE)),
+        )
+        .route(
+            "/extensions/:object/:id/migrations",
+            get(get_extension_migrations).route_layer(require_permission!(EXTENSION_READ)),
+        )
+        .route(
+            "/extensions/:object/:id/migrations",
+            post(create_extension_migration).route_layer(require_permission!(EXTENSION_WRITE)),
+        )
+        .route(
+            "/extensions/:object/:id/migrations/:migration_id",
+            get(get_extension_migration).route_layer(require_permission!(EXTENSION_READ)),
+        );
+
+    // the types and their fields are managed like the extensions, access to the
+    // records is checked per type by the handlers
+    let custom_objects = Router::new()
+        .route(
+            "/custom-objects",
+            get(get_custom_object_types).route_layer(require_permission!(EXTENSION_READ)),
+        )
+        .route(
+            "/custom-objects",
+            post(create_custom_object_type).route_layer(require_permission!(EXTENSION_WRITE)),
+        )
+        .route(
+            "/custom-objects/:object",
+            get(get_custom_object_type).route_layer(require_permission!(EXTENSION_READ)),
+        )
+        .route(
+            "/custom-objects/:object",
+            delete(delete_custom_object_type).route_layer(require_permission!(EXTENSION_WRITE)),
+        )
+        .route(
+            "/custom-objects/:object/fields",
+            post(create_custom_object_field).route_layer(require_permission!(EXTENSION_WRITE)),
+        )
+        .route(
+            "/custom-objects/:object/fields/:id",
+            put(update_custom_object_field).route_layer(require_permission!(EXTENSION_WRITE)),
+        )
+        .route(
+            "/custom-objects/:object/fields/:id",
+            delete(delete_custom_object_field).route_layer(require_permission!(EXTENSION_WRITE)),
+        )
+        .route(
+            "/custom-objects/:object/records",
+            get(get_custom_object_records),
+        )
+        .route(
+            "/custom-objects/:object/records",
+            post(create_custom_object_record),
+        )
+        .route(
+            "/custom-objects/:object/records/:id",
+            get(get_custom_object_record),
+        )
+        .route(
+            "/custom-objects/:object/records/:id",
+            put(update_custom_object_record),
+        )
+        .route(
+            "/custom-objects/:object/records/:id",
+            delete(delete_custom_object_record),
+        );
+
+    let logs = Router::new()
+        .route("/logs", get(get_logs))
+        .route("/logs", post(create_log));
+
+    let auth_routes = Router::new()
+        .merge(categories)
+        .merge(product)
+        .merge(inventory)
+        .merge(pricebooks)
+        .merge(returns)
+        .merge(shipping)
+        .merge(taxes)
+        .merge(portal)
+        .merge(customers)
+        .merge(extensions)
+        .merge(custom_objects)
+        .merge(logs)
+        .route_layer(axum::middleware::from_fn_with_state(
+            commercyfy_state.clone(),
+            middlewares::authentication::auth,
+        ));
+
+    let signin = Router::new()
+        .route("/portal/signin", post(signin_portal_user))
+        .route("/portal/token/refresh", post(refresh_portal_user_token))
+        .route("/portal/signin/mfa", post(signin_portal_user_mfa))
+        .route("/portal/password/forgot", post(forgot_portal_user_password))
+        .route(
+            "/portal/password/recover",
+            post(recover_portal_user_password),
+        )
+        .route("/.well-known/jwks.json", get(get_jwks));
+
+    let storefront = Router::new()
+        .route("/storefront/customer/profile", get(get_customer_profile))
+        .route("/storefront/customer/profile", put(update_customer_profile))
+        .route(
+            "/storefront/customer/addresses",
+            get(get_customer_addresses),
+        )
+        .route(
+            "/storefront/customer/addresses",
+            post(create_customer_address),
+        )
+        .route(
+            "/storefront/customer/addresses/:id",
+            delete(delete_customer_address),
+        )
+        .route(
+            "/storefront/product/:id/price",
+            get(get_customer_product_price),
+        )
+        .route("/storefront/lists", get(get_product_lists))
+        .route("/storefront/lists", post(create_product_list))
+        .route("/storefront/lists/:id", get(get_product_list))
+        .route("/storefront/lists/:id", put(update_product_list))
+        .route("/storefront/lists/:id", delete(delete_product_list))
+        .route("/storefront/lists/:id/items", put(put_product_list_item))
+        .route(
+            "/storefront/lists/:id/items/:product",
+            delete(delete_product_list_item),
+        )
+        .route_layer(axum::middleware::from_fn_with_state(
+            commercyfy_state.clone(),
+            middlewares::authentication::customer_auth,
+        ));
+
+    let storefront_signin = Router::new()
+        .route("/storefront/customer/register", post(register_customer))
+        .route("/storefront/customer/signin", post(signin_customer))
+        .route(
+            "/storefront/shared/lists/:token",
+            get(get_shared_product_list),
+        );
+
+    return Router::new()
+        .merge(auth_routes)
+        .merge(signin)
+        .merge(storefront)
+        .merge(storefront_signin)
+        .with_state(commercyfy_state);
+}
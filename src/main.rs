@@ -1,52 +1,74 @@
-mod middlewares;
-mod models;
-mod routes;
-mod schemas;
-mod services;
-mod utils;
-
-use axum::{
-    extract::State,
-    routing::{get, post},
-    serve, Router,
-};
-use routes::{
-    base_extensions::{create_extension, get_extensions},
-    category::{assign_products_to_category, create_category, get_categories, get_category},
-    inventory::{
-        create_inventory, create_inventory_record, get_inventories, get_inventory,
-        get_inventory_record,
+use axum::serve;
+use base64::{prelude::BASE64_STANDARD, Engine};
+use commercyfy_core::{
+    create_router,
+    models::signing_key::SigningAlgorithm,
+    services::{
+        db::{DbService, PgDbService},
+        logger::GenericLogger,
+        mailer::FileMailer,
+        role_validation::RoleValidation,
+        signing_keys::SigningKeys,
+        unstructureddb::{postgres::PgUnstructuredDb, MongoDb, UnstructuredDb},
     },
-    logs::{create_log, get_logs},
-    portal::{create_portal_user, get_portal_user, signin_portal_user},
-    pricebook::{
-        create_pricebook, create_pricebook_record, get_pricebook, get_pricebook_record,
-        get_pricebooks,
-    },
-    product::{create_product, create_product_image, get_product, get_products},
-};
-use services::{
-    db::PgDbService,
-    logger::GenericLogger,
-    role_validation::RoleValidation,
-    unstructureddb::MongoDb,
+    utils, CommercyfyState,
 };
 use sqlx::postgres::PgPoolOptions;
 use std::sync::Arc;
 
-pub struct CommercyfyState {
-    pub db_service: PgDbService,
-    pub role_service: RoleValidation,
-    pub unstructureddb: MongoDb,
-    pub logger: GenericLogger,
-}
-
-type CommercyfyExtrState = State<Arc<CommercyfyState>>;
-
 #[tokio::main]
 pub async fn main() {
     dotenv::dotenv().expect("Could not load env variables!");
-    std::env::var("JWT_TOKEN_SECRET").expect("JWT_TOKEN_SECRET was not found in .env!");
+
+    // HS256 with the shared secret stays the default, with an asymmetric
+    // algorithm the secret is only needed to accept the tokens issued before
+    let jwt_secret = std::env::var("JWT_TOKEN_SECRET")
+        .ok()
+        .filter(|secret| return !secret.is_empty());
+    let signing_algorithm = match std::env::var("JWT_SIGNING_ALGORITHM")
+        .unwrap_or_default()
+        .as_str()
+    {
+        "" | "HS256" => None,
+        "RS256" => Some(SigningAlgorithm::RS256),
+        "EDDSA" => Some(SigningAlgorithm::EDDSA),
+        algorithm => panic!("Unsupported JWT_SIGNING_ALGORITHM '{}'!", algorithm),
+    };
+    if signing_algorithm.is_none() && jwt_secret.is_none() {
+        panic!("JWT_TOKEN_SECRET was not found in .env!");
+    }
+    let key_rotation_days = std::env::var("JWT_KEY_ROTATION_DAYS")
+        .ok()
+        .filter(|days| return !days.is_empty())
+        .map_or(30, |days| {
+            return days
+                .parse::<i64>()
+                .expect("JWT_KEY_ROTATION_DAYS is not a number!");
+        });
+    // the private keys of the asymmetric algorithms are stored encrypted with
+    // this key, the secret signed tokens are accepted until the given time or
+    // else until the first key was active for the grace period
+    let key_encryption_key = std::env::var("JWT_KEY_ENCRYPTION_KEY")
+        .ok()
+        .filter(|key| return !key.is_empty())
+        .map(|key| {
+            return BASE64_STANDARD
+                .decode(key)
+                .ok()
+                .and_then(|key| return <[u8; 32]>::try_from(key).ok())
+                .expect("JWT_KEY_ENCRYPTION_KEY is not the base64 of 32 bytes!");
+        });
+    if signing_algorithm.is_some() && key_encryption_key.is_none() {
+        panic!("JWT_KEY_ENCRYPTION_KEY was not found in .env!");
+    }
+    let secret_accepted_until = std::env::var("JWT_TOKEN_SECRET_ACCEPTED_UNTIL")
+        .ok()
+        .filter(|until| return !until.is_empty())
+        .map(|until| {
+            return chrono::DateTime::parse_from_rfc3339(&until)
+                .expect("JWT_TOKEN_SECRET_ACCEPTED_UNTIL is not an RFC 3339 date!")
+                .to_utc();
+        });
 
     let db_connstr = std::env::var("DATABASE_URL").expect("DATABASE_URL was not found in .env!");
     let pool = PgPoolOptions::new()
@@ -55,98 +77,92 @@ pub async fn main() {
         .await
         .expect("Could not connect to the database!");
 
-    let mongo_client = mongodb::Client::with_uri_str(
-        std::env::var("MONGODB_URL").expect("MONGODB_URL was not found in .env!"),
-    )
-    .await
-    .expect("Could not connect to mongodb!");
-    let mongodb = mongo_client.database("commercyfy-core");
-
-    let db_service = PgDbService::new(pool);
+    // the custom field values are kept in mongodb unless UNSTRUCTURED_DB picks
+    // the postgres table, then MONGODB_URL is not needed
+    let unstructureddb: Arc<dyn UnstructuredDb + Send + Sync> =
+        match std::env::var("UNSTRUCTURED_DB")
+            .unwrap_or_default()
+            .as_str()
+        {
+            "" | "mongodb" => {
+                let mongo_client = mongodb::Client::with_uri_str(
+                    std::env::var("MONGODB_URL").expect("MONGODB_URL was not found in .env!"),
+                )
+                .await
+                .expect("Could not connect to mongodb!");
+                let mongodb = MongoDb::new(mongo_client.database("commercyfy-core"));
+
+                mongodb.validate_collections().await.expect(
+                "There was an error with creating the needed collections for the usntructureddb.",
+            );
+
+                Arc::new(mongodb)
+            }
+            "postgres" => Arc::new(PgUnstructuredDb::new(pool.clone())),
+            db => panic!("Unsupported UNSTRUCTURED_DB '{}'!", db),
+        };
+
+    let db_service: Arc<dyn DbService + Send + Sync> = Arc::new(PgDbService::new(pool));
     let role_service = RoleValidation::default();
-    let unstructureddb = MongoDb::new(mongodb);
-    let logger = GenericLogger::new();
-
-    unstructureddb
-        .validate_collections()
-        .await
-        .expect("There was an error with creating the needed collections for the usntructureddb.");
+    let logger = Box::new(GenericLogger::new());
+    let mailer = FileMailer::new(
+        std::env::var("MAILER_OUTPUT_DIR")
+            .ok()
+            .filter(|dir| return !dir.is_empty()),
+    );
+    let signing_keys = SigningKeys::new(
+        signing_algorithm,
+        jwt_secret,
+        secret_accepted_until,
+        key_encryption_key,
+        chrono::TimeDelta::days(key_rotation_days),
+    );
 
     let commercyfy_state = Arc::new(CommercyfyState {
         db_service,
         role_service,
         unstructureddb,
         logger,
+        mailer,
+        signing_keys,
     });
 
-    let categories = Router::new()
-        .route("/categories", get(get_categories))
-        .route("/categories", post(create_category))
-        .route("/categories/:id", get(get_category))
-        .route(
-            "/categories/assign/products",
-            post(assign_products_to_category),
-        );
-
-    let product = Router::new()
-        .route("/products", get(get_products))
-        .route("/product/:id", get(get_product))
-        .route("/product", post(create_product))
-        .route("/product/:id/images", post(create_product_image));
-
-    let inventory = Router::new()
-        .route("/inventories", get(get_inventories))
-        .route("/inventory/:id", get(get_inventory))
-        .route("/inventory", post(create_inventory))
-        .route("/inventory/record", post(create_inventory_record))
-        .route(
-            "/inventory/:inventory/record/:product",
-            get(get_inventory_record),
-        );
-
-    let pricebooks = Router::new()
-        .route("/pricebooks", get(get_pricebooks))
-        .route("/pricebook/:id", get(get_pricebook))
-        .route("/pricebook", post(create_pricebook))
-        .route("/pricebook/record", post(create_pricebook_record))
-        .route(
-            "/pricebook/:pricebook/record/:product",
-            get(get_pricebook_record),
-        );
-
-    let portal = Router::new()
-        .route("/portal/user/:id", get(get_portal_user))
-        .route("/portal/user", post(create_portal_user));
-
-    let logs = Router::new()
-        .route("/logs", get(get_logs))
-        .route("/logs", post(create_log));
-
-    let auth_routes = Router::new()
-        .merge(categories)
-        .merge(product)
-        .merge(inventory)
-        .merge(pricebooks)
-        .merge(portal)
-        .merge(logs)
-        .route_layer(axum::middleware::from_fn(middlewares::authentication::auth));
-
-    let signin = Router::new().route("/portal/signin", post(signin_portal_user));
-
-    let extensions = Router::new()
-        .route("/extensions/:object", get(get_extensions))
-        .route("/extensions", post(create_extension));
-
-    let app = Router::new()
-        .merge(auth_routes)
-        .merge(signin)
-        .merge(extensions)
-        .with_state(commercyfy_state);
+    match commercyfy_state
+        .db_service
+        .fail_running_custom_field_migrations()
+        .await
+    {
+        Ok(0) => {}
+        Ok(failed) => {
+            let _ = commercyfy_state.logger.category_warn(
+                "custom-fields",
+                &format!(
+                    "{} custom field migration(s) were interrupted and marked as failed",
+                    failed
+                ),
+            );
+        }
+        Err(err) => panic!("Could not recover the custom field migrations: {}", err),
+    };
+
+    if commercyfy_state.signing_keys.algorithm().is_some() {
+        utils::signing_keys::rotate_signing_keys(&commercyfy_state, false)
+            .await
+            .expect("Could not load the signing keys!");
+        tokio::spawn(utils::signing_keys::run_signing_key_rotation(
+            commercyfy_state.clone(),
+        ));
+    }
+
+    let app = create_router(commercyfy_state);
 
     let listener = tokio::net::TcpListener::bind("0.0.0.0:3000")
         .await
         .expect("Could not bind the TCP socket!");
-    serve(listener, app)
-        .await
-        .expect("Could not serve the server!");
+    serve(
+        listener,
+        app.into_make_service_with_connect_info::<std::net::SocketAddr>(),
+    )
+    .await
+    .expect("Could not serve the server!");
 }
@@ -1,9 +1,23 @@
-use axum::{extract::Request, http, http::StatusCode, middleware::Next, response::Response};
-use jsonwebtoken::{decode, DecodingKey, Validation};
+use axum::{
+    extract::{Request, State},
+    http,
+    http::StatusCode,
+    middleware::Next,
+    response::Response,
+};
 
-use crate::models::portal_user::JWTClaims;
+use crate::models::{
+    customer::{CustomerJWTClaims, CUSTOMER_JWT_AUDIENCE},
+    portal_user::{JWTClaims, PORTAL_JWT_AUDIENCE},
+};
+use crate::services::role_validation::RoleService;
+use crate::utils::auth::{split_credential, verify_password};
+use crate::{CommercyfyExtrState, CommercyfyState};
 
-pub async fn auth(mut req: Request, next: Next) -> Result<Response, StatusCode> {
+pub const API_KEY_SCHEME: &str = "ApiKey";
+
+// returns the scheme and the credentials of the authorization header
+fn get_authorization(req: &Request) -> Result<(String, String), StatusCode> {
     let auth_header = if let Some(auth_header) = req.headers().get(http::header::AUTHORIZATION) {
         auth_header
     } else {
@@ -20,28 +34,200 @@ pub async fn auth(mut req: Request, next: Next) -> Result<Response, StatusCode>
         return Err(StatusCode::UNAUTHORIZED);
     };
 
-    let token = if let Some(token) = auth_header_str.split(" ").skip(1).take(1).nth(0) {
-        token
+    let (scheme, token) = if let Some(parts) = auth_header_str.split_once(' ') {
+        parts
     } else {
         return Err(StatusCode::UNAUTHORIZED);
     };
 
-    let jwt_secret = std::env::var("JWT_TOKEN_SECRET");
-    if let Err(_err) = &jwt_secret {
+    return Ok((scheme.to_string(), token.to_string()));
+}
+
+async fn authenticate_api_key(
+    state: &CommercyfyState,
+    credential: &str,
+) -> Result<JWTClaims, StatusCode> {
+    let (id, secret) = if let Some(credential) = split_credential(credential) {
+        credential
+    } else {
+        return Err(StatusCode::UNAUTHORIZED);
+    };
+
+    let api_key = match state.db_service.get_api_key(id).await {
+        Ok(Some(api_key)) => api_key,
+        Ok(None) => return Err(StatusCode::UNAUTHORIZED),
+        Err(_err) => return Err(StatusCode::INTERNAL_SERVER_ERROR),
+    };
+
+    let now = chrono::Utc::now();
+    if api_key.revoked_at.is_some() || api_key.expires_at.is_some_and(|exp| return exp <= now) {
+        return Err(StatusCode::UNAUTHORIZED);
+    }
+
+    if !verify_password(&api_key.key_hash, secret).unwrap_or(false) {
         return Err(StatusCode::UNAUTHORIZED);
     }
 
-    let claims = if let Ok(claims) = decode::<JWTClaims>(
-        &token,
-        &DecodingKey::from_secret(&jwt_secret.unwrap().as_bytes()),
-        &Validation::default(),
-    ) {
+    // a key only works while the portal user who created it is active, like the
+    // sessions of the user
+    let created_by = if let Some(created_by) = api_key.created_by {
+        created_by
+    } else {
+        return Err(StatusCode::UNAUTHORIZED);
+    };
+    let creator = match state
+        .db_service
+        .get_portal_user(&created_by.to_string())
+        .await
+    {
+        Ok(Some(portal_user)) if portal_user.active => portal_user,
+        Ok(_) => return Err(StatusCode::UNAUTHORIZED),
+        Err(_err) => return Err(StatusCode::INTERNAL_SERVER_ERROR),
+    };
+
+    // the scopes were bounded by the roles of the creator when the key was made,
+    // they are bounded again on every request so demoting the creator also
+    // limits their keys. A scope the creator only partly holds anymore is
+    // narrowed to the permissions they still hold.
+    let mut creator_permissions = match state
+        .db_service
+        .get_portal_user_permissions(&creator.id.to_string())
+        .await
+    {
+        Ok(permissions) => permissions,
+        Err(_err) => return Err(StatusCode::INTERNAL_SERVER_ERROR),
+    };
+    for role in &creator.roles {
+        for permission in state.role_service.get_role_permissions(role) {
+            creator_permissions.push(permission.to_string());
+        }
+    }
+
+    let mut roles = vec![];
+    let mut permissions: Vec<String> = vec![];
+    for scope in api_key.scopes {
+        let scope_permissions = state.role_service.get_role_permissions(&scope);
+        if creator.roles.contains(&scope)
+            || scope_permissions
+                .iter()
+                .all(|permission| return creator_permissions.iter().any(|x| return x == permission))
+        {
+            roles.push(scope);
+            continue;
+        }
+
+        for permission in scope_permissions {
+            if creator_permissions.iter().any(|x| return x == permission)
+                && !permissions.iter().any(|x| return x == permission)
+            {
+                permissions.push(permission.to_string());
+            }
+        }
+    }
+
+    let _ = state.db_service.touch_api_key(api_key.id).await;
+
+    // api keys act as a portal user with the key scopes as roles, so the
+    // handlers keep working with the same claims
+    return Ok(JWTClaims {
+        sub: api_key.id.to_string(),
+        email: api_key.name,
+        exp: api_key
+            .expires_at
+            .map_or(u64::MAX, |exp| return exp.timestamp() as u64),
+        aud: PORTAL_JWT_AUDIENCE.to_string(),
+        roles,
+        jti: api_key.id.to_string(),
+        session_version: 0,
+        permissions,
+        api_key_id: Some(api_key.id),
+    });
+}
+
+async fn authenticate_portal_user(
+    state: &CommercyfyState,
+    token: &str,
+) -> Result<JWTClaims, StatusCode> {
+    let mut claims = if let Ok(claims) = state
+        .signing_keys
+        .decode::<JWTClaims>(token, PORTAL_JWT_AUDIENCE)
+    {
+        claims
+    } else {
+        return Err(StatusCode::UNAUTHORIZED);
+    };
+
+    // a signed token is only honoured while its session is still alive and the
+    // user is active, this is what makes sign-out, revocation and deactivation
+    // take effect before the token expires
+    let portal_user = match state
+        .db_service
+        .get_portal_user_by_session(&claims.jti, &claims.sub, claims.session_version)
+        .await
+    {
+        Ok(Some(portal_user)) => portal_user,
+        Ok(None) => return Err(StatusCode::UNAUTHORIZED),
+        Err(_err) => return Err(StatusCode::INTERNAL_SERVER_ERROR),
+    };
+
+    // role changes apply right away instead of on the next sign-in
+    claims.roles = portal_user.roles;
+
+    claims.permissions = match state
+        .db_service
+        .get_portal_user_permissions(&claims.sub)
+        .await
+    {
+        Ok(permissions) => permissions,
+        Err(_err) => return Err(StatusCode::INTERNAL_SERVER_ERROR),
+    };
+
+    return Ok(claims);
+}
+
+pub async fn auth(
+    State(state): CommercyfyExtrState,
+    mut req: Request,
+    next: Next,
+) -> Result<Response, StatusCode> {
+    let (scheme, token) = get_authorization(&req)?;
+
+    let mut claims = if scheme == API_KEY_SCHEME {
+        authenticate_api_key(&state, &token).await?
+    } else {
+        authenticate_portal_user(&state, &token).await?
+    };
+
+    for role in &claims.roles {
+        for permission in state.role_service.get_role_permissions(role) {
+            if !claims.permissions.iter().any(|x| return x == permission) {
+                claims.permissions.push(permission.to_string());
+            }
+        }
+    }
+
+    req.extensions_mut().insert(claims);
+
+    return Ok(next.run(req).await);
+}
+
+pub async fn customer_auth(
+    State(state): CommercyfyExtrState,
+    mut req: Request,
+    next: Next,
+) -> Result<Response, StatusCode> {
+    let (_scheme, token) = get_authorization(&req)?;
+
+    let claims = if let Ok(claims) = state
+        .signing_keys
+        .decode::<CustomerJWTClaims>(&token, CUSTOMER_JWT_AUDIENCE)
+    {
         claims
     } else {
         return Err(StatusCode::UNAUTHORIZED);
     };
 
-    req.extensions_mut().insert(claims.claims);
+    req.extensions_mut().insert(claims);
 
     return Ok(next.run(req).await);
 }
@@ -0,0 +1,36 @@
+use axum::{extract::Request, http::StatusCode, middleware::Next, response::Response};
+
+use crate::models::portal_user::JWTClaims;
+
+pub async fn authorize(
+    permission: &'static str,
+    req: Request,
+    next: Next,
+) -> Result<Response, StatusCode> {
+    // the claims are put in place by the auth middleware, a route without them
+    // was not meant to be guarded by a permission
+    let claims = if let Some(claims) = req.extensions().get::<JWTClaims>() {
+        claims
+    } else {
+        return Err(StatusCode::UNAUTHORIZED);
+    };
+
+    if !claims.permissions.iter().any(|x| return x == permission) {
+        return Err(StatusCode::FORBIDDEN);
+    }
+
+    return Ok(next.run(req).await);
+}
+
+// route_layer(require_permission!(PRODUCT_WRITE)) on a method router guards it
+// with the given permission
+#[macro_export]
+macro_rules! require_permission {
+    ($permission: expr) => {
+        axum::middleware::from_fn(
+            |req: axum::extract::Request, next: axum::middleware::Next| {
+                return $crate::middlewares::authorization::authorize($permission, req, next);
+            },
+        )
+    };
+}
@@ -0,0 +1,25 @@
+use super::portal_user::PortalUsersRoles;
+
+#[derive(serde::Serialize, sqlx::FromRow, Clone)]
+pub struct ApiKey {
+    pub id: uuid::Uuid,
+    pub name: String,
+
+    #[serde(skip_serializing)]
+    pub key_hash: String,
+
+    pub scopes: Vec<PortalUsersRoles>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub expires_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub last_used_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub revoked_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub created_by: Option<uuid::Uuid>,
+}
+
+#[derive(serde::Serialize)]
+pub struct CreatedApiKey {
+    pub id: uuid::Uuid,
+
+    // '<id>.<secret>', this is the only time the plain key is available
+    pub key: String,
+}
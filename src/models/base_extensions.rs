@@ -1,4 +1,4 @@
-#[derive(serde::Serialize, serde::Deserialize, sqlx::Type, Clone)]
+#[derive(serde::Serialize, serde::Deserialize, sqlx::Type, Clone, PartialEq)]
 #[serde(rename_all = "lowercase")]
 #[sqlx(type_name = "metadataobjecttype")]
 pub enum FieldExtensionObject {
@@ -6,17 +6,32 @@ pub enum FieldExtensionObject {
     CATEGORY,
     INVENTORY,
     PRICEBOOK,
+    PRODUCTIMAGE,
+    INVENTORYRECORD,
+    PRICEBOOKRECORD,
+    PORTALUSER,
+    CUSTOMER,
+    // the records of the custom object types
+    CUSTOMOBJECT,
 }
 
-#[derive(serde::Serialize, sqlx::Type)]
+#[derive(serde::Serialize, sqlx::Type, Clone)]
 #[serde(rename_all = "lowercase")]
 #[sqlx(type_name = "metadatafieldtype")]
 pub enum FieldExtensionType {
     STRING,
     INT,
+    BOOLEAN,
+    DECIMAL,
+    DATE,
+    DATETIME,
+    ENUM,
+    SET,
+    REFERENCE,
+    JSON,
 }
 
-#[derive(serde::Serialize, sqlx::FromRow)]
+#[derive(serde::Serialize, sqlx::FromRow, Clone)]
 pub struct FieldExtension {
     pub id: uuid::Uuid,
 
@@ -39,4 +54,80 @@ pub struct FieldExtension {
 
     #[serde(skip_serializing_if = "Option::is_none")]
     pub min_len: Option<i64>,
+
+    // the maximum number of decimal places of a DECIMAL field
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scale: Option<i32>,
+
+    // the values an ENUM or SET field can take
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub allowed_values: Option<Vec<String>>,
+
+    // the object type the ids of a REFERENCE field point to
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reference_object: Option<FieldExtensionObject>,
+
+    // the custom object type the field belongs to when the object is CUSTOMOBJECT
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub custom_object_id: Option<uuid::Uuid>,
+
+    // raised by every change of the definition
+    pub version: i32,
+}
+
+// the definition of a field as it was at one of its versions, the object and
+// type of a field never change
+#[derive(serde::Serialize, sqlx::FromRow, Clone)]
+pub struct FieldExtensionVersion {
+    pub id: uuid::Uuid,
+    pub custom_field_id: uuid::Uuid,
+    pub version: i32,
+    pub name: String,
+    pub mandatory: bool,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_len: Option<i64>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min_len: Option<i64>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub allowed_values: Option<Vec<String>>,
+
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, sqlx::Type, Clone, Copy, PartialEq, Eq, Debug)]
+#[sqlx(type_name = "customfieldmigrationstatus")]
+pub enum CustomFieldMigrationStatus {
+    RUNNING,
+    COMPLETED,
+
+    // the definition is left at the version the migration started from
+    FAILED,
+}
+
+// a job moving the stored values of a field to a new version of its definition
+#[derive(serde::Serialize, sqlx::FromRow, Clone)]
+pub struct CustomFieldMigration {
+    pub id: uuid::Uuid,
+    pub custom_field_id: uuid::Uuid,
+    pub from_version: i32,
+    pub to_version: i32,
+    pub status: CustomFieldMigrationStatus,
+
+    // the values that were rewritten, a renamed field rewrites all of them
+    pub migrated: i64,
+    pub dropped: i64,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+
+    pub created_at: chrono::DateTime<chrono::Utc>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub finished_at: Option<chrono::DateTime<chrono::Utc>>,
 }
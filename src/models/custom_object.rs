@@ -0,0 +1,27 @@
+#[derive(serde::Serialize, sqlx::FromRow, Clone)]
+pub struct CustomObjectType {
+    pub id: uuid::Uuid,
+    pub name: String,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(serde::Serialize, sqlx::FromRow, Clone)]
+pub struct CustomObjectRecord {
+    pub id: uuid::Uuid,
+    pub custom_object_id: uuid::Uuid,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub updated_at: chrono::DateTime<chrono::Utc>,
+}
+
+// the name ends up in urls and permissions
+pub fn is_custom_object_name(name: &str) -> bool {
+    return name.len() <= 64
+        && name.starts_with(|x: char| return x.is_ascii_lowercase())
+        && name
+            .chars()
+            .all(|x| return x.is_ascii_lowercase() || x.is_ascii_digit() || x == '-');
+}
@@ -0,0 +1,38 @@
+pub const CUSTOMER_JWT_AUDIENCE: &str = "commercyfy-storefront";
+
+#[derive(serde::Serialize, sqlx::FromRow, Clone)]
+pub struct Customer {
+    pub id: uuid::Uuid,
+    pub email: String,
+    pub first_name: String,
+    pub last_name: String,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub phone: Option<String>,
+
+    #[serde(skip_serializing)]
+    pub password: String,
+}
+
+#[derive(serde::Serialize, sqlx::FromRow, Clone)]
+pub struct CustomerAddress {
+    pub id: uuid::Uuid,
+    pub customer_id: uuid::Uuid,
+    pub first_name: String,
+    pub last_name: String,
+    pub country_code: String,
+    pub region: Option<String>,
+    pub city: Option<String>,
+    pub postal_code: Option<String>,
+    pub address_line: Option<String>,
+    pub is_default: bool,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Clone)]
+pub struct CustomerJWTClaims {
+    // the id of the customer
+    pub sub: String,
+    pub email: String,
+    pub exp: u64,
+    pub aud: String,
+}
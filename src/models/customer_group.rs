@@ -0,0 +1,45 @@
+#[derive(serde::Serialize, serde::Deserialize, sqlx::Type, Clone, Copy, PartialEq, Eq, Debug)]
+#[sqlx(type_name = "customergrouptype")]
+pub enum CustomerGroupType {
+    STATIC,
+    DYNAMIC,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, sqlx::Type, Clone, Copy, PartialEq, Eq, Debug)]
+#[sqlx(type_name = "customergroupruletype")]
+pub enum CustomerGroupRuleType {
+    // every signed up customer
+    REGISTERED,
+
+    // customers whose email ends with '@<rule_value>'
+    DOMAIN,
+
+    // customers with an address book entry in the '<rule_value>' country
+    COUNTRY,
+}
+
+#[derive(serde::Serialize, sqlx::FromRow, Clone)]
+pub struct CustomerGroup {
+    pub id: uuid::Uuid,
+    pub customer_group_name: String,
+    pub customer_group_type: CustomerGroupType,
+    pub customer_group_reference: String,
+}
+
+#[derive(serde::Serialize, sqlx::FromRow, Clone)]
+pub struct CustomerGroupRule {
+    pub id: uuid::Uuid,
+    pub customer_group_id: uuid::Uuid,
+    pub rule_type: CustomerGroupRuleType,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rule_value: Option<String>,
+}
+
+#[derive(serde::Serialize)]
+pub struct CustomerPrice {
+    pub product_id: uuid::Uuid,
+    pub pricebook_id: uuid::Uuid,
+    pub currency_code: String,
+    pub price: rust_decimal::Decimal,
+}
@@ -1,4 +1,4 @@
-#[derive(sqlx::FromRow, serde::Serialize, Debug)]
+#[derive(sqlx::FromRow, serde::Serialize, Debug, Clone)]
 pub struct ProductInventoryRecord {
     pub id: uuid::Uuid,
     pub product_id: uuid::Uuid,
@@ -6,9 +6,19 @@ pub struct ProductInventoryRecord {
     pub allocation: i32,
 }
 
-#[derive(sqlx::FromRow, serde::Serialize)]
+#[derive(sqlx::FromRow, serde::Serialize, Clone)]
 pub struct Inventory {
     pub id: uuid::Uuid,
     pub inventory_name: String,
     pub inventory_reference: String,
 }
+
+#[derive(sqlx::FromRow, serde::Serialize, Clone)]
+pub struct InventoryAdjustment {
+    pub id: uuid::Uuid,
+    pub inventory_id: uuid::Uuid,
+    pub product_id: uuid::Uuid,
+    pub quantity: i32,
+    pub reason: String,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
@@ -0,0 +1,33 @@
+#[derive(serde::Serialize, serde::Deserialize, sqlx::Type, Clone, Copy, PartialEq, Eq)]
+#[sqlx(type_name = "loginfailurereason")]
+pub enum LoginFailureReason {
+    // there is no portal user with that email
+    UNKNOWN,
+    PASSWORD,
+    DEACTIVATED,
+    LOCKED,
+    MFA,
+}
+
+#[derive(serde::Serialize, sqlx::FromRow, Clone)]
+pub struct LoginAttempt {
+    pub id: uuid::Uuid,
+    pub email: String,
+    pub ip_address: String,
+    pub user_agent: Option<String>,
+    pub successful: bool,
+    pub failure_reason: Option<LoginFailureReason>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub portal_user_id: Option<uuid::Uuid>,
+}
+
+#[derive(sqlx::FromRow)]
+pub struct LoginFailures {
+    pub count: i64,
+    pub last_attempt_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+pub struct LoginClient {
+    pub ip_address: String,
+    pub user_agent: Option<String>,
+}
@@ -0,0 +1,97 @@
+use super::custom_object::is_custom_object_name;
+
+pub const CATEGORY_READ: &str = "category:read";
+pub const CATEGORY_WRITE: &str = "category:write";
+pub const PRODUCT_READ: &str = "product:read";
+pub const PRODUCT_WRITE: &str = "product:write";
+pub const INVENTORY_READ: &str = "inventory:read";
+pub const INVENTORY_WRITE: &str = "inventory:write";
+pub const INVENTORY_ADJUST: &str = "inventory:adjust";
+pub const PRICEBOOK_READ: &str = "pricebook:read";
+pub const PRICEBOOK_WRITE: &str = "pricebook:write";
+pub const CUSTOMER_READ: &str = "customer:read";
+pub const CUSTOMER_WRITE: &str = "customer:write";
+pub const CUSTOMER_GROUP_READ: &str = "customer_group:read";
+pub const CUSTOMER_GROUP_WRITE: &str = "customer_group:write";
+pub const RETURN_READ: &str = "return:read";
+pub const RETURN_WRITE: &str = "return:write";
+pub const RETURN_INSPECT: &str = "return:inspect";
+pub const SHIPPING_READ: &str = "shipping:read";
+pub const SHIPPING_WRITE: &str = "shipping:write";
+pub const TAX_READ: &str = "tax:read";
+pub const TAX_WRITE: &str = "tax:write";
+pub const PORTAL_USER_READ: &str = "portal_user:read";
+pub const PORTAL_USER_WRITE: &str = "portal_user:write";
+pub const ROLE_READ: &str = "role:read";
+pub const ROLE_WRITE: &str = "role:write";
+pub const API_KEY_READ: &str = "api_key:read";
+pub const API_KEY_WRITE: &str = "api_key:write";
+pub const AUDIT_READ: &str = "audit:read";
+pub const SIGNING_KEY_READ: &str = "signing_key:read";
+pub const SIGNING_KEY_WRITE: &str = "signing_key:write";
+pub const EXTENSION_READ: &str = "extension:read";
+pub const EXTENSION_WRITE: &str = "extension:write";
+// the records of every custom object type, a single type is granted with the
+// permissions of custom_object_permission
+pub const CUSTOM_OBJECT_READ: &str = "custom_object:read";
+pub const CUSTOM_OBJECT_WRITE: &str = "custom_object:write";
+
+pub const ALL_PERMISSIONS: [&str; 33] = [
+    CATEGORY_READ,
+    CATEGORY_WRITE,
+    PRODUCT_READ,
+    PRODUCT_WRITE,
+    INVENTORY_READ,
+    INVENTORY_WRITE,
+    INVENTORY_ADJUST,
+    PRICEBOOK_READ,
+    PRICEBOOK_WRITE,
+    CUSTOMER_READ,
+    CUSTOMER_WRITE,
+    CUSTOMER_GROUP_READ,
+    CUSTOMER_GROUP_WRITE,
+    RETURN_READ,
+    RETURN_WRITE,
+    RETURN_INSPECT,
+    SHIPPING_READ,
+    SHIPPING_WRITE,
+    TAX_READ,
+    TAX_WRITE,
+    PORTAL_USER_READ,
+    PORTAL_USER_WRITE,
+    ROLE_READ,
+    ROLE_WRITE,
+    API_KEY_READ,
+    API_KEY_WRITE,
+    AUDIT_READ,
+    SIGNING_KEY_READ,
+    SIGNING_KEY_WRITE,
+    EXTENSION_READ,
+    EXTENSION_WRITE,
+    CUSTOM_OBJECT_READ,
+    CUSTOM_OBJECT_WRITE,
+];
+
+pub fn custom_object_permission(name: &str, write: bool) -> String {
+    return format!(
+        "custom_object:{}:{}",
+        name,
+        if write { "write" } else { "read" }
+    );
+}
+
+pub fn is_custom_object_permission(permission: &str) -> bool {
+    return permission
+        .strip_prefix("custom_object:")
+        .and_then(|x| return x.rsplit_once(':'))
+        .is_some_and(|(name, access)| {
+            return is_custom_object_name(name) && (access == "read" || access == "write");
+        });
+}
+
+#[derive(serde::Serialize, sqlx::FromRow, Clone)]
+pub struct PortalRole {
+    pub id: uuid::Uuid,
+    pub name: String,
+    pub permissions: Vec<String>,
+}
@@ -14,7 +14,7 @@ impl PgHasArrayType for PortalUsersRoles {
     }
 }
 
-#[derive(serde::Serialize, sqlx::FromRow)]
+#[derive(serde::Serialize, sqlx::FromRow, Clone)]
 pub struct PortalUser {
     pub id: uuid::Uuid,
     pub email: String,
@@ -25,16 +25,129 @@ pub struct PortalUser {
     pub password: String,
 
     pub roles: Vec<PortalUsersRoles>,
+    pub active: bool,
+
+    #[serde(skip_serializing)]
+    pub session_version: i32,
+
+    #[serde(skip_serializing)]
+    pub mfa_secret: Option<String>,
+    pub mfa_enabled: bool,
+
+    #[serde(skip_serializing)]
+    pub mfa_last_step: Option<i64>,
+}
+
+#[derive(serde::Serialize, sqlx::FromRow, Clone)]
+pub struct PortalUserSession {
+    pub id: uuid::Uuid,
+    pub portal_user_id: uuid::Uuid,
+
+    #[serde(skip_serializing)]
+    pub refresh_token: String,
+
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub expires_at: chrono::DateTime<chrono::Utc>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub revoked_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+#[derive(serde::Serialize, sqlx::FromRow, Clone)]
+pub struct PasswordResetToken {
+    pub id: uuid::Uuid,
+    pub portal_user_id: uuid::Uuid,
+
+    #[serde(skip_serializing)]
+    pub token: String,
+
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub expires_at: chrono::DateTime<chrono::Utc>,
+    pub used_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+#[derive(serde::Serialize, sqlx::FromRow, Clone)]
+pub struct RecoveryCode {
+    pub id: uuid::Uuid,
+    pub portal_user_id: uuid::Uuid,
+
+    #[serde(skip_serializing)]
+    pub code: String,
+
+    pub used_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+#[derive(serde::Serialize, sqlx::FromRow, Clone)]
+pub struct MfaChallenge {
+    pub id: uuid::Uuid,
+    pub portal_user_id: uuid::Uuid,
+
+    #[serde(skip_serializing)]
+    pub token: String,
+
+    pub attempts: i32,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub expires_at: chrono::DateTime<chrono::Utc>,
+    pub used_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+#[derive(serde::Serialize)]
+pub struct MfaChallengeToken {
+    pub mfa_challenge: String,
+    pub expires_at: chrono::DateTime<chrono::Utc>,
+}
+
+// portal users with mfa get a challenge from the sign-in that has to be
+// completed with a code before there is a token
+#[derive(serde::Serialize)]
+#[serde(untagged)]
+pub enum PortalSignIn {
+    Token(SignInToken),
+    Challenge(MfaChallengeToken),
+}
+
+#[derive(serde::Serialize)]
+pub struct MfaEnrollment {
+    pub secret: String,
+    pub provisioning_uri: String,
+}
+
+#[derive(serde::Serialize)]
+pub struct RecoveryCodes {
+    pub recovery_codes: Vec<String>,
 }
 
 #[derive(serde::Serialize)]
 pub struct SignInToken {
     pub jwt: String,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub refresh_token: Option<String>,
 }
 
+pub const PORTAL_JWT_AUDIENCE: &str = "commercyfy-portal";
+
 #[derive(serde::Serialize, serde::Deserialize, Clone)]
 pub struct JWTClaims {
+    // the id of the portal user
+    pub sub: String,
     pub email: String,
     pub exp: u64,
+    pub aud: String,
     pub roles: Vec<PortalUsersRoles>,
+
+    // the session that issued the token and the session version of the user at
+    // the time, both are checked on every request so tokens can be revoked
+    pub jti: String,
+    pub session_version: i32,
+
+    // resolved from the built-in and custom roles by the auth middleware on
+    // every request, never part of the signed token
+    #[serde(default, skip_serializing)]
+    pub permissions: Vec<String>,
+
+    // set by the auth middleware when an api key made the request, sub is the
+    // id of the key then instead of a portal user
+    #[serde(default, skip_serializing)]
+    pub api_key_id: Option<uuid::Uuid>,
 }
@@ -1,12 +1,26 @@
-#[derive(sqlx::FromRow, serde::Serialize)]
+#[derive(
+    serde::Serialize, serde::Deserialize, sqlx::Type, Clone, Copy, PartialEq, Eq, Default, Debug,
+)]
+#[sqlx(type_name = "pricebookpricemode")]
+pub enum PricebookPriceMode {
+    // prices in the pricebook exclude taxes
+    #[default]
+    NET,
+
+    // prices in the pricebook already include taxes
+    GROSS,
+}
+
+#[derive(sqlx::FromRow, serde::Serialize, Clone)]
 pub struct Pricebook {
     pub id: uuid::Uuid,
     pub pricebook_name: String,
     pub pricebook_reference: String,
     pub pricebook_currency_code: String,
+    pub pricebook_price_mode: PricebookPriceMode,
 }
 
-#[derive(serde::Serialize, sqlx::FromRow)]
+#[derive(serde::Serialize, sqlx::FromRow, Clone)]
 pub struct PricebookRecord {
     pub id: uuid::Uuid,
     pub pricebook_id: uuid::Uuid,
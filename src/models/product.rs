@@ -1,6 +1,6 @@
 use serde::{Deserialize, Serialize};
 
-#[derive(Serialize, sqlx::FromRow)]
+#[derive(Serialize, sqlx::FromRow, Clone)]
 pub struct ProductImage {
     pub id: uuid::Uuid,
     pub src: String,
@@ -9,10 +9,12 @@ pub struct ProductImage {
     pub product_id: uuid::Uuid,
 }
 
-#[derive(Serialize, Deserialize, Debug, sqlx::FromRow)]
+#[derive(Serialize, Deserialize, Debug, sqlx::FromRow, Clone)]
 pub struct Product {
     pub id: uuid::Uuid,
     pub product_name: String,
     pub product_description: String,
     pub product_color: Option<String>,
+    pub product_weight: Option<rust_decimal::Decimal>,
+    pub tax_class_id: Option<uuid::Uuid>,
 }
@@ -0,0 +1,25 @@
+#[derive(serde::Serialize, serde::Deserialize, sqlx::Type, Clone, Copy, PartialEq, Eq, Debug)]
+#[sqlx(type_name = "productlisttype")]
+pub enum ProductListType {
+    WISHLIST,
+    REGISTRY,
+}
+
+#[derive(serde::Serialize, sqlx::FromRow, Clone)]
+pub struct ProductList {
+    pub id: uuid::Uuid,
+    pub customer_id: uuid::Uuid,
+    pub product_list_name: String,
+    pub product_list_type: ProductListType,
+    pub is_public: bool,
+    pub share_token: String,
+}
+
+#[derive(serde::Serialize, sqlx::FromRow, Clone)]
+pub struct ProductListItem {
+    pub id: uuid::Uuid,
+    pub product_list_id: uuid::Uuid,
+    pub product_id: uuid::Uuid,
+    pub quantity: i32,
+    pub priority: i32,
+}
@@ -0,0 +1,73 @@
+#[derive(serde::Serialize, serde::Deserialize, sqlx::Type, Clone, Copy, PartialEq, Eq, Debug)]
+#[sqlx(type_name = "returnstatus")]
+pub enum ReturnStatus {
+    // the return is accepted and the goods are expected back
+    AUTHORIZED,
+
+    // the goods were received and every line has an inspection outcome
+    INSPECTED,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, sqlx::Type, Clone, Copy, PartialEq, Eq, Debug)]
+#[sqlx(type_name = "returnreason")]
+pub enum ReturnReason {
+    DAMAGED,
+    DEFECTIVE,
+    MISDELIVERED,
+    MISDESCRIBED,
+    UNWANTED,
+    OTHER,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, sqlx::Type, Clone, Copy, PartialEq, Eq, Debug)]
+#[sqlx(type_name = "returninspectionoutcome")]
+pub enum ReturnInspectionOutcome {
+    // refunded and put back into an inventory
+    RESTOCK,
+
+    // refunded but the goods can not be sold again
+    DISCARD,
+
+    // not refunded
+    REJECT,
+}
+
+#[derive(serde::Serialize, sqlx::FromRow, Clone)]
+pub struct Return {
+    pub id: uuid::Uuid,
+    pub return_reference: String,
+    pub order_reference: String,
+    pub currency_code: String,
+    pub status: ReturnStatus,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub refund_amount: Option<rust_decimal::Decimal>,
+
+    pub created_at: chrono::DateTime<chrono::Utc>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub customer_id: Option<uuid::Uuid>,
+}
+
+#[derive(serde::Serialize, sqlx::FromRow, Clone)]
+pub struct ReturnLine {
+    pub id: uuid::Uuid,
+    pub return_id: uuid::Uuid,
+    pub product_id: uuid::Uuid,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub order_line_reference: Option<String>,
+
+    pub quantity: i32,
+    pub unit_price: rust_decimal::Decimal,
+    pub reason: ReturnReason,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub comment: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub inspection_outcome: Option<ReturnInspectionOutcome>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub restock_inventory_id: Option<uuid::Uuid>,
+}
@@ -0,0 +1,37 @@
+#[derive(sqlx::FromRow, serde::Serialize, Clone)]
+pub struct ShippingMethod {
+    pub id: uuid::Uuid,
+    pub shipping_method_name: String,
+    pub shipping_method_description: Option<String>,
+    pub shipping_method_countries: Vec<String>,
+    pub shipping_method_reference: String,
+}
+
+#[derive(sqlx::FromRow, serde::Serialize, Clone)]
+pub struct ShippingMethodCost {
+    pub id: uuid::Uuid,
+    pub shipping_method_id: uuid::Uuid,
+    pub currency_code: String,
+    pub cost: rust_decimal::Decimal,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub free_shipping_threshold: Option<rust_decimal::Decimal>,
+}
+
+#[derive(sqlx::FromRow, serde::Serialize, Clone)]
+pub struct ShippingMethodWeightTier {
+    pub id: uuid::Uuid,
+    pub shipping_method_id: uuid::Uuid,
+    pub currency_code: String,
+    pub min_weight: rust_decimal::Decimal,
+    pub cost: rust_decimal::Decimal,
+}
+
+#[derive(serde::Serialize)]
+pub struct ApplicableShippingMethod {
+    pub id: uuid::Uuid,
+    pub shipping_method_name: String,
+    pub shipping_method_reference: String,
+    pub currency_code: String,
+    pub cost: rust_decimal::Decimal,
+}
@@ -0,0 +1,23 @@
+#[derive(serde::Serialize, serde::Deserialize, sqlx::Type, Clone, Copy, PartialEq, Eq)]
+#[sqlx(type_name = "signingalgorithm")]
+pub enum SigningAlgorithm {
+    RS256,
+    EDDSA,
+}
+
+#[derive(serde::Serialize, sqlx::FromRow, Clone)]
+pub struct SigningKey {
+    pub id: uuid::Uuid,
+    pub algorithm: SigningAlgorithm,
+
+    #[serde(skip_serializing)]
+    pub private_key: String,
+
+    #[serde(skip_serializing)]
+    pub public_key: String,
+
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub activates_at: chrono::DateTime<chrono::Utc>,
+    pub retired_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub expires_at: Option<chrono::DateTime<chrono::Utc>>,
+}
@@ -0,0 +1,39 @@
+#[derive(sqlx::FromRow, serde::Serialize, Clone)]
+pub struct TaxClass {
+    pub id: uuid::Uuid,
+    pub tax_class_name: String,
+    pub tax_class_reference: String,
+}
+
+#[derive(sqlx::FromRow, serde::Serialize, Clone)]
+pub struct TaxRate {
+    pub id: uuid::Uuid,
+    pub tax_class_id: uuid::Uuid,
+    pub country_code: String,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub region: Option<String>,
+
+    pub rate: rust_decimal::Decimal,
+}
+
+#[derive(serde::Serialize)]
+pub struct TaxLine {
+    pub product_id: uuid::Uuid,
+    pub quantity: i32,
+    pub unit_price: rust_decimal::Decimal,
+    pub tax_rate: rust_decimal::Decimal,
+    pub net_total: rust_decimal::Decimal,
+    pub tax_total: rust_decimal::Decimal,
+    pub gross_total: rust_decimal::Decimal,
+}
+
+#[derive(serde::Serialize)]
+pub struct TaxCalculation {
+    pub currency_code: String,
+    pub price_mode: super::pricebook::PricebookPriceMode,
+    pub lines: Vec<TaxLine>,
+    pub net_total: rust_decimal::Decimal,
+    pub tax_total: rust_decimal::Decimal,
+    pub gross_total: rust_decimal::Decimal,
+}
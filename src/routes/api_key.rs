@@ -0,0 +1,114 @@
+use super::{logs::EmptyResponse, CommercyfyResponse};
+use crate::{
+    models::{
+        api_key::{ApiKey, CreatedApiKey},
+        portal_user::JWTClaims,
+    },
+    schemas::api_key::CreateApiKey,
+    utils::{
+        auth::{generate_token, hash_password},
+        permissions::holds_role,
+    },
+    CommercyfyExtrState,
+};
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Extension, Json,
+};
+
+pub async fn get_api_keys(State(state): CommercyfyExtrState) -> CommercyfyResponse<Vec<ApiKey>> {
+    return match state.db_service.get_api_keys().await {
+        Ok(api_keys) => commercyfy_success!(api_keys),
+        Err(err) => commercyfy_fail!(err.to_string()),
+    };
+}
+
+pub async fn get_api_key(
+    State(state): CommercyfyExtrState,
+    Path(id): Path<String>,
+) -> CommercyfyResponse<ApiKey> {
+    return match state.db_service.get_api_key(&id).await {
+        Ok(Some(api_key)) => commercyfy_success!(api_key),
+        Ok(None) => commercyfy_fail!(
+            StatusCode::NOT_FOUND,
+            format!("API key with id '{}' was not found", id)
+        ),
+        Err(err) => commercyfy_fail!(err.to_string()),
+    };
+}
+
+pub async fn create_api_key(
+    Extension(claims): Extension<JWTClaims>,
+    State(state): CommercyfyExtrState,
+    Json(payload): Json<CreateApiKey>,
+) -> CommercyfyResponse<CreatedApiKey> {
+    if let Err(err) = payload.validate() {
+        return commercyfy_fail!(err);
+    }
+
+    // a key has no portal user to own the new key
+    if claims.api_key_id.is_some() {
+        return commercyfy_fail!(
+            StatusCode::FORBIDDEN,
+            "API keys can not create other API keys, sign in as a portal user".to_string()
+        );
+    }
+
+    // a key can not grant more than the user creating it holds
+    let exceeds_caller = payload
+        .scopes
+        .iter()
+        .any(|scope| return !holds_role(&state, &claims, scope));
+    if exceeds_caller {
+        return commercyfy_fail!(
+            StatusCode::FORBIDDEN,
+            "'scopes' can only contain the roles you hold".to_string()
+        );
+    }
+
+    let secret = generate_token(32);
+    let key_hash = match hash_password(&secret) {
+        Ok(hash) => hash,
+        Err(_err) => {
+            return commercyfy_fail!("There was an error handling your request".to_string())
+        }
+    };
+
+    return match state
+        .db_service
+        .create_api_key(&payload, &key_hash, &claims.sub)
+        .await
+    {
+        Ok(api_key) => commercyfy_success!(
+            StatusCode::CREATED,
+            CreatedApiKey {
+                id: api_key.id,
+                key: format!("{}.{}", api_key.id, secret),
+            }
+        ),
+        Err(err) => commercyfy_fail!(err.to_string()),
+    };
+}
+
+pub async fn revoke_api_key(
+    State(state): CommercyfyExtrState,
+    Path(id): Path<String>,
+) -> CommercyfyResponse<EmptyResponse> {
+    match state.db_service.get_api_key(&id).await {
+        Ok(Some(_)) => {}
+        Ok(None) => {
+            return commercyfy_fail!(
+                StatusCode::NOT_FOUND,
+                format!("API key with id '{}' was not found", id)
+            )
+        }
+        Err(err) => return commercyfy_fail!(err.to_string()),
+    };
+
+    if let Err(err) = state.db_service.revoke_api_key(&id).await {
+        return commercyfy_fail!(err.to_string());
+    }
+
+    return commercyfy_success!(EmptyResponse {});
+}
@@ -1,15 +1,27 @@
-use super::{CommercyfyResponse, CreatedEntryResponse};
+use super::{logs::EmptyResponse, CommercyfyResponse, CreatedEntryResponse};
 use crate::{
-    models::base_extensions::{FieldExtension, FieldExtensionObject},
-    schemas::base_extensions::CreateCustomField,
-    services::db::DbService,
-    CommercyfyExtrState,
+    models::base_extensions::{
+        CustomFieldMigration, CustomFieldMigrationStatus, FieldExtension, FieldExtensionObject,
+        FieldExtensionVersion,
+    },
+    schemas::base_extensions::{
+        CreateCustomField, DeleteCustomFieldQuery, MigrateCustomField, UpdateCustomField,
+    },
+    services::unstructureddb::entry::{UnstructuredEntry, UnstructuredEntryType},
+    utils::{
+        custom_field_migrations::{
+            plan_custom_field_migration, run_custom_field_migration, CustomFieldMigrationReport,
+        },
+        custom_fields::{get_object_custom_fields, validate_custom_fields},
+    },
+    CommercyfyExtrState, CommercyfyState,
 };
 use axum::{
-    extract::{Path, State},
+    extract::{Path, Query, State},
     http::StatusCode,
     Json,
 };
+use std::collections::HashMap;
 
 pub async fn create_extension(
     State(state): CommercyfyExtrState,
@@ -34,7 +46,7 @@ pub async fn create_extension(
         );
     }
 
-    let custom_field = match state.db_service.create_custom_field(payload).await {
+    let custom_field = match state.db_service.create_custom_field(payload, None).await {
         Ok(field) => field,
         Err(err) => return commercyfy_fail!(err.to_string()),
     };
@@ -47,29 +59,530 @@ pub async fn create_extension(
     );
 }
 
+fn get_extension_object(object_type: &str) -> Option<FieldExtensionObject> {
+    return match object_type.to_lowercase().as_str() {
+        "product" => Some(FieldExtensionObject::PRODUCT),
+        "category" => Some(FieldExtensionObject::CATEGORY),
+        "inventory" => Some(FieldExtensionObject::INVENTORY),
+        "pricebook" => Some(FieldExtensionObject::PRICEBOOK),
+        "productimage" => Some(FieldExtensionObject::PRODUCTIMAGE),
+        "inventoryrecord" => Some(FieldExtensionObject::INVENTORYRECORD),
+        "pricebookrecord" => Some(FieldExtensionObject::PRICEBOOKRECORD),
+        "portaluser" => Some(FieldExtensionObject::PORTALUSER),
+        "customer" => Some(FieldExtensionObject::CUSTOMER),
+        _ => None,
+    };
+}
+
 pub async fn get_extensions(
     State(state): CommercyfyExtrState,
     Path(object_type): Path<String>,
 ) -> CommercyfyResponse<Vec<FieldExtension>> {
-    let object = match object_type.to_lowercase().as_str() {
-        "product" => FieldExtensionObject::PRODUCT,
-        "category" => FieldExtensionObject::CATEGORY,
-        "inventory" => FieldExtensionObject::INVENTORY,
-        "pricebook" => FieldExtensionObject::PRICEBOOK,
+    let object = if let Some(object) = get_extension_object(&object_type) {
+        object
+    } else {
+        return commercyfy_fail!(
+            StatusCode::NOT_FOUND,
+            format!("There is no object type '{}'", object_type)
+        );
+    };
+
+    let extensions = state.db_service.get_custom_fields(object).await;
+
+    if let Err(err) = extensions {
+        return commercyfy_fail!(err.to_string());
+    }
+
+    return commercyfy_success!(extensions.unwrap());
+}
+
+async fn get_extension(
+    state: &CommercyfyState,
+    object_type: &str,
+    id: &str,
+) -> Result<FieldExtension, (StatusCode, String)> {
+    let object = if let Some(object) = get_extension_object(object_type) {
+        object
+    } else {
+        return Err((
+            StatusCode::NOT_FOUND,
+            format!("There is no object type '{}'", object_type),
+        ));
+    };
+
+    return match state.db_service.get_custom_field_by_id(object, id).await {
+        Ok(Some(field)) => Ok(field),
+        Ok(None) => Err((
+            StatusCode::NOT_FOUND,
+            format!("Custom field with id '{}' was not found", id),
+        )),
+        Err(err) => Err((StatusCode::BAD_REQUEST, err.to_string())),
+    };
+}
+
+pub async fn update_extension(
+    State(state): CommercyfyExtrState,
+    Path((object_type, id)): Path<(String, String)>,
+    Json(payload): Json<UpdateCustomField>,
+) -> CommercyfyResponse<FieldExtension> {
+    if let Err(err) = payload.validate() {
+        return commercyfy_fail!(err);
+    }
+
+    let field = match get_extension(&state, &object_type, &id).await {
+        Ok(field) => field,
+        Err((status, err)) => return commercyfy_fail!(status, err),
+    };
+
+    return update_field(&state, field, &payload).await;
+}
+
+// Values that were stored before the change would no longer be valid, so a
+// change is refused while any of them does not fit, the migrations rewrite or
+// drop them instead.
+pub async fn update_field(
+    state: &CommercyfyState,
+    field: FieldExtension,
+    payload: &UpdateCustomField,
+) -> CommercyfyResponse<FieldExtension> {
+    let change = MigrateCustomField {
+        name: None,
+        description: payload.description.clone(),
+        mandatory: payload.mandatory,
+        max_len: payload.max_len,
+        min_len: payload.min_len,
+        allowed_values: None,
+        value_map: None,
+        on_violation: None,
+        version: None,
+    };
+
+    let report = match plan_custom_field_migration(state, &field, &change).await {
+        Ok(report) => report,
+        Err(err) => return commercyfy_fail!(err),
+    };
+
+    if !report.violations.is_empty() {
+        return commercyfy_fail!(
+            StatusCode::CONFLICT,
+            format!(
+                "{} existing value(s) of '{}' do not fit the change, use the migrations of the field to change them",
+                report.violations.len(),
+                field.name
+            )
+        );
+    }
+
+    return match state
+        .db_service
+        .update_custom_field(field.id, payload)
+        .await
+    {
+        Ok(field) => commercyfy_success!(field),
+        Err(err) => commercyfy_fail!(err.to_string()),
+    };
+}
+
+pub async fn get_extension_versions(
+    State(state): CommercyfyExtrState,
+    Path((object_type, id)): Path<(String, String)>,
+) -> CommercyfyResponse<Vec<FieldExtensionVersion>> {
+    let field = match get_extension(&state, &object_type, &id).await {
+        Ok(field) => field,
+        Err((status, err)) => return commercyfy_fail!(status, err),
+    };
+
+    return match state.db_service.get_custom_field_versions(field.id).await {
+        Ok(versions) => commercyfy_success!(versions),
+        Err(err) => commercyfy_fail!(err.to_string()),
+    };
+}
+
+// shared by the dry-run and the migration
+async fn plan_extension_migration(
+    state: &CommercyfyState,
+    field: &FieldExtension,
+    payload: &MigrateCustomField,
+) -> Result<CustomFieldMigrationReport, (StatusCode, String)> {
+    if let Err(err) = payload.validate() {
+        return Err((StatusCode::BAD_REQUEST, err));
+    }
+
+    if payload.version.is_some_and(|x| return x != field.version) {
+        return Err((
+            StatusCode::CONFLICT,
+            format!(
+                "'{}' was changed since, it is at version {}",
+                field.name, field.version
+            ),
+        ));
+    }
+
+    if let Some(name) = payload.name.as_ref().filter(|x| return **x != field.name) {
+        match state
+            .db_service
+            .get_custom_field(field.object.clone(), name)
+            .await
+        {
+            Ok(None) => {}
+            Ok(Some(_)) => {
+                return Err((
+                    StatusCode::BAD_REQUEST,
+                    "Field with that name already exists on the provided object type".to_string(),
+                ))
+            }
+            Err(err) => return Err((StatusCode::BAD_REQUEST, err.to_string())),
+        };
+    }
+
+    return match plan_custom_field_migration(state, field, payload).await {
+        Ok(report) => Ok(report),
+        Err(err) => Err((StatusCode::BAD_REQUEST, err)),
+    };
+}
+
+// reports what a migration would do with the stored values without changing them
+pub async fn dry_run_extension_migration(
+    State(state): CommercyfyExtrState,
+    Path((object_type, id)): Path<(String, String)>,
+    Json(payload): Json<MigrateCustomField>,
+) -> CommercyfyResponse<CustomFieldMigrationReport> {
+    let field = match get_extension(&state, &object_type, &id).await {
+        Ok(field) => field,
+        Err((status, err)) => return commercyfy_fail!(status, err),
+    };
+
+    return match plan_extension_migration(&state, &field, &payload).await {
+        Ok(report) => commercyfy_success!(report),
+        Err((status, err)) => commercyfy_fail!(status, err),
+    };
+}
 
-        _ => {
+// The values are moved in the background, the migration is returned right away
+// and can be followed by its status. Only one migration of a field runs at a
+// time and it is refused while any value would not fit.
+pub async fn create_extension_migration(
+    State(state): CommercyfyExtrState,
+    Path((object_type, id)): Path<(String, String)>,
+    Json(payload): Json<MigrateCustomField>,
+) -> CommercyfyResponse<CustomFieldMigration> {
+    let field = match get_extension(&state, &object_type, &id).await {
+        Ok(field) => field,
+        Err((status, err)) => return commercyfy_fail!(status, err),
+    };
+
+    let migrations = match state.db_service.get_custom_field_migrations(field.id).await {
+        Ok(migrations) => migrations,
+        Err(err) => return commercyfy_fail!(err.to_string()),
+    };
+
+    if migrations
+        .iter()
+        .any(|x| return x.status == CustomFieldMigrationStatus::RUNNING)
+    {
+        return commercyfy_fail!(
+            StatusCode::CONFLICT,
+            format!("'{}' is already being migrated", field.name)
+        );
+    }
+
+    let report = match plan_extension_migration(&state, &field, &payload).await {
+        Ok(report) => report,
+        Err((status, err)) => return commercyfy_fail!(status, err),
+    };
+
+    if !report.violations.is_empty() {
+        return commercyfy_fail!(
+            StatusCode::CONFLICT,
+            format!(
+                "{} existing value(s) of '{}' do not fit the change, see the dry-run for them",
+                report.violations.len(),
+                field.name
+            )
+        );
+    }
+
+    // the check above is only a shortcut, a migration started in the meantime
+    // is caught here
+    let migration = match state
+        .db_service
+        .create_custom_field_migration(field.id, field.version)
+        .await
+    {
+        Ok(Some(migration)) => migration,
+        Ok(None) => {
             return commercyfy_fail!(
-                StatusCode::NOT_FOUND,
-                format!("There is no object type '{}'", object_type)
+                StatusCode::CONFLICT,
+                format!("'{}' is already being migrated", field.name)
             )
         }
+        Err(err) => return commercyfy_fail!(err.to_string()),
     };
 
-    let extensions = state.db_service.get_custom_fields(object).await;
+    tokio::spawn(run_custom_field_migration(
+        state.clone(),
+        field,
+        payload,
+        migration.clone(),
+    ));
 
-    if let Err(err) = extensions {
+    return commercyfy_success!(StatusCode::ACCEPTED, migration);
+}
+
+pub async fn get_extension_migrations(
+    State(state): CommercyfyExtrState,
+    Path((object_type, id)): Path<(String, String)>,
+) -> CommercyfyResponse<Vec<CustomFieldMigration>> {
+    let field = match get_extension(&state, &object_type, &id).await {
+        Ok(field) => field,
+        Err((status, err)) => return commercyfy_fail!(status, err),
+    };
+
+    return match state.db_service.get_custom_field_migrations(field.id).await {
+        Ok(migrations) => commercyfy_success!(migrations),
+        Err(err) => commercyfy_fail!(err.to_string()),
+    };
+}
+
+pub async fn get_extension_migration(
+    State(state): CommercyfyExtrState,
+    Path((object_type, id, migration_id)): Path<(String, String, String)>,
+) -> CommercyfyResponse<CustomFieldMigration> {
+    let field = match get_extension(&state, &object_type, &id).await {
+        Ok(field) => field,
+        Err((status, err)) => return commercyfy_fail!(status, err),
+    };
+
+    return match state
+        .db_service
+        .get_custom_field_migration(field.id, &migration_id)
+        .await
+    {
+        Ok(Some(migration)) => commercyfy_success!(migration),
+        Ok(None) => commercyfy_fail!(
+            StatusCode::NOT_FOUND,
+            format!("Migration with id '{}' was not found", migration_id)
+        ),
+        Err(err) => commercyfy_fail!(err.to_string()),
+    };
+}
+
+pub async fn delete_extension(
+    State(state): CommercyfyExtrState,
+    Path((object_type, id)): Path<(String, String)>,
+    Query(query): Query<DeleteCustomFieldQuery>,
+) -> CommercyfyResponse<EmptyResponse> {
+    let field = match get_extension(&state, &object_type, &id).await {
+        Ok(field) => field,
+        Err((status, err)) => return commercyfy_fail!(status, err),
+    };
+
+    return delete_field(&state, field, None, query.force.unwrap_or(false)).await;
+}
+
+// A field that still has values is only deleted when forced, the values are
+// removed along with it then. The fields of the custom object types only own
+// the values of the given objects.
+pub async fn delete_field(
+    state: &CommercyfyState,
+    field: FieldExtension,
+    extr_refs: Option<&[String]>,
+    force: bool,
+) -> CommercyfyResponse<EmptyResponse> {
+    let values = match extr_refs {
+        Some(extr_refs) => state
+            .unstructureddb
+            .get_custom_field_values(field.object.clone(), &field.name)
+            .await
+            .map(|values| {
+                return values
+                    .iter()
+                    .filter(|entry| return extr_refs.contains(&entry.extr_ref))
+                    .count() as u64;
+            }),
+        None => {
+            state
+                .unstructureddb
+                .count_custom_field_values(field.object.clone(), &field.name)
+                .await
+        }
+    };
+
+    let values = match values {
+        Ok(values) => values,
+        Err(err) => return commercyfy_fail!(err),
+    };
+
+    if values > 0 && !force {
+        return commercyfy_fail!(
+            StatusCode::CONFLICT,
+            format!(
+                "'{}' still has {} value(s), delete it with 'force=true' to remove them as well",
+                field.name, values
+            )
+        );
+    }
+
+    // the values go first, when they can not be removed the field stays and
+    // the delete can be retried instead of leaving values without a field
+    if values > 0 {
+        let deletion = match extr_refs {
+            Some(extr_refs) => {
+                state
+                    .unstructureddb
+                    .delete_custom_field_values_of(field.object, &field.name, extr_refs)
+                    .await
+            }
+            None => {
+                state
+                    .unstructureddb
+                    .delete_custom_field_values(field.object, &field.name)
+                    .await
+            }
+        };
+
+        if let Err(err) = deletion {
+            return commercyfy_fail!(err);
+        }
+    }
+
+    if let Err(err) = state.db_service.delete_custom_field(field.id).await {
         return commercyfy_fail!(err.to_string());
     }
 
-    return commercyfy_success!(extensions.unwrap());
+    return commercyfy_success!(EmptyResponse {});
+}
+
+fn get_object_ref(id: &str) -> Result<String, (StatusCode, String)> {
+    return match uuid::Uuid::parse_str(id) {
+        Ok(id) => Ok(id.to_string()),
+        Err(_err) => Err((
+            StatusCode::NOT_FOUND,
+            format!("Object with id '{}' was not found", id),
+        )),
+    };
+}
+
+async fn check_object_exists(
+    state: &CommercyfyState,
+    object: FieldExtensionObject,
+    id: &str,
+) -> Result<(), (StatusCode, String)> {
+    return match state
+        .db_service
+        .custom_field_object_exists(object, id)
+        .await
+    {
+        Ok(true) => Ok(()),
+        Ok(false) => Err((
+            StatusCode::NOT_FOUND,
+            format!("Object with id '{}' was not found", id),
+        )),
+        Err(err) => Err((StatusCode::BAD_REQUEST, err.to_string())),
+    };
+}
+
+// Shared by the custom field routes of the objects, only the provided fields
+// are changed and the resulting custom fields of the object are returned.
+pub async fn set_object_custom_fields(
+    state: &CommercyfyState,
+    object: FieldExtensionObject,
+    id: &str,
+    custom_fields: HashMap<String, serde_json::Value>,
+) -> CommercyfyResponse<HashMap<String, UnstructuredEntryType>> {
+    let extr_ref = match get_object_ref(id) {
+        Ok(extr_ref) => extr_ref,
+        Err((status, err)) => return commercyfy_fail!(status, err),
+    };
+
+    if let Err((status, err)) = check_object_exists(state, object.clone(), &extr_ref).await {
+        return commercyfy_fail!(status, err);
+    }
+
+    if custom_fields.is_empty() {
+        return commercyfy_fail!("No custom fields were provided".to_string());
+    }
+
+    let values =
+        match validate_custom_fields(state, object.clone(), &Some(custom_fields), false).await {
+            Ok(fields) if fields.errors.is_empty() => fields.values,
+            Ok(fields) => return commercyfy_fields_fail!(fields.errors),
+            Err(err) => return commercyfy_fail!(err),
+        };
+
+    let entries = values
+        .into_iter()
+        .map(|(field_name, value)| {
+            return UnstructuredEntry {
+                extr_ref: extr_ref.clone(),
+                field_name,
+                value,
+            };
+        })
+        .collect::<Vec<UnstructuredEntry>>();
+
+    if let Err(err) = state
+        .unstructureddb
+        .upsert_custom_fields(object.clone(), entries)
+        .await
+    {
+        return commercyfy_fail!(err);
+    }
+
+    return match get_object_custom_fields(state, object, &extr_ref).await {
+        Ok(custom_fields) => commercyfy_success!(custom_fields),
+        Err(err) => commercyfy_fail!(err),
+    };
+}
+
+// mandatory fields can only be changed, not removed
+pub async fn unset_object_custom_field(
+    state: &CommercyfyState,
+    object: FieldExtensionObject,
+    id: &str,
+    field_name: &str,
+) -> CommercyfyResponse<HashMap<String, UnstructuredEntryType>> {
+    let extr_ref = match get_object_ref(id) {
+        Ok(extr_ref) => extr_ref,
+        Err((status, err)) => return commercyfy_fail!(status, err),
+    };
+
+    if let Err((status, err)) = check_object_exists(state, object.clone(), &extr_ref).await {
+        return commercyfy_fail!(status, err);
+    }
+
+    let field = match state
+        .db_service
+        .get_custom_field(object.clone(), field_name)
+        .await
+    {
+        Ok(Some(field)) => field,
+        Ok(None) => {
+            return commercyfy_fail!(
+                StatusCode::NOT_FOUND,
+                format!("Custom field '{}' does not exist", field_name)
+            )
+        }
+        Err(err) => return commercyfy_fail!(err.to_string()),
+    };
+
+    if field.mandatory {
+        return commercyfy_fail!(format!(
+            "Custom field '{}' is mandatory and can not be removed",
+            field.name
+        ));
+    }
+
+    if let Err(err) = state
+        .unstructureddb
+        .delete_custom_fields(object.clone(), &extr_ref, &[field.name])
+        .await
+    {
+        return commercyfy_fail!(err);
+    }
+
+    return match get_object_custom_fields(state, object, &extr_ref).await {
+        Ok(custom_fields) => commercyfy_success!(custom_fields),
+        Err(err) => commercyfy_fail!(err),
+    };
 }
@@ -1,60 +1,49 @@
 use std::collections::HashMap;
 
-use super::{CommercyfyResponse, CreatedEntryResponse};
+use super::{
+    base_extensions::{set_object_custom_fields, unset_object_custom_field},
+    CommercyfyResponse, CreatedEntryResponse,
+};
 use crate::{
-    models::{
-        base_extensions::FieldExtensionObject,
-        category::Category,
-        portal_user::{JWTClaims, PortalUsersRoles},
-        product::Product,
-    },
+    models::{base_extensions::FieldExtensionObject, category::Category, product::Product},
     schemas::category::{AssignProductToCategory, CreateCategory},
-    services::{
-        db::DbService,
-        role_validation::RoleService,
-        unstructureddb::{entry::UnstructuredEntryType, UnstructuredDb},
+    services::unstructureddb::entry::UnstructuredEntryType,
+    utils::custom_fields::{
+        create_custom_fields, find_custom_field_matches, validate_custom_fields,
     },
-    utils::custom_fields::create_custom_fields,
     CommercyfyExtrState,
 };
 use axum::{
-    extract::{Path, State},
+    extract::{Path, Query, State},
     http::StatusCode,
-    Extension, Json,
+    Json,
 };
 
 pub async fn get_categories(
-    Extension(claims): Extension<JWTClaims>,
+    Query(params): Query<HashMap<String, String>>,
     State(state): CommercyfyExtrState,
 ) -> CommercyfyResponse<Vec<Category>> {
-    if let Err(err) = state.role_service.validate_any(
-        &claims,
-        vec![PortalUsersRoles::ADMIN, PortalUsersRoles::READER],
-    ) {
-        return commercyfy_fail!(err);
-    }
-
-    let categories = state.db_service.get_categories().await;
+    let matches =
+        match find_custom_field_matches(&state, FieldExtensionObject::CATEGORY, &params).await {
+            Ok(matches) if matches.errors.is_empty() => matches,
+            Ok(matches) => return commercyfy_fields_fail!(matches.errors),
+            Err(err) => return commercyfy_fail!(err),
+        };
+
+    let mut categories = match state.db_service.get_categories().await {
+        Ok(categories) => categories,
+        Err(error) => return commercyfy_fail!(error.to_string()),
+    };
 
-    if let Err(error) = categories {
-        return commercyfy_fail!(error.to_string());
-    }
+    categories.retain(|x| return matches.matches(&x.id.to_string()));
 
-    return commercyfy_success!(categories.unwrap());
+    return commercyfy_success!(categories);
 }
 
 pub async fn create_category(
-    Extension(claims): Extension<JWTClaims>,
     State(state): CommercyfyExtrState,
     Json(payload): Json<CreateCategory>,
 ) -> CommercyfyResponse<CreatedEntryResponse> {
-    if let Err(err) = state.role_service.validate_any(
-        &claims,
-        vec![PortalUsersRoles::ADMIN, PortalUsersRoles::EDITOR],
-    ) {
-        return commercyfy_fail!(err);
-    }
-
     if let Err(error) = payload.validate() {
         return commercyfy_fail!(error);
     }
@@ -72,6 +61,19 @@ pub async fn create_category(
         }
     }
 
+    let custom_fields = match validate_custom_fields(
+        &state,
+        FieldExtensionObject::CATEGORY,
+        &payload.custom_fields,
+        true,
+    )
+    .await
+    {
+        Ok(fields) if fields.errors.is_empty() => fields.values,
+        Ok(fields) => return commercyfy_fields_fail!(fields.errors),
+        Err(err) => return commercyfy_fail!(err),
+    };
+
     let created = state.db_service.create_category(&payload).await;
     if let Err(error) = created {
         return commercyfy_fail!(error.to_string());
@@ -79,15 +81,15 @@ pub async fn create_category(
 
     let category = created.unwrap();
 
-    if let Err(err) = create_custom_fields(
+    if let Err((status, err)) = create_custom_fields(
         state,
-        category.id.to_string(),
+        category.id,
         FieldExtensionObject::CATEGORY,
-        &payload.custom_fields,
+        custom_fields,
     )
     .await
     {
-        return commercyfy_fail!(err);
+        return commercyfy_fail!(status, err);
     }
 
     return commercyfy_success!(
@@ -104,17 +106,9 @@ pub struct CategoryView {
     custom_fields: HashMap<String, UnstructuredEntryType>,
 }
 pub async fn get_category(
-    Extension(claims): Extension<JWTClaims>,
     State(state): CommercyfyExtrState,
     Path(id): Path<String>,
 ) -> CommercyfyResponse<CategoryView> {
-    if let Err(err) = state.role_service.validate_any(
-        &claims,
-        vec![PortalUsersRoles::ADMIN, PortalUsersRoles::READER],
-    ) {
-        return commercyfy_fail!(err);
-    }
-
     if let Ok(category) = state.db_service.get_category_by_id(&id).await {
         if let Some(cat) = category {
             let mut category_view = CategoryView {
@@ -188,17 +182,9 @@ pub async fn get_category(
 }
 
 pub async fn assign_products_to_category(
-    Extension(claims): Extension<JWTClaims>,
     State(state): CommercyfyExtrState,
     Json(payload): Json<AssignProductToCategory>,
 ) -> CommercyfyResponse<CreatedEntryResponse> {
-    if let Err(err) = state.role_service.validate_any(
-        &claims,
-        vec![PortalUsersRoles::ADMIN, PortalUsersRoles::EDITOR],
-    ) {
-        return commercyfy_fail!(err);
-    }
-
     if let Err(err) = payload.validate() {
         return commercyfy_fail!(err);
     }
@@ -219,3 +205,18 @@ pub async fn assign_products_to_category(
         Err(err) => return commercyfy_fail!(err.to_string()),
     };
 }
+
+pub async fn put_category_custom_fields(
+    State(state): CommercyfyExtrState,
+    Path(id): Path<String>,
+    Json(payload): Json<HashMap<String, serde_json::Value>>,
+) -> CommercyfyResponse<HashMap<String, UnstructuredEntryType>> {
+    return set_object_custom_fields(&state, FieldExtensionObject::CATEGORY, &id, payload).await;
+}
+
+pub async fn delete_category_custom_field(
+    State(state): CommercyfyExtrState,
+    Path((id, field)): Path<(String, String)>,
+) -> CommercyfyResponse<HashMap<String, UnstructuredEntryType>> {
+    return unset_object_custom_field(&state, FieldExtensionObject::CATEGORY, &id, &field).await;
+}
@@ -0,0 +1,660 @@
+use std::collections::HashMap;
+
+use super::{
+    base_extensions::{delete_field, update_field},
+    logs::EmptyResponse,
+    CommercyfyResponse, CreatedEntryResponse,
+};
+use crate::{
+    models::{
+        base_extensions::{FieldExtension, FieldExtensionObject},
+        custom_object::{CustomObjectRecord, CustomObjectType},
+        permission::{custom_object_permission, CUSTOM_OBJECT_READ, CUSTOM_OBJECT_WRITE},
+        portal_user::JWTClaims,
+    },
+    schemas::{
+        base_extensions::{CreateCustomField, DeleteCustomFieldQuery, UpdateCustomField},
+        custom_object::{
+            CreateCustomObjectField, CreateCustomObjectRecord, CreateCustomObjectType,
+            DeleteCustomObjectTypeQuery,
+        },
+    },
+    services::unstructureddb::entry::{UnstructuredEntry, UnstructuredEntryType},
+    utils::custom_fields::{
+        check_custom_fields, create_custom_fields, get_object_custom_fields, match_custom_fields,
+    },
+    CommercyfyExtrState, CommercyfyState,
+};
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    Extension, Json,
+};
+
+#[derive(serde::Serialize)]
+pub struct CustomObjectTypeView {
+    #[serde(flatten)]
+    custom_object: CustomObjectType,
+    fields: Vec<FieldExtension>,
+}
+
+#[derive(serde::Serialize)]
+pub struct CustomObjectRecordView {
+    #[serde(flatten)]
+    record: CustomObjectRecord,
+    custom_fields: HashMap<String, UnstructuredEntryType>,
+}
+
+async fn get_custom_object(
+    state: &CommercyfyState,
+    name: &str,
+) -> Result<CustomObjectType, (StatusCode, String)> {
+    return match state.db_service.get_custom_object_type(name).await {
+        Ok(Some(custom_object)) => Ok(custom_object),
+        Ok(None) => Err((
+            StatusCode::NOT_FOUND,
+            format!("There is no custom object '{}'", name),
+        )),
+        Err(err) => Err((StatusCode::BAD_REQUEST, err.to_string())),
+    };
+}
+
+// the records of a type are open to the holders of the permission of every
+// custom object type or of the one of that type
+fn check_record_access(
+    claims: &JWTClaims,
+    custom_object: &CustomObjectType,
+    write: bool,
+) -> Result<(), (StatusCode, String)> {
+    let any = if write {
+        CUSTOM_OBJECT_WRITE
+    } else {
+        CUSTOM_OBJECT_READ
+    };
+    let single = custom_object_permission(&custom_object.name, write);
+
+    if !claims
+        .permissions
+        .iter()
+        .any(|x| return x == any || *x == single)
+    {
+        return Err((
+            StatusCode::FORBIDDEN,
+            format!("Missing the '{}' permission", single),
+        ));
+    }
+
+    return Ok(());
+}
+
+async fn get_record_refs(
+    state: &CommercyfyState,
+    custom_object: &CustomObjectType,
+) -> Result<Vec<String>, String> {
+    return match state
+        .db_service
+        .get_custom_object_records(custom_object.id)
+        .await
+    {
+        Ok(records) => Ok(records.iter().map(|x| return x.id.to_string()).collect()),
+        Err(err) => Err(err.to_string()),
+    };
+}
+
+pub async fn get_custom_object_types(
+    State(state): CommercyfyExtrState,
+) -> CommercyfyResponse<Vec<CustomObjectType>> {
+    return match state.db_service.get_custom_object_types().await {
+        Ok(custom_objects) => commercyfy_success!(custom_objects),
+        Err(err) => commercyfy_fail!(err.to_string()),
+    };
+}
+
+pub async fn get_custom_object_type(
+    State(state): CommercyfyExtrState,
+    Path(name): Path<String>,
+) -> CommercyfyResponse<CustomObjectTypeView> {
+    let custom_object = match get_custom_object(&state, &name).await {
+        Ok(custom_object) => custom_object,
+        Err((status, err)) => return commercyfy_fail!(status, err),
+    };
+
+    let fields = match state
+        .db_service
+        .get_custom_object_fields(custom_object.id)
+        .await
+    {
+        Ok(fields) => fields,
+        Err(err) => return commercyfy_fail!(err.to_string()),
+    };
+
+    return commercyfy_success!(CustomObjectTypeView {
+        custom_object,
+        fields
+    });
+}
+
+pub async fn create_custom_object_type(
+    State(state): CommercyfyExtrState,
+    Json(payload): Json<CreateCustomObjectType>,
+) -> CommercyfyResponse<CreatedEntryResponse> {
+    if let Err(err) = payload.validate() {
+        return commercyfy_fail!(err);
+    }
+
+    match state.db_service.get_custom_object_type(&payload.name).await {
+        Ok(Some(_)) => {
+            return commercyfy_fail!(
+                StatusCode::CONFLICT,
+                format!("Custom object '{}' already exists", payload.name)
+            )
+        }
+        Ok(None) => {}
+        Err(err) => return commercyfy_fail!(err.to_string()),
+    };
+
+    return match state.db_service.create_custom_object_type(&payload).await {
+        Ok(custom_object) => commercyfy_success!(
+            StatusCode::CREATED,
+            CreatedEntryResponse {
+                id: custom_object.id
+            }
+        ),
+        Err(err) => commercyfy_fail!(err.to_string()),
+    };
+}
+
+// A type that still has records is only deleted when forced, the records and
+// their values are removed along with it then.
+pub async fn delete_custom_object_type(
+    State(state): CommercyfyExtrState,
+    Path(name): Path<String>,
+    Query(query): Query<DeleteCustomObjectTypeQuery>,
+) -> CommercyfyResponse<EmptyResponse> {
+    let custom_object = match get_custom_object(&state, &name).await {
+        Ok(custom_object) => custom_object,
+        Err((status, err)) => return commercyfy_fail!(status, err),
+    };
+
+    let extr_refs = match get_record_refs(&state, &custom_object).await {
+        Ok(extr_refs) => extr_refs,
+        Err(err) => return commercyfy_fail!(err),
+    };
+
+    if !extr_refs.is_empty() && !query.force.unwrap_or(false) {
+        return commercyfy_fail!(
+            StatusCode::CONFLICT,
+            format!(
+                "'{}' still has {} record(s), delete it with 'force=true' to remove them as well",
+                custom_object.name,
+                extr_refs.len()
+            )
+        );
+    }
+
+    let fields = match state
+        .db_service
+        .get_custom_object_fields(custom_object.id)
+        .await
+    {
+        Ok(fields) => fields,
+        Err(err) => return commercyfy_fail!(err.to_string()),
+    };
+
+    // the values go first so a failed removal leaves the type to delete again
+    if !extr_refs.is_empty() {
+        for field in fields {
+            if let Err(err) = state
+                .unstructureddb
+                .delete_custom_field_values_of(field.object, &field.name, &extr_refs)
+                .await
+            {
+                return commercyfy_fail!(err);
+            }
+        }
+    }
+
+    if let Err(err) = state
+        .db_service
+        .delete_custom_object_type(custom_object.id)
+        .await
+    {
+        return commercyfy_fail!(err.to_string());
+    }
+
+    return commercyfy_success!(EmptyResponse {});
+}
+
+pub async fn create_custom_object_field(
+    State(state): CommercyfyExtrState,
+    Path(name): Path<String>,
+    Json(payload): Json<CreateCustomObjectField>,
+) -> CommercyfyResponse<CreatedEntryResponse> {
+    if let Err(err) = payload.validate() {
+        return commercyfy_fail!(err);
+    }
+
+    let custom_object = match get_custom_object(&state, &name).await {
+        Ok(custom_object) => custom_object,
+        Err((status, err)) => return commercyfy_fail!(status, err),
+    };
+
+    let fields = match state
+        .db_service
+        .get_custom_object_fields(custom_object.id)
+        .await
+    {
+        Ok(fields) => fields,
+        Err(err) => return commercyfy_fail!(err.to_string()),
+    };
+
+    if fields
+        .iter()
+        .any(|x| return x.name == payload.base_felds.name)
+    {
+        return commercyfy_fail!(
+            "Field with that name already exists on the provided custom object".to_string()
+        );
+    }
+
+    let payload = CreateCustomField {
+        object: FieldExtensionObject::CUSTOMOBJECT,
+        base_felds: payload.base_felds,
+        custom: payload.custom,
+    };
+
+    return match state
+        .db_service
+        .create_custom_field(payload, Some(custom_object.id))
+        .await
+    {
+        Ok(field) => {
+            commercyfy_success!(StatusCode::CREATED, CreatedEntryResponse { id: field.id })
+        }
+        Err(err) => commercyfy_fail!(err.to_string()),
+    };
+}
+
+async fn get_custom_object_field(
+    state: &CommercyfyState,
+    custom_object: &CustomObjectType,
+    id: &str,
+) -> Result<FieldExtension, (StatusCode, String)> {
+    return match state
+        .db_service
+        .get_custom_field_by_id(FieldExtensionObject::CUSTOMOBJECT, id)
+        .await
+    {
+        Ok(Some(field)) if field.custom_object_id == Some(custom_object.id) => Ok(field),
+        Ok(_) => Err((
+            StatusCode::NOT_FOUND,
+            format!("Custom field with id '{}' was not found", id),
+        )),
+        Err(err) => Err((StatusCode::BAD_REQUEST, err.to_string())),
+    };
+}
+
+pub async fn update_custom_object_field(
+    State(state): CommercyfyExtrState,
+    Path((name, id)): Path<(String, String)>,
+    Json(payload): Json<UpdateCustomField>,
+) -> CommercyfyResponse<FieldExtension> {
+    if let Err(err) = payload.validate() {
+        return commercyfy_fail!(err);
+    }
+
+    let custom_object = match get_custom_object(&state, &name).await {
+        Ok(custom_object) => custom_object,
+        Err((status, err)) => return commercyfy_fail!(status, err),
+    };
+
+    let field = match get_custom_object_field(&state, &custom_object, &id).await {
+        Ok(field) => field,
+        Err((status, err)) => return commercyfy_fail!(status, err),
+    };
+
+    return update_field(&state, field, &payload).await;
+}
+
+pub async fn delete_custom_object_field(
+    State(state): CommercyfyExtrState,
+    Path((name, id)): Path<(String, String)>,
+    Query(query): Query<DeleteCustomFieldQuery>,
+) -> CommercyfyResponse<EmptyResponse> {
+    let custom_object = match get_custom_object(&state, &name).await {
+        Ok(custom_object) => custom_object,
+        Err((status, err)) => return commercyfy_fail!(status, err),
+    };
+
+    let field = match get_custom_object_field(&state, &custom_object, &id).await {
+        Ok(field) => field,
+        Err((status, err)) => return commercyfy_fail!(status, err),
+    };
+
+    let extr_refs = match get_record_refs(&state, &custom_object).await {
+        Ok(extr_refs) => extr_refs,
+        Err(err) => return commercyfy_fail!(err),
+    };
+
+    return delete_field(
+        &state,
+        field,
+        Some(&extr_refs),
+        query.force.unwrap_or(false),
+    )
+    .await;
+}
+
+// the records can be filtered by their fields like the lists of the built-in
+// objects
+pub async fn get_custom_object_records(
+    Extension(claims): Extension<JWTClaims>,
+    State(state): CommercyfyExtrState,
+    Path(name): Path<String>,
+    Query(params): Query<HashMap<String, String>>,
+) -> CommercyfyResponse<Vec<CustomObjectRecordView>> {
+    let custom_object = match get_custom_object(&state, &name).await {
+        Ok(custom_object) => custom_object,
+        Err((status, err)) => return commercyfy_fail!(status, err),
+    };
+
+    if let Err((status, err)) = check_record_access(&claims, &custom_object, false) {
+        return commercyfy_fail!(status, err);
+    }
+
+    let fields = match state
+        .db_service
+        .get_custom_object_fields(custom_object.id)
+        .await
+    {
+        Ok(fields) => fields,
+        Err(err) => return commercyfy_fail!(err.to_string()),
+    };
+
+    let matches =
+        match match_custom_fields(&state, FieldExtensionObject::CUSTOMOBJECT, &fields, &params)
+            .await
+        {
+            Ok(matches) if matches.errors.is_empty() => matches,
+            Ok(matches) => return commercyfy_fields_fail!(matches.errors),
+            Err(err) => return commercyfy_fail!(err),
+        };
+
+    let mut records = match state
+        .db_service
+        .get_custom_object_records(custom_object.id)
+        .await
+    {
+        Ok(records) => records,
+        Err(err) => return commercyfy_fail!(err.to_string()),
+    };
+
+    records.retain(|x| return matches.matches(&x.id.to_string()));
+
+    let extr_refs = records
+        .iter()
+        .map(|x| return x.id.to_string())
+        .collect::<Vec<String>>();
+
+    let entries = match state
+        .unstructureddb
+        .get_objects_custom_fields(FieldExtensionObject::CUSTOMOBJECT, &extr_refs)
+        .await
+    {
+        Ok(entries) => entries,
+        Err(err) => return commercyfy_fail!(err),
+    };
+
+    let mut custom_fields: HashMap<String, HashMap<String, UnstructuredEntryType>> = HashMap::new();
+    for entry in entries {
+        custom_fields
+            .entry(entry.extr_ref)
+            .or_default()
+            .insert(entry.field_name, entry.value);
+    }
+
+    return commercyfy_success!(records
+        .into_iter()
+        .map(|record| {
+            return CustomObjectRecordView {
+                custom_fields: custom_fields
+                    .remove(&record.id.to_string())
+                    .unwrap_or_default(),
+                record,
+            };
+        })
+        .collect::<Vec<CustomObjectRecordView>>());
+}
+
+async fn get_record_view(
+    state: &CommercyfyState,
+    record: CustomObjectRecord,
+) -> CommercyfyResponse<CustomObjectRecordView> {
+    return match get_object_custom_fields(
+        state,
+        FieldExtensionObject::CUSTOMOBJECT,
+        &record.id.to_string(),
+    )
+    .await
+    {
+        Ok(custom_fields) => commercyfy_success!(CustomObjectRecordView {
+            record,
+            custom_fields
+        }),
+        Err(err) => commercyfy_fail!(err),
+    };
+}
+
+async fn get_record(
+    state: &CommercyfyState,
+    custom_object: &CustomObjectType,
+    id: &str,
+) -> Result<CustomObjectRecord, (StatusCode, String)> {
+    return match state
+        .db_service
+        .get_custom_object_record(custom_object.id, id)
+        .await
+    {
+        Ok(Some(record)) => Ok(record),
+        Ok(None) => Err((
+            StatusCode::NOT_FOUND,
+            format!("Record with id '{}' was not found", id),
+        )),
+        Err(err) => Err((StatusCode::BAD_REQUEST, err.to_string())),
+    };
+}
+
+pub async fn get_custom_object_record(
+    Extension(claims): Extension<JWTClaims>,
+    State(state): CommercyfyExtrState,
+    Path((name, id)): Path<(String, String)>,
+) -> CommercyfyResponse<CustomObjectRecordView> {
+    let custom_object = match get_custom_object(&state, &name).await {
+        Ok(custom_object) => custom_object,
+        Err((status, err)) => return commercyfy_fail!(status, err),
+    };
+
+    if let Err((status, err)) = check_record_access(&claims, &custom_object, false) {
+        return commercyfy_fail!(status, err);
+    }
+
+    return match get_record(&state, &custom_object, &id).await {
+        Ok(record) => get_record_view(&state, record).await,
+        Err((status, err)) => commercyfy_fail!(status, err),
+    };
+}
+
+pub async fn create_custom_object_record(
+    Extension(claims): Extension<JWTClaims>,
+    State(state): CommercyfyExtrState,
+    Path(name): Path<String>,
+    Json(payload): Json<CreateCustomObjectRecord>,
+) -> CommercyfyResponse<CreatedEntryResponse> {
+    let custom_object = match get_custom_object(&state, &name).await {
+        Ok(custom_object) => custom_object,
+        Err((status, err)) => return commercyfy_fail!(status, err),
+    };
+
+    if let Err((status, err)) = check_record_access(&claims, &custom_object, true) {
+        return commercyfy_fail!(status, err);
+    }
+
+    let fields = match state
+        .db_service
+        .get_custom_object_fields(custom_object.id)
+        .await
+    {
+        Ok(fields) => fields,
+        Err(err) => return commercyfy_fail!(err.to_string()),
+    };
+
+    let custom_fields =
+        match check_custom_fields(&state, &fields, &payload.custom_fields, true).await {
+            Ok(fields) if fields.errors.is_empty() => fields.values,
+            Ok(fields) => return commercyfy_fields_fail!(fields.errors),
+            Err(err) => return commercyfy_fail!(err),
+        };
+
+    let record = match state
+        .db_service
+        .create_custom_object_record(custom_object.id)
+        .await
+    {
+        Ok(record) => record,
+        Err(err) => return commercyfy_fail!(err.to_string()),
+    };
+
+    if let Err((status, err)) = create_custom_fields(
+        state,
+        record.id,
+        FieldExtensionObject::CUSTOMOBJECT,
+        custom_fields,
+    )
+    .await
+    {
+        return commercyfy_fail!(status, err);
+    }
+
+    return commercyfy_success!(StatusCode::CREATED, CreatedEntryResponse { id: record.id });
+}
+
+// only the provided fields are changed
+pub async fn update_custom_object_record(
+    Extension(claims): Extension<JWTClaims>,
+    State(state): CommercyfyExtrState,
+    Path((name, id)): Path<(String, String)>,
+    Json(payload): Json<HashMap<String, serde_json::Value>>,
+) -> CommercyfyResponse<CustomObjectRecordView> {
+    let custom_object = match get_custom_object(&state, &name).await {
+        Ok(custom_object) => custom_object,
+        Err((status, err)) => return commercyfy_fail!(status, err),
+    };
+
+    if let Err((status, err)) = check_record_access(&claims, &custom_object, true) {
+        return commercyfy_fail!(status, err);
+    }
+
+    let record = match get_record(&state, &custom_object, &id).await {
+        Ok(record) => record,
+        Err((status, err)) => return commercyfy_fail!(status, err),
+    };
+
+    if payload.is_empty() {
+        return commercyfy_fail!("No custom fields were provided".to_string());
+    }
+
+    let fields = match state
+        .db_service
+        .get_custom_object_fields(custom_object.id)
+        .await
+    {
+        Ok(fields) => fields,
+        Err(err) => return commercyfy_fail!(err.to_string()),
+    };
+
+    let values = match check_custom_fields(&state, &fields, &Some(payload), false).await {
+        Ok(fields) if fields.errors.is_empty() => fields.values,
+        Ok(fields) => return commercyfy_fields_fail!(fields.errors),
+        Err(err) => return commercyfy_fail!(err),
+    };
+
+    let entries = values
+        .into_iter()
+        .map(|(field_name, value)| {
+            return UnstructuredEntry {
+                extr_ref: record.id.to_string(),
+                field_name,
+                value,
+            };
+        })
+        .collect::<Vec<UnstructuredEntry>>();
+
+    if let Err(err) = state
+        .unstructureddb
+        .upsert_custom_fields(FieldExtensionObject::CUSTOMOBJECT, entries)
+        .await
+    {
+        return commercyfy_fail!(err);
+    }
+
+    return match state.db_service.touch_custom_object_record(record.id).await {
+        Ok(record) => get_record_view(&state, record).await,
+        Err(err) => commercyfy_fail!(err.to_string()),
+    };
+}
+
+pub async fn delete_custom_object_record(
+    Extension(claims): Extension<JWTClaims>,
+    State(state): CommercyfyExtrState,
+    Path((name, id)): Path<(String, String)>,
+) -> CommercyfyResponse<EmptyResponse> {
+    let custom_object = match get_custom_object(&state, &name).await {
+        Ok(custom_object) => custom_object,
+        Err((status, err)) => return commercyfy_fail!(status, err),
+    };
+
+    if let Err((status, err)) = check_record_access(&claims, &custom_object, true) {
+        return commercyfy_fail!(status, err);
+    }
+
+    let record = match get_record(&state, &custom_object, &id).await {
+        Ok(record) => record,
+        Err((status, err)) => return commercyfy_fail!(status, err),
+    };
+
+    let fields = match state
+        .db_service
+        .get_custom_object_fields(custom_object.id)
+        .await
+    {
+        Ok(fields) => fields,
+        Err(err) => return commercyfy_fail!(err.to_string()),
+    };
+
+    if let Err(err) = state
+        .db_service
+        .delete_custom_object_record(record.id)
+        .await
+    {
+        return commercyfy_fail!(err.to_string());
+    }
+
+    let field_names = fields
+        .into_iter()
+        .map(|x| return x.name)
+        .collect::<Vec<String>>();
+
+    if let Err(err) = state
+        .unstructureddb
+        .delete_custom_fields(
+            FieldExtensionObject::CUSTOMOBJECT,
+            &record.id.to_string(),
+            &field_names,
+        )
+        .await
+    {
+        return commercyfy_fail!(err);
+    }
+
+    return commercyfy_success!(EmptyResponse {});
+}
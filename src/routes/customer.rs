@@ -0,0 +1,261 @@
+use std::{collections::HashMap, time::Duration};
+
+use super::{
+    base_extensions::{set_object_custom_fields, unset_object_custom_field},
+    CommercyfyResponse, CreatedEntryResponse,
+};
+use crate::{
+    models::{
+        base_extensions::FieldExtensionObject,
+        customer::{Customer, CustomerAddress, CustomerJWTClaims, CUSTOMER_JWT_AUDIENCE},
+        portal_user::SignInToken,
+    },
+    schemas::customer::{CreateCustomerAddress, CustomerCreate, CustomerSignin, CustomerUpdate},
+    services::unstructureddb::entry::UnstructuredEntryType,
+    utils::{
+        auth::{get_token_expiration, verify_dummy_password, verify_password},
+        custom_fields::get_object_custom_fields,
+    },
+    CommercyfyExtrState,
+};
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Extension, Json,
+};
+
+#[derive(serde::Serialize)]
+pub struct CustomerView {
+    #[serde(flatten)]
+    customer: Customer,
+    addresses: Vec<CustomerAddress>,
+
+    // only shown in the portal, the customer does not see them
+    #[serde(skip_serializing_if = "Option::is_none")]
+    custom_fields: Option<HashMap<String, UnstructuredEntryType>>,
+}
+
+pub async fn get_customers(State(state): CommercyfyExtrState) -> CommercyfyResponse<Vec<Customer>> {
+    let customers = match state.db_service.get_customers().await {
+        Ok(customers) => customers,
+        Err(err) => return commercyfy_fail!(err.to_string()),
+    };
+
+    return commercyfy_success!(customers);
+}
+
+async fn get_customer_view(
+    state: &crate::CommercyfyState,
+    id: &str,
+    with_custom_fields: bool,
+) -> CommercyfyResponse<CustomerView> {
+    let customer = match state.db_service.get_customer(id).await {
+        Ok(Some(customer)) => customer,
+        Ok(None) => {
+            return commercyfy_fail!(
+                StatusCode::NOT_FOUND,
+                format!("Customer with id '{}' was not found", id)
+            )
+        }
+        Err(err) => return commercyfy_fail!(err.to_string()),
+    };
+
+    let addresses = match state.db_service.get_customer_addresses(id).await {
+        Ok(addresses) => addresses,
+        Err(err) => return commercyfy_fail!(err.to_string()),
+    };
+
+    let custom_fields = if with_custom_fields {
+        match get_object_custom_fields(state, FieldExtensionObject::CUSTOMER, id).await {
+            Ok(custom_fields) => Some(custom_fields),
+            Err(err) => return commercyfy_fail!(err),
+        }
+    } else {
+        None
+    };
+
+    return commercyfy_success!(CustomerView {
+        customer,
+        addresses,
+        custom_fields
+    });
+}
+
+pub async fn get_customer(
+    State(state): CommercyfyExtrState,
+    Path(id): Path<String>,
+) -> CommercyfyResponse<CustomerView> {
+    return get_customer_view(&state, &id, true).await;
+}
+
+pub async fn register_customer(
+    State(state): CommercyfyExtrState,
+    Json(payload): Json<CustomerCreate>,
+) -> CommercyfyResponse<CreatedEntryResponse> {
+    if let Err(err) = payload.validate() {
+        return commercyfy_fail!(err);
+    }
+
+    match state.db_service.get_customer_by_email(&payload.email).await {
+        Ok(Some(_)) => {
+            return commercyfy_fail!("Customer with that email already exists".to_string())
+        }
+        Ok(None) => {}
+        Err(err) => return commercyfy_fail!(err.to_string()),
+    };
+
+    let customer = match state.db_service.create_customer(payload).await {
+        Ok(customer) => customer,
+        Err(sqlx::Error::Io(_)) => {
+            return commercyfy_fail!("There was an error creating a customer".to_string())
+        }
+        Err(err) => return commercyfy_fail!(err.to_string()),
+    };
+
+    return commercyfy_success!(
+        StatusCode::CREATED,
+        CreatedEntryResponse { id: customer.id }
+    );
+}
+
+pub async fn signin_customer(
+    State(state): CommercyfyExtrState,
+    Json(payload): Json<CustomerSignin>,
+) -> CommercyfyResponse<SignInToken> {
+    if let Err(err) = payload.validate() {
+        return commercyfy_fail!(err);
+    }
+
+    let customer = match state.db_service.get_customer_by_email(&payload.email).await {
+        Ok(Some(customer)) => customer,
+        Ok(None) => {
+            verify_dummy_password(&payload.password);
+            return commercyfy_fail!("No matching credentials".to_string());
+        }
+        Err(err) => return commercyfy_fail!(err.to_string()),
+    };
+
+    match verify_password(&customer.password, &payload.password) {
+        Ok(true) => {}
+        Ok(false) => return commercyfy_fail!("No matching credentials".to_string()),
+        Err(_err) => {
+            return commercyfy_fail!("There was an error handling your request".to_string())
+        }
+    };
+
+    let exp = match get_token_expiration(Duration::from_secs(60 * 60 * 24)) {
+        Ok(exp) => exp,
+        Err(err) => return commercyfy_fail!(err),
+    };
+
+    let claims = CustomerJWTClaims {
+        sub: customer.id.to_string(),
+        email: customer.email,
+        exp,
+        aud: CUSTOMER_JWT_AUDIENCE.to_string(),
+    };
+
+    return match state.signing_keys.encode(&claims) {
+        Ok(jwt) => commercyfy_success!(SignInToken {
+            jwt,
+            refresh_token: None
+        }),
+        Err(err) => commercyfy_fail!(err),
+    };
+}
+
+pub async fn get_customer_profile(
+    Extension(claims): Extension<CustomerJWTClaims>,
+    State(state): CommercyfyExtrState,
+) -> CommercyfyResponse<CustomerView> {
+    return get_customer_view(&state, &claims.sub, false).await;
+}
+
+pub async fn update_customer_profile(
+    Extension(claims): Extension<CustomerJWTClaims>,
+    State(state): CommercyfyExtrState,
+    Json(payload): Json<CustomerUpdate>,
+) -> CommercyfyResponse<Customer> {
+    if let Err(err) = payload.validate() {
+        return commercyfy_fail!(err);
+    }
+
+    return match state
+        .db_service
+        .update_customer(&claims.sub, &payload)
+        .await
+    {
+        Ok(customer) => commercyfy_success!(customer),
+        Err(err) => commercyfy_fail!(err.to_string()),
+    };
+}
+
+pub async fn get_customer_addresses(
+    Extension(claims): Extension<CustomerJWTClaims>,
+    State(state): CommercyfyExtrState,
+) -> CommercyfyResponse<Vec<CustomerAddress>> {
+    return match state.db_service.get_customer_addresses(&claims.sub).await {
+        Ok(addresses) => commercyfy_success!(addresses),
+        Err(err) => commercyfy_fail!(err.to_string()),
+    };
+}
+
+pub async fn create_customer_address(
+    Extension(claims): Extension<CustomerJWTClaims>,
+    State(state): CommercyfyExtrState,
+    Json(payload): Json<CreateCustomerAddress>,
+) -> CommercyfyResponse<CreatedEntryResponse> {
+    if let Err(err) = payload.validate() {
+        return commercyfy_fail!(err);
+    }
+
+    let address = match state
+        .db_service
+        .create_customer_address(&claims.sub, &payload)
+        .await
+    {
+        Ok(address) => address,
+        Err(err) => return commercyfy_fail!(err.to_string()),
+    };
+
+    return commercyfy_success!(StatusCode::CREATED, CreatedEntryResponse { id: address.id });
+}
+
+pub async fn delete_customer_address(
+    Extension(claims): Extension<CustomerJWTClaims>,
+    State(state): CommercyfyExtrState,
+    Path(id): Path<String>,
+) -> CommercyfyResponse<CreatedEntryResponse> {
+    let address_id = match uuid::Uuid::parse_str(&id) {
+        Ok(address_id) => address_id,
+        Err(_err) => return commercyfy_fail!(format!("'{}' is not a valid address id", id)),
+    };
+
+    return match state
+        .db_service
+        .delete_customer_address(&claims.sub, &id)
+        .await
+    {
+        Ok(true) => commercyfy_success!(CreatedEntryResponse { id: address_id }),
+        Ok(false) => commercyfy_fail!(
+            StatusCode::NOT_FOUND,
+            format!("Address with id '{}' was not found", id)
+        ),
+        Err(err) => commercyfy_fail!(err.to_string()),
+    };
+}
+
+pub async fn put_customer_custom_fields(
+    State(state): CommercyfyExtrState,
+    Path(id): Path<String>,
+    Json(payload): Json<HashMap<String, serde_json::Value>>,
+) -> CommercyfyResponse<HashMap<String, UnstructuredEntryType>> {
+    return set_object_custom_fields(&state, FieldExtensionObject::CUSTOMER, &id, payload).await;
+}
+
+pub async fn delete_customer_custom_field(
+    State(state): CommercyfyExtrState,
+    Path((id, field)): Path<(String, String)>,
+) -> CommercyfyResponse<HashMap<String, UnstructuredEntryType>> {
+    return unset_object_custom_field(&state, FieldExtensionObject::CUSTOMER, &id, &field).await;
+}
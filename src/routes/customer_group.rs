@@ -0,0 +1,260 @@
+use super::{CommercyfyResponse, CreatedEntryResponse};
+use crate::{
+    models::{
+        customer::{Customer, CustomerJWTClaims},
+        customer_group::{CustomerGroup, CustomerGroupRule, CustomerGroupType, CustomerPrice},
+        pricebook::Pricebook,
+    },
+    schemas::customer_group::{
+        AssignCustomersToGroup, AssignPricebooksToGroup, CreateCustomerGroup, CustomerPriceQuery,
+    },
+    utils::customer_groups::get_customer_groups as resolve_customer_groups,
+    CommercyfyExtrState,
+};
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    Extension, Json,
+};
+
+pub async fn get_customer_groups(
+    State(state): CommercyfyExtrState,
+) -> CommercyfyResponse<Vec<CustomerGroup>> {
+    return match state.db_service.get_customer_groups().await {
+        Ok(groups) => commercyfy_success!(groups),
+        Err(err) => commercyfy_fail!(err.to_string()),
+    };
+}
+
+#[derive(serde::Serialize)]
+pub struct CustomerGroupView {
+    #[serde(flatten)]
+    customer_group: CustomerGroup,
+    rules: Vec<CustomerGroupRule>,
+    customers: Vec<Customer>,
+    pricebooks: Vec<Pricebook>,
+}
+
+pub async fn get_customer_group(
+    State(state): CommercyfyExtrState,
+    Path(id): Path<String>,
+) -> CommercyfyResponse<CustomerGroupView> {
+    let customer_group = match state.db_service.get_customer_group_by_id(&id).await {
+        Ok(Some(group)) => group,
+        Ok(None) => match state.db_service.get_customer_group_by_reference(&id).await {
+            Ok(Some(group)) => group,
+            Ok(None) => {
+                return commercyfy_fail!(
+                    StatusCode::NOT_FOUND,
+                    format!(
+                        "Customer group with the provided, {}, id/reference was not found",
+                        id
+                    )
+                )
+            }
+            Err(err) => return commercyfy_fail!(err.to_string()),
+        },
+        Err(err) => return commercyfy_fail!(err.to_string()),
+    };
+
+    let group_id = customer_group.id.to_string();
+    let rules = match state.db_service.get_customer_group_rules(&group_id).await {
+        Ok(rules) => rules,
+        Err(err) => return commercyfy_fail!(err.to_string()),
+    };
+
+    let customers = match state
+        .db_service
+        .get_customer_group_customers(&group_id)
+        .await
+    {
+        Ok(customers) => customers,
+        Err(err) => return commercyfy_fail!(err.to_string()),
+    };
+
+    let pricebooks = match state
+        .db_service
+        .get_customer_group_pricebooks(&group_id)
+        .await
+    {
+        Ok(pricebooks) => pricebooks,
+        Err(err) => return commercyfy_fail!(err.to_string()),
+    };
+
+    return commercyfy_success!(CustomerGroupView {
+        customer_group,
+        rules,
+        customers,
+        pricebooks,
+    });
+}
+
+pub async fn create_customer_group(
+    State(state): CommercyfyExtrState,
+    Json(payload): Json<CreateCustomerGroup>,
+) -> CommercyfyResponse<CreatedEntryResponse> {
+    if let Err(err) = payload.validate() {
+        return commercyfy_fail!(err);
+    }
+
+    match state
+        .db_service
+        .get_customer_group_by_reference(&payload.customer_group_reference)
+        .await
+    {
+        Ok(Some(_)) => {
+            return commercyfy_fail!(format!(
+                "Customer group with 'customer_group_reference' '{}' already exists",
+                payload.customer_group_reference
+            ))
+        }
+        Ok(None) => {}
+        Err(err) => return commercyfy_fail!(err.to_string()),
+    };
+
+    return match state.db_service.create_customer_group(&payload).await {
+        Ok(group) => {
+            commercyfy_success!(StatusCode::CREATED, CreatedEntryResponse { id: group.id })
+        }
+        Err(err) => commercyfy_fail!(err.to_string()),
+    };
+}
+
+pub async fn assign_customers_to_group(
+    State(state): CommercyfyExtrState,
+    Json(payload): Json<AssignCustomersToGroup>,
+) -> CommercyfyResponse<CreatedEntryResponse> {
+    if let Err(err) = payload.validate() {
+        return commercyfy_fail!(err);
+    }
+
+    let group_id = payload.customer_group_id.to_string();
+    match state.db_service.get_customer_group_by_id(&group_id).await {
+        Ok(Some(group)) => {
+            if group.customer_group_type != CustomerGroupType::STATIC {
+                return commercyfy_fail!(
+                    "Customers can only be assigned to STATIC customer groups".to_string()
+                );
+            }
+        }
+        Ok(None) => {
+            return commercyfy_fail!(format!(
+                "Customer group with id '{}' does not exist",
+                group_id
+            ))
+        }
+        Err(err) => return commercyfy_fail!(err.to_string()),
+    };
+
+    if let Err(err) = state
+        .db_service
+        .create_customer_group_customer_entries(&payload)
+        .await
+    {
+        return commercyfy_fail!(err.to_string());
+    }
+
+    return commercyfy_success!(CreatedEntryResponse {
+        id: payload.customer_group_id
+    });
+}
+
+pub async fn assign_pricebooks_to_group(
+    State(state): CommercyfyExtrState,
+    Json(payload): Json<AssignPricebooksToGroup>,
+) -> CommercyfyResponse<CreatedEntryResponse> {
+    if let Err(err) = payload.validate() {
+        return commercyfy_fail!(err);
+    }
+
+    let group_id = payload.customer_group_id.to_string();
+    match state.db_service.get_customer_group_by_id(&group_id).await {
+        Ok(Some(_)) => {}
+        Ok(None) => {
+            return commercyfy_fail!(format!(
+                "Customer group with id '{}' does not exist",
+                group_id
+            ))
+        }
+        Err(err) => return commercyfy_fail!(err.to_string()),
+    };
+
+    if let Err(err) = state
+        .db_service
+        .create_customer_group_pricebook_entries(&payload)
+        .await
+    {
+        return commercyfy_fail!(err.to_string());
+    }
+
+    return commercyfy_success!(CreatedEntryResponse {
+        id: payload.customer_group_id
+    });
+}
+
+pub async fn get_customer_membership(
+    State(state): CommercyfyExtrState,
+    Path(id): Path<String>,
+) -> CommercyfyResponse<Vec<CustomerGroup>> {
+    let customer = match state.db_service.get_customer(&id).await {
+        Ok(Some(customer)) => customer,
+        Ok(None) => {
+            return commercyfy_fail!(
+                StatusCode::NOT_FOUND,
+                format!("Customer with id '{}' was not found", id)
+            )
+        }
+        Err(err) => return commercyfy_fail!(err.to_string()),
+    };
+
+    return match resolve_customer_groups(state, &customer).await {
+        Ok(groups) => commercyfy_success!(groups),
+        Err(err) => commercyfy_fail!(err),
+    };
+}
+
+pub async fn get_customer_product_price(
+    Extension(claims): Extension<CustomerJWTClaims>,
+    State(state): CommercyfyExtrState,
+    Path(id): Path<String>,
+    Query(query): Query<CustomerPriceQuery>,
+) -> CommercyfyResponse<CustomerPrice> {
+    let customer = match state.db_service.get_customer(&claims.sub).await {
+        Ok(Some(customer)) => customer,
+        Ok(None) => {
+            return commercyfy_fail!(StatusCode::UNAUTHORIZED, "Unknown customer".to_string())
+        }
+        Err(err) => return commercyfy_fail!(err.to_string()),
+    };
+
+    let groups = match resolve_customer_groups(state.clone(), &customer).await {
+        Ok(groups) => groups.into_iter().map(|group| return group.id).collect(),
+        Err(err) => return commercyfy_fail!(err),
+    };
+
+    let records = match state
+        .db_service
+        .get_product_customer_prices(&id, &query.currency, groups)
+        .await
+    {
+        Ok(records) => records,
+        Err(err) => return commercyfy_fail!(err.to_string()),
+    };
+
+    // the records are ordered by price, the customer always gets the best one
+    return match records.into_iter().next() {
+        Some(record) => commercyfy_success!(CustomerPrice {
+            product_id: record.product_id,
+            pricebook_id: record.pricebook_id,
+            currency_code: query.currency,
+            price: record.price,
+        }),
+        None => commercyfy_fail!(
+            StatusCode::NOT_FOUND,
+            format!(
+                "Product with id '{}' has no price in currency '{}'",
+                id, query.currency
+            )
+        ),
+    };
+}
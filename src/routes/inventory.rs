@@ -1,38 +1,42 @@
 use std::collections::HashMap;
 
-use axum::extract::{Path, State};
+use axum::extract::{Path, Query, State};
 use axum::http::StatusCode;
-use axum::{Extension, Json};
+use axum::Json;
 
-use super::{CommercyfyResponse, CreatedEntryResponse};
+use super::{
+    base_extensions::{set_object_custom_fields, unset_object_custom_field},
+    CommercyfyResponse, CreatedEntryResponse,
+};
 use crate::models::base_extensions::FieldExtensionObject;
-use crate::models::inventory::ProductInventoryRecord;
-use crate::models::portal_user::{JWTClaims, PortalUsersRoles};
+use crate::models::inventory::{InventoryAdjustment, ProductInventoryRecord};
 use crate::schemas::inventory::{CreateInventory, CreateInventoryRecord};
-use crate::services::db::DbService;
-use crate::services::role_validation::RoleService;
 use crate::services::unstructureddb::entry::UnstructuredEntryType;
-use crate::services::unstructureddb::UnstructuredDb;
-use crate::utils::custom_fields::create_custom_fields;
+use crate::utils::custom_fields::{
+    create_custom_fields, find_custom_field_matches, get_object_custom_fields,
+    validate_custom_fields,
+};
 use crate::{models::inventory::Inventory, CommercyfyExtrState};
 
 pub async fn get_inventories(
-    Extension(claims): Extension<JWTClaims>,
+    Query(params): Query<HashMap<String, String>>,
     State(state): CommercyfyExtrState,
 ) -> CommercyfyResponse<Vec<Inventory>> {
-    if let Err(err) = state.role_service.validate_any(
-        &claims,
-        vec![PortalUsersRoles::ADMIN, PortalUsersRoles::READER],
-    ) {
-        return commercyfy_fail!(err);
-    }
+    let matches =
+        match find_custom_field_matches(&state, FieldExtensionObject::INVENTORY, &params).await {
+            Ok(matches) if matches.errors.is_empty() => matches,
+            Ok(matches) => return commercyfy_fields_fail!(matches.errors),
+            Err(err) => return commercyfy_fail!(err),
+        };
 
-    let inventories = state.db_service.get_inventories().await;
-    if let Err(error) = inventories {
-        return commercyfy_fail!(error.to_string());
-    }
+    let mut inventories = match state.db_service.get_inventories().await {
+        Ok(inventories) => inventories,
+        Err(error) => return commercyfy_fail!(error.to_string()),
+    };
+
+    inventories.retain(|x| return matches.matches(&x.id.to_string()));
 
-    return commercyfy_success!(inventories.unwrap());
+    return commercyfy_success!(inventories);
 }
 
 #[derive(serde::Serialize)]
@@ -43,17 +47,9 @@ pub struct InventoryView {
     custom_fields: HashMap<String, UnstructuredEntryType>,
 }
 pub async fn get_inventory(
-    Extension(claims): Extension<JWTClaims>,
     Path(id): Path<String>,
     State(state): CommercyfyExtrState,
 ) -> CommercyfyResponse<InventoryView> {
-    if let Err(err) = state.role_service.validate_any(
-        &claims,
-        vec![PortalUsersRoles::ADMIN, PortalUsersRoles::READER],
-    ) {
-        return commercyfy_fail!(err);
-    }
-
     let inventory_id_check = state.db_service.get_inventory_by_id(&id).await;
     if let Err(error) = inventory_id_check {
         return commercyfy_fail!(error.to_string());
@@ -140,17 +136,9 @@ pub async fn get_inventory(
 }
 
 pub async fn create_inventory(
-    Extension(claims): Extension<JWTClaims>,
     State(state): CommercyfyExtrState,
     Json(payload): Json<CreateInventory>,
 ) -> CommercyfyResponse<CreatedEntryResponse> {
-    if let Err(err) = state.role_service.validate_any(
-        &claims,
-        vec![PortalUsersRoles::ADMIN, PortalUsersRoles::EDITOR],
-    ) {
-        return commercyfy_fail!(err);
-    }
-
     if let Err(error) = payload.validate() {
         return commercyfy_fail!(error.to_string());
     }
@@ -168,37 +156,42 @@ pub async fn create_inventory(
         return commercyfy_fail!(format!("Inventory with that reference already exists"));
     }
 
+    let custom_fields = match validate_custom_fields(
+        &state,
+        FieldExtensionObject::INVENTORY,
+        &payload.custom_fields,
+        true,
+    )
+    .await
+    {
+        Ok(fields) if fields.errors.is_empty() => fields.values,
+        Ok(fields) => return commercyfy_fields_fail!(fields.errors),
+        Err(err) => return commercyfy_fail!(err),
+    };
+
     let inventory = match state.db_service.create_inventory(&payload).await {
         Ok(inv) => inv,
         Err(error) => return commercyfy_fail!(error.to_string()),
     };
 
-    if let Err(err) = create_custom_fields(
+    if let Err((status, err)) = create_custom_fields(
         state,
-        inventory.id.to_string(),
+        inventory.id,
         FieldExtensionObject::INVENTORY,
-        &payload.custom_fields,
+        custom_fields,
     )
     .await
     {
-        return commercyfy_fail!(err.to_string());
+        return commercyfy_fail!(status, err);
     }
 
     return commercyfy_success!(CreatedEntryResponse { id: inventory.id });
 }
 
 pub async fn create_inventory_record(
-    Extension(claims): Extension<JWTClaims>,
     State(state): CommercyfyExtrState,
     Json(payload): Json<CreateInventoryRecord>,
 ) -> CommercyfyResponse<CreatedEntryResponse> {
-    if let Err(err) = state.role_service.validate_any(
-        &claims,
-        vec![PortalUsersRoles::ADMIN, PortalUsersRoles::EDITOR],
-    ) {
-        return commercyfy_fail!(err);
-    }
-
     if let Err(error) = payload.validate() {
         return commercyfy_fail!(error);
     }
@@ -251,6 +244,19 @@ pub async fn create_inventory_record(
         ));
     }
 
+    let custom_fields = match validate_custom_fields(
+        &state,
+        FieldExtensionObject::INVENTORYRECORD,
+        &payload.custom_fields,
+        true,
+    )
+    .await
+    {
+        Ok(fields) if fields.errors.is_empty() => fields.values,
+        Ok(fields) => return commercyfy_fields_fail!(fields.errors),
+        Err(err) => return commercyfy_fail!(err),
+    };
+
     let record_check = state
         .db_service
         .create_product_inventory_record(payload)
@@ -259,26 +265,32 @@ pub async fn create_inventory_record(
         return commercyfy_fail!(err.to_string());
     }
 
-    return commercyfy_success!(
-        StatusCode::CREATED,
-        CreatedEntryResponse {
-            id: record_check.unwrap().id
-        }
-    );
+    let record = record_check.unwrap();
+    if let Err((status, err)) = create_custom_fields(
+        state,
+        record.id,
+        FieldExtensionObject::INVENTORYRECORD,
+        custom_fields,
+    )
+    .await
+    {
+        return commercyfy_fail!(status, err);
+    }
+
+    return commercyfy_success!(StatusCode::CREATED, CreatedEntryResponse { id: record.id });
+}
+
+#[derive(serde::Serialize)]
+pub struct InventoryRecordView {
+    #[serde(flatten)]
+    record: ProductInventoryRecord,
+    custom_fields: HashMap<String, UnstructuredEntryType>,
 }
 
 pub async fn get_inventory_record(
-    Extension(claims): Extension<JWTClaims>,
     State(state): CommercyfyExtrState,
     Path(path): Path<(String, String)>,
-) -> CommercyfyResponse<ProductInventoryRecord> {
-    if let Err(err) = state.role_service.validate_any(
-        &claims,
-        vec![PortalUsersRoles::ADMIN, PortalUsersRoles::READER],
-    ) {
-        return commercyfy_fail!(err);
-    }
-
+) -> CommercyfyResponse<InventoryRecordView> {
     let (inventory_id, product_id) = path;
     let record_check = state
         .db_service
@@ -288,10 +300,69 @@ pub async fn get_inventory_record(
         return commercyfy_fail!(error.to_string());
     }
 
-    let record = record_check.unwrap();
-    if let None = record {
+    let record = if let Some(record) = record_check.unwrap() {
+        record
+    } else {
         return commercyfy_fail!(StatusCode::NOT_FOUND, format!("No record was found"));
+    };
+
+    let custom_fields = match get_object_custom_fields(
+        &state,
+        FieldExtensionObject::INVENTORYRECORD,
+        &record.id.to_string(),
+    )
+    .await
+    {
+        Ok(custom_fields) => custom_fields,
+        Err(err) => return commercyfy_fail!(err),
+    };
+
+    return commercyfy_success!(InventoryRecordView {
+        record,
+        custom_fields
+    });
+}
+
+pub async fn get_inventory_adjustments(
+    State(state): CommercyfyExtrState,
+    Path(id): Path<String>,
+) -> CommercyfyResponse<Vec<InventoryAdjustment>> {
+    let adjustments = state.db_service.get_inventory_adjustments(&id).await;
+    if let Err(error) = adjustments {
+        return commercyfy_fail!(error.to_string());
     }
 
-    return commercyfy_success!(record.unwrap());
+    return commercyfy_success!(adjustments.unwrap());
+}
+
+pub async fn put_inventory_custom_fields(
+    State(state): CommercyfyExtrState,
+    Path(id): Path<String>,
+    Json(payload): Json<HashMap<String, serde_json::Value>>,
+) -> CommercyfyResponse<HashMap<String, UnstructuredEntryType>> {
+    return set_object_custom_fields(&state, FieldExtensionObject::INVENTORY, &id, payload).await;
+}
+
+pub async fn delete_inventory_custom_field(
+    State(state): CommercyfyExtrState,
+    Path((id, field)): Path<(String, String)>,
+) -> CommercyfyResponse<HashMap<String, UnstructuredEntryType>> {
+    return unset_object_custom_field(&state, FieldExtensionObject::INVENTORY, &id, &field).await;
+}
+
+pub async fn put_inventory_record_custom_fields(
+    State(state): CommercyfyExtrState,
+    Path(id): Path<String>,
+    Json(payload): Json<HashMap<String, serde_json::Value>>,
+) -> CommercyfyResponse<HashMap<String, UnstructuredEntryType>> {
+    return set_object_custom_fields(&state, FieldExtensionObject::INVENTORYRECORD, &id, payload)
+        .await;
+}
+
+pub async fn delete_inventory_record_custom_field(
+    State(state): CommercyfyExtrState,
+    Path((id, field)): Path<(String, String)>,
+) -> CommercyfyResponse<HashMap<String, UnstructuredEntryType>> {
+    return unset_object_custom_field(&state, FieldExtensionObject::INVENTORYRECORD, &id, &field)
+        .await;
 }
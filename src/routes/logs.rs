@@ -6,7 +6,6 @@ use std::str::Chars;
 
 use axum::{extract::State, Json};
 
-use crate::services::logger::Logger;
 use crate::{schemas::logs::CreateLog, CommercyfyExtrState};
 
 use super::CommercyfyResponse;
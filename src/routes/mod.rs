@@ -4,7 +4,13 @@ use axum::Json;
 #[serde(untagged)]
 pub enum CommercyfyResponseData<T: serde::Serialize> {
     Success(T),
-    Error { error: String },
+    Error {
+        error: String,
+    },
+    FieldErrors {
+        error: String,
+        fields: Vec<FieldError>,
+    },
 }
 pub type CommercyfyResponse<T> = (axum::http::StatusCode, Json<CommercyfyResponseData<T>>);
 
@@ -13,6 +19,12 @@ pub struct CreatedEntryResponse {
     pub id: sqlx::types::Uuid,
 }
 
+#[derive(serde::Serialize, Debug)]
+pub struct FieldError {
+    pub field: String,
+    pub error: String,
+}
+
 #[macro_export]
 macro_rules! commercyfy_success {
     ($x: expr) => {
@@ -47,10 +59,34 @@ macro_rules! commercyfy_fail {
     };
 }
 
+// every invalid field is reported at once instead of only the first one
+#[macro_export]
+macro_rules! commercyfy_fields_fail {
+    ($x: expr) => {
+        (
+            axum::http::StatusCode::BAD_REQUEST,
+            axum::Json($crate::routes::CommercyfyResponseData::FieldErrors {
+                error: "Some of the provided fields are not valid".to_string(),
+                fields: $x,
+            }),
+        )
+    };
+}
+
+pub mod api_key;
 pub mod base_extensions;
 pub mod category;
+pub mod custom_object;
+pub mod customer;
+pub mod customer_group;
 pub mod inventory;
+pub mod logs;
+pub mod permission;
 pub mod portal;
 pub mod pricebook;
 pub mod product;
-pub mod logs;
+pub mod product_list;
+pub mod returns;
+pub mod shipping;
+pub mod signing_key;
+pub mod tax;
@@ -0,0 +1,158 @@
+use super::{logs::EmptyResponse, CommercyfyResponse, CreatedEntryResponse};
+use crate::{
+    models::{
+        permission::{custom_object_permission, PortalRole, ALL_PERMISSIONS},
+        portal_user::JWTClaims,
+    },
+    schemas::permission::{AssignPortalRoles, CreatePortalRole},
+    utils::permissions::{check_manages_portal_user, holds_permission},
+    CommercyfyExtrState,
+};
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Extension, Json,
+};
+
+// the built-in permissions followed by the ones of the custom object types
+pub async fn get_permissions(State(state): CommercyfyExtrState) -> CommercyfyResponse<Vec<String>> {
+    let custom_objects = match state.db_service.get_custom_object_types().await {
+        Ok(custom_objects) => custom_objects,
+        Err(err) => return commercyfy_fail!(err.to_string()),
+    };
+
+    let mut permissions = ALL_PERMISSIONS
+        .iter()
+        .map(|x| return x.to_string())
+        .collect::<Vec<String>>();
+
+    for custom_object in custom_objects {
+        permissions.push(custom_object_permission(&custom_object.name, false));
+        permissions.push(custom_object_permission(&custom_object.name, true));
+    }
+
+    return commercyfy_success!(permissions);
+}
+
+pub async fn get_portal_roles(
+    State(state): CommercyfyExtrState,
+) -> CommercyfyResponse<Vec<PortalRole>> {
+    return match state.db_service.get_portal_roles().await {
+        Ok(roles) => commercyfy_success!(roles),
+        Err(err) => commercyfy_fail!(err.to_string()),
+    };
+}
+
+pub async fn get_portal_role(
+    State(state): CommercyfyExtrState,
+    Path(id): Path<String>,
+) -> CommercyfyResponse<PortalRole> {
+    return match state.db_service.get_portal_role(&id).await {
+        Ok(Some(role)) => commercyfy_success!(role),
+        Ok(None) => commercyfy_fail!(
+            StatusCode::NOT_FOUND,
+            format!("Role with id '{}' was not found", id)
+        ),
+        Err(err) => commercyfy_fail!(err.to_string()),
+    };
+}
+
+pub async fn create_portal_role(
+    State(state): CommercyfyExtrState,
+    Json(payload): Json<CreatePortalRole>,
+) -> CommercyfyResponse<CreatedEntryResponse> {
+    if let Err(err) = payload.validate() {
+        return commercyfy_fail!(err);
+    }
+
+    return match state.db_service.create_portal_role(&payload).await {
+        Ok(role) => commercyfy_success!(StatusCode::CREATED, CreatedEntryResponse { id: role.id }),
+        Err(err) => commercyfy_fail!(err.to_string()),
+    };
+}
+
+pub async fn delete_portal_role(
+    State(state): CommercyfyExtrState,
+    Path(id): Path<String>,
+) -> CommercyfyResponse<EmptyResponse> {
+    match state.db_service.get_portal_role(&id).await {
+        Ok(Some(_)) => {}
+        Ok(None) => {
+            return commercyfy_fail!(
+                StatusCode::NOT_FOUND,
+                format!("Role with id '{}' was not found", id)
+            )
+        }
+        Err(err) => return commercyfy_fail!(err.to_string()),
+    };
+
+    if let Err(err) = state.db_service.delete_portal_role(&id).await {
+        return commercyfy_fail!(err.to_string());
+    }
+
+    return commercyfy_success!(EmptyResponse {});
+}
+
+pub async fn get_portal_user_roles(
+    State(state): CommercyfyExtrState,
+    Path(id): Path<String>,
+) -> CommercyfyResponse<Vec<PortalRole>> {
+    return match state.db_service.get_portal_user_roles(&id).await {
+        Ok(roles) => commercyfy_success!(roles),
+        Err(err) => commercyfy_fail!(err.to_string()),
+    };
+}
+
+pub async fn assign_portal_roles(
+    Extension(claims): Extension<JWTClaims>,
+    State(state): CommercyfyExtrState,
+    Path(id): Path<String>,
+    Json(payload): Json<AssignPortalRoles>,
+) -> CommercyfyResponse<EmptyResponse> {
+    let portal_user = match state.db_service.get_portal_user(&id).await {
+        Ok(Some(portal_user)) => portal_user,
+        Ok(None) => {
+            return commercyfy_fail!(
+                StatusCode::NOT_FOUND,
+                format!("Portal user with id '{}' was not found", id)
+            )
+        }
+        Err(err) => return commercyfy_fail!(err.to_string()),
+    };
+
+    if let Err((status, err)) = check_manages_portal_user(&state, &claims, &portal_user).await {
+        return commercyfy_fail!(status, err);
+    }
+
+    // like the built-in roles only the permissions the caller holds are granted
+    for role_id in &payload.role_ids {
+        match state.db_service.get_portal_role(&role_id.to_string()).await {
+            Ok(Some(role))
+                if !role
+                    .permissions
+                    .iter()
+                    .all(|permission| return holds_permission(&claims, permission)) =>
+            {
+                return commercyfy_fail!(
+                    StatusCode::FORBIDDEN,
+                    format!("Role '{}' has permissions you do not hold", role.name)
+                )
+            }
+            Ok(Some(_)) => {}
+            Ok(None) => {
+                return commercyfy_fail!(format!("Role with id '{}' was not found", role_id))
+            }
+            Err(err) => return commercyfy_fail!(err.to_string()),
+        };
+    }
+
+    if let Err(err) = state
+        .db_service
+        .assign_portal_roles(portal_user.id, &payload)
+        .await
+    {
+        return commercyfy_fail!(err.to_string());
+    }
+
+    return commercyfy_success!(EmptyResponse {});
+}
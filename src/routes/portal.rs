@@ -1,39 +1,89 @@
-use std::time::{self, Duration, SystemTime};
+use std::{collections::HashMap, net::SocketAddr, time::Duration};
 
+use super::base_extensions::{set_object_custom_fields, unset_object_custom_field};
+use super::logs::EmptyResponse;
 use super::{CommercyfyResponse, CreatedEntryResponse};
 use crate::{
-    models::portal_user::JWTClaims,
-    services::{db::DbService, role_validation::RoleService},
+    models::base_extensions::FieldExtensionObject,
+    models::portal_user::{
+        MfaChallengeToken, MfaEnrollment, PortalSignIn, PortalUser, RecoveryCodes, SignInToken,
+    },
+    schemas::login_attempt::LoginAttemptQuery,
+    schemas::portal_user::{
+        PortalUserCreate, PortalUserMfaCode, PortalUserMfaDisable, PortalUserMfaSignin,
+        PortalUserPasswordChange, PortalUserPasswordForgot, PortalUserPasswordRecover,
+        PortalUserPasswordReset, PortalUserQuery, PortalUserRefresh, PortalUserSignin,
+        PortalUserUpdate,
+    },
+    CommercyfyExtrState, CommercyfyState,
 };
 use crate::{
-    models::portal_user::{PortalUser, SignInToken},
-    schemas::portal_user::{PortalUserCreate, PortalUserSignin},
-    CommercyfyExtrState,
+    models::{
+        login_attempt::{LoginAttempt, LoginFailureReason},
+        permission::ROLE_WRITE,
+        portal_user::{JWTClaims, PortalUserSession, PortalUsersRoles, PORTAL_JWT_AUDIENCE},
+    },
+    services::{
+        mailer::{Mail, Mailer},
+        unstructureddb::entry::UnstructuredEntryType,
+    },
+    utils::{
+        auth::{
+            generate_token, get_token_expiration, hash_password, split_credential,
+            verify_dummy_password, verify_password,
+        },
+        custom_fields::{create_custom_fields, get_object_custom_fields, validate_custom_fields},
+        login_attempts::{get_login_client, get_login_lockout},
+        mfa::{
+            generate_mfa_secret, generate_recovery_codes, get_provisioning_uri, verify_totp_code,
+        },
+        permissions::{check_grants_roles, check_manages_portal_user, holds_permission},
+    },
 };
-use argon2::{PasswordHash, PasswordVerifier};
+
 use axum::{
-    extract::{Path, State},
-    http::StatusCode,
+    extract::{ConnectInfo, Path, Query, State},
+    http::{HeaderMap, StatusCode},
     Extension, Json,
 };
-use jsonwebtoken::{EncodingKey, Header};
+
+const REFRESH_TOKEN_LIFETIME: chrono::TimeDelta = chrono::TimeDelta::days(30);
+const PASSWORD_RESET_TOKEN_LIFETIME: chrono::TimeDelta = chrono::TimeDelta::hours(1);
+const MFA_CHALLENGE_LIFETIME: chrono::TimeDelta = chrono::TimeDelta::minutes(5);
+const MFA_CHALLENGE_ATTEMPTS: i32 = 5;
+
+#[derive(serde::Serialize)]
+pub struct PortalUserView {
+    #[serde(flatten)]
+    portal_user: PortalUser,
+    custom_fields: HashMap<String, UnstructuredEntryType>,
+}
 
 pub async fn get_portal_user(
-    Extension(claim): Extension<JWTClaims>,
     State(state): CommercyfyExtrState,
     Path(id): Path<String>,
-) -> CommercyfyResponse<PortalUser> {
-    if let Err(err) = state.role_service.validate_admin(&claim) {
-        return commercyfy_fail!(err);
-    }
-
+) -> CommercyfyResponse<PortalUserView> {
     let portal_user = state.db_service.get_portal_user(&id).await;
     if let Err(err) = portal_user {
         return commercyfy_fail!(err.to_string());
     }
 
     if let Some(portal_user) = portal_user.unwrap() {
-        return commercyfy_success!(portal_user);
+        let custom_fields = match get_object_custom_fields(
+            &state,
+            FieldExtensionObject::PORTALUSER,
+            &portal_user.id.to_string(),
+        )
+        .await
+        {
+            Ok(custom_fields) => custom_fields,
+            Err(err) => return commercyfy_fail!(err),
+        };
+
+        return commercyfy_success!(PortalUserView {
+            portal_user,
+            custom_fields
+        });
     }
 
     return commercyfy_fail!(
@@ -43,16 +93,18 @@ pub async fn get_portal_user(
 }
 
 pub async fn create_portal_user(
-    Extension(claim): Extension<JWTClaims>,
+    Extension(claims): Extension<JWTClaims>,
     State(state): CommercyfyExtrState,
     Json(payload): Json<PortalUserCreate>,
 ) -> CommercyfyResponse<CreatedEntryResponse> {
-    if let Err(err) = state.role_service.validate_admin(&claim) {
+    if let Err(err) = payload.validate() {
         return commercyfy_fail!(err);
     }
 
-    if let Err(err) = payload.validate() {
-        return commercyfy_fail!(err);
+    if !payload.roles.is_empty() {
+        if let Err((status, err)) = check_changes_roles(&state, &claims, &payload.roles) {
+            return commercyfy_fail!(status, err);
+        }
     }
 
     let existing = state
@@ -66,6 +118,19 @@ pub async fn create_portal_user(
         return commercyfy_fail!("User with that email already exists".to_string());
     }
 
+    let custom_fields = match validate_custom_fields(
+        &state,
+        FieldExtensionObject::PORTALUSER,
+        &payload.custom_fields,
+        true,
+    )
+    .await
+    {
+        Ok(fields) if fields.errors.is_empty() => fields.values,
+        Ok(fields) => return commercyfy_fields_fail!(fields.errors),
+        Err(err) => return commercyfy_fail!(err),
+    };
+
     let user = state.db_service.create_portal_user(payload).await;
     if let Err(err) = user {
         match err {
@@ -76,82 +141,1103 @@ pub async fn create_portal_user(
         }
     }
 
-    return commercyfy_success!(
-        StatusCode::CREATED,
-        CreatedEntryResponse {
-            id: user.unwrap().id
+    let user = user.unwrap();
+    if let Err((status, err)) = create_custom_fields(
+        state,
+        user.id,
+        FieldExtensionObject::PORTALUSER,
+        custom_fields,
+    )
+    .await
+    {
+        return commercyfy_fail!(status, err);
+    }
+
+    return commercyfy_success!(StatusCode::CREATED, CreatedEntryResponse { id: user.id });
+}
+
+pub async fn get_portal_users(
+    State(state): CommercyfyExtrState,
+    Query(query): Query<PortalUserQuery>,
+) -> CommercyfyResponse<Vec<PortalUser>> {
+    return match state.db_service.get_portal_users(&query).await {
+        Ok(portal_users) => commercyfy_success!(portal_users),
+        Err(err) => commercyfy_fail!(err.to_string()),
+    };
+}
+
+pub async fn update_portal_user(
+    Extension(claims): Extension<JWTClaims>,
+    State(state): CommercyfyExtrState,
+    Path(id): Path<String>,
+    Json(payload): Json<PortalUserUpdate>,
+) -> CommercyfyResponse<PortalUser> {
+    if let Err(err) = payload.validate() {
+        return commercyfy_fail!(err);
+    }
+
+    let portal_user = match state.db_service.get_portal_user(&id).await {
+        Ok(Some(portal_user)) => portal_user,
+        Ok(None) => {
+            return commercyfy_fail!(
+                StatusCode::NOT_FOUND,
+                format!("Portal user with id '{}' was not found", id)
+            )
         }
-    );
+        Err(err) => return commercyfy_fail!(err.to_string()),
+    };
+
+    if let Err((status, err)) = check_manages_portal_user(&state, &claims, &portal_user).await {
+        return commercyfy_fail!(status, err);
+    }
+
+    if let Some(roles) = &payload.roles {
+        if let Err((status, err)) = check_changes_roles(&state, &claims, roles) {
+            return commercyfy_fail!(status, err);
+        }
+    }
+
+    if let Some(email) = &payload.email {
+        match state.db_service.get_portal_user_by_email(email).await {
+            Ok(Some(existing)) if existing.id != portal_user.id => {
+                return commercyfy_fail!("User with that email already exists".to_string())
+            }
+            Ok(_) => {}
+            Err(err) => return commercyfy_fail!(err.to_string()),
+        };
+    }
+
+    return match state
+        .db_service
+        .update_portal_user(portal_user.id, &payload)
+        .await
+    {
+        Ok(portal_user) => commercyfy_success!(portal_user),
+        Err(err) => commercyfy_fail!(err.to_string()),
+    };
+}
+
+// the roles are only changed by callers allowed to manage the roles, and only
+// to the roles they hold themselves
+fn check_changes_roles(
+    state: &CommercyfyState,
+    claims: &JWTClaims,
+    roles: &[PortalUsersRoles],
+) -> Result<(), (StatusCode, String)> {
+    if !holds_permission(claims, ROLE_WRITE) {
+        return Err((
+            StatusCode::FORBIDDEN,
+            format!("Setting 'roles' needs the '{}' permission", ROLE_WRITE),
+        ));
+    }
+
+    return check_grants_roles(state, claims, roles);
+}
+
+async fn set_portal_user_active(
+    state: &CommercyfyState,
+    claims: &JWTClaims,
+    id: &str,
+    active: bool,
+) -> CommercyfyResponse<EmptyResponse> {
+    if !active && claims.sub == id {
+        return commercyfy_fail!("You can not deactivate your own account".to_string());
+    }
+
+    let portal_user = match state.db_service.get_portal_user(id).await {
+        Ok(Some(portal_user)) => portal_user,
+        Ok(None) => {
+            return commercyfy_fail!(
+                StatusCode::NOT_FOUND,
+                format!("Portal user with id '{}' was not found", id)
+            )
+        }
+        Err(err) => return commercyfy_fail!(err.to_string()),
+    };
+
+    if let Err((status, err)) = check_manages_portal_user(state, claims, &portal_user).await {
+        return commercyfy_fail!(status, err);
+    }
+
+    if let Err(err) = state
+        .db_service
+        .set_portal_user_active(portal_user.id, active)
+        .await
+    {
+        return commercyfy_fail!(err.to_string());
+    }
+
+    return commercyfy_success!(EmptyResponse {});
+}
+
+pub async fn deactivate_portal_user(
+    Extension(claims): Extension<JWTClaims>,
+    State(state): CommercyfyExtrState,
+    Path(id): Path<String>,
+) -> CommercyfyResponse<EmptyResponse> {
+    return set_portal_user_active(&state, &claims, &id, false).await;
+}
+
+pub async fn reactivate_portal_user(
+    Extension(claims): Extension<JWTClaims>,
+    State(state): CommercyfyExtrState,
+    Path(id): Path<String>,
+) -> CommercyfyResponse<EmptyResponse> {
+    return set_portal_user_active(&state, &claims, &id, true).await;
+}
+
+pub async fn reset_portal_user_password(
+    Extension(claims): Extension<JWTClaims>,
+    State(state): CommercyfyExtrState,
+    Path(id): Path<String>,
+    Json(payload): Json<PortalUserPasswordReset>,
+) -> CommercyfyResponse<EmptyResponse> {
+    if let Err(err) = payload.validate() {
+        return commercyfy_fail!(err);
+    }
+
+    let portal_user = match state.db_service.get_portal_user(&id).await {
+        Ok(Some(portal_user)) => portal_user,
+        Ok(None) => {
+            return commercyfy_fail!(
+                StatusCode::NOT_FOUND,
+                format!("Portal user with id '{}' was not found", id)
+            )
+        }
+        Err(err) => return commercyfy_fail!(err.to_string()),
+    };
+
+    if let Err((status, err)) = check_manages_portal_user(&state, &claims, &portal_user).await {
+        return commercyfy_fail!(status, err);
+    }
+
+    let password = match hash_password(&payload.password) {
+        Ok(hash) => hash,
+        Err(_err) => {
+            return commercyfy_fail!("There was an error handling your request".to_string())
+        }
+    };
+
+    // a reset signs the user out everywhere
+    if let Err(err) = state
+        .db_service
+        .update_portal_user_password(portal_user.id, &password, None)
+        .await
+    {
+        return commercyfy_fail!(err.to_string());
+    }
+
+    return commercyfy_success!(EmptyResponse {});
+}
+
+pub async fn change_portal_user_password(
+    Extension(claims): Extension<JWTClaims>,
+    State(state): CommercyfyExtrState,
+    Json(payload): Json<PortalUserPasswordChange>,
+) -> CommercyfyResponse<EmptyResponse> {
+    if let Err(err) = payload.validate() {
+        return commercyfy_fail!(err);
+    }
+
+    // api keys carry the claims of a portal user but have no password
+    let portal_user = match state.db_service.get_portal_user(&claims.sub).await {
+        Ok(Some(portal_user)) => portal_user,
+        Ok(None) => {
+            return commercyfy_fail!(StatusCode::FORBIDDEN, "Not a portal user".to_string())
+        }
+        Err(err) => return commercyfy_fail!(err.to_string()),
+    };
+
+    match verify_password(&portal_user.password, &payload.current_password) {
+        Ok(true) => {}
+        Ok(false) => return commercyfy_fail!("Current password does not match".to_string()),
+        Err(_err) => {
+            return commercyfy_fail!("There was an error handling your request".to_string())
+        }
+    };
+
+    let password = match hash_password(&payload.new_password) {
+        Ok(hash) => hash,
+        Err(_err) => {
+            return commercyfy_fail!("There was an error handling your request".to_string())
+        }
+    };
+
+    // every other session is signed out, the one making the change stays
+    if let Err(err) = state
+        .db_service
+        .update_portal_user_password(portal_user.id, &password, Some(&claims.jti))
+        .await
+    {
+        return commercyfy_fail!(err.to_string());
+    }
+
+    return commercyfy_success!(EmptyResponse {});
 }
 
 pub async fn signin_portal_user(
     State(state): CommercyfyExtrState,
+    ConnectInfo(address): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
     Json(payload): Json<PortalUserSignin>,
-) -> CommercyfyResponse<SignInToken> {
+) -> CommercyfyResponse<PortalSignIn> {
     if let Err(err) = payload.validate() {
         return commercyfy_fail!(err);
     }
 
-    let user_check = state
+    let client = get_login_client(&address, &headers);
+
+    match get_login_lockout(&state, &payload.email, &client).await {
+        Ok(None) => {}
+        Ok(Some(lockout_end)) => {
+            let _ = state
+                .db_service
+                .create_login_attempt(
+                    &payload.email,
+                    None,
+                    &client,
+                    Some(LoginFailureReason::LOCKED),
+                )
+                .await;
+
+            return commercyfy_fail!(
+                StatusCode::TOO_MANY_REQUESTS,
+                format!(
+                    "Too many failed sign-in attempts, try again in {} seconds",
+                    (lockout_end - chrono::Utc::now()).num_seconds().max(1)
+                )
+            );
+        }
+        Err(err) => return commercyfy_fail!(err),
+    };
+
+    let portal_user = match state
         .db_service
         .get_portal_user_by_email(&payload.email)
-        .await;
-    if let Err(err) = user_check {
+        .await
+    {
+        Ok(portal_user) => portal_user,
+        Err(err) => return commercyfy_fail!(err.to_string()),
+    };
+
+    let failure_reason = match &portal_user {
+        None => {
+            verify_dummy_password(&payload.password);
+            Some(LoginFailureReason::UNKNOWN)
+        }
+        Some(portal_user) => match verify_password(&portal_user.password, &payload.password) {
+            Ok(true) if !portal_user.active => Some(LoginFailureReason::DEACTIVATED),
+            Ok(true) => None,
+            Ok(false) => Some(LoginFailureReason::PASSWORD),
+            Err(_err) => {
+                return commercyfy_fail!("There was an error handling your request".to_string())
+            }
+        },
+    };
+
+    // without the audit entry the attempt would not count towards a lockout,
+    // so the sign-in does not go through when it can not be written. With mfa
+    // the attempt is only recorded once the challenge is completed.
+    let awaits_mfa = failure_reason.is_none()
+        && portal_user
+            .as_ref()
+            .is_some_and(|portal_user| return portal_user.mfa_enabled);
+    if !awaits_mfa {
+        if let Err(err) = state
+            .db_service
+            .create_login_attempt(
+                &payload.email,
+                portal_user
+                    .as_ref()
+                    .map(|portal_user| return portal_user.id),
+                &client,
+                failure_reason,
+            )
+            .await
+        {
+            return commercyfy_fail!(err.to_string());
+        }
+    }
+
+    let portal_user = match (portal_user, failure_reason) {
+        (Some(portal_user), None) => portal_user,
+        (_, Some(LoginFailureReason::DEACTIVATED)) => {
+            return commercyfy_fail!(
+                StatusCode::FORBIDDEN,
+                "This account has been deactivated".to_string()
+            )
+        }
+        _ => return commercyfy_fail!("No matching credentials".to_string()),
+    };
+
+    if portal_user.mfa_enabled {
+        return match create_mfa_challenge(&state, &portal_user).await {
+            Ok(challenge) => commercyfy_success!(PortalSignIn::Challenge(challenge)),
+            Err(err) => commercyfy_fail!(err),
+        };
+    }
+
+    return match create_portal_sign_in(&state, &portal_user).await {
+        Ok(token) => commercyfy_success!(PortalSignIn::Token(token)),
+        Err(err) => commercyfy_fail!(err),
+    };
+}
+
+async fn create_portal_sign_in(
+    state: &CommercyfyState,
+    portal_user: &PortalUser,
+) -> Result<SignInToken, String> {
+    let secret = generate_token(32);
+    let refresh_token_hash = match hash_password(&secret) {
+        Ok(hash) => hash,
+        Err(_err) => return Err("There was an error logging in.".to_string()),
+    };
+
+    let session = match state
+        .db_service
+        .create_portal_user_session(
+            portal_user.id,
+            &refresh_token_hash,
+            chrono::Utc::now() + REFRESH_TOKEN_LIFETIME,
+        )
+        .await
+    {
+        Ok(session) => session,
+        Err(err) => return Err(err.to_string()),
+    };
+
+    let jwt = encode_portal_token(state, portal_user, &session)?;
+
+    return Ok(SignInToken {
+        jwt,
+        refresh_token: Some(format!("{}.{}", session.id, secret)),
+    });
+}
+
+async fn create_mfa_challenge(
+    state: &CommercyfyState,
+    portal_user: &PortalUser,
+) -> Result<MfaChallengeToken, String> {
+    let secret = generate_token(32);
+    let token_hash = match hash_password(&secret) {
+        Ok(hash) => hash,
+        Err(_err) => return Err("There was an error logging in.".to_string()),
+    };
+
+    let challenge = match state
+        .db_service
+        .create_mfa_challenge(
+            portal_user.id,
+            &token_hash,
+            chrono::Utc::now() + MFA_CHALLENGE_LIFETIME,
+        )
+        .await
+    {
+        Ok(challenge) => challenge,
+        Err(err) => return Err(err.to_string()),
+    };
+
+    return Ok(MfaChallengeToken {
+        mfa_challenge: format!("{}.{}", challenge.id, secret),
+        expires_at: challenge.expires_at,
+    });
+}
+
+fn encode_portal_token(
+    state: &CommercyfyState,
+    portal_user: &PortalUser,
+    session: &PortalUserSession,
+) -> Result<String, String> {
+    let exp = get_token_expiration(Duration::from_secs(60 * 60 * 3))?;
+
+    let claims = JWTClaims {
+        sub: portal_user.id.to_string(),
+        email: portal_user.email.clone(),
+        exp,
+        aud: PORTAL_JWT_AUDIENCE.to_string(),
+        roles: portal_user.roles.clone(),
+        jti: session.id.to_string(),
+        session_version: portal_user.session_version,
+        permissions: vec![],
+        api_key_id: None,
+    };
+
+    return state.signing_keys.encode(&claims);
+}
+
+pub async fn refresh_portal_user_token(
+    State(state): CommercyfyExtrState,
+    Json(payload): Json<PortalUserRefresh>,
+) -> CommercyfyResponse<SignInToken> {
+    if let Err(err) = payload.validate() {
+        return commercyfy_fail!(err);
+    }
+
+    let (session_id, secret) = match split_credential(&payload.refresh_token) {
+        Some(credential) => credential,
+        None => {
+            return commercyfy_fail!(
+                StatusCode::UNAUTHORIZED,
+                "Invalid refresh token".to_string()
+            )
+        }
+    };
+
+    let session = match state.db_service.get_portal_user_session(session_id).await {
+        Ok(Some(session)) => session,
+        Ok(None) => {
+            return commercyfy_fail!(
+                StatusCode::UNAUTHORIZED,
+                "Invalid refresh token".to_string()
+            )
+        }
+        Err(err) => return commercyfy_fail!(err.to_string()),
+    };
+
+    let is_usable = session.revoked_at.is_none() && session.expires_at > chrono::Utc::now();
+    if !is_usable || !verify_password(&session.refresh_token, secret).unwrap_or(false) {
+        return commercyfy_fail!(
+            StatusCode::UNAUTHORIZED,
+            "Invalid refresh token".to_string()
+        );
+    }
+
+    let portal_user = match state
+        .db_service
+        .get_portal_user(&session.portal_user_id.to_string())
+        .await
+    {
+        Ok(Some(portal_user)) if portal_user.active => portal_user,
+        Ok(_) => {
+            return commercyfy_fail!(
+                StatusCode::UNAUTHORIZED,
+                "Invalid refresh token".to_string()
+            )
+        }
+        Err(err) => return commercyfy_fail!(err.to_string()),
+    };
+
+    // every refresh token is single use, the session keeps its id so the access
+    // tokens issued from it stay revocable together
+    let secret = generate_token(32);
+    let refresh_token_hash = match hash_password(&secret) {
+        Ok(hash) => hash,
+        Err(_err) => {
+            return commercyfy_fail!("There was an error handling your request".to_string())
+        }
+    };
+
+    let rotated_session = match state
+        .db_service
+        .rotate_portal_user_session(
+            session.id,
+            &session.refresh_token,
+            &refresh_token_hash,
+            chrono::Utc::now() + REFRESH_TOKEN_LIFETIME,
+        )
+        .await
+    {
+        Ok(rotated_session) => rotated_session,
+        Err(err) => return commercyfy_fail!(err.to_string()),
+    };
+
+    // another request rotated the same refresh token first, it was used twice so
+    // the whole session is no longer trusted
+    let session = if let Some(session) = rotated_session {
+        session
+    } else {
+        if let Err(err) = state
+            .db_service
+            .revoke_portal_user_session(&session.id.to_string())
+            .await
+        {
+            return commercyfy_fail!(err.to_string());
+        }
+
+        return commercyfy_fail!(
+            StatusCode::UNAUTHORIZED,
+            "Invalid refresh token".to_string()
+        );
+    };
+
+    return match encode_portal_token(&state, &portal_user, &session) {
+        Ok(jwt) => commercyfy_success!(SignInToken {
+            jwt,
+            refresh_token: Some(format!("{}.{}", session.id, secret)),
+        }),
+        Err(err) => commercyfy_fail!(err),
+    };
+}
+
+pub async fn signout_portal_user(
+    Extension(claims): Extension<JWTClaims>,
+    State(state): CommercyfyExtrState,
+) -> CommercyfyResponse<EmptyResponse> {
+    if let Err(err) = state
+        .db_service
+        .revoke_portal_user_session(&claims.jti)
+        .await
+    {
+        return commercyfy_fail!(err.to_string());
+    }
+
+    return commercyfy_success!(EmptyResponse {});
+}
+
+pub async fn signout_portal_user_everywhere(
+    Extension(claims): Extension<JWTClaims>,
+    State(state): CommercyfyExtrState,
+) -> CommercyfyResponse<EmptyResponse> {
+    if let Err(err) = state
+        .db_service
+        .revoke_portal_user_sessions(&claims.sub)
+        .await
+    {
         return commercyfy_fail!(err.to_string());
     }
 
-    let user = user_check.unwrap();
-    if let None = user {
-        return commercyfy_fail!("No matching credentials".to_string());
+    return commercyfy_success!(EmptyResponse {});
+}
+
+pub async fn revoke_portal_user_sessions(
+    Extension(claims): Extension<JWTClaims>,
+    State(state): CommercyfyExtrState,
+    Path(id): Path<String>,
+) -> CommercyfyResponse<EmptyResponse> {
+    let portal_user = match state.db_service.get_portal_user(&id).await {
+        Ok(Some(portal_user)) => portal_user,
+        Ok(None) => {
+            return commercyfy_fail!(
+                StatusCode::NOT_FOUND,
+                format!("Portal user with id '{}' was not found", id)
+            )
+        }
+        Err(err) => return commercyfy_fail!(err.to_string()),
+    };
+
+    if let Err((status, err)) = check_manages_portal_user(&state, &claims, &portal_user).await {
+        return commercyfy_fail!(status, err);
     }
 
-    let portal_user = user.unwrap();
-    let parse_hash = PasswordHash::new(&portal_user.password);
-    if let Err(_err) = parse_hash {
-        return commercyfy_fail!("There was an error handling your request".to_string());
+    if let Err(err) = state.db_service.revoke_portal_user_sessions(&id).await {
+        return commercyfy_fail!(err.to_string());
     }
 
-    let parsed_hash = parse_hash.unwrap();
+    return commercyfy_success!(EmptyResponse {});
+}
 
-    let is_password_valid =
-        argon2::Argon2::default().verify_password(payload.password.as_bytes(), &parsed_hash);
-    if let Err(_err) = is_password_valid {
-        return commercyfy_fail!("No matching credentials".to_string());
+pub async fn forgot_portal_user_password(
+    State(state): CommercyfyExtrState,
+    Json(payload): Json<PortalUserPasswordForgot>,
+) -> CommercyfyResponse<EmptyResponse> {
+    if let Err(err) = payload.validate() {
+        return commercyfy_fail!(err);
     }
 
-    let current_time = SystemTime::now();
-    let current_time_plus_3h = current_time.checked_add(Duration::from_secs(60 * 60 * 3));
-    if let None = current_time_plus_3h {
-        return commercyfy_fail!("Internal server error".to_string());
+    // the response is the same whether the email is known or not, so the
+    // endpoint can not be used to find out which accounts exist
+    let portal_user = match state
+        .db_service
+        .get_portal_user_by_email(&payload.email)
+        .await
+    {
+        Ok(Some(portal_user)) if portal_user.active => portal_user,
+        Ok(_) => return commercyfy_success!(EmptyResponse {}),
+        Err(err) => return commercyfy_fail!(err.to_string()),
+    };
+
+    let secret = generate_token(32);
+    let token_hash = match hash_password(&secret) {
+        Ok(hash) => hash,
+        Err(_err) => {
+            return commercyfy_fail!("There was an error handling your request".to_string())
+        }
+    };
+
+    let reset_token = match state
+        .db_service
+        .create_password_reset_token(
+            portal_user.id,
+            &token_hash,
+            chrono::Utc::now() + PASSWORD_RESET_TOKEN_LIFETIME,
+        )
+        .await
+    {
+        Ok(reset_token) => reset_token,
+        Err(err) => return commercyfy_fail!(err.to_string()),
+    };
+
+    let mail = Mail {
+        to: portal_user.email,
+        subject: "Reset your password".to_string(),
+        body: format!(
+            "Hello {},\n\nUse the following token to reset your password, it expires at {}:\n\n{}.{}\n\nIf you did not ask for a password reset you can ignore this email.",
+            portal_user.first_name,
+            reset_token.expires_at.format("%Y-%m-%d %H:%M:%S UTC"),
+            reset_token.id,
+            secret
+        ),
+    };
+
+    if let Err(err) = state.mailer.send(mail).await {
+        return commercyfy_fail!(err);
     }
 
-    let exp = current_time_plus_3h
-        .unwrap()
-        .duration_since(time::UNIX_EPOCH);
-    if let Err(_err) = exp {
-        return commercyfy_fail!("Internal server error".to_string());
+    return commercyfy_success!(EmptyResponse {});
+}
+
+pub async fn recover_portal_user_password(
+    State(state): CommercyfyExtrState,
+    Json(payload): Json<PortalUserPasswordRecover>,
+) -> CommercyfyResponse<EmptyResponse> {
+    if let Err(err) = payload.validate() {
+        return commercyfy_fail!(err);
     }
 
-    let claims = JWTClaims {
-        email: payload.email,
-        exp: exp.unwrap().as_secs(),
-        roles: portal_user.roles,
+    let (id, secret) = match split_credential(&payload.token) {
+        Some(credential) => credential,
+        None => return commercyfy_fail!("Invalid or expired reset token".to_string()),
+    };
+
+    let reset_token = match state.db_service.get_password_reset_token(id).await {
+        Ok(Some(reset_token)) => reset_token,
+        Ok(None) => return commercyfy_fail!("Invalid or expired reset token".to_string()),
+        Err(err) => return commercyfy_fail!(err.to_string()),
     };
-    let jwt_secret = std::env::var("JWT_TOKEN_SECRET");
-    if let Err(_err) = &jwt_secret {
-        return commercyfy_fail!("There was an error logging in.".to_string());
+
+    let is_usable = reset_token.used_at.is_none() && reset_token.expires_at > chrono::Utc::now();
+    if !is_usable || !verify_password(&reset_token.token, secret).unwrap_or(false) {
+        return commercyfy_fail!("Invalid or expired reset token".to_string());
     }
 
-    let token = jsonwebtoken::encode(
-        &Header::default(),
-        &claims,
-        &EncodingKey::from_secret(jwt_secret.unwrap().as_bytes()),
-    );
-    if let Err(err) = token {
+    let password = match hash_password(&payload.password) {
+        Ok(hash) => hash,
+        Err(_err) => {
+            return commercyfy_fail!("There was an error handling your request".to_string())
+        }
+    };
+
+    return match state
+        .db_service
+        .use_password_reset_token(reset_token.id, &password)
+        .await
+    {
+        Ok(true) => commercyfy_success!(EmptyResponse {}),
+        Ok(false) => commercyfy_fail!("Invalid or expired reset token".to_string()),
+        Err(err) => commercyfy_fail!(err.to_string()),
+    };
+}
+
+pub async fn get_login_attempts(
+    State(state): CommercyfyExtrState,
+    Query(query): Query<LoginAttemptQuery>,
+) -> CommercyfyResponse<Vec<LoginAttempt>> {
+    if let Err(err) = query.validate() {
+        return commercyfy_fail!(err);
+    }
+
+    return match state.db_service.get_login_attempts(&query).await {
+        Ok(login_attempts) => commercyfy_success!(login_attempts),
+        Err(err) => commercyfy_fail!(err.to_string()),
+    };
+}
+
+pub async fn signin_portal_user_mfa(
+    State(state): CommercyfyExtrState,
+    ConnectInfo(address): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    Json(payload): Json<PortalUserMfaSignin>,
+) -> CommercyfyResponse<SignInToken> {
+    if let Err(err) = payload.validate() {
+        return commercyfy_fail!(err);
+    }
+
+    let (challenge_id, secret) = match split_credential(&payload.mfa_challenge) {
+        Some(credential) => credential,
+        None => {
+            return commercyfy_fail!(
+                StatusCode::UNAUTHORIZED,
+                "Invalid or expired mfa challenge".to_string()
+            )
+        }
+    };
+
+    let challenge = match state.db_service.get_mfa_challenge(challenge_id).await {
+        Ok(Some(challenge)) => challenge,
+        Ok(None) => {
+            return commercyfy_fail!(
+                StatusCode::UNAUTHORIZED,
+                "Invalid or expired mfa challenge".to_string()
+            )
+        }
+        Err(err) => return commercyfy_fail!(err.to_string()),
+    };
+
+    let is_usable = challenge.used_at.is_none()
+        && challenge.expires_at > chrono::Utc::now()
+        && challenge.attempts < MFA_CHALLENGE_ATTEMPTS;
+    if !is_usable || !verify_password(&challenge.token, secret).unwrap_or(false) {
+        return commercyfy_fail!(
+            StatusCode::UNAUTHORIZED,
+            "Invalid or expired mfa challenge".to_string()
+        );
+    }
+
+    let portal_user = match state
+        .db_service
+        .get_portal_user(&challenge.portal_user_id.to_string())
+        .await
+    {
+        Ok(Some(portal_user)) if portal_user.active && portal_user.mfa_enabled => portal_user,
+        Ok(_) => {
+            return commercyfy_fail!(
+                StatusCode::UNAUTHORIZED,
+                "Invalid or expired mfa challenge".to_string()
+            )
+        }
+        Err(err) => return commercyfy_fail!(err.to_string()),
+    };
+
+    let client = get_login_client(&address, &headers);
+
+    match get_login_lockout(&state, &portal_user.email, &client).await {
+        Ok(None) => {}
+        Ok(Some(lockout_end)) => {
+            return commercyfy_fail!(
+                StatusCode::TOO_MANY_REQUESTS,
+                format!(
+                    "Too many failed sign-in attempts, try again in {} seconds",
+                    (lockout_end - chrono::Utc::now()).num_seconds().max(1)
+                )
+            );
+        }
+        Err(err) => return commercyfy_fail!(err),
+    };
+
+    let verified = match (&payload.code, &payload.recovery_code) {
+        (Some(code), _) => verify_portal_user_totp(&state, &portal_user, code).await,
+        (_, Some(recovery_code)) => {
+            use_portal_user_recovery_code(&state, &portal_user, recovery_code).await
+        }
+        _ => Ok(false),
+    };
+
+    let verified = match verified {
+        Ok(verified) => verified,
+        Err(err) => return commercyfy_fail!(err),
+    };
+
+    if !verified {
+        let _ = state.db_service.fail_mfa_challenge(challenge.id).await;
+        let _ = state
+            .db_service
+            .create_login_attempt(
+                &portal_user.email,
+                Some(portal_user.id),
+                &client,
+                Some(LoginFailureReason::MFA),
+            )
+            .await;
+
+        return commercyfy_fail!(
+            StatusCode::UNAUTHORIZED,
+            "Invalid verification code".to_string()
+        );
+    }
+
+    match state.db_service.use_mfa_challenge(challenge.id).await {
+        Ok(true) => {}
+        Ok(false) => {
+            return commercyfy_fail!(
+                StatusCode::UNAUTHORIZED,
+                "Invalid or expired mfa challenge".to_string()
+            )
+        }
+        Err(err) => return commercyfy_fail!(err.to_string()),
+    };
+
+    if let Err(err) = state
+        .db_service
+        .create_login_attempt(&portal_user.email, Some(portal_user.id), &client, None)
+        .await
+    {
         return commercyfy_fail!(err.to_string());
     }
 
-    return commercyfy_success!(SignInToken {
-        jwt: token.unwrap()
+    return match create_portal_sign_in(&state, &portal_user).await {
+        Ok(token) => commercyfy_success!(token),
+        Err(err) => commercyfy_fail!(err),
+    };
+}
+
+async fn verify_portal_user_totp(
+    state: &CommercyfyState,
+    portal_user: &PortalUser,
+    code: &str,
+) -> Result<bool, String> {
+    let mfa_secret = match &portal_user.mfa_secret {
+        Some(mfa_secret) => mfa_secret,
+        None => return Ok(false),
+    };
+
+    let step = match verify_totp_code(
+        mfa_secret,
+        &portal_user.email,
+        code,
+        portal_user.mfa_last_step,
+    )? {
+        Some(step) => step,
+        None => return Ok(false),
+    };
+
+    // the step is claimed in the database as well, so two requests racing with
+    // the same code can not both get through
+    return state
+        .db_service
+        .use_portal_user_mfa_step(portal_user.id, step)
+        .await
+        .map_err(|err| return err.to_string());
+}
+
+async fn use_portal_user_recovery_code(
+    state: &CommercyfyState,
+    portal_user: &PortalUser,
+    recovery_code: &str,
+) -> Result<bool, String> {
+    let recovery_codes = match state
+        .db_service
+        .get_portal_user_recovery_codes(portal_user.id)
+        .await
+    {
+        Ok(recovery_codes) => recovery_codes,
+        Err(err) => return Err(err.to_string()),
+    };
+
+    for code in recovery_codes {
+        if verify_password(&code.code, recovery_code).unwrap_or(false) {
+            return state
+                .db_service
+                .use_portal_user_recovery_code(code.id)
+                .await
+                .map_err(|err| return err.to_string());
+        }
+    }
+
+    return Ok(false);
+}
+
+fn hash_recovery_codes(recovery_codes: &[String]) -> Result<Vec<String>, String> {
+    return recovery_codes
+        .iter()
+        .map(|code| return hash_password(code))
+        .collect::<Result<Vec<String>, _>>()
+        .map_err(|_err| return "There was an error handling your request".to_string());
+}
+
+// api keys carry the claims of a portal user but are not one
+async fn get_signed_in_portal_user(
+    state: &CommercyfyState,
+    claims: &JWTClaims,
+) -> Result<PortalUser, (StatusCode, String)> {
+    return match state.db_service.get_portal_user(&claims.sub).await {
+        Ok(Some(portal_user)) => Ok(portal_user),
+        Ok(None) => Err((StatusCode::FORBIDDEN, "Not a portal user".to_string())),
+        Err(err) => Err((StatusCode::BAD_REQUEST, err.to_string())),
+    };
+}
+
+pub async fn enroll_portal_user_mfa(
+    Extension(claims): Extension<JWTClaims>,
+    State(state): CommercyfyExtrState,
+) -> CommercyfyResponse<MfaEnrollment> {
+    let portal_user = match get_signed_in_portal_user(&state, &claims).await {
+        Ok(portal_user) => portal_user,
+        Err((status, err)) => return commercyfy_fail!(status, err),
+    };
+
+    if portal_user.mfa_enabled {
+        return commercyfy_fail!("Multi-factor authentication is already enabled".to_string());
+    }
+
+    let secret = generate_mfa_secret();
+    let provisioning_uri = match get_provisioning_uri(&secret, &portal_user.email) {
+        Ok(provisioning_uri) => provisioning_uri,
+        Err(err) => return commercyfy_fail!(err),
+    };
+
+    if let Err(err) = state
+        .db_service
+        .start_portal_user_mfa_enrollment(portal_user.id, &secret)
+        .await
+    {
+        return commercyfy_fail!(err.to_string());
+    }
+
+    return commercyfy_success!(MfaEnrollment {
+        secret,
+        provisioning_uri,
     });
 }
+
+pub async fn verify_portal_user_mfa(
+    Extension(claims): Extension<JWTClaims>,
+    State(state): CommercyfyExtrState,
+    Json(payload): Json<PortalUserMfaCode>,
+) -> CommercyfyResponse<RecoveryCodes> {
+    if let Err(err) = payload.validate() {
+        return commercyfy_fail!(err);
+    }
+
+    let portal_user = match get_signed_in_portal_user(&state, &claims).await {
+        Ok(portal_user) => portal_user,
+        Err((status, err)) => return commercyfy_fail!(status, err),
+    };
+
+    let mfa_secret = match &portal_user.mfa_secret {
+        Some(mfa_secret) if !portal_user.mfa_enabled => mfa_secret,
+        _ => return commercyfy_fail!("There is no pending mfa enrollment".to_string()),
+    };
+
+    let step = match verify_totp_code(mfa_secret, &portal_user.email, &payload.code, None) {
+        Ok(Some(step)) => step,
+        Ok(None) => return commercyfy_fail!("Invalid verification code".to_string()),
+        Err(err) => return commercyfy_fail!(err),
+    };
+
+    let recovery_codes = generate_recovery_codes();
+    let recovery_code_hashes = match hash_recovery_codes(&recovery_codes) {
+        Ok(hashes) => hashes,
+        Err(err) => return commercyfy_fail!(err),
+    };
+
+    if let Err(err) = state
+        .db_service
+        .enable_portal_user_mfa(portal_user.id, step, &recovery_code_hashes)
+        .await
+    {
+        return commercyfy_fail!(err.to_string());
+    }
+
+    return commercyfy_success!(RecoveryCodes { recovery_codes });
+}
+
+pub async fn regenerate_portal_user_recovery_codes(
+    Extension(claims): Extension<JWTClaims>,
+    State(state): CommercyfyExtrState,
+    Json(payload): Json<PortalUserMfaCode>,
+) -> CommercyfyResponse<RecoveryCodes> {
+    if let Err(err) = payload.validate() {
+        return commercyfy_fail!(err);
+    }
+
+    let portal_user = match get_signed_in_portal_user(&state, &claims).await {
+        Ok(portal_user) => portal_user,
+        Err((status, err)) => return commercyfy_fail!(status, err),
+    };
+
+    if !portal_user.mfa_enabled {
+        return commercyfy_fail!("Multi-factor authentication is not enabled".to_string());
+    }
+
+    match verify_portal_user_totp(&state, &portal_user, &payload.code).await {
+        Ok(true) => {}
+        Ok(false) => return commercyfy_fail!("Invalid verification code".to_string()),
+        Err(err) => return commercyfy_fail!(err),
+    };
+
+    let recovery_codes = generate_recovery_codes();
+    let recovery_code_hashes = match hash_recovery_codes(&recovery_codes) {
+        Ok(hashes) => hashes,
+        Err(err) => return commercyfy_fail!(err),
+    };
+
+    if let Err(err) = state
+        .db_service
+        .replace_portal_user_recovery_codes(portal_user.id, &recovery_code_hashes)
+        .await
+    {
+        return commercyfy_fail!(err.to_string());
+    }
+
+    return commercyfy_success!(RecoveryCodes { recovery_codes });
+}
+
+pub async fn disable_portal_user_mfa(
+    Extension(claims): Extension<JWTClaims>,
+    State(state): CommercyfyExtrState,
+    Json(payload): Json<PortalUserMfaDisable>,
+) -> CommercyfyResponse<EmptyResponse> {
+    if let Err(err) = payload.validate() {
+        return commercyfy_fail!(err);
+    }
+
+    let portal_user = match get_signed_in_portal_user(&state, &claims).await {
+        Ok(portal_user) => portal_user,
+        Err((status, err)) => return commercyfy_fail!(status, err),
+    };
+
+    match verify_password(&portal_user.password, &payload.password) {
+        Ok(true) => {}
+        Ok(false) => return commercyfy_fail!("Password does not match".to_string()),
+        Err(_err) => {
+            return commercyfy_fail!("There was an error handling your request".to_string())
+        }
+    };
+
+    if let Err(err) = state
+        .db_service
+        .disable_portal_user_mfa(portal_user.id)
+        .await
+    {
+        return commercyfy_fail!(err.to_string());
+    }
+
+    return commercyfy_success!(EmptyResponse {});
+}
+
+// for users that lost both their authenticator and their recovery codes
+pub async fn reset_portal_user_mfa(
+    Extension(claims): Extension<JWTClaims>,
+    State(state): CommercyfyExtrState,
+    Path(id): Path<String>,
+) -> CommercyfyResponse<EmptyResponse> {
+    let portal_user = match state.db_service.get_portal_user(&id).await {
+        Ok(Some(portal_user)) => portal_user,
+        Ok(None) => {
+            return commercyfy_fail!(
+                StatusCode::NOT_FOUND,
+                format!("Portal user with id '{}' was not found", id)
+            )
+        }
+        Err(err) => return commercyfy_fail!(err.to_string()),
+    };
+
+    if let Err((status, err)) = check_manages_portal_user(&state, &claims, &portal_user).await {
+        return commercyfy_fail!(status, err);
+    }
+
+    if let Err(err) = state
+        .db_service
+        .disable_portal_user_mfa(portal_user.id)
+        .await
+    {
+        return commercyfy_fail!(err.to_string());
+    }
+
+    return commercyfy_success!(EmptyResponse {});
+}
+
+pub async fn put_portal_user_custom_fields(
+    State(state): CommercyfyExtrState,
+    Path(id): Path<String>,
+    Json(payload): Json<HashMap<String, serde_json::Value>>,
+) -> CommercyfyResponse<HashMap<String, UnstructuredEntryType>> {
+    return set_object_custom_fields(&state, FieldExtensionObject::PORTALUSER, &id, payload).await;
+}
+
+pub async fn delete_portal_user_custom_field(
+    State(state): CommercyfyExtrState,
+    Path((id, field)): Path<(String, String)>,
+) -> CommercyfyResponse<HashMap<String, UnstructuredEntryType>> {
+    return unset_object_custom_field(&state, FieldExtensionObject::PORTALUSER, &id, &field).await;
+}
@@ -1,44 +1,47 @@
 use std::collections::HashMap;
 
-use super::{CommercyfyResponse, CreatedEntryResponse};
+use super::{
+    base_extensions::{set_object_custom_fields, unset_object_custom_field},
+    CommercyfyResponse, CreatedEntryResponse,
+};
 use crate::{
     models::{
         base_extensions::FieldExtensionObject,
-        portal_user::{JWTClaims, PortalUsersRoles},
         pricebook::{Pricebook, PricebookRecord},
     },
     schemas::pricebook::{CreatePricebook, CreatePricebookRecord},
-    services::{
-        db::DbService,
-        role_validation::RoleService,
-        unstructureddb::{entry::UnstructuredEntryType, UnstructuredDb},
+    services::unstructureddb::entry::UnstructuredEntryType,
+    utils::custom_fields::{
+        create_custom_fields, find_custom_field_matches, get_object_custom_fields,
+        validate_custom_fields,
     },
-    utils::custom_fields::create_custom_fields,
     CommercyfyExtrState,
 };
 use axum::{
-    extract::{Path, State},
+    extract::{Path, Query, State},
     http::StatusCode,
-    Extension, Json,
+    Json,
 };
 
 pub async fn get_pricebooks(
-    Extension(claims): Extension<JWTClaims>,
+    Query(params): Query<HashMap<String, String>>,
     State(state): CommercyfyExtrState,
 ) -> CommercyfyResponse<Vec<Pricebook>> {
-    if let Err(err) = state.role_service.validate_any(
-        &claims,
-        vec![PortalUsersRoles::ADMIN, PortalUsersRoles::READER],
-    ) {
-        return commercyfy_fail!(err);
-    }
+    let matches =
+        match find_custom_field_matches(&state, FieldExtensionObject::PRICEBOOK, &params).await {
+            Ok(matches) if matches.errors.is_empty() => matches,
+            Ok(matches) => return commercyfy_fields_fail!(matches.errors),
+            Err(err) => return commercyfy_fail!(err),
+        };
 
-    let pricebooks = state.db_service.get_pricebooks().await;
-    if let Err(error) = pricebooks {
-        return commercyfy_fail!(error.to_string());
-    }
+    let mut pricebooks = match state.db_service.get_pricebooks().await {
+        Ok(pricebooks) => pricebooks,
+        Err(error) => return commercyfy_fail!(error.to_string()),
+    };
+
+    pricebooks.retain(|x| return matches.matches(&x.id.to_string()));
 
-    return commercyfy_success!(pricebooks.unwrap());
+    return commercyfy_success!(pricebooks);
 }
 
 #[derive(serde::Serialize)]
@@ -49,17 +52,9 @@ pub struct PricebookView {
     custom_fields: HashMap<String, UnstructuredEntryType>,
 }
 pub async fn get_pricebook(
-    Extension(claims): Extension<JWTClaims>,
     State(state): CommercyfyExtrState,
     Path(id): Path<String>,
 ) -> CommercyfyResponse<PricebookView> {
-    if let Err(err) = state.role_service.validate_any(
-        &claims,
-        vec![PortalUsersRoles::ADMIN, PortalUsersRoles::READER],
-    ) {
-        return commercyfy_fail!(err);
-    }
-
     let pricebook = state.db_service.get_pricebook_by_id(&id).await;
     if let Err(err) = pricebook {
         return commercyfy_fail!(err.to_string());
@@ -144,35 +139,40 @@ pub async fn get_pricebook(
 }
 
 pub async fn create_pricebook(
-    Extension(claims): Extension<JWTClaims>,
     State(state): CommercyfyExtrState,
     Json(payload): Json<CreatePricebook>,
 ) -> CommercyfyResponse<CreatedEntryResponse> {
-    if let Err(err) = state.role_service.validate_any(
-        &claims,
-        vec![PortalUsersRoles::ADMIN, PortalUsersRoles::EDITOR],
-    ) {
-        return commercyfy_fail!(err);
-    }
-
     if let Err(err) = payload.validate() {
         return commercyfy_fail!(err);
     }
 
+    let custom_fields = match validate_custom_fields(
+        &state,
+        FieldExtensionObject::PRICEBOOK,
+        &payload.custom_fields,
+        true,
+    )
+    .await
+    {
+        Ok(fields) if fields.errors.is_empty() => fields.values,
+        Ok(fields) => return commercyfy_fields_fail!(fields.errors),
+        Err(err) => return commercyfy_fail!(err),
+    };
+
     let pricebook_creation = match state.db_service.create_pricebook(&payload).await {
         Ok(pricebook) => pricebook,
         Err(err) => return commercyfy_fail!(err.to_string()),
     };
 
-    if let Err(err) = create_custom_fields(
+    if let Err((status, err)) = create_custom_fields(
         state,
-        pricebook_creation.id.to_string(),
+        pricebook_creation.id,
         FieldExtensionObject::PRICEBOOK,
-        &payload.custom_fields,
+        custom_fields,
     )
     .await
     {
-        return commercyfy_fail!(err);
+        return commercyfy_fail!(status, err);
     }
 
     return commercyfy_success!(
@@ -184,17 +184,9 @@ pub async fn create_pricebook(
 }
 
 pub async fn create_pricebook_record(
-    Extension(claims): Extension<JWTClaims>,
     State(state): CommercyfyExtrState,
     Json(payload): Json<CreatePricebookRecord>,
 ) -> CommercyfyResponse<CreatedEntryResponse> {
-    if let Err(err) = state.role_service.validate_any(
-        &claims,
-        vec![PortalUsersRoles::ADMIN, PortalUsersRoles::EDITOR],
-    ) {
-        return commercyfy_fail!(err);
-    }
-
     if let Err(err) = payload.validate() {
         return commercyfy_fail!(err);
     }
@@ -224,6 +216,19 @@ pub async fn create_pricebook_record(
         ));
     }
 
+    let custom_fields = match validate_custom_fields(
+        &state,
+        FieldExtensionObject::PRICEBOOKRECORD,
+        &payload.custom_fields,
+        true,
+    )
+    .await
+    {
+        Ok(fields) if fields.errors.is_empty() => fields.values,
+        Ok(fields) => return commercyfy_fields_fail!(fields.errors),
+        Err(err) => return commercyfy_fail!(err),
+    };
+
     let pricebook_record = state
         .db_service
         .create_product_pricebook_record(payload)
@@ -232,26 +237,37 @@ pub async fn create_pricebook_record(
         return commercyfy_fail!(err.to_string());
     }
 
+    let pricebook_record = pricebook_record.unwrap();
+    if let Err((status, err)) = create_custom_fields(
+        state,
+        pricebook_record.id,
+        FieldExtensionObject::PRICEBOOKRECORD,
+        custom_fields,
+    )
+    .await
+    {
+        return commercyfy_fail!(status, err);
+    }
+
     return commercyfy_success!(
         StatusCode::CREATED,
         CreatedEntryResponse {
-            id: pricebook_record.unwrap().id
+            id: pricebook_record.id
         }
     );
 }
 
+#[derive(serde::Serialize)]
+pub struct PricebookRecordView {
+    #[serde(flatten)]
+    record: PricebookRecord,
+    custom_fields: HashMap<String, UnstructuredEntryType>,
+}
+
 pub async fn get_pricebook_record(
-    Extension(claims): Extension<JWTClaims>,
     State(state): CommercyfyExtrState,
     Path(path): Path<(String, String)>,
-) -> CommercyfyResponse<PricebookRecord> {
-    if let Err(err) = state.role_service.validate_any(
-        &claims,
-        vec![PortalUsersRoles::ADMIN, PortalUsersRoles::READER],
-    ) {
-        return commercyfy_fail!(err);
-    }
-
+) -> CommercyfyResponse<PricebookRecordView> {
     let (pricebook_id, product_id) = path;
 
     let pricebook_record = state
@@ -263,8 +279,22 @@ pub async fn get_pricebook_record(
         return commercyfy_fail!(err.to_string());
     }
 
-    if let Some(pricebook_record) = pricebook_record.unwrap() {
-        return commercyfy_success!(pricebook_record);
+    if let Some(record) = pricebook_record.unwrap() {
+        let custom_fields = match get_object_custom_fields(
+            &state,
+            FieldExtensionObject::PRICEBOOKRECORD,
+            &record.id.to_string(),
+        )
+        .await
+        {
+            Ok(custom_fields) => custom_fields,
+            Err(err) => return commercyfy_fail!(err),
+        };
+
+        return commercyfy_success!(PricebookRecordView {
+            record,
+            custom_fields
+        });
     }
 
     return commercyfy_fail!(
@@ -272,3 +302,35 @@ pub async fn get_pricebook_record(
         format!("There is no pricebook record with the provided ids.")
     );
 }
+
+pub async fn put_pricebook_custom_fields(
+    State(state): CommercyfyExtrState,
+    Path(id): Path<String>,
+    Json(payload): Json<HashMap<String, serde_json::Value>>,
+) -> CommercyfyResponse<HashMap<String, UnstructuredEntryType>> {
+    return set_object_custom_fields(&state, FieldExtensionObject::PRICEBOOK, &id, payload).await;
+}
+
+pub async fn delete_pricebook_custom_field(
+    State(state): CommercyfyExtrState,
+    Path((id, field)): Path<(String, String)>,
+) -> CommercyfyResponse<HashMap<String, UnstructuredEntryType>> {
+    return unset_object_custom_field(&state, FieldExtensionObject::PRICEBOOK, &id, &field).await;
+}
+
+pub async fn put_pricebook_record_custom_fields(
+    State(state): CommercyfyExtrState,
+    Path(id): Path<String>,
+    Json(payload): Json<HashMap<String, serde_json::Value>>,
+) -> CommercyfyResponse<HashMap<String, UnstructuredEntryType>> {
+    return set_object_custom_fields(&state, FieldExtensionObject::PRICEBOOKRECORD, &id, payload)
+        .await;
+}
+
+pub async fn delete_pricebook_record_custom_field(
+    State(state): CommercyfyExtrState,
+    Path((id, field)): Path<(String, String)>,
+) -> CommercyfyResponse<HashMap<String, UnstructuredEntryType>> {
+    return unset_object_custom_field(&state, FieldExtensionObject::PRICEBOOKRECORD, &id, &field)
+        .await;
+}
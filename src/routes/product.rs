@@ -1,47 +1,58 @@
 use std::collections::HashMap;
 
-use super::{CommercyfyResponse, CreatedEntryResponse};
+use super::{
+    base_extensions::{set_object_custom_fields, unset_object_custom_field},
+    CommercyfyResponse, CreatedEntryResponse,
+};
 use crate::models::base_extensions::FieldExtensionObject;
 use crate::models::category::Category;
 use crate::models::inventory::ProductInventoryRecord;
-use crate::models::portal_user::{JWTClaims, PortalUsersRoles};
 use crate::models::pricebook::PricebookRecord;
 use crate::models::product::ProductImage;
 use crate::schemas::product::{CreateProduct, CreateProductImage};
 use crate::services::unstructureddb::entry::UnstructuredEntryType;
-use crate::services::{
-    db::DbService, role_validation::RoleService, unstructureddb::UnstructuredDb,
+use crate::utils::custom_fields::{
+    create_custom_fields, find_custom_field_matches, get_object_custom_fields,
+    validate_custom_fields,
 };
-use crate::utils::custom_fields::create_custom_fields;
 use crate::{models::product::Product, CommercyfyExtrState};
 use axum::extract::{Path, Query, State};
 use axum::http::StatusCode;
-use axum::{Extension, Json};
+use axum::Json;
 
 pub async fn get_products(
-    Extension(claims): Extension<JWTClaims>,
+    Query(params): Query<HashMap<String, String>>,
     State(state): CommercyfyExtrState,
 ) -> CommercyfyResponse<Vec<Product>> {
-    if let Err(err) = state.role_service.validate_any(
-        &claims,
-        vec![PortalUsersRoles::ADMIN, PortalUsersRoles::READER],
-    ) {
-        return commercyfy_fail!(err);
-    }
-
-    let products = match state.db_service.get_products().await {
+    let matches =
+        match find_custom_field_matches(&state, FieldExtensionObject::PRODUCT, &params).await {
+            Ok(matches) if matches.errors.is_empty() => matches,
+            Ok(matches) => return commercyfy_fields_fail!(matches.errors),
+            Err(err) => return commercyfy_fail!(err),
+        };
+
+    let mut products = match state.db_service.get_products().await {
         Ok(products) => products,
         Err(error) => return commercyfy_fail!(error.to_string()),
     };
 
+    products.retain(|x| return matches.matches(&x.id.to_string()));
+
     return commercyfy_success!(products);
 }
 
+#[derive(serde::Serialize)]
+pub struct ProductImageView {
+    #[serde(flatten)]
+    image: ProductImage,
+    custom_fields: HashMap<String, UnstructuredEntryType>,
+}
+
 #[derive(serde::Serialize)]
 pub struct ProductView {
     #[serde(flatten)]
     product: Product,
-    images: Vec<ProductImage>,
+    images: Vec<ProductImageView>,
     custom_fields: HashMap<String, UnstructuredEntryType>,
 
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -56,17 +67,9 @@ pub struct ProductView {
 
 pub async fn get_product(
     Query(params): Query<HashMap<String, String>>,
-    Extension(claims): Extension<JWTClaims>,
     State(state): CommercyfyExtrState,
     Path(id): Path<String>,
 ) -> CommercyfyResponse<ProductView> {
-    if let Err(err) = state.role_service.validate_any(
-        &claims,
-        vec![PortalUsersRoles::ADMIN, PortalUsersRoles::READER],
-    ) {
-        return commercyfy_fail!(err);
-    }
-
     let product_check = state.db_service.get_product(&id).await;
 
     if let Err(error) = product_check {
@@ -92,7 +95,23 @@ pub async fn get_product(
 
     let images_check = state.db_service.get_product_images(&id).await;
     if let Ok(images) = images_check {
-        product_view.images = images;
+        for image in images {
+            let custom_fields = match get_object_custom_fields(
+                &state,
+                FieldExtensionObject::PRODUCTIMAGE,
+                &image.id.to_string(),
+            )
+            .await
+            {
+                Ok(custom_fields) => custom_fields,
+                Err(err) => return commercyfy_fail!(err),
+            };
+
+            product_view.images.push(ProductImageView {
+                image,
+                custom_fields,
+            });
+        }
     }
 
     if let Ok(custom_fields) = state
@@ -155,65 +174,69 @@ pub async fn get_product(
 }
 
 pub async fn create_product(
-    Extension(claims): Extension<JWTClaims>,
     State(state): CommercyfyExtrState,
     Json(payload): Json<CreateProduct>,
 ) -> CommercyfyResponse<CreatedEntryResponse> {
-    if let Err(err) = state.role_service.validate_any(
-        &claims,
-        vec![PortalUsersRoles::ADMIN, PortalUsersRoles::EDITOR],
-    ) {
-        return commercyfy_fail!(err);
-    }
-
     if let Err(error) = payload.validate() {
         return commercyfy_fail!(error.to_string());
     }
 
-    let category_assignments = payload.category_assignments.clone();
-    let product_create = state.db_service.create_product(&payload).await;
-    if let Err(err) = product_create {
-        return commercyfy_fail!(err.to_string());
-    }
-
-    let product = product_create.unwrap();
-    if let Some(categories) = category_assignments {
-        if let Err(error) = state
+    if let Some(tax_class_id) = payload.tax_class_id {
+        match state
             .db_service
-            .create_product_category_assignment(product.id, categories)
+            .get_tax_class_by_id(&tax_class_id.to_string())
             .await
         {
-            return commercyfy_fail!(format!("Assignment failed because: {}, but the cretion of the product should be successfull", error.to_string()));
-        }
+            Ok(Some(_)) => {}
+            Ok(None) => {
+                return commercyfy_fail!(format!(
+                    "Tax class with id '{}' does not exist",
+                    tax_class_id
+                ))
+            }
+            Err(err) => return commercyfy_fail!(err.to_string()),
+        };
     }
 
-    if let Err(err) = create_custom_fields(
-        state,
-        product.id.to_string(),
+    let custom_fields = match validate_custom_fields(
+        &state,
         FieldExtensionObject::PRODUCT,
         &payload.custom_fields,
+        true,
+    )
+    .await
+    {
+        Ok(fields) if fields.errors.is_empty() => fields.values,
+        Ok(fields) => return commercyfy_fields_fail!(fields.errors),
+        Err(err) => return commercyfy_fail!(err),
+    };
+
+    // the category assignments are part of the same transaction, an unknown
+    // category leaves no product behind
+    let product = match state.db_service.create_product(&payload).await {
+        Ok(product) => product,
+        Err(err) => return commercyfy_fail!(err.to_string()),
+    };
+
+    if let Err((status, err)) = create_custom_fields(
+        state,
+        product.id,
+        FieldExtensionObject::PRODUCT,
+        custom_fields,
     )
     .await
     {
-        return commercyfy_fail!(err);
+        return commercyfy_fail!(status, err);
     }
 
     return commercyfy_success!(StatusCode::CREATED, CreatedEntryResponse { id: product.id });
 }
 
 pub async fn create_product_image(
-    Extension(claims): Extension<JWTClaims>,
     Path(id): Path<String>,
     State(state): CommercyfyExtrState,
     Json(payload): Json<CreateProductImage>,
 ) -> CommercyfyResponse<CreatedEntryResponse> {
-    if let Err(err) = state.role_service.validate_any(
-        &claims,
-        vec![PortalUsersRoles::ADMIN, PortalUsersRoles::EDITOR],
-    ) {
-        return commercyfy_fail!(err);
-    }
-
     if let Err(error) = payload.validate() {
         return commercyfy_fail!(error);
     }
@@ -230,12 +253,67 @@ pub async fn create_product_image(
         );
     }
 
+    let custom_fields = match validate_custom_fields(
+        &state,
+        FieldExtensionObject::PRODUCTIMAGE,
+        &payload.custom_fields,
+        true,
+    )
+    .await
+    {
+        Ok(fields) if fields.errors.is_empty() => fields.values,
+        Ok(fields) => return commercyfy_fields_fail!(fields.errors),
+        Err(err) => return commercyfy_fail!(err),
+    };
+
     let create_check = state.db_service.create_product_image(&id, payload).await;
     if let Err(error) = create_check {
         return commercyfy_fail!(error.to_string());
     }
 
-    return commercyfy_success!(CreatedEntryResponse {
-        id: create_check.unwrap().id
-    });
+    let image = create_check.unwrap();
+    if let Err((status, err)) = create_custom_fields(
+        state,
+        image.id,
+        FieldExtensionObject::PRODUCTIMAGE,
+        custom_fields,
+    )
+    .await
+    {
+        return commercyfy_fail!(status, err);
+    }
+
+    return commercyfy_success!(CreatedEntryResponse { id: image.id });
+}
+
+pub async fn put_product_custom_fields(
+    State(state): CommercyfyExtrState,
+    Path(id): Path<String>,
+    Json(payload): Json<HashMap<String, serde_json::Value>>,
+) -> CommercyfyResponse<HashMap<String, UnstructuredEntryType>> {
+    return set_object_custom_fields(&state, FieldExtensionObject::PRODUCT, &id, payload).await;
+}
+
+pub async fn delete_product_custom_field(
+    State(state): CommercyfyExtrState,
+    Path((id, field)): Path<(String, String)>,
+) -> CommercyfyResponse<HashMap<String, UnstructuredEntryType>> {
+    return unset_object_custom_field(&state, FieldExtensionObject::PRODUCT, &id, &field).await;
+}
+
+pub async fn put_product_image_custom_fields(
+    State(state): CommercyfyExtrState,
+    Path(id): Path<String>,
+    Json(payload): Json<HashMap<String, serde_json::Value>>,
+) -> CommercyfyResponse<HashMap<String, UnstructuredEntryType>> {
+    return set_object_custom_fields(&state, FieldExtensionObject::PRODUCTIMAGE, &id, payload)
+        .await;
+}
+
+pub async fn delete_product_image_custom_field(
+    State(state): CommercyfyExtrState,
+    Path((id, field)): Path<(String, String)>,
+) -> CommercyfyResponse<HashMap<String, UnstructuredEntryType>> {
+    return unset_object_custom_field(&state, FieldExtensionObject::PRODUCTIMAGE, &id, &field)
+        .await;
 }
@@ -0,0 +1,242 @@
+use super::{CommercyfyResponse, CreatedEntryResponse};
+use crate::{
+    models::{
+        customer::CustomerJWTClaims,
+        product_list::{ProductList, ProductListItem},
+    },
+    schemas::product_list::{CreateProductList, PutProductListItem, UpdateProductList},
+    utils::auth::generate_token,
+    CommercyfyExtrState, CommercyfyState,
+};
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Extension, Json,
+};
+
+#[derive(serde::Serialize)]
+pub struct ProductListView {
+    #[serde(flatten)]
+    product_list: ProductList,
+    items: Vec<ProductListItem>,
+}
+
+async fn get_product_list_view(
+    state: &CommercyfyState,
+    product_list: ProductList,
+) -> CommercyfyResponse<ProductListView> {
+    return match state
+        .db_service
+        .get_product_list_items(&product_list.id.to_string())
+        .await
+    {
+        Ok(items) => commercyfy_success!(ProductListView {
+            product_list,
+            items
+        }),
+        Err(err) => commercyfy_fail!(err.to_string()),
+    };
+}
+
+pub async fn get_product_lists(
+    Extension(claims): Extension<CustomerJWTClaims>,
+    State(state): CommercyfyExtrState,
+) -> CommercyfyResponse<Vec<ProductList>> {
+    return match state
+        .db_service
+        .get_customer_product_lists(&claims.sub)
+        .await
+    {
+        Ok(lists) => commercyfy_success!(lists),
+        Err(err) => commercyfy_fail!(err.to_string()),
+    };
+}
+
+pub async fn get_product_list(
+    Extension(claims): Extension<CustomerJWTClaims>,
+    State(state): CommercyfyExtrState,
+    Path(id): Path<String>,
+) -> CommercyfyResponse<ProductListView> {
+    let product_list = match state
+        .db_service
+        .get_customer_product_list(&claims.sub, &id)
+        .await
+    {
+        Ok(Some(list)) => list,
+        Ok(None) => {
+            return commercyfy_fail!(
+                StatusCode::NOT_FOUND,
+                format!("Product list with id '{}' was not found", id)
+            )
+        }
+        Err(err) => return commercyfy_fail!(err.to_string()),
+    };
+
+    return get_product_list_view(&state, product_list).await;
+}
+
+pub async fn get_shared_product_list(
+    State(state): CommercyfyExtrState,
+    Path(share_token): Path<String>,
+) -> CommercyfyResponse<ProductListView> {
+    let product_list = match state
+        .db_service
+        .get_product_list_by_share_token(&share_token)
+        .await
+    {
+        Ok(Some(list)) if list.is_public => list,
+        Ok(_) => {
+            return commercyfy_fail!(
+                StatusCode::NOT_FOUND,
+                "Product list was not found".to_string()
+            )
+        }
+        Err(err) => return commercyfy_fail!(err.to_string()),
+    };
+
+    return get_product_list_view(&state, product_list).await;
+}
+
+pub async fn create_product_list(
+    Extension(claims): Extension<CustomerJWTClaims>,
+    State(state): CommercyfyExtrState,
+    Json(payload): Json<CreateProductList>,
+) -> CommercyfyResponse<CreatedEntryResponse> {
+    if let Err(err) = payload.validate() {
+        return commercyfy_fail!(err);
+    }
+
+    let share_token = generate_token(24);
+    return match state
+        .db_service
+        .create_product_list(&claims.sub, &share_token, &payload)
+        .await
+    {
+        Ok(list) => commercyfy_success!(StatusCode::CREATED, CreatedEntryResponse { id: list.id }),
+        Err(err) => commercyfy_fail!(err.to_string()),
+    };
+}
+
+pub async fn update_product_list(
+    Extension(claims): Extension<CustomerJWTClaims>,
+    State(state): CommercyfyExtrState,
+    Path(id): Path<String>,
+    Json(payload): Json<UpdateProductList>,
+) -> CommercyfyResponse<ProductList> {
+    if let Err(err) = payload.validate() {
+        return commercyfy_fail!(err);
+    }
+
+    return match state
+        .db_service
+        .update_product_list(&claims.sub, &id, &payload)
+        .await
+    {
+        Ok(Some(list)) => commercyfy_success!(list),
+        Ok(None) => commercyfy_fail!(
+            StatusCode::NOT_FOUND,
+            format!("Product list with id '{}' was not found", id)
+        ),
+        Err(err) => commercyfy_fail!(err.to_string()),
+    };
+}
+
+pub async fn delete_product_list(
+    Extension(claims): Extension<CustomerJWTClaims>,
+    State(state): CommercyfyExtrState,
+    Path(id): Path<String>,
+) -> CommercyfyResponse<CreatedEntryResponse> {
+    let list_id = match uuid::Uuid::parse_str(&id) {
+        Ok(list_id) => list_id,
+        Err(_err) => return commercyfy_fail!(format!("'{}' is not a valid product list id", id)),
+    };
+
+    return match state.db_service.delete_product_list(&claims.sub, &id).await {
+        Ok(true) => commercyfy_success!(CreatedEntryResponse { id: list_id }),
+        Ok(false) => commercyfy_fail!(
+            StatusCode::NOT_FOUND,
+            format!("Product list with id '{}' was not found", id)
+        ),
+        Err(err) => commercyfy_fail!(err.to_string()),
+    };
+}
+
+pub async fn put_product_list_item(
+    Extension(claims): Extension<CustomerJWTClaims>,
+    State(state): CommercyfyExtrState,
+    Path(id): Path<String>,
+    Json(payload): Json<PutProductListItem>,
+) -> CommercyfyResponse<CreatedEntryResponse> {
+    if let Err(err) = payload.validate() {
+        return commercyfy_fail!(err);
+    }
+
+    match state
+        .db_service
+        .get_customer_product_list(&claims.sub, &id)
+        .await
+    {
+        Ok(Some(_)) => {}
+        Ok(None) => {
+            return commercyfy_fail!(
+                StatusCode::NOT_FOUND,
+                format!("Product list with id '{}' was not found", id)
+            )
+        }
+        Err(err) => return commercyfy_fail!(err.to_string()),
+    };
+
+    match state
+        .db_service
+        .get_product(&payload.product_id.to_string())
+        .await
+    {
+        Ok(Some(_)) => {}
+        Ok(None) => {
+            return commercyfy_fail!(format!(
+                "Product with id '{}' does not exist",
+                payload.product_id
+            ))
+        }
+        Err(err) => return commercyfy_fail!(err.to_string()),
+    };
+
+    return match state.db_service.put_product_list_item(&id, &payload).await {
+        Ok(item) => commercyfy_success!(CreatedEntryResponse { id: item.id }),
+        Err(err) => commercyfy_fail!(err.to_string()),
+    };
+}
+
+pub async fn delete_product_list_item(
+    Extension(claims): Extension<CustomerJWTClaims>,
+    State(state): CommercyfyExtrState,
+    Path((id, product_id)): Path<(String, String)>,
+) -> CommercyfyResponse<CreatedEntryResponse> {
+    let list_id = match state
+        .db_service
+        .get_customer_product_list(&claims.sub, &id)
+        .await
+    {
+        Ok(Some(list)) => list.id,
+        Ok(None) => {
+            return commercyfy_fail!(
+                StatusCode::NOT_FOUND,
+                format!("Product list with id '{}' was not found", id)
+            )
+        }
+        Err(err) => return commercyfy_fail!(err.to_string()),
+    };
+
+    return match state
+        .db_service
+        .delete_product_list_item(&id, &product_id)
+        .await
+    {
+        Ok(true) => commercyfy_success!(CreatedEntryResponse { id: list_id }),
+        Ok(false) => commercyfy_fail!(
+            StatusCode::NOT_FOUND,
+            format!("Product '{}' is not in the product list", product_id)
+        ),
+        Err(err) => commercyfy_fail!(err.to_string()),
+    };
+}
@@ -0,0 +1,183 @@
+use super::{CommercyfyResponse, CreatedEntryResponse};
+use crate::{
+    models::returns::{Return, ReturnLine, ReturnStatus},
+    schemas::returns::{CreateReturn, InspectReturn},
+    utils::returns::calculate_refund,
+    CommercyfyExtrState,
+};
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+
+pub async fn get_returns(State(state): CommercyfyExtrState) -> CommercyfyResponse<Vec<Return>> {
+    return match state.db_service.get_returns().await {
+        Ok(returns) => commercyfy_success!(returns),
+        Err(err) => commercyfy_fail!(err.to_string()),
+    };
+}
+
+#[derive(serde::Serialize)]
+pub struct ReturnView {
+    #[serde(flatten)]
+    rma: Return,
+    lines: Vec<ReturnLine>,
+}
+
+pub async fn get_return(
+    State(state): CommercyfyExtrState,
+    Path(id): Path<String>,
+) -> CommercyfyResponse<ReturnView> {
+    let rma = match state.db_service.get_return_by_id(&id).await {
+        Ok(Some(rma)) => rma,
+        Ok(None) => match state.db_service.get_return_by_reference(&id).await {
+            Ok(Some(rma)) => rma,
+            Ok(None) => {
+                return commercyfy_fail!(
+                    StatusCode::NOT_FOUND,
+                    format!(
+                        "Return with the provided, {}, id/reference was not found",
+                        id
+                    )
+                )
+            }
+            Err(err) => return commercyfy_fail!(err.to_string()),
+        },
+        Err(err) => return commercyfy_fail!(err.to_string()),
+    };
+
+    return match state.db_service.get_return_lines(&rma.id.to_string()).await {
+        Ok(lines) => commercyfy_success!(ReturnView { rma, lines }),
+        Err(err) => commercyfy_fail!(err.to_string()),
+    };
+}
+
+pub async fn create_return(
+    State(state): CommercyfyExtrState,
+    Json(payload): Json<CreateReturn>,
+) -> CommercyfyResponse<CreatedEntryResponse> {
+    if let Err(err) = payload.validate() {
+        return commercyfy_fail!(err);
+    }
+
+    match state
+        .db_service
+        .get_return_by_reference(&payload.return_reference)
+        .await
+    {
+        Ok(Some(_)) => {
+            return commercyfy_fail!(format!(
+                "Return with 'return_reference' '{}' already exists",
+                payload.return_reference
+            ))
+        }
+        Ok(None) => {}
+        Err(err) => return commercyfy_fail!(err.to_string()),
+    };
+
+    if let Some(customer_id) = payload.customer_id {
+        match state
+            .db_service
+            .get_customer(&customer_id.to_string())
+            .await
+        {
+            Ok(Some(_)) => {}
+            Ok(None) => {
+                return commercyfy_fail!(format!(
+                    "Customer with id '{}' does not exist",
+                    customer_id
+                ))
+            }
+            Err(err) => return commercyfy_fail!(err.to_string()),
+        };
+    }
+
+    for line in &payload.lines {
+        match state
+            .db_service
+            .get_product(&line.product_id.to_string())
+            .await
+        {
+            Ok(Some(_)) => {}
+            Ok(None) => {
+                return commercyfy_fail!(format!(
+                    "Product with id '{}' does not exist",
+                    line.product_id
+                ))
+            }
+            Err(err) => return commercyfy_fail!(err.to_string()),
+        };
+    }
+
+    return match state.db_service.create_return(&payload).await {
+        Ok(rma) => commercyfy_success!(StatusCode::CREATED, CreatedEntryResponse { id: rma.id }),
+        Err(err) => commercyfy_fail!(err.to_string()),
+    };
+}
+
+pub async fn inspect_return(
+    State(state): CommercyfyExtrState,
+    Path(id): Path<String>,
+    Json(payload): Json<InspectReturn>,
+) -> CommercyfyResponse<Return> {
+    if let Err(err) = payload.validate() {
+        return commercyfy_fail!(err);
+    }
+
+    let rma = match state.db_service.get_return_by_id(&id).await {
+        Ok(Some(rma)) => rma,
+        Ok(None) => {
+            return commercyfy_fail!(
+                StatusCode::NOT_FOUND,
+                format!("Return with id '{}' was not found", id)
+            )
+        }
+        Err(err) => return commercyfy_fail!(err.to_string()),
+    };
+
+    if rma.status != ReturnStatus::AUTHORIZED {
+        return commercyfy_fail!(format!(
+            "Return '{}' was already inspected",
+            rma.return_reference
+        ));
+    }
+
+    let lines = match state.db_service.get_return_lines(&id).await {
+        Ok(lines) => lines,
+        Err(err) => return commercyfy_fail!(err.to_string()),
+    };
+
+    let refund_amount = match calculate_refund(&lines, &payload) {
+        Ok(refund) => refund,
+        Err(err) => return commercyfy_fail!(err),
+    };
+
+    for line in &payload.lines {
+        if let Some(inventory_id) = line.inventory_id {
+            match state
+                .db_service
+                .get_inventory_by_id(&inventory_id.to_string())
+                .await
+            {
+                Ok(Some(_)) => {}
+                Ok(None) => {
+                    return commercyfy_fail!(format!(
+                        "Inventory with id '{}' does not exist",
+                        inventory_id
+                    ))
+                }
+                Err(err) => return commercyfy_fail!(err.to_string()),
+            };
+        }
+    }
+
+    return match state
+        .db_service
+        .inspect_return(&rma, &payload, refund_amount)
+        .await
+    {
+        Ok(rma) => commercyfy_success!(rma),
+        Err(err) => commercyfy_fail!(err.to_string()),
+    };
+}
@@ -0,0 +1,160 @@
+use super::{CommercyfyResponse, CreatedEntryResponse};
+use crate::{
+    models::{
+        category::Category,
+        shipping::{
+            ApplicableShippingMethod, ShippingMethod, ShippingMethodCost, ShippingMethodWeightTier,
+        },
+    },
+    schemas::{basket::Basket, shipping::CreateShippingMethod},
+    utils::shipping::get_applicable_shipping_methods,
+    CommercyfyExtrState,
+};
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+
+pub async fn get_shipping_methods(
+    State(state): CommercyfyExtrState,
+) -> CommercyfyResponse<Vec<ShippingMethod>> {
+    let shipping_methods = match state.db_service.get_shipping_methods().await {
+        Ok(methods) => methods,
+        Err(err) => return commercyfy_fail!(err.to_string()),
+    };
+
+    return commercyfy_success!(shipping_methods);
+}
+
+#[derive(serde::Serialize)]
+pub struct ShippingMethodView {
+    #[serde(flatten)]
+    shipping_method: ShippingMethod,
+    costs: Vec<ShippingMethodCost>,
+    weight_tiers: Vec<ShippingMethodWeightTier>,
+    excluded_categories: Vec<Category>,
+}
+
+pub async fn get_shipping_method(
+    State(state): CommercyfyExtrState,
+    Path(id): Path<String>,
+) -> CommercyfyResponse<ShippingMethodView> {
+    let shipping_method = match state.db_service.get_shipping_method_by_id(&id).await {
+        Ok(Some(method)) => method,
+        Ok(None) => match state.db_service.get_shipping_method_by_reference(&id).await {
+            Ok(Some(method)) => method,
+            Ok(None) => {
+                return commercyfy_fail!(
+                    StatusCode::NOT_FOUND,
+                    format!(
+                        "Shipping method with the provided, {}, id/reference was not found",
+                        id
+                    )
+                )
+            }
+            Err(err) => return commercyfy_fail!(err.to_string()),
+        },
+        Err(err) => return commercyfy_fail!(err.to_string()),
+    };
+
+    let method_id = shipping_method.id.to_string();
+    let costs = match state.db_service.get_shipping_method_costs(&method_id).await {
+        Ok(costs) => costs,
+        Err(err) => return commercyfy_fail!(err.to_string()),
+    };
+
+    let weight_tiers = match state
+        .db_service
+        .get_shipping_method_weight_tiers(&method_id)
+        .await
+    {
+        Ok(tiers) => tiers,
+        Err(err) => return commercyfy_fail!(err.to_string()),
+    };
+
+    let excluded_categories = match state
+        .db_service
+        .get_shipping_method_excluded_categories(&method_id)
+        .await
+    {
+        Ok(categories) => categories,
+        Err(err) => return commercyfy_fail!(err.to_string()),
+    };
+
+    return commercyfy_success!(ShippingMethodView {
+        shipping_method,
+        costs,
+        weight_tiers,
+        excluded_categories,
+    });
+}
+
+pub async fn create_shipping_method(
+    State(state): CommercyfyExtrState,
+    Json(payload): Json<CreateShippingMethod>,
+) -> CommercyfyResponse<CreatedEntryResponse> {
+    if let Err(err) = payload.validate() {
+        return commercyfy_fail!(err);
+    }
+
+    match state
+        .db_service
+        .get_shipping_method_by_reference(&payload.shipping_method_reference)
+        .await
+    {
+        Ok(Some(_)) => {
+            return commercyfy_fail!(format!(
+                "Shipping method with 'shipping_method_reference' '{}' already exists",
+                payload.shipping_method_reference
+            ))
+        }
+        Ok(None) => {}
+        Err(err) => return commercyfy_fail!(err.to_string()),
+    };
+
+    if let Some(categories) = &payload.excluded_categories {
+        for category_id in categories {
+            match state
+                .db_service
+                .get_category_by_id(&category_id.to_string())
+                .await
+            {
+                Ok(Some(_)) => {}
+                Ok(None) => {
+                    return commercyfy_fail!(format!(
+                        "Category with id '{}' does not exist",
+                        category_id
+                    ))
+                }
+                Err(err) => return commercyfy_fail!(err.to_string()),
+            };
+        }
+    }
+
+    let shipping_method = match state.db_service.create_shipping_method(&payload).await {
+        Ok(method) => method,
+        Err(err) => return commercyfy_fail!(err.to_string()),
+    };
+
+    return commercyfy_success!(
+        StatusCode::CREATED,
+        CreatedEntryResponse {
+            id: shipping_method.id
+        }
+    );
+}
+
+pub async fn get_basket_shipping_methods(
+    State(state): CommercyfyExtrState,
+    Json(payload): Json<Basket>,
+) -> CommercyfyResponse<Vec<ApplicableShippingMethod>> {
+    if let Err(err) = payload.validate() {
+        return commercyfy_fail!(err);
+    }
+
+    return match get_applicable_shipping_methods(state, &payload).await {
+        Ok(methods) => commercyfy_success!(methods),
+        Err(err) => commercyfy_fail!(err),
+    };
+}
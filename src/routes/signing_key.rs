@@ -0,0 +1,52 @@
+use super::{logs::EmptyResponse, CommercyfyResponse, CreatedEntryResponse};
+use crate::{
+    models::signing_key::SigningKey, utils::signing_keys::rotate_signing_keys, CommercyfyExtrState,
+};
+use axum::{
+    extract::State,
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+
+// Served as a plain JWK set so other services can verify the tokens with any
+// JWT library, they are expected to refresh it when they see an unknown 'kid'.
+pub async fn get_jwks(State(state): CommercyfyExtrState) -> Response {
+    return match state.signing_keys.jwks() {
+        Ok(jwks) => (
+            StatusCode::OK,
+            [(header::CACHE_CONTROL, "public, max-age=300")],
+            Json(jwks),
+        )
+            .into_response(),
+        Err(err) => {
+            let response: CommercyfyResponse<EmptyResponse> =
+                commercyfy_fail!(StatusCode::INTERNAL_SERVER_ERROR, err);
+            return response.into_response();
+        }
+    };
+}
+
+pub async fn get_signing_keys(
+    State(state): CommercyfyExtrState,
+) -> CommercyfyResponse<Vec<SigningKey>> {
+    return match state.db_service.get_signing_keys().await {
+        Ok(signing_keys) => commercyfy_success!(signing_keys),
+        Err(err) => commercyfy_fail!(err.to_string()),
+    };
+}
+
+// the new key is published right away but only signs once the publish delay
+// passed, the current one stays valid for the tokens it signed
+pub async fn rotate_signing_key(
+    State(state): CommercyfyExtrState,
+) -> CommercyfyResponse<CreatedEntryResponse> {
+    return match rotate_signing_keys(&state, true).await {
+        Ok(Some(signing_key)) => commercyfy_success!(
+            StatusCode::CREATED,
+            CreatedEntryResponse { id: signing_key.id }
+        ),
+        Ok(None) => commercyfy_fail!("No signing key was created".to_string()),
+        Err(err) => commercyfy_fail!(err),
+    };
+}
@@ -0,0 +1,157 @@
+use super::{CommercyfyResponse, CreatedEntryResponse};
+use crate::{
+    models::tax::{TaxCalculation, TaxClass, TaxRate},
+    schemas::{
+        basket::Basket,
+        tax::{CreateTaxClass, CreateTaxRate},
+    },
+    utils::tax::calculate_basket_tax,
+    CommercyfyExtrState,
+};
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+
+pub async fn get_tax_classes(
+    State(state): CommercyfyExtrState,
+) -> CommercyfyResponse<Vec<TaxClass>> {
+    let tax_classes = match state.db_service.get_tax_classes().await {
+        Ok(classes) => classes,
+        Err(err) => return commercyfy_fail!(err.to_string()),
+    };
+
+    return commercyfy_success!(tax_classes);
+}
+
+#[derive(serde::Serialize)]
+pub struct TaxClassView {
+    #[serde(flatten)]
+    tax_class: TaxClass,
+    rates: Vec<TaxRate>,
+}
+
+pub async fn get_tax_class(
+    State(state): CommercyfyExtrState,
+    Path(id): Path<String>,
+) -> CommercyfyResponse<TaxClassView> {
+    let tax_class = match state.db_service.get_tax_class_by_id(&id).await {
+        Ok(Some(tax_class)) => tax_class,
+        Ok(None) => match state.db_service.get_tax_class_by_reference(&id).await {
+            Ok(Some(tax_class)) => tax_class,
+            Ok(None) => {
+                return commercyfy_fail!(
+                    StatusCode::NOT_FOUND,
+                    format!(
+                        "Tax class with the provided, {}, id/reference was not found",
+                        id
+                    )
+                )
+            }
+            Err(err) => return commercyfy_fail!(err.to_string()),
+        },
+        Err(err) => return commercyfy_fail!(err.to_string()),
+    };
+
+    let rates = match state
+        .db_service
+        .get_tax_rates(&tax_class.id.to_string())
+        .await
+    {
+        Ok(rates) => rates,
+        Err(err) => return commercyfy_fail!(err.to_string()),
+    };
+
+    return commercyfy_success!(TaxClassView { tax_class, rates });
+}
+
+pub async fn create_tax_class(
+    State(state): CommercyfyExtrState,
+    Json(payload): Json<CreateTaxClass>,
+) -> CommercyfyResponse<CreatedEntryResponse> {
+    if let Err(err) = payload.validate() {
+        return commercyfy_fail!(err);
+    }
+
+    match state
+        .db_service
+        .get_tax_class_by_reference(&payload.tax_class_reference)
+        .await
+    {
+        Ok(Some(_)) => {
+            return commercyfy_fail!(format!(
+                "Tax class with 'tax_class_reference' '{}' already exists",
+                payload.tax_class_reference
+            ))
+        }
+        Ok(None) => {}
+        Err(err) => return commercyfy_fail!(err.to_string()),
+    };
+
+    let tax_class = match state.db_service.create_tax_class(&payload).await {
+        Ok(tax_class) => tax_class,
+        Err(err) => return commercyfy_fail!(err.to_string()),
+    };
+
+    return commercyfy_success!(
+        StatusCode::CREATED,
+        CreatedEntryResponse { id: tax_class.id }
+    );
+}
+
+pub async fn create_tax_rate(
+    State(state): CommercyfyExtrState,
+    Path(id): Path<String>,
+    Json(payload): Json<CreateTaxRate>,
+) -> CommercyfyResponse<CreatedEntryResponse> {
+    if let Err(err) = payload.validate() {
+        return commercyfy_fail!(err);
+    }
+
+    match state.db_service.get_tax_class_by_id(&id).await {
+        Ok(Some(_)) => {}
+        Ok(None) => {
+            return commercyfy_fail!(
+                StatusCode::NOT_FOUND,
+                format!("Tax class with id '{}' was not found", id)
+            )
+        }
+        Err(err) => return commercyfy_fail!(err.to_string()),
+    };
+
+    match state
+        .db_service
+        .get_tax_rate(&id, &payload.country_code, payload.region.as_deref())
+        .await
+    {
+        Ok(Some(_)) => {
+            return commercyfy_fail!(
+                "Tax rate for the provided jurisdiction already exists".to_string()
+            )
+        }
+        Ok(None) => {}
+        Err(err) => return commercyfy_fail!(err.to_string()),
+    };
+
+    let rate = match state.db_service.create_tax_rate(&id, &payload).await {
+        Ok(rate) => rate,
+        Err(err) => return commercyfy_fail!(err.to_string()),
+    };
+
+    return commercyfy_success!(StatusCode::CREATED, CreatedEntryResponse { id: rate.id });
+}
+
+pub async fn get_basket_tax(
+    State(state): CommercyfyExtrState,
+    Json(payload): Json<Basket>,
+) -> CommercyfyResponse<TaxCalculation> {
+    if let Err(err) = payload.validate() {
+        return commercyfy_fail!(err);
+    }
+
+    return match calculate_basket_tax(state, &payload).await {
+        Ok(calculation) => commercyfy_success!(calculation),
+        Err(err) => commercyfy_fail!(err),
+    };
+}
@@ -0,0 +1,18 @@
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
+pub struct Address {
+    pub country_code: String,
+    pub region: Option<String>,
+    pub city: Option<String>,
+    pub postal_code: Option<String>,
+    pub address_line: Option<String>,
+}
+
+impl Address {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.country_code.is_empty() {
+            return Err("'country_code' is a mandatory field".to_string());
+        }
+
+        return Ok(());
+    }
+}
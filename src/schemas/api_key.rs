@@ -0,0 +1,28 @@
+use crate::models::portal_user::PortalUsersRoles;
+
+#[derive(serde::Deserialize)]
+pub struct CreateApiKey {
+    pub name: String,
+    pub scopes: Vec<PortalUsersRoles>,
+    pub expires_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+impl CreateApiKey {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.name.is_empty() {
+            return Err("'name' field is mandatory".to_string());
+        }
+
+        if self.scopes.is_empty() {
+            return Err("'scopes' should contain at least one scope".to_string());
+        }
+
+        if let Some(expires_at) = self.expires_at {
+            if expires_at <= chrono::Utc::now() {
+                return Err("'expires_at' should be in the future".to_string());
+            }
+        }
+
+        return Ok(());
+    }
+}
@@ -15,11 +15,53 @@ pub struct CreateStringField {
     pub min_len: Option<i64>,
 }
 
+#[derive(serde::Deserialize)]
+pub struct CreateDecimalField {
+    pub scale: Option<i32>,
+}
+
+#[derive(serde::Deserialize)]
+pub struct CreateEnumField {
+    pub allowed_values: Vec<String>,
+}
+
+#[derive(serde::Deserialize)]
+pub struct CreateSetField {
+    pub allowed_values: Option<Vec<String>>,
+}
+
+#[derive(serde::Deserialize)]
+pub struct CreateReferenceField {
+    pub reference_object: FieldExtensionObject,
+}
+
 #[derive(serde::Deserialize)]
 #[serde(tag = "$type", rename_all = "lowercase")]
 pub enum CreateCustomFieldEntry {
     STRING(CreateStringField),
     INT,
+    BOOLEAN,
+    DECIMAL(CreateDecimalField),
+    DATE,
+    DATETIME,
+    ENUM(CreateEnumField),
+    SET(CreateSetField),
+    REFERENCE(CreateReferenceField),
+    JSON,
+}
+
+fn validate_allowed_values(allowed_values: &[String]) -> Result<(), String> {
+    if allowed_values.iter().any(|x| return x.is_empty()) {
+        return Err("'allowed_values' can not contain empty values".to_string());
+    }
+
+    for (i, value) in allowed_values.iter().enumerate() {
+        if allowed_values[..i].contains(value) {
+            return Err(format!("'{}' is listed twice in 'allowed_values'", value));
+        }
+    }
+
+    return Ok(());
 }
 
 #[derive(serde::Deserialize)]
@@ -36,19 +78,147 @@ pub struct CreateCustomField {
 
 impl CreateCustomField {
     pub fn validate(&self) -> Result<(), String> {
-        if self.base_felds.name.is_empty() {
-            return Err("'name' is mandatory field".to_string());
+        if let FieldExtensionObject::CUSTOMOBJECT = self.object {
+            return Err("Fields of custom objects are created on their custom object".to_string());
+        }
+
+        return validate_field_definition(&self.base_felds, &self.custom);
+    }
+}
+
+// shared by the fields of the built-in and the custom objects
+pub fn validate_field_definition(
+    base_fields: &CreateBaseField,
+    custom: &CreateCustomFieldEntry,
+) -> Result<(), String> {
+    if base_fields.name.is_empty() {
+        return Err("'name' is mandatory field".to_string());
+    }
+
+    match custom {
+        CreateCustomFieldEntry::STRING(fields) => {
+            if fields.max_len.is_some_and(|x| return x < 0)
+                || fields.min_len.is_some_and(|x| return x < 0)
+            {
+                return Err("'max_len' and 'min_len' can not be negative".to_string());
+            }
+
+            if let (Some(max_len), Some(min_len)) = (fields.max_len, fields.min_len) {
+                if min_len > max_len {
+                    return Err("'min_len' can not be greater than 'max_len'".to_string());
+                }
+            }
+        }
+        CreateCustomFieldEntry::DECIMAL(fields)
+            if fields.scale.is_some_and(|x| return !(0..=28).contains(&x)) =>
+        {
+            return Err("'scale' should be between 0 and 28".to_string());
+        }
+        CreateCustomFieldEntry::ENUM(fields) => {
+            if fields.allowed_values.is_empty() {
+                return Err("'allowed_values' is mandatory for enum fields".to_string());
+            }
+
+            validate_allowed_values(&fields.allowed_values)?;
+        }
+        CreateCustomFieldEntry::SET(fields) => {
+            if let Some(allowed_values) = &fields.allowed_values {
+                validate_allowed_values(allowed_values)?;
+            }
+        }
+        CreateCustomFieldEntry::REFERENCE(fields) => {
+            if let FieldExtensionObject::CUSTOMOBJECT = fields.reference_object {
+                return Err("'reference_object' should be a built-in object".to_string());
+            }
+        }
+        _ => {}
+    }
+
+    return Ok(());
+}
+
+// the name, object and type of a field are fixed once it was created, the
+// values stored for it depend on them
+#[derive(serde::Deserialize)]
+pub struct UpdateCustomField {
+    pub description: Option<String>,
+    pub mandatory: Option<bool>,
+    pub max_len: Option<i64>,
+    pub min_len: Option<i64>,
+}
+
+impl UpdateCustomField {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.max_len.is_some_and(|x| return x < 0) || self.min_len.is_some_and(|x| return x < 0)
+        {
+            return Err("'max_len' and 'min_len' can not be negative".to_string());
+        }
+
+        return Ok(());
+    }
+}
+
+// what happens to the stored values that do not fit a migrated field
+#[derive(serde::Deserialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum CustomFieldViolationAction {
+    // the migration is refused while any value does not fit
+    #[default]
+    FAIL,
+
+    // strings that are too long are cut to 'max_len', the others still fail
+    TRUNCATE,
+
+    // the values are removed from their objects
+    DROP,
+}
+
+// A change of a field that can rewrite its stored values, unlike
+// UpdateCustomField it can rename the field and tighten its bounds. Renaming
+// moves the values to the new name.
+#[derive(serde::Deserialize)]
+pub struct MigrateCustomField {
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub mandatory: Option<bool>,
+    pub max_len: Option<i64>,
+    pub min_len: Option<i64>,
+    pub allowed_values: Option<Vec<String>>,
+
+    // replaces the values, or the items of the sets, before they are checked
+    pub value_map: Option<HashMap<String, String>>,
+
+    pub on_violation: Option<CustomFieldViolationAction>,
+
+    // the version the change was planned against, it is refused when the
+    // field was changed since
+    pub version: Option<i32>,
+}
+
+impl MigrateCustomField {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.name.as_ref().is_some_and(|x| return x.is_empty()) {
+            return Err("'name' can not be empty".to_string());
+        }
+
+        if self.max_len.is_some_and(|x| return x < 0) || self.min_len.is_some_and(|x| return x < 0)
+        {
+            return Err("'max_len' and 'min_len' can not be negative".to_string());
+        }
+
+        if let Some(allowed_values) = &self.allowed_values {
+            validate_allowed_values(allowed_values)?;
         }
 
         return Ok(());
     }
 }
 
-#[derive(serde::Serialize, serde::Deserialize, Debug)]
-#[serde(untagged)]
-pub enum ObjectCustomField {
-    STRING(String),
-    INT(i64),
+#[derive(serde::Deserialize)]
+pub struct DeleteCustomFieldQuery {
+    // also removes the values stored for the field
+    pub force: Option<bool>,
 }
 
-pub type ObjectCustomFields = Option<HashMap<String, ObjectCustomField>>;
+// the values are read according to the type of their field definition
+pub type ObjectCustomFields = Option<HashMap<String, serde_json::Value>>;
@@ -0,0 +1,30 @@
+use serde::Deserialize;
+
+use super::address::Address;
+
+#[derive(Deserialize)]
+pub struct BasketItem {
+    pub product_id: uuid::Uuid,
+    pub quantity: i32,
+}
+
+#[derive(Deserialize)]
+pub struct Basket {
+    pub pricebook_id: uuid::Uuid,
+    pub items: Vec<BasketItem>,
+    pub address: Address,
+}
+
+impl Basket {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.items.is_empty() {
+            return Err("'items' should contain at least one basket item".to_string());
+        }
+
+        if self.items.iter().any(|item| return item.quantity <= 0) {
+            return Err("'quantity' should be a positive number".to_string());
+        }
+
+        return self.address.validate();
+    }
+}
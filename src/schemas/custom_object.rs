@@ -0,0 +1,47 @@
+use super::base_extensions::{
+    validate_field_definition, CreateBaseField, CreateCustomFieldEntry, ObjectCustomFields,
+};
+use crate::models::custom_object::is_custom_object_name;
+
+#[derive(serde::Deserialize)]
+pub struct CreateCustomObjectType {
+    pub name: String,
+    pub description: Option<String>,
+}
+
+impl CreateCustomObjectType {
+    pub fn validate(&self) -> Result<(), String> {
+        if !is_custom_object_name(&self.name) {
+            return Err("'name' should start with a lowercase letter and contain only lowercase letters, digits and '-'".to_string());
+        }
+
+        return Ok(());
+    }
+}
+
+#[derive(serde::Deserialize)]
+pub struct DeleteCustomObjectTypeQuery {
+    // also removes the records of the type
+    pub force: Option<bool>,
+}
+
+// the same definition as the fields of the built-in objects, without the object
+#[derive(serde::Deserialize)]
+pub struct CreateCustomObjectField {
+    #[serde(flatten)]
+    pub base_felds: CreateBaseField,
+
+    #[serde(flatten)]
+    pub custom: CreateCustomFieldEntry,
+}
+
+impl CreateCustomObjectField {
+    pub fn validate(&self) -> Result<(), String> {
+        return validate_field_definition(&self.base_felds, &self.custom);
+    }
+}
+
+#[derive(serde::Deserialize)]
+pub struct CreateCustomObjectRecord {
+    pub custom_fields: ObjectCustomFields,
+}
@@ -0,0 +1,102 @@
+use super::address::Address;
+
+#[derive(serde::Deserialize)]
+pub struct CustomerCreate {
+    pub email: String,
+    pub first_name: String,
+    pub last_name: String,
+    pub phone: Option<String>,
+    pub password: String,
+}
+
+impl CustomerCreate {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.email.is_empty() {
+            return Err("'email' field is mandatory".to_string());
+        }
+
+        if self.first_name.is_empty() {
+            return Err("'first_name' field is mandatory".to_string());
+        }
+
+        if self.last_name.is_empty() {
+            return Err("'last_name' field is mandatory".to_string());
+        }
+
+        if self.password.len() <= 4 {
+            return Err("'password' should be longer than 4 symbols".to_string());
+        }
+
+        return Ok(());
+    }
+}
+
+#[derive(serde::Deserialize)]
+pub struct CustomerSignin {
+    pub email: String,
+    pub password: String,
+}
+
+impl CustomerSignin {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.email.is_empty() {
+            return Err("'email' is mandatory field".to_string());
+        }
+
+        if self.password.is_empty() {
+            return Err("'password' is mandatory field".to_string());
+        }
+
+        return Ok(());
+    }
+}
+
+#[derive(serde::Deserialize)]
+pub struct CustomerUpdate {
+    pub first_name: Option<String>,
+    pub last_name: Option<String>,
+    pub phone: Option<String>,
+}
+
+impl CustomerUpdate {
+    pub fn validate(&self) -> Result<(), String> {
+        if let Some(first_name) = &self.first_name {
+            if first_name.is_empty() {
+                return Err("'first_name' should not be empty".to_string());
+            }
+        }
+
+        if let Some(last_name) = &self.last_name {
+            if last_name.is_empty() {
+                return Err("'last_name' should not be empty".to_string());
+            }
+        }
+
+        return Ok(());
+    }
+}
+
+#[derive(serde::Deserialize)]
+pub struct CreateCustomerAddress {
+    pub first_name: String,
+    pub last_name: String,
+
+    #[serde(flatten)]
+    pub address: Address,
+
+    pub is_default: Option<bool>,
+}
+
+impl CreateCustomerAddress {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.first_name.is_empty() {
+            return Err("'first_name' field is mandatory".to_string());
+        }
+
+        if self.last_name.is_empty() {
+            return Err("'last_name' field is mandatory".to_string());
+        }
+
+        return self.address.validate();
+    }
+}
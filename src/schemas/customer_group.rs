@@ -0,0 +1,90 @@
+use serde::Deserialize;
+
+use crate::models::customer_group::{CustomerGroupRuleType, CustomerGroupType};
+
+#[derive(Deserialize)]
+pub struct CreateCustomerGroupRule {
+    pub rule_type: CustomerGroupRuleType,
+    pub rule_value: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct CreateCustomerGroup {
+    pub customer_group_name: String,
+    pub customer_group_reference: String,
+    pub customer_group_type: CustomerGroupType,
+    pub rules: Option<Vec<CreateCustomerGroupRule>>,
+}
+
+impl CreateCustomerGroup {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.customer_group_name.is_empty() {
+            return Err("'customer_group_name' is a mandatory field".to_string());
+        }
+
+        if self.customer_group_reference.is_empty() {
+            return Err("'customer_group_reference' is a mandatory field".to_string());
+        }
+
+        let rules = match (&self.customer_group_type, &self.rules) {
+            (CustomerGroupType::STATIC, Some(rules)) if !rules.is_empty() => {
+                return Err("'rules' can only be set on DYNAMIC customer groups".to_string())
+            }
+            (CustomerGroupType::STATIC, _) => return Ok(()),
+            (CustomerGroupType::DYNAMIC, Some(rules)) if !rules.is_empty() => rules,
+            (CustomerGroupType::DYNAMIC, _) => {
+                return Err("DYNAMIC customer groups should have at least one rule".to_string())
+            }
+        };
+
+        for rule in rules {
+            let needs_value = rule.rule_type != CustomerGroupRuleType::REGISTERED;
+            let has_value = rule
+                .rule_value
+                .as_ref()
+                .is_some_and(|v| return !v.is_empty());
+            if needs_value && !has_value {
+                return Err(format!("Rule {:?} requires a 'rule_value'", rule.rule_type));
+            }
+        }
+
+        return Ok(());
+    }
+}
+
+#[derive(Deserialize)]
+pub struct AssignCustomersToGroup {
+    pub customer_group_id: uuid::Uuid,
+    pub customer_ids: Vec<uuid::Uuid>,
+}
+
+impl AssignCustomersToGroup {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.customer_ids.is_empty() {
+            return Err("\"customer_ids\" should contain at least one customer id".to_string());
+        }
+
+        return Ok(());
+    }
+}
+
+#[derive(Deserialize)]
+pub struct AssignPricebooksToGroup {
+    pub customer_group_id: uuid::Uuid,
+    pub pricebook_ids: Vec<uuid::Uuid>,
+}
+
+impl AssignPricebooksToGroup {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.pricebook_ids.is_empty() {
+            return Err("\"pricebook_ids\" should contain at least one pricebook id".to_string());
+        }
+
+        return Ok(());
+    }
+}
+
+#[derive(Deserialize)]
+pub struct CustomerPriceQuery {
+    pub currency: String,
+}
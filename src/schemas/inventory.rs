@@ -28,6 +28,7 @@ pub struct CreateInventoryRecord {
     pub product_id: uuid::Uuid,
     pub inventory_id: uuid::Uuid,
     pub allocation: i32,
+    pub custom_fields: ObjectCustomFields,
 }
 
 impl CreateInventoryRecord {
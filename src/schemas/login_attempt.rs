@@ -0,0 +1,19 @@
+#[derive(serde::Deserialize)]
+pub struct LoginAttemptQuery {
+    pub email: Option<String>,
+    pub ip_address: Option<String>,
+    pub successful: Option<bool>,
+    pub limit: Option<i64>,
+}
+
+impl LoginAttemptQuery {
+    pub fn validate(&self) -> Result<(), String> {
+        if let Some(limit) = self.limit {
+            if !(1..=1000).contains(&limit) {
+                return Err("'limit' should be between 1 and 1000".to_string());
+            }
+        }
+
+        return Ok(());
+    }
+}
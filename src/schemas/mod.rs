@@ -1,7 +1,19 @@
+pub mod address;
+pub mod api_key;
 pub mod base_extensions;
+pub mod basket;
 pub mod category;
+pub mod custom_object;
+pub mod customer;
+pub mod customer_group;
 pub mod inventory;
+pub mod login_attempt;
+pub mod logs;
+pub mod permission;
 pub mod portal_user;
 pub mod pricebook;
 pub mod product;
-pub mod logs;
+pub mod product_list;
+pub mod returns;
+pub mod shipping;
+pub mod tax;
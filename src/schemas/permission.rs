@@ -0,0 +1,34 @@
+use crate::models::permission::{is_custom_object_permission, ALL_PERMISSIONS};
+
+#[derive(serde::Deserialize)]
+pub struct CreatePortalRole {
+    pub name: String,
+    pub permissions: Vec<String>,
+}
+
+impl CreatePortalRole {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.name.is_empty() {
+            return Err("'name' field is mandatory".to_string());
+        }
+
+        if self.permissions.is_empty() {
+            return Err("'permissions' should contain at least one permission".to_string());
+        }
+
+        for permission in &self.permissions {
+            if !ALL_PERMISSIONS.contains(&permission.as_str())
+                && !is_custom_object_permission(permission)
+            {
+                return Err(format!("Permission '{}' does not exist", permission));
+            }
+        }
+
+        return Ok(());
+    }
+}
+
+#[derive(serde::Deserialize)]
+pub struct AssignPortalRoles {
+    pub role_ids: Vec<uuid::Uuid>,
+}
@@ -1,3 +1,5 @@
+use super::base_extensions::ObjectCustomFields;
+
 #[derive(serde::Deserialize)]
 pub struct PortalUserCreate {
     pub email: String,
@@ -5,6 +7,7 @@ pub struct PortalUserCreate {
     pub last_name: String,
     pub password: String,
     pub roles: Vec<crate::models::portal_user::PortalUsersRoles>,
+    pub custom_fields: ObjectCustomFields,
 }
 
 impl PortalUserCreate {
@@ -52,3 +55,178 @@ impl PortalUserSignin {
         return Ok(());
     }
 }
+
+#[derive(serde::Deserialize)]
+pub struct PortalUserRefresh {
+    pub refresh_token: String,
+}
+
+impl PortalUserRefresh {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.refresh_token.is_empty() {
+            return Err("'refresh_token' is mandatory field".to_string());
+        }
+
+        return Ok(());
+    }
+}
+
+#[derive(serde::Deserialize)]
+pub struct PortalUserQuery {
+    // matched against the email, first and last name
+    pub search: Option<String>,
+    pub active: Option<bool>,
+}
+
+#[derive(serde::Deserialize)]
+pub struct PortalUserUpdate {
+    pub email: Option<String>,
+    pub first_name: Option<String>,
+    pub last_name: Option<String>,
+    pub roles: Option<Vec<crate::models::portal_user::PortalUsersRoles>>,
+}
+
+impl PortalUserUpdate {
+    pub fn validate(&self) -> Result<(), String> {
+        if let Some(email) = &self.email {
+            if email.is_empty() {
+                return Err("'email' should not be empty".to_string());
+            }
+        }
+
+        if let Some(first_name) = &self.first_name {
+            if first_name.is_empty() {
+                return Err("'first_name' should not be empty".to_string());
+            }
+        }
+
+        if let Some(last_name) = &self.last_name {
+            if last_name.is_empty() {
+                return Err("'last_name' should not be empty".to_string());
+            }
+        }
+
+        return Ok(());
+    }
+}
+
+#[derive(serde::Deserialize)]
+pub struct PortalUserPasswordReset {
+    pub password: String,
+}
+
+impl PortalUserPasswordReset {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.password.len() <= 4 {
+            return Err("'password' should be longer than 4 symbols".to_string());
+        }
+
+        return Ok(());
+    }
+}
+
+#[derive(serde::Deserialize)]
+pub struct PortalUserPasswordChange {
+    pub current_password: String,
+    pub new_password: String,
+}
+
+impl PortalUserPasswordChange {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.current_password.is_empty() {
+            return Err("'current_password' is mandatory field".to_string());
+        }
+
+        if self.new_password.len() <= 4 {
+            return Err("'new_password' should be longer than 4 symbols".to_string());
+        }
+
+        return Ok(());
+    }
+}
+
+#[derive(serde::Deserialize)]
+pub struct PortalUserPasswordForgot {
+    pub email: String,
+}
+
+impl PortalUserPasswordForgot {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.email.is_empty() {
+            return Err("'email' is mandatory field".to_string());
+        }
+
+        return Ok(());
+    }
+}
+
+#[derive(serde::Deserialize)]
+pub struct PortalUserPasswordRecover {
+    pub token: String,
+    pub password: String,
+}
+
+impl PortalUserPasswordRecover {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.token.is_empty() {
+            return Err("'token' is mandatory field".to_string());
+        }
+
+        if self.password.len() <= 4 {
+            return Err("'password' should be longer than 4 symbols".to_string());
+        }
+
+        return Ok(());
+    }
+}
+
+#[derive(serde::Deserialize)]
+pub struct PortalUserMfaSignin {
+    pub mfa_challenge: String,
+    pub code: Option<String>,
+    pub recovery_code: Option<String>,
+}
+
+impl PortalUserMfaSignin {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.mfa_challenge.is_empty() {
+            return Err("'mfa_challenge' is mandatory field".to_string());
+        }
+
+        if self.code.is_some() == self.recovery_code.is_some() {
+            return Err("Exactly one of 'code' and 'recovery_code' should be provided".to_string());
+        }
+
+        return Ok(());
+    }
+}
+
+#[derive(serde::Deserialize)]
+pub struct PortalUserMfaCode {
+    pub code: String,
+}
+
+impl PortalUserMfaCode {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.code.is_empty() {
+            return Err("'code' is mandatory field".to_string());
+        }
+
+        return Ok(());
+    }
+}
+
+#[derive(serde::Deserialize)]
+pub struct PortalUserMfaDisable {
+    pub password: String,
+}
+
+impl PortalUserMfaDisable {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.password.is_empty() {
+            return Err("'password' is mandatory field".to_string());
+        }
+
+        return Ok(());
+    }
+}
@@ -2,12 +2,14 @@ use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
 
 use super::base_extensions::ObjectCustomFields;
+use crate::models::pricebook::PricebookPriceMode;
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct CreatePricebook {
     pub pricebook_name: String,
     pub pricebook_reference: String,
     pub pricebook_currency_code: String,
+    pub pricebook_price_mode: Option<PricebookPriceMode>,
     pub custom_fields: ObjectCustomFields,
 }
 
@@ -34,6 +36,7 @@ pub struct CreatePricebookRecord {
     pub pricebook_id: String,
     pub product_id: String,
     pub price: Decimal,
+    pub custom_fields: ObjectCustomFields,
 }
 
 impl CreatePricebookRecord {
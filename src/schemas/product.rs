@@ -5,6 +5,7 @@ pub struct CreateProductImage {
     pub src: String,
     pub srcset: Option<String>,
     pub alt: Option<String>,
+    pub custom_fields: ObjectCustomFields,
 }
 
 impl CreateProductImage {
@@ -21,6 +22,8 @@ pub struct CreateProduct {
     pub product_name: String,
     pub product_description: String,
     pub product_color: Option<String>,
+    pub product_weight: Option<rust_decimal::Decimal>,
+    pub tax_class_id: Option<uuid::Uuid>,
     pub category_assignments: Option<Vec<uuid::Uuid>>,
     pub custom_fields: ObjectCustomFields,
 }
@@ -35,6 +38,12 @@ impl CreateProduct {
             return Err("'product_description' is mandatory field".to_string());
         }
 
+        if let Some(weight) = self.product_weight {
+            if weight.is_sign_negative() {
+                return Err("'product_weight' should not be negative".to_string());
+            }
+        }
+
         return Ok(());
     }
 }
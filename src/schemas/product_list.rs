@@ -0,0 +1,63 @@
+use serde::Deserialize;
+
+use crate::models::product_list::ProductListType;
+
+#[derive(Deserialize)]
+pub struct CreateProductList {
+    pub product_list_name: String,
+    pub product_list_type: Option<ProductListType>,
+    pub is_public: Option<bool>,
+}
+
+impl CreateProductList {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.product_list_name.is_empty() {
+            return Err("'product_list_name' is a mandatory field".to_string());
+        }
+
+        return Ok(());
+    }
+}
+
+#[derive(Deserialize)]
+pub struct UpdateProductList {
+    pub product_list_name: Option<String>,
+    pub is_public: Option<bool>,
+}
+
+impl UpdateProductList {
+    pub fn validate(&self) -> Result<(), String> {
+        if let Some(name) = &self.product_list_name {
+            if name.is_empty() {
+                return Err("'product_list_name' should not be empty".to_string());
+            }
+        }
+
+        return Ok(());
+    }
+}
+
+#[derive(Deserialize)]
+pub struct PutProductListItem {
+    pub product_id: uuid::Uuid,
+    pub quantity: Option<i32>,
+    pub priority: Option<i32>,
+}
+
+impl PutProductListItem {
+    pub fn validate(&self) -> Result<(), String> {
+        if let Some(quantity) = self.quantity {
+            if quantity <= 0 {
+                return Err("'quantity' should be a positive number".to_string());
+            }
+        }
+
+        if let Some(priority) = self.priority {
+            if priority < 0 {
+                return Err("'priority' should not be negative".to_string());
+            }
+        }
+
+        return Ok(());
+    }
+}
@@ -0,0 +1,87 @@
+use rust_decimal::Decimal;
+use serde::Deserialize;
+
+use crate::models::returns::{ReturnInspectionOutcome, ReturnReason};
+
+#[derive(Deserialize)]
+pub struct CreateReturnLine {
+    pub product_id: uuid::Uuid,
+    pub order_line_reference: Option<String>,
+    pub quantity: i32,
+    pub unit_price: Decimal,
+    pub reason: ReturnReason,
+    pub comment: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct CreateReturn {
+    pub return_reference: String,
+    pub order_reference: String,
+    pub currency_code: String,
+    pub customer_id: Option<uuid::Uuid>,
+    pub lines: Vec<CreateReturnLine>,
+}
+
+impl CreateReturn {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.return_reference.is_empty() {
+            return Err("'return_reference' is a mandatory field".to_string());
+        }
+
+        if self.order_reference.is_empty() {
+            return Err("'order_reference' is a mandatory field".to_string());
+        }
+
+        if self.currency_code.is_empty() {
+            return Err("'currency_code' is a mandatory field".to_string());
+        }
+
+        if self.lines.is_empty() {
+            return Err("'lines' should contain at least one returned line".to_string());
+        }
+
+        for line in &self.lines {
+            if line.quantity <= 0 {
+                return Err("'quantity' should be a positive number".to_string());
+            }
+
+            if line.unit_price.is_sign_negative() {
+                return Err("'unit_price' should not be negative".to_string());
+            }
+        }
+
+        return Ok(());
+    }
+}
+
+#[derive(Deserialize)]
+pub struct InspectReturnLine {
+    pub line_id: uuid::Uuid,
+    pub outcome: ReturnInspectionOutcome,
+    pub inventory_id: Option<uuid::Uuid>,
+}
+
+#[derive(Deserialize)]
+pub struct InspectReturn {
+    pub lines: Vec<InspectReturnLine>,
+}
+
+impl InspectReturn {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.lines.is_empty() {
+            return Err("'lines' should contain an outcome for every return line".to_string());
+        }
+
+        for line in &self.lines {
+            let needs_inventory = line.outcome == ReturnInspectionOutcome::RESTOCK;
+            if needs_inventory && line.inventory_id.is_none() {
+                return Err(format!(
+                    "Line '{}' is restocked, 'inventory_id' is mandatory",
+                    line.line_id
+                ));
+            }
+        }
+
+        return Ok(());
+    }
+}
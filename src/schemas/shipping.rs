@@ -0,0 +1,83 @@
+use rust_decimal::Decimal;
+use serde::Deserialize;
+
+#[derive(Deserialize)]
+pub struct CreateShippingMethodCost {
+    pub currency_code: String,
+    pub cost: Decimal,
+    pub free_shipping_threshold: Option<Decimal>,
+}
+
+#[derive(Deserialize)]
+pub struct CreateShippingMethodWeightTier {
+    pub currency_code: String,
+    pub min_weight: Decimal,
+    pub cost: Decimal,
+}
+
+#[derive(Deserialize)]
+pub struct CreateShippingMethod {
+    pub shipping_method_name: String,
+    pub shipping_method_description: Option<String>,
+    pub shipping_method_reference: String,
+    pub shipping_method_countries: Option<Vec<String>>,
+    pub costs: Vec<CreateShippingMethodCost>,
+    pub weight_tiers: Option<Vec<CreateShippingMethodWeightTier>>,
+    pub excluded_categories: Option<Vec<uuid::Uuid>>,
+}
+
+impl CreateShippingMethod {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.shipping_method_name.is_empty() {
+            return Err("'shipping_method_name' is a mandatory field".to_string());
+        }
+
+        if self.shipping_method_reference.is_empty() {
+            return Err("'shipping_method_reference' is a mandatory field".to_string());
+        }
+
+        if self.costs.is_empty() {
+            return Err("'costs' should contain at least one currency cost".to_string());
+        }
+
+        for cost in &self.costs {
+            if cost.currency_code.is_empty() {
+                return Err("'currency_code' is a mandatory field for every cost".to_string());
+            }
+
+            if cost.cost.is_sign_negative() {
+                return Err("'cost' should not be negative".to_string());
+            }
+
+            if let Some(threshold) = cost.free_shipping_threshold {
+                if threshold.is_sign_negative() {
+                    return Err("'free_shipping_threshold' should not be negative".to_string());
+                }
+            }
+        }
+
+        if let Some(tiers) = &self.weight_tiers {
+            for tier in tiers {
+                if !self
+                    .costs
+                    .iter()
+                    .any(|cost| return cost.currency_code == tier.currency_code)
+                {
+                    return Err(format!(
+                        "Weight tier currency '{}' has no matching base cost",
+                        tier.currency_code
+                    ));
+                }
+
+                if tier.min_weight.is_sign_negative() || tier.cost.is_sign_negative() {
+                    return Err(
+                        "'min_weight' and 'cost' of a weight tier should not be negative"
+                            .to_string(),
+                    );
+                }
+            }
+        }
+
+        return Ok(());
+    }
+}
@@ -0,0 +1,43 @@
+use rust_decimal::Decimal;
+use serde::Deserialize;
+
+#[derive(Deserialize)]
+pub struct CreateTaxClass {
+    pub tax_class_name: String,
+    pub tax_class_reference: String,
+}
+
+impl CreateTaxClass {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.tax_class_name.is_empty() {
+            return Err("'tax_class_name' is a mandatory field".to_string());
+        }
+
+        if self.tax_class_reference.is_empty() {
+            return Err("'tax_class_reference' is a mandatory field".to_string());
+        }
+
+        return Ok(());
+    }
+}
+
+#[derive(Deserialize)]
+pub struct CreateTaxRate {
+    pub country_code: String,
+    pub region: Option<String>,
+    pub rate: Decimal,
+}
+
+impl CreateTaxRate {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.country_code.is_empty() {
+            return Err("'country_code' is a mandatory field".to_string());
+        }
+
+        if self.rate.is_sign_negative() || self.rate > Decimal::ONE {
+            return Err("'rate' should be a fraction between 0 and 1".to_string());
+        }
+
+        return Ok(());
+    }
+}
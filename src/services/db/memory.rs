@@ -0,0 +1,2985 @@
+use std::sync::{Mutex, MutexGuard};
+
+use super::{DbService, DbServiceResult};
+use crate::models::api_key::ApiKey;
+use crate::models::base_extensions::{
+    CustomFieldMigration, CustomFieldMigrationStatus, FieldExtension, FieldExtensionObject,
+    FieldExtensionType, FieldExtensionVersion,
+};
+use crate::models::category::Category;
+use crate::models::custom_object::{CustomObjectRecord, CustomObjectType};
+use crate::models::customer::{Customer, CustomerAddress};
+use crate::models::customer_group::{CustomerGroup, CustomerGroupRule, CustomerGroupType};
+use crate::models::inventory::{Inventory, InventoryAdjustment, ProductInventoryRecord};
+use crate::models::login_attempt::{LoginAttempt, LoginClient, LoginFailureReason, LoginFailures};
+use crate::models::permission::PortalRole;
+use crate::models::portal_user::{
+    MfaChallenge, PasswordResetToken, PortalUser, PortalUserSession, RecoveryCode,
+};
+use crate::models::pricebook::{Pricebook, PricebookRecord};
+use crate::models::product::{Product, ProductImage};
+use crate::models::product_list::{ProductList, ProductListItem, ProductListType};
+use crate::models::returns::{Return, ReturnInspectionOutcome, ReturnLine, ReturnStatus};
+use crate::models::shipping::{ShippingMethod, ShippingMethodCost, ShippingMethodWeightTier};
+use crate::models::signing_key::{SigningAlgorithm, SigningKey};
+use crate::models::tax::{TaxClass, TaxRate};
+use crate::schemas::api_key::CreateApiKey;
+use crate::schemas::base_extensions::{
+    CreateCustomField, CreateCustomFieldEntry, MigrateCustomField, UpdateCustomField,
+};
+use crate::schemas::category::{AssignProductToCategory, CreateCategory};
+use crate::schemas::custom_object::CreateCustomObjectType;
+use crate::schemas::customer::{CreateCustomerAddress, CustomerCreate, CustomerUpdate};
+use crate::schemas::customer_group::{
+    AssignCustomersToGroup, AssignPricebooksToGroup, CreateCustomerGroup,
+};
+use crate::schemas::inventory::{CreateInventory, CreateInventoryRecord};
+use crate::schemas::login_attempt::LoginAttemptQuery;
+use crate::schemas::permission::{AssignPortalRoles, CreatePortalRole};
+use crate::schemas::portal_user::{PortalUserCreate, PortalUserQuery, PortalUserUpdate};
+use crate::schemas::pricebook::{CreatePricebook, CreatePricebookRecord};
+use crate::schemas::product::{CreateProduct, CreateProductImage};
+use crate::schemas::product_list::{CreateProductList, PutProductListItem, UpdateProductList};
+use crate::schemas::returns::{CreateReturn, InspectReturn};
+use crate::schemas::shipping::CreateShippingMethod;
+use crate::schemas::tax::{CreateTaxClass, CreateTaxRate};
+use crate::services::signing_keys::SIGNING_KEY_GRACE_PERIOD;
+use crate::utils::auth::hash_password;
+
+// the rows of every table, the join tables only keep the ids they link
+#[derive(Default)]
+struct MemoryTables {
+    categories: Vec<Category>,
+    categories_products: Vec<(uuid::Uuid, uuid::Uuid)>,
+    products: Vec<Product>,
+    images: Vec<ProductImage>,
+    inventories: Vec<Inventory>,
+    inventories_products: Vec<ProductInventoryRecord>,
+    inventory_adjustments: Vec<InventoryAdjustment>,
+    pricebooks: Vec<Pricebook>,
+    pricebooks_products: Vec<PricebookRecord>,
+    portal_users: Vec<PortalUser>,
+    portal_users_sessions: Vec<PortalUserSession>,
+    portal_users_password_resets: Vec<PasswordResetToken>,
+    portal_users_login_attempts: Vec<LoginAttempt>,
+    portal_users_recovery_codes: Vec<RecoveryCode>,
+    portal_users_mfa_challenges: Vec<MfaChallenge>,
+    api_keys: Vec<ApiKey>,
+    portal_roles: Vec<PortalRole>,
+    portal_users_portal_roles: Vec<(uuid::Uuid, uuid::Uuid)>,
+    custom_fields: Vec<FieldExtension>,
+    custom_field_versions: Vec<FieldExtensionVersion>,
+    custom_field_migrations: Vec<CustomFieldMigration>,
+    custom_objects: Vec<CustomObjectType>,
+    custom_object_records: Vec<CustomObjectRecord>,
+    shipping_methods: Vec<ShippingMethod>,
+    shipping_methods_costs: Vec<ShippingMethodCost>,
+    shipping_methods_weight_tiers: Vec<ShippingMethodWeightTier>,
+    shipping_methods_excluded_categories: Vec<(uuid::Uuid, uuid::Uuid)>,
+    tax_classes: Vec<TaxClass>,
+    tax_rates: Vec<TaxRate>,
+    customers: Vec<Customer>,
+    customers_addresses: Vec<CustomerAddress>,
+    customer_groups: Vec<CustomerGroup>,
+    customer_groups_rules: Vec<CustomerGroupRule>,
+    customer_groups_customers: Vec<(uuid::Uuid, uuid::Uuid)>,
+    customer_groups_pricebooks: Vec<(uuid::Uuid, uuid::Uuid)>,
+    product_lists: Vec<ProductList>,
+    product_lists_items: Vec<ProductListItem>,
+    returns: Vec<Return>,
+    returns_lines: Vec<ReturnLine>,
+    signing_keys: Vec<SigningKey>,
+}
+
+impl MemoryTables {
+    fn push_custom_field_version(&mut self, field: &FieldExtension) {
+        self.custom_field_versions.push(FieldExtensionVersion {
+            id: uuid::Uuid::new_v4(),
+            custom_field_id: field.id,
+            version: field.version,
+            name: field.name.clone(),
+            mandatory: field.mandatory,
+            description: field.description.clone(),
+            max_len: field.max_len,
+            min_len: field.min_len,
+            allowed_values: field.allowed_values.clone(),
+            created_at: chrono::Utc::now(),
+        });
+    }
+
+    fn revoke_sessions<F>(&mut self, portal_user_id: uuid::Uuid, keep: F)
+    where
+        F: Fn(&PortalUserSession) -> bool,
+    {
+        let now = chrono::Utc::now();
+        for session in self.portal_users_sessions.iter_mut() {
+            if session.portal_user_id == portal_user_id
+                && session.revoked_at.is_none()
+                && !keep(session)
+            {
+                session.revoked_at = Some(now);
+            }
+        }
+    }
+
+    fn replace_recovery_codes(&mut self, portal_user_id: uuid::Uuid, recovery_codes: &[String]) {
+        self.portal_users_recovery_codes
+            .retain(|x| return x.portal_user_id != portal_user_id);
+        for code in recovery_codes {
+            self.portal_users_recovery_codes.push(RecoveryCode {
+                id: uuid::Uuid::new_v4(),
+                portal_user_id,
+                code: code.clone(),
+                used_at: None,
+            });
+        }
+    }
+
+    fn login_failures<F>(&self, predicate: F) -> LoginFailures
+    where
+        F: Fn(&LoginAttempt) -> bool,
+    {
+        let failures = self.portal_users_login_attempts.iter().filter(|x| {
+            return !x.successful
+                && x.failure_reason != Some(LoginFailureReason::LOCKED)
+                && predicate(x);
+        });
+
+        return LoginFailures {
+            count: failures.clone().count() as i64,
+            last_attempt_at: failures.map(|x| return x.created_at).max(),
+        };
+    }
+}
+
+// A DbService that keeps everything in memory, for the integration tests and
+// demos without a database. The unique and foreign key constraints of the
+// migrations are checked so the handlers see the same failures.
+#[derive(Default)]
+pub struct MemoryDbService {
+    tables: Mutex<MemoryTables>,
+}
+
+impl MemoryDbService {
+    pub fn new() -> Self {
+        return Self::default();
+    }
+
+    fn lock(&self) -> MutexGuard<'_, MemoryTables> {
+        return self
+            .tables
+            .lock()
+            .expect("The in-memory tables were poisoned!");
+    }
+}
+
+fn unique_violation(constraint: &str) -> sqlx::Error {
+    return sqlx::Error::Protocol(format!(
+        "duplicate key value violates unique constraint \"{}\"",
+        constraint
+    ));
+}
+
+fn foreign_key_violation(constraint: &str) -> sqlx::Error {
+    return sqlx::Error::Protocol(format!(
+        "insert or update violates foreign key constraint \"{}\"",
+        constraint
+    ));
+}
+
+// the `$1::uuid` casts of the queries
+fn parse_uuid(value: &str) -> DbServiceResult<uuid::Uuid> {
+    return uuid::Uuid::parse_str(value).map_err(|_| {
+        return sqlx::Error::Protocol(format!("invalid input syntax for type uuid: \"{}\"", value));
+    });
+}
+
+// the `id::text = $1` comparisons of the queries
+fn is_id(id: &uuid::Uuid, value: &str) -> bool {
+    return id.to_string() == value;
+}
+
+fn find<T: Clone, F>(rows: &[T], predicate: F) -> Option<T>
+where
+    F: Fn(&T) -> bool,
+{
+    return rows.iter().find(|x| return predicate(x)).cloned();
+}
+
+fn filter<T: Clone, F>(rows: &[T], predicate: F) -> Vec<T>
+where
+    F: Fn(&T) -> bool,
+{
+    return rows
+        .iter()
+        .filter(|x| return predicate(x))
+        .cloned()
+        .collect();
+}
+
+// the rows of `rows` linked to `id` by the (left, right) pairs of a join table
+fn join<T: Clone, F>(
+    links: &[(uuid::Uuid, uuid::Uuid)],
+    rows: &[T],
+    id: &str,
+    left: bool,
+    row_id: F,
+) -> Vec<T>
+where
+    F: Fn(&T) -> uuid::Uuid,
+{
+    return links
+        .iter()
+        .filter(|(x, y)| return is_id(if left { x } else { y }, id))
+        .filter_map(|(x, y)| {
+            let linked = if left { y } else { x };
+            return rows
+                .iter()
+                .find(|row| return row_id(row) == *linked)
+                .cloned();
+        })
+        .collect();
+}
+
+#[async_trait::async_trait]
+// the futures async_trait wraps the bodies in are returned implicitly
+#[allow(clippy::implicit_return)]
+impl DbService for MemoryDbService {
+    async fn get_categories(&self) -> DbServiceResult<Vec<Category>> {
+        return Ok(self.lock().categories.clone());
+    }
+
+    async fn create_category(&self, category: &CreateCategory) -> DbServiceResult<Category> {
+        let mut tables = self.lock();
+        if tables
+            .categories
+            .iter()
+            .any(|x| return x.category_reference == category.category_reference)
+        {
+            return Err(unique_violation("categories_category_reference_key"));
+        }
+
+        let category = Category {
+            id: uuid::Uuid::new_v4(),
+            category_name: category.category_name.clone(),
+            category_description: category.category_description.clone(),
+            category_reference: category.category_reference.clone(),
+        };
+        tables.categories.push(category.clone());
+
+        return Ok(category);
+    }
+
+    async fn get_category_by_id(&self, id: &str) -> DbServiceResult<Option<Category>> {
+        return Ok(find(&self.lock().categories, |x| return is_id(&x.id, id)));
+    }
+
+    async fn get_category_by_reference(
+        &self,
+        reference: &str,
+    ) -> DbServiceResult<Option<Category>> {
+        return Ok(find(&self.lock().categories, |x| {
+            return x.category_reference == reference;
+        }));
+    }
+
+    async fn get_category_products_by_id(&self, id: &str) -> DbServiceResult<Vec<Product>> {
+        let tables = self.lock();
+        return Ok(join(
+            &tables.categories_products,
+            &tables.products,
+            id,
+            true,
+            |x| return x.id,
+        ));
+    }
+
+    async fn create_category_product_entries(
+        &self,
+        payload: &AssignProductToCategory,
+    ) -> DbServiceResult<()> {
+        let mut tables = self.lock();
+        if !tables
+            .categories
+            .iter()
+            .any(|x| return x.id == payload.category_id)
+        {
+            return Err(foreign_key_violation(
+                "categories_products_category_id_fkey",
+            ));
+        }
+        if payload
+            .product_ids
+            .iter()
+            .any(|id| return !tables.products.iter().any(|x| return x.id == *id))
+        {
+            return Err(foreign_key_violation("categories_products_product_id_fkey"));
+        }
+
+        for product_id in &payload.product_ids {
+            tables
+                .categories_products
+                .push((payload.category_id, *product_id));
+        }
+
+        return Ok(());
+    }
+
+    async fn get_product(&self, id: &str) -> DbServiceResult<Option<Product>> {
+        return Ok(find(&self.lock().products, |x| return is_id(&x.id, id)));
+    }
+
+    async fn get_products(&self) -> DbServiceResult<Vec<Product>> {
+        return Ok(self.lock().products.clone());
+    }
+
+    async fn get_product_categories(&self, id: &str) -> DbServiceResult<Vec<Category>> {
+        let product_id = parse_uuid(id)?;
+        let tables = self.lock();
+        return Ok(join(
+            &tables.categories_products,
+            &tables.categories,
+            &product_id.to_string(),
+            false,
+            |x| return x.id,
+        ));
+    }
+
+    async fn get_product_images(&self, id: &str) -> DbServiceResult<Vec<ProductImage>> {
+        return Ok(filter(&self.lock().images, |x| {
+            return is_id(&x.product_id, id);
+        }));
+    }
+
+    async fn create_product(&self, payload: &CreateProduct) -> DbServiceResult<Product> {
+        let mut tables = self.lock();
+        if let Some(tax_class_id) = payload.tax_class_id {
+            if !tables
+                .tax_classes
+                .iter()
+                .any(|x| return x.id == tax_class_id)
+            {
+                return Err(foreign_key_violation("products_tax_class_id_fkey"));
+            }
+        }
+
+        let categories = payload.category_assignments.as_deref().unwrap_or_default();
+        if categories
+            .iter()
+            .any(|id| return !tables.categories.iter().any(|x| return x.id == *id))
+        {
+            return Err(foreign_key_violation(
+                "categories_products_category_id_fkey",
+            ));
+        }
+
+        let product = Product {
+            id: uuid::Uuid::new_v4(),
+            product_name: payload.product_name.clone(),
+            product_description: payload.product_description.clone(),
+            product_color: payload.product_color.clone(),
+            product_weight: payload.product_weight,
+            tax_class_id: payload.tax_class_id,
+        };
+        tables.products.push(product.clone());
+
+        for category_id in categories {
+            tables.categories_products.push((*category_id, product.id));
+        }
+
+        return Ok(product);
+    }
+
+    async fn create_product_image(
+        &self,
+        id: &str,
+        payload: CreateProductImage,
+    ) -> DbServiceResult<ProductImage> {
+        let product_id = parse_uuid(id)?;
+        let mut tables = self.lock();
+        if !tables.products.iter().any(|x| return x.id == product_id) {
+            return Err(foreign_key_violation("images_product_id_fkey"));
+        }
+
+        let image = ProductImage {
+            id: uuid::Uuid::new_v4(),
+            src: payload.src,
+            srcset: payload.srcset,
+            alt: payload.alt,
+            product_id,
+        };
+        tables.images.push(image.clone());
+
+        return Ok(image);
+    }
+
+    async fn get_inventories(&self) -> DbServiceResult<Vec<Inventory>> {
+        return Ok(self.lock().inventories.clone());
+    }
+
+    async fn get_inventory_by_id(&self, id: &str) -> DbServiceResult<Option<Inventory>> {
+        return Ok(find(&self.lock().inventories, |x| return is_id(&x.id, id)));
+    }
+
+    async fn get_inventory_by_reference(
+        &self,
+        refernece: &str,
+    ) -> DbServiceResult<Option<Inventory>> {
+        return Ok(find(&self.lock().inventories, |x| {
+            return x.inventory_reference == refernece;
+        }));
+    }
+
+    async fn get_inventory_records(
+        &self,
+        id: &str,
+    ) -> DbServiceResult<Vec<ProductInventoryRecord>> {
+        return Ok(filter(&self.lock().inventories_products, |x| {
+            return is_id(&x.inventory_id, id);
+        }));
+    }
+
+    async fn create_inventory(&self, payload: &CreateInventory) -> DbServiceResult<Inventory> {
+        let mut tables = self.lock();
+        if tables
+            .inventories
+            .iter()
+            .any(|x| return x.inventory_name == payload.inventory_name)
+        {
+            return Err(unique_violation("inventories_inventory_name_key"));
+        }
+        if tables
+            .inventories
+            .iter()
+            .any(|x| return x.inventory_reference == payload.inventory_reference)
+        {
+            return Err(unique_violation("inventories_inventory_reference_key"));
+        }
+
+        let inventory = Inventory {
+            id: uuid::Uuid::new_v4(),
+            inventory_name: payload.inventory_name.clone(),
+            inventory_reference: payload.inventory_reference.clone(),
+        };
+        tables.inventories.push(inventory.clone());
+
+        return Ok(inventory);
+    }
+
+    async fn get_product_inventory_record(
+        &self,
+        product_id: &str,
+        inventory_id: &str,
+    ) -> DbServiceResult<Option<ProductInventoryRecord>> {
+        return Ok(find(&self.lock().inventories_products, |x| {
+            return is_id(&x.product_id, product_id) && is_id(&x.inventory_id, inventory_id);
+        }));
+    }
+
+    async fn get_product_inventory_records(
+        &self,
+        product_id: &str,
+    ) -> DbServiceResult<Vec<ProductInventoryRecord>> {
+        return Ok(filter(&self.lock().inventories_products, |x| {
+            return is_id(&x.product_id, product_id);
+        }));
+    }
+
+    async fn create_product_inventory_record(
+        &self,
+        payload: CreateInventoryRecord,
+    ) -> DbServiceResult<ProductInventoryRecord> {
+        let mut tables = self.lock();
+        if !tables
+            .inventories
+            .iter()
+            .any(|x| return x.id == payload.inventory_id)
+        {
+            return Err(foreign_key_violation(
+                "inventories_products_inventory_id_fkey",
+            ));
+        }
+        if !tables
+            .products
+            .iter()
+            .any(|x| return x.id == payload.product_id)
+        {
+            return Err(foreign_key_violation(
+                "inventories_products_product_id_fkey",
+            ));
+        }
+        if tables.inventories_products.iter().any(|x| {
+            return x.inventory_id == payload.inventory_id && x.product_id == payload.product_id;
+        }) {
+            return Err(unique_violation(
+                "inventories_products_inventory_id_product_id_key",
+            ));
+        }
+
+        let record = ProductInventoryRecord {
+            id: uuid::Uuid::new_v4(),
+            product_id: payload.product_id,
+            inventory_id: payload.inventory_id,
+            allocation: payload.allocation,
+        };
+        tables.inventories_products.push(record.clone());
+
+        return Ok(record);
+    }
+
+    async fn get_pricebooks(&self) -> DbServiceResult<Vec<Pricebook>> {
+        return Ok(self.lock().pricebooks.clone());
+    }
+
+    async fn get_pricebook_by_id(&self, id: &str) -> DbServiceResult<Option<Pricebook>> {
+        return Ok(find(&self.lock().pricebooks, |x| return is_id(&x.id, id)));
+    }
+
+    async fn get_pricebook_by_reference(
+        &self,
+        reference: &str,
+    ) -> DbServiceResult<Option<Pricebook>> {
+        return Ok(find(&self.lock().pricebooks, |x| {
+            return x.pricebook_reference == reference;
+        }));
+    }
+
+    async fn get_pricebook_records(&self, id: &str) -> DbServiceResult<Vec<PricebookRecord>> {
+        return Ok(filter(&self.lock().pricebooks_products, |x| {
+            return is_id(&x.pricebook_id, id);
+        }));
+    }
+
+    async fn create_pricebook(&self, payload: &CreatePricebook) -> DbServiceResult<Pricebook> {
+        let mut tables = self.lock();
+        if tables
+            .pricebooks
+            .iter()
+            .any(|x| return x.pricebook_name == payload.pricebook_name)
+        {
+            return Err(unique_violation("pricebooks_pricebook_name_key"));
+        }
+        if tables
+            .pricebooks
+            .iter()
+            .any(|x| return x.pricebook_reference == payload.pricebook_reference)
+        {
+            return Err(unique_violation("pricebooks_pricebook_reference_key"));
+        }
+
+        let pricebook = Pricebook {
+            id: uuid::Uuid::new_v4(),
+            pricebook_name: payload.pricebook_name.clone(),
+            pricebook_reference: payload.pricebook_reference.clone(),
+            pricebook_currency_code: payload.pricebook_currency_code.clone(),
+            pricebook_price_mode: payload.pricebook_price_mode.unwrap_or_default(),
+        };
+        tables.pricebooks.push(pricebook.clone());
+
+        return Ok(pricebook);
+    }
+
+    async fn create_product_pricebook_record(
+        &self,
+        payload: CreatePricebookRecord,
+    ) -> DbServiceResult<PricebookRecord> {
+        let product_id = parse_uuid(&payload.product_id)?;
+        let pricebook_id = parse_uuid(&payload.pricebook_id)?;
+        let mut tables = self.lock();
+        if !tables
+            .pricebooks
+            .iter()
+            .any(|x| return x.id == pricebook_id)
+        {
+            return Err(foreign_key_violation(
+                "pricebooks_products_pricebook_id_fkey",
+            ));
+        }
+        if !tables.products.iter().any(|x| return x.id == product_id) {
+            return Err(foreign_key_violation("pricebooks_products_product_id_fkey"));
+        }
+        if tables.pricebooks_products.iter().any(|x| {
+            return x.pricebook_id == pricebook_id && x.product_id == product_id;
+        }) {
+            return Err(unique_violation(
+                "pricebooks_products_pricebook_id_product_id_key",
+            ));
+        }
+
+        let record = PricebookRecord {
+            id: uuid::Uuid::new_v4(),
+            pricebook_id,
+            product_id,
+            price: payload.price,
+        };
+        tables.pricebooks_products.push(record.clone());
+
+        return Ok(record);
+    }
+
+    async fn get_product_pricebook_record(
+        &self,
+        product_id: &str,
+        pricebook_id: &str,
+    ) -> DbServiceResult<Option<PricebookRecord>> {
+        return Ok(find(&self.lock().pricebooks_products, |x| {
+            return is_id(&x.product_id, product_id) && is_id(&x.pricebook_id, pricebook_id);
+        }));
+    }
+
+    async fn get_product_pricebooks(
+        &self,
+        product_id: &str,
+    ) -> DbServiceResult<Vec<PricebookRecord>> {
+        return Ok(filter(&self.lock().pricebooks_products, |x| {
+            return is_id(&x.product_id, product_id);
+        }));
+    }
+
+    async fn get_portal_user(&self, id: &str) -> DbServiceResult<Option<PortalUser>> {
+        return Ok(find(&self.lock().portal_users, |x| return is_id(&x.id, id)));
+    }
+
+    async fn create_portal_user(&self, payload: PortalUserCreate) -> DbServiceResult<PortalUser> {
+        let password = match hash_password(&payload.password) {
+            Ok(password) => password,
+            Err(_) => return Err(sqlx::Error::Io(std::io::ErrorKind::InvalidInput.into())),
+        };
+
+        let mut tables = self.lock();
+        if tables
+            .portal_users
+            .iter()
+            .any(|x| return x.email == payload.email)
+        {
+            return Err(unique_violation("portal_users_email_key"));
+        }
+
+        let portal_user = PortalUser {
+            id: uuid::Uuid::new_v4(),
+            email: payload.email,
+            first_name: payload.first_name,
+            last_name: payload.last_name,
+            password,
+            roles: payload.roles,
+            active: true,
+            session_version: 0,
+            mfa_secret: None,
+            mfa_enabled: false,
+            mfa_last_step: None,
+        };
+        tables.portal_users.push(portal_user.clone());
+
+        return Ok(portal_user);
+    }
+
+    async fn get_portal_user_by_email(&self, email: &str) -> DbServiceResult<Option<PortalUser>> {
+        return Ok(find(&self.lock().portal_users, |x| return x.email == email));
+    }
+
+    async fn get_portal_user_session(
+        &self,
+        id: &str,
+    ) -> DbServiceResult<Option<PortalUserSession>> {
+        return Ok(find(&self.lock().portal_users_sessions, |x| {
+            return is_id(&x.id, id);
+        }));
+    }
+
+    async fn create_portal_user_session(
+        &self,
+        portal_user_id: uuid::Uuid,
+        refresh_token: &str,
+        expires_at: chrono::DateTime<chrono::Utc>,
+    ) -> DbServiceResult<PortalUserSession> {
+        let mut tables = self.lock();
+        if !tables
+            .portal_users
+            .iter()
+            .any(|x| return x.id == portal_user_id)
+        {
+            return Err(foreign_key_violation(
+                "portal_users_sessions_portal_user_id_fkey",
+            ));
+        }
+
+        let session = PortalUserSession {
+            id: uuid::Uuid::new_v4(),
+            portal_user_id,
+            refresh_token: refresh_token.to_string(),
+            created_at: chrono::Utc::now(),
+            expires_at,
+            revoked_at: None,
+        };
+        tables.portal_users_sessions.push(session.clone());
+
+        return Ok(session);
+    }
+
+    async fn rotate_portal_user_session(
+        &self,
+        id: uuid::Uuid,
+        current_refresh_token: &str,
+        refresh_token: &str,
+        expires_at: chrono::DateTime<chrono::Utc>,
+    ) -> DbServiceResult<Option<PortalUserSession>> {
+        let now = chrono::Utc::now();
+        let mut tables = self.lock();
+        let session = match tables.portal_users_sessions.iter_mut().find(|x| {
+            return x.id == id
+                && x.refresh_token == current_refresh_token
+                && x.revoked_at.is_none()
+                && x.expires_at > now;
+        }) {
+            Some(session) => session,
+            None => return Ok(None),
+        };
+
+        session.refresh_token = refresh_token.to_string();
+        session.expires_at = expires_at;
+
+        return Ok(Some(session.clone()));
+    }
+
+    async fn revoke_portal_user_session(&self, id: &str) -> DbServiceResult<()> {
+        let now = chrono::Utc::now();
+        for session in self.lock().portal_users_sessions.iter_mut() {
+            if is_id(&session.id, id) && session.revoked_at.is_none() {
+                session.revoked_at = Some(now);
+            }
+        }
+
+        return Ok(());
+    }
+
+    async fn revoke_portal_user_sessions(&self, portal_user_id: &str) -> DbServiceResult<()> {
+        let mut tables = self.lock();
+        let portal_user = match tables
+            .portal_users
+            .iter_mut()
+            .find(|x| return is_id(&x.id, portal_user_id))
+        {
+            Some(portal_user) => portal_user,
+            None => return Ok(()),
+        };
+
+        portal_user.session_version += 1;
+        let portal_user_id = portal_user.id;
+        tables.revoke_sessions(portal_user_id, |_| return false);
+
+        return Ok(());
+    }
+
+    async fn get_portal_user_by_session(
+        &self,
+        id: &str,
+        portal_user_id: &str,
+        session_version: i32,
+    ) -> DbServiceResult<Option<PortalUser>> {
+        let tables = self.lock();
+        let now = chrono::Utc::now();
+        let session = tables.portal_users_sessions.iter().find(|x| {
+            return is_id(&x.id, id) && x.revoked_at.is_none() && x.expires_at > now;
+        });
+
+        return Ok(session.and_then(|session| {
+            return find(&tables.portal_users, |x| {
+                return x.id == session.portal_user_id
+                    && is_id(&x.id, portal_user_id)
+                    && x.session_version == session_version
+                    && x.active;
+            });
+        }));
+    }
+
+    async fn get_portal_users(&self, query: &PortalUserQuery) -> DbServiceResult<Vec<PortalUser>> {
+        let search = query.search.as_ref().map(|x| return x.to_lowercase());
+        let mut portal_users = filter(&self.lock().portal_users, |x| {
+            let found = search.as_ref().is_none_or(|search| {
+                return [&x.email, &x.first_name, &x.last_name]
+                    .iter()
+                    .any(|x| return x.to_lowercase().contains(search));
+            });
+            return found && query.active.is_none_or(|active| return x.active == active);
+        });
+        portal_users.sort_by(|x, y| return x.email.cmp(&y.email));
+
+        return Ok(portal_users);
+    }
+
+    async fn update_portal_user(
+        &self,
+        id: uuid::Uuid,
+        payload: &PortalUserUpdate,
+    ) -> DbServiceResult<PortalUser> {
+        let mut tables = self.lock();
+        if let Some(email) = &payload.email {
+            if tables
+                .portal_users
+                .iter()
+                .any(|x| return x.id != id && x.email == *email)
+            {
+                return Err(unique_violation("portal_users_email_key"));
+            }
+        }
+
+        let portal_user = match tables.portal_users.iter_mut().find(|x| return x.id == id) {
+            Some(portal_user) => portal_user,
+            None => return Err(sqlx::Error::RowNotFound),
+        };
+
+        if let Some(email) = &payload.email {
+            portal_user.email = email.clone();
+        }
+        if let Some(first_name) = &payload.first_name {
+            portal_user.first_name = first_name.clone();
+        }
+        if let Some(last_name) = &payload.last_name {
+            portal_user.last_name = last_name.clone();
+        }
+        if let Some(roles) = &payload.roles {
+            portal_user.roles = roles.clone();
+        }
+
+        return Ok(portal_user.clone());
+    }
+
+    async fn set_portal_user_active(&self, id: uuid::Uuid, active: bool) -> DbServiceResult<()> {
+        let mut tables = self.lock();
+        for portal_user in tables.portal_users.iter_mut().filter(|x| return x.id == id) {
+            portal_user.active = active;
+        }
+
+        if !active {
+            tables.revoke_sessions(id, |_| return false);
+        }
+
+        return Ok(());
+    }
+
+    async fn update_portal_user_password(
+        &self,
+        id: uuid::Uuid,
+        password: &str,
+        keep_session_id: Option<&str>,
+    ) -> DbServiceResult<()> {
+        let mut tables = self.lock();
+        for portal_user in tables.portal_users.iter_mut().filter(|x| return x.id == id) {
+            portal_user.password = password.to_string();
+        }
+
+        tables.revoke_sessions(id, |x| {
+            return keep_session_id.is_some_and(|keep| return is_id(&x.id, keep));
+        });
+
+        return Ok(());
+    }
+
+    async fn create_password_reset_token(
+        &self,
+        portal_user_id: uuid::Uuid,
+        token: &str,
+        expires_at: chrono::DateTime<chrono::Utc>,
+    ) -> DbServiceResult<PasswordResetToken> {
+        let mut tables = self.lock();
+        if !tables
+            .portal_users
+            .iter()
+            .any(|x| return x.id == portal_user_id)
+        {
+            return Err(foreign_key_violation(
+                "portal_users_password_resets_portal_user_id_fkey",
+            ));
+        }
+
+        let reset_token = PasswordResetToken {
+            id: uuid::Uuid::new_v4(),
+            portal_user_id,
+            token: token.to_string(),
+            created_at: chrono::Utc::now(),
+            expires_at,
+            used_at: None,
+        };
+        tables
+            .portal_users_password_resets
+            .push(reset_token.clone());
+
+        return Ok(reset_token);
+    }
+
+    async fn get_password_reset_token(
+        &self,
+        id: &str,
+    ) -> DbServiceResult<Option<PasswordResetToken>> {
+        return Ok(find(&self.lock().portal_users_password_resets, |x| {
+            return is_id(&x.id, id);
+        }));
+    }
+
+    async fn use_password_reset_token(
+        &self,
+        id: uuid::Uuid,
+        password: &str,
+    ) -> DbServiceResult<bool> {
+        let mut tables = self.lock();
+        let now = chrono::Utc::now();
+        let portal_user_id = match tables
+            .portal_users_password_resets
+            .iter()
+            .find(|x| return x.id == id && x.used_at.is_none() && x.expires_at > now)
+        {
+            Some(reset_token) => reset_token.portal_user_id,
+            None => return Ok(false),
+        };
+
+        for portal_user in tables
+            .portal_users
+            .iter_mut()
+            .filter(|x| return x.id == portal_user_id)
+        {
+            portal_user.password = password.to_string();
+        }
+
+        for reset_token in tables.portal_users_password_resets.iter_mut() {
+            if reset_token.portal_user_id == portal_user_id && reset_token.used_at.is_none() {
+                reset_token.used_at = Some(now);
+            }
+        }
+
+        tables.revoke_sessions(portal_user_id, |_| return false);
+
+        return Ok(true);
+    }
+
+    async fn create_login_attempt(
+        &self,
+        email: &str,
+        portal_user_id: Option<uuid::Uuid>,
+        client: &LoginClient,
+        failure_reason: Option<LoginFailureReason>,
+    ) -> DbServiceResult<LoginAttempt> {
+        let login_attempt = LoginAttempt {
+            id: uuid::Uuid::new_v4(),
+            email: email.to_string(),
+            ip_address: client.ip_address.clone(),
+            user_agent: client.user_agent.clone(),
+            successful: failure_reason.is_none(),
+            failure_reason,
+            created_at: chrono::Utc::now(),
+            portal_user_id,
+        };
+        self.lock()
+            .portal_users_login_attempts
+            .push(login_attempt.clone());
+
+        return Ok(login_attempt);
+    }
+
+    async fn get_login_attempts(
+        &self,
+        query: &LoginAttemptQuery,
+    ) -> DbServiceResult<Vec<LoginAttempt>> {
+        let mut login_attempts = filter(&self.lock().portal_users_login_attempts, |x| {
+            return query
+                .email
+                .as_ref()
+                .is_none_or(|email| return x.email == *email)
+                && query
+                    .ip_address
+                    .as_ref()
+                    .is_none_or(|ip_address| return x.ip_address == *ip_address)
+                && query
+                    .successful
+                    .is_none_or(|successful| return x.successful == successful);
+        });
+        login_attempts.reverse();
+        login_attempts.sort_by(|x, y| return y.created_at.cmp(&x.created_at));
+        login_attempts.truncate(query.limit.unwrap_or(100).max(0) as usize);
+
+        return Ok(login_attempts);
+    }
+
+    async fn get_account_login_failures(&self, email: &str) -> DbServiceResult<LoginFailures> {
+        let tables = self.lock();
+        let since = chrono::Utc::now() - chrono::TimeDelta::days(1);
+        let last_success = tables
+            .portal_users_login_attempts
+            .iter()
+            .filter(|x| return x.email == email && x.successful)
+            .map(|x| return x.created_at)
+            .max();
+
+        return Ok(tables.login_failures(|x| {
+            return x.email == email
+                && x.created_at > since
+                && last_success.is_none_or(|last_success| return x.created_at > last_success);
+        }));
+    }
+
+    async fn get_ip_address_login_failures(
+        &self,
+        ip_address: &str,
+    ) -> DbServiceResult<LoginFailures> {
+        let since = chrono::Utc::now() - chrono::TimeDelta::hours(1);
+
+        return Ok(self.lock().login_failures(|x| {
+            return x.ip_address == ip_address && x.created_at > since;
+        }));
+    }
+
+    async fn start_portal_user_mfa_enrollment(
+        &self,
+        id: uuid::Uuid,
+        secret: &str,
+    ) -> DbServiceResult<()> {
+        for portal_user in self
+            .lock()
+            .portal_users
+            .iter_mut()
+            .filter(|x| return x.id == id)
+        {
+            portal_user.mfa_secret = Some(secret.to_string());
+            portal_user.mfa_enabled = false;
+            portal_user.mfa_last_step = None;
+        }
+
+        return Ok(());
+    }
+
+    async fn enable_portal_user_mfa(
+        &self,
+        id: uuid::Uuid,
+        step: i64,
+        recovery_codes: &[String],
+    ) -> DbServiceResult<()> {
+        let mut tables = self.lock();
+        for portal_user in tables.portal_users.iter_mut().filter(|x| return x.id == id) {
+            portal_user.mfa_enabled = true;
+            portal_user.mfa_last_step = Some(step);
+        }
+
+        tables.replace_recovery_codes(id, recovery_codes);
+
+        return Ok(());
+    }
+
+    async fn disable_portal_user_mfa(&self, id: uuid::Uuid) -> DbServiceResult<()> {
+        let mut tables = self.lock();
+        for portal_user in tables.portal_users.iter_mut().filter(|x| return x.id == id) {
+            portal_user.mfa_secret = None;
+            portal_user.mfa_enabled = false;
+            portal_user.mfa_last_step = None;
+        }
+
+        tables.replace_recovery_codes(id, &[]);
+
+        return Ok(());
+    }
+
+    async fn use_portal_user_mfa_step(&self, id: uuid::Uuid, step: i64) -> DbServiceResult<bool> {
+        let mut tables = self.lock();
+        let portal_user = tables.portal_users.iter_mut().find(|x| {
+            return x.id == id
+                && x.mfa_last_step
+                    .is_none_or(|last_step| return last_step < step);
+        });
+
+        return Ok(match portal_user {
+            Some(portal_user) => {
+                portal_user.mfa_last_step = Some(step);
+                true
+            }
+            None => false,
+        });
+    }
+
+    async fn replace_portal_user_recovery_codes(
+        &self,
+        id: uuid::Uuid,
+        recovery_codes: &[String],
+    ) -> DbServiceResult<()> {
+        self.lock().replace_recovery_codes(id, recovery_codes);
+
+        return Ok(());
+    }
+
+    async fn get_portal_user_recovery_codes(
+        &self,
+        id: uuid::Uuid,
+    ) -> DbServiceResult<Vec<RecoveryCode>> {
+        return Ok(filter(&self.lock().portal_users_recovery_codes, |x| {
+            return x.portal_user_id == id && x.used_at.is_none();
+        }));
+    }
+
+    async fn use_portal_user_recovery_code(&self, id: uuid::Uuid) -> DbServiceResult<bool> {
+        let mut tables = self.lock();
+        let recovery_code = tables
+            .portal_users_recovery_codes
+            .iter_mut()
+            .find(|x| return x.id == id && x.used_at.is_none());
+
+        return Ok(match recovery_code {
+            Some(recovery_code) => {
+                recovery_code.used_at = Some(chrono::Utc::now());
+                true
+            }
+            None => false,
+        });
+    }
+
+    async fn create_mfa_challenge(
+        &self,
+        portal_user_id: uuid::Uuid,
+        token: &str,
+        expires_at: chrono::DateTime<chrono::Utc>,
+    ) -> DbServiceResult<MfaChallenge> {
+        let mut tables = self.lock();
+        if !tables
+            .portal_users
+            .iter()
+            .any(|x| return x.id == portal_user_id)
+        {
+            return Err(foreign_key_violation(
+                "portal_users_mfa_challenges_portal_user_id_fkey",
+            ));
+        }
+
+        let challenge = MfaChallenge {
+            id: uuid::Uuid::new_v4(),
+            portal_user_id,
+            token: token.to_string(),
+            attempts: 0,
+            created_at: chrono::Utc::now(),
+            expires_at,
+            used_at: None,
+        };
+        tables.portal_users_mfa_challenges.push(challenge.clone());
+
+        return Ok(challenge);
+    }
+
+    async fn get_mfa_challenge(&self, id: &str) -> DbServiceResult<Option<MfaChallenge>> {
+        return Ok(find(&self.lock().portal_users_mfa_challenges, |x| {
+            return is_id(&x.id, id);
+        }));
+    }
+
+    async fn fail_mfa_challenge(&self, id: uuid::Uuid) -> DbServiceResult<()> {
+        for challenge in self
+            .lock()
+            .portal_users_mfa_challenges
+            .iter_mut()
+            .filter(|x| return x.id == id)
+        {
+            challenge.attempts += 1;
+        }
+
+        return Ok(());
+    }
+
+    async fn use_mfa_challenge(&self, id: uuid::Uuid) -> DbServiceResult<bool> {
+        let mut tables = self.lock();
+        let now = chrono::Utc::now();
+        let challenge = tables
+            .portal_users_mfa_challenges
+            .iter_mut()
+            .find(|x| return x.id == id && x.used_at.is_none() && x.expires_at > now);
+
+        return Ok(match challenge {
+            Some(challenge) => {
+                challenge.used_at = Some(now);
+                true
+            }
+            None => false,
+        });
+    }
+
+    async fn get_api_keys(&self) -> DbServiceResult<Vec<ApiKey>> {
+        let mut api_keys = self.lock().api_keys.clone();
+        api_keys.reverse();
+        api_keys.sort_by(|x, y| return y.created_at.cmp(&x.created_at));
+
+        return Ok(api_keys);
+    }
+
+    async fn get_api_key(&self, id: &str) -> DbServiceResult<Option<ApiKey>> {
+        return Ok(find(&self.lock().api_keys, |x| return is_id(&x.id, id)));
+    }
+
+    async fn create_api_key(
+        &self,
+        payload: &CreateApiKey,
+        key_hash: &str,
+        created_by: &str,
+    ) -> DbServiceResult<ApiKey> {
+        let mut tables = self.lock();
+        let created_by = tables
+            .portal_users
+            .iter()
+            .find(|x| return is_id(&x.id, created_by))
+            .map(|x| return x.id);
+
+        let api_key = ApiKey {
+            id: uuid::Uuid::new_v4(),
+            name: payload.name.clone(),
+            key_hash: key_hash.to_string(),
+            scopes: payload.scopes.clone(),
+            created_at: chrono::Utc::now(),
+            expires_at: payload.expires_at,
+            last_used_at: None,
+            revoked_at: None,
+            created_by,
+        };
+        tables.api_keys.push(api_key.clone());
+
+        return Ok(api_key);
+    }
+
+    async fn revoke_api_key(&self, id: &str) -> DbServiceResult<()> {
+        let now = chrono::Utc::now();
+        for api_key in self.lock().api_keys.iter_mut() {
+            if is_id(&api_key.id, id) && api_key.revoked_at.is_none() {
+                api_key.revoked_at = Some(now);
+            }
+        }
+
+        return Ok(());
+    }
+
+    async fn touch_api_key(&self, id: uuid::Uuid) -> DbServiceResult<()> {
+        let now = chrono::Utc::now();
+        for api_key in self
+            .lock()
+            .api_keys
+            .iter_mut()
+            .filter(|x| return x.id == id)
+        {
+            api_key.last_used_at = Some(now);
+        }
+
+        return Ok(());
+    }
+
+    async fn get_portal_roles(&self) -> DbServiceResult<Vec<PortalRole>> {
+        let mut roles = self.lock().portal_roles.clone();
+        roles.sort_by(|x, y| return x.name.cmp(&y.name));
+
+        return Ok(roles);
+    }
+
+    async fn get_portal_role(&self, id: &str) -> DbServiceResult<Option<PortalRole>> {
+        return Ok(find(&self.lock().portal_roles, |x| return is_id(&x.id, id)));
+    }
+
+    async fn create_portal_role(&self, payload: &CreatePortalRole) -> DbServiceResult<PortalRole> {
+        let mut tables = self.lock();
+        if tables
+            .portal_roles
+            .iter()
+            .any(|x| return x.name == payload.name)
+        {
+            return Err(unique_violation("portal_roles_name_key"));
+        }
+
+        let role = PortalRole {
+            id: uuid::Uuid::new_v4(),
+            name: payload.name.clone(),
+            permissions: payload.permissions.clone(),
+        };
+        tables.portal_roles.push(role.clone());
+
+        return Ok(role);
+    }
+
+    async fn delete_portal_role(&self, id: &str) -> DbServiceResult<()> {
+        let mut tables = self.lock();
+        tables.portal_roles.retain(|x| return !is_id(&x.id, id));
+        tables
+            .portal_users_portal_roles
+            .retain(|(_, role_id)| return !is_id(role_id, id));
+
+        return Ok(());
+    }
+
+    async fn get_portal_user_roles(
+        &self,
+        portal_user_id: &str,
+    ) -> DbServiceResult<Vec<PortalRole>> {
+        let tables = self.lock();
+        let mut roles = join(
+            &tables.portal_users_portal_roles,
+            &tables.portal_roles,
+            portal_user_id,
+            true,
+            |x| return x.id,
+        );
+        roles.sort_by(|x, y| return x.name.cmp(&y.name));
+
+        return Ok(roles);
+    }
+
+    async fn assign_portal_roles(
+        &self,
+        portal_user_id: uuid::Uuid,
+        payload: &AssignPortalRoles,
+    ) -> DbServiceResult<()> {
+        let mut tables = self.lock();
+        if !tables
+            .portal_users
+            .iter()
+            .any(|x| return x.id == portal_user_id)
+        {
+            return Err(foreign_key_violation(
+                "portal_users_portal_roles_portal_user_id_fkey",
+            ));
+        }
+        if payload
+            .role_ids
+            .iter()
+            .any(|id| return !tables.portal_roles.iter().any(|x| return x.id == *id))
+        {
+            return Err(foreign_key_violation(
+                "portal_users_portal_roles_portal_role_id_fkey",
+            ));
+        }
+
+        tables
+            .portal_users_portal_roles
+            .retain(|(user_id, _)| return *user_id != portal_user_id);
+        for role_id in &payload.role_ids {
+            if tables
+                .portal_users_portal_roles
+                .contains(&(portal_user_id, *role_id))
+            {
+                return Err(unique_violation("portal_users_portal_roles_pkey"));
+            }
+            tables
+                .portal_users_portal_roles
+                .push((portal_user_id, *role_id));
+        }
+
+        return Ok(());
+    }
+
+    async fn get_portal_user_permissions(
+        &self,
+        portal_user_id: &str,
+    ) -> DbServiceResult<Vec<String>> {
+        let tables = self.lock();
+        let roles = join(
+            &tables.portal_users_portal_roles,
+            &tables.portal_roles,
+            portal_user_id,
+            true,
+            |x| return x.id,
+        );
+
+        let mut permissions: Vec<String> = Vec::new();
+        for permission in roles.into_iter().flat_map(|x| return x.permissions) {
+            if !permissions.contains(&permission) {
+                permissions.push(permission);
+            }
+        }
+
+        return Ok(permissions);
+    }
+
+    async fn create_custom_field(
+        &self,
+        payload: CreateCustomField,
+        custom_object_id: Option<uuid::Uuid>,
+    ) -> DbServiceResult<FieldExtension> {
+        let mut tables = self.lock();
+        if tables.custom_fields.iter().any(|x| {
+            return x.object == payload.object
+                && x.custom_object_id == custom_object_id
+                && x.name == payload.base_felds.name;
+        }) {
+            return Err(unique_violation("_metadata_custom_fields_name_key"));
+        }
+        if let Some(custom_object_id) = custom_object_id {
+            if !tables
+                .custom_objects
+                .iter()
+                .any(|x| return x.id == custom_object_id)
+            {
+                return Err(foreign_key_violation(
+                    "_metadata_custom_fields_custom_object_id_fkey",
+                ));
+            }
+        }
+
+        let mut field = FieldExtension {
+            id: uuid::Uuid::new_v4(),
+            object: payload.object,
+            r#type: FieldExtensionType::STRING,
+            name: payload.base_felds.name,
+            mandatory: payload.base_felds.mandatory,
+            description: payload.base_felds.description,
+            max_len: None,
+            min_len: None,
+            scale: None,
+            allowed_values: None,
+            reference_object: None,
+            custom_object_id,
+            version: 1,
+        };
+
+        match payload.custom {
+            CreateCustomFieldEntry::STRING(fields) => {
+                field.max_len = fields.max_len;
+                field.min_len = fields.min_len;
+            }
+            CreateCustomFieldEntry::INT => field.r#type = FieldExtensionType::INT,
+            CreateCustomFieldEntry::BOOLEAN => field.r#type = FieldExtensionType::BOOLEAN,
+            CreateCustomFieldEntry::DECIMAL(fields) => {
+                field.r#type = FieldExtensionType::DECIMAL;
+                field.scale = fields.scale;
+            }
+            CreateCustomFieldEntry::DATE => field.r#type = FieldExtensionType::DATE,
+            CreateCustomFieldEntry::DATETIME => field.r#type = FieldExtensionType::DATETIME,
+            CreateCustomFieldEntry::ENUM(fields) => {
+                field.r#type = FieldExtensionType::ENUM;
+                field.allowed_values = Some(fields.allowed_values);
+            }
+            CreateCustomFieldEntry::SET(fields) => {
+                field.r#type = FieldExtensionType::SET;
+                field.allowed_values = fields.allowed_values;
+            }
+            CreateCustomFieldEntry::REFERENCE(fields) => {
+                field.r#type = FieldExtensionType::REFERENCE;
+                field.reference_object = Some(fields.reference_object);
+            }
+            CreateCustomFieldEntry::JSON => field.r#type = FieldExtensionType::JSON,
+        };
+
+        tables.push_custom_field_version(&field);
+        tables.custom_fields.push(field.clone());
+
+        return Ok(field);
+    }
+
+    async fn get_custom_field(
+        &self,
+        object_type: FieldExtensionObject,
+        field_name: &str,
+    ) -> DbServiceResult<Option<FieldExtension>> {
+        return Ok(find(&self.lock().custom_fields, |x| {
+            return x.object == object_type && x.name == field_name;
+        }));
+    }
+
+    async fn get_custom_fields(
+        &self,
+        object_type: FieldExtensionObject,
+    ) -> DbServiceResult<Vec<FieldExtension>> {
+        return Ok(filter(&self.lock().custom_fields, |x| {
+            return x.object == object_type;
+        }));
+    }
+
+    async fn get_custom_field_by_id(
+        &self,
+        object_type: FieldExtensionObject,
+        id: &str,
+    ) -> DbServiceResult<Option<FieldExtension>> {
+        return Ok(find(&self.lock().custom_fields, |x| {
+            return x.object == object_type && is_id(&x.id, id);
+        }));
+    }
+
+    async fn update_custom_field(
+        &self,
+        id: uuid::Uuid,
+        payload: &UpdateCustomField,
+    ) -> DbServiceResult<FieldExtension> {
+        let mut tables = self.lock();
+        let field = match tables.custom_fields.iter_mut().find(|x| return x.id == id) {
+            Some(field) => field,
+            None => return Err(sqlx::Error::RowNotFound),
+        };
+
+        if let Some(description) = &payload.description {
+            field.description = Some(description.clone());
+        }
+        if let Some(mandatory) = payload.mandatory {
+            field.mandatory = mandatory;
+        }
+        if let Some(max_len) = payload.max_len {
+            field.max_len = Some(max_len);
+        }
+        if let Some(min_len) = payload.min_len {
+            field.min_len = Some(min_len);
+        }
+        field.version += 1;
+
+        let field = field.clone();
+        tables.push_custom_field_version(&field);
+
+        return Ok(field);
+    }
+
+    async fn delete_custom_field(&self, id: uuid::Uuid) -> DbServiceResult<()> {
+        let mut tables = self.lock();
+        tables.custom_fields.retain(|x| return x.id != id);
+        tables
+            .custom_field_versions
+            .retain(|x| return x.custom_field_id != id);
+        tables
+            .custom_field_migrations
+            .retain(|x| return x.custom_field_id != id);
+
+        return Ok(());
+    }
+
+    async fn migrate_custom_field(
+        &self,
+        id: uuid::Uuid,
+        version: i32,
+        payload: &MigrateCustomField,
+    ) -> DbServiceResult<Option<FieldExtension>> {
+        let mut tables = self.lock();
+        let field = match tables.custom_fields.iter().find(|x| return x.id == id) {
+            Some(field) if field.version == version => field,
+            _ => return Ok(None),
+        };
+
+        if let Some(name) = &payload.name {
+            if tables.custom_fields.iter().any(|x| {
+                return x.id != id
+                    && x.object == field.object
+                    && x.custom_object_id == field.custom_object_id
+                    && &x.name == name;
+            }) {
+                return Err(unique_violation("_metadata_custom_fields_name_key"));
+            }
+        }
+
+        let field = match tables.custom_fields.iter_mut().find(|x| return x.id == id) {
+            Some(field) => field,
+            None => return Ok(None),
+        };
+
+        if let Some(name) = &payload.name {
+            field.name = name.clone();
+        }
+        if let Some(description) = &payload.description {
+            field.description = Some(description.clone());
+        }
+        if let Some(mandatory) = payload.mandatory {
+            field.mandatory = mandatory;
+        }
+        if let Some(max_len) = payload.max_len {
+            field.max_len = Some(max_len);
+        }
+        if let Some(min_len) = payload.min_len {
+            field.min_len = Some(min_len);
+        }
+        if let Some(allowed_values) = &payload.allowed_values {
+            field.allowed_values = Some(allowed_values.clone());
+        }
+        field.version += 1;
+
+        let field = field.clone();
+        tables.push_custom_field_version(&field);
+
+        return Ok(Some(field));
+    }
+
+    async fn get_custom_field_versions(
+        &self,
+        custom_field_id: uuid::Uuid,
+    ) -> DbServiceResult<Vec<FieldExtensionVersion>> {
+        let mut versions = filter(&self.lock().custom_field_versions, |x| {
+            return x.custom_field_id == custom_field_id;
+        });
+        versions.sort_by_key(|x| return x.version);
+
+        return Ok(versions);
+    }
+
+    async fn create_custom_field_migration(
+        &self,
+        custom_field_id: uuid::Uuid,
+        from_version: i32,
+    ) -> DbServiceResult<Option<CustomFieldMigration>> {
+        let mut tables = self.lock();
+        if !tables
+            .custom_fields
+            .iter()
+            .any(|x| return x.id == custom_field_id)
+        {
+            return Err(foreign_key_violation(
+                "_metadata_custom_field_migrations_custom_field_id_fkey",
+            ));
+        }
+
+        let migration = CustomFieldMigration {
+            id: uuid::Uuid::new_v4(),
+            custom_field_id,
+            from_version,
+            to_version: from_version + 1,
+            status: CustomFieldMigrationStatus::RUNNING,
+            migrated: 0,
+            dropped: 0,
+            error: None,
+            created_at: chrono::Utc::now(),
+            finished_at: None,
+        };
+        if tables.custom_field_migrations.iter().any(|x| {
+            return x.custom_field_id == custom_field_id
+                && x.status == CustomFieldMigrationStatus::RUNNING;
+        }) {
+            return Ok(None);
+        }
+
+        tables.custom_field_migrations.push(migration.clone());
+
+        return Ok(Some(migration));
+    }
+
+    async fn fail_running_custom_field_migrations(&self) -> DbServiceResult<u64> {
+        let mut tables = self.lock();
+        let mut failed = 0;
+        for migration in tables
+            .custom_field_migrations
+            .iter_mut()
+            .filter(|x| return x.status == CustomFieldMigrationStatus::RUNNING)
+        {
+            migration.status = CustomFieldMigrationStatus::FAILED;
+            migration.error =
+                Some("The service stopped while the migration was running".to_string());
+            migration.finished_at = Some(chrono::Utc::now());
+            failed += 1;
+        }
+
+        return Ok(failed);
+    }
+
+    async fn finish_custom_field_migration(
+        &self,
+        id: uuid::Uuid,
+        status: CustomFieldMigrationStatus,
+        migrated: i64,
+        dropped: i64,
+        error: Option<String>,
+    ) -> DbServiceResult<CustomFieldMigration> {
+        let mut tables = self.lock();
+        let migration = match tables
+            .custom_field_migrations
+            .iter_mut()
+            .find(|x| return x.id == id)
+        {
+            Some(migration) => migration,
+            None => return Err(sqlx::Error::RowNotFound),
+        };
+
+        migration.status = status;
+        migration.migrated = migrated;
+        migration.dropped = dropped;
+        migration.error = error;
+        migration.finished_at = Some(chrono::Utc::now());
+
+        return Ok(migration.clone());
+    }
+
+    async fn get_custom_field_migrations(
+        &self,
+        custom_field_id: uuid::Uuid,
+    ) -> DbServiceResult<Vec<CustomFieldMigration>> {
+        let mut migrations = filter(&self.lock().custom_field_migrations, |x| {
+            return x.custom_field_id == custom_field_id;
+        });
+        migrations.reverse();
+
+        return Ok(migrations);
+    }
+
+    async fn get_custom_field_migration(
+        &self,
+        custom_field_id: uuid::Uuid,
+        id: &str,
+    ) -> DbServiceResult<Option<CustomFieldMigration>> {
+        return Ok(find(&self.lock().custom_field_migrations, |x| {
+            return x.custom_field_id == custom_field_id && is_id(&x.id, id);
+        }));
+    }
+
+    async fn custom_field_object_exists(
+        &self,
+        object_type: FieldExtensionObject,
+        id: &str,
+    ) -> DbServiceResult<bool> {
+        let tables = self.lock();
+        return Ok(match object_type {
+            FieldExtensionObject::PRODUCT => {
+                tables.products.iter().any(|x| return is_id(&x.id, id))
+            }
+            FieldExtensionObject::CATEGORY => {
+                tables.categories.iter().any(|x| return is_id(&x.id, id))
+            }
+            FieldExtensionObject::INVENTORY => {
+                tables.inventories.iter().any(|x| return is_id(&x.id, id))
+            }
+            FieldExtensionObject::PRICEBOOK => {
+                tables.pricebooks.iter().any(|x| return is_id(&x.id, id))
+            }
+            FieldExtensionObject::PRODUCTIMAGE => {
+                tables.images.iter().any(|x| return is_id(&x.id, id))
+            }
+            FieldExtensionObject::INVENTORYRECORD => tables
+                .inventories_products
+                .iter()
+                .any(|x| return is_id(&x.id, id)),
+            FieldExtensionObject::PRICEBOOKRECORD => tables
+                .pricebooks_products
+                .iter()
+                .any(|x| return is_id(&x.id, id)),
+            FieldExtensionObject::PORTALUSER => {
+                tables.portal_users.iter().any(|x| return is_id(&x.id, id))
+            }
+            FieldExtensionObject::CUSTOMER => {
+                tables.customers.iter().any(|x| return is_id(&x.id, id))
+            }
+            FieldExtensionObject::CUSTOMOBJECT => tables
+                .custom_object_records
+                .iter()
+                .any(|x| return is_id(&x.id, id)),
+        });
+    }
+
+    async fn get_custom_field_object_ids(
+        &self,
+        object_type: FieldExtensionObject,
+        custom_object_id: Option<uuid::Uuid>,
+    ) -> DbServiceResult<Vec<String>> {
+        fn ids<T>(rows: &[T], id: impl Fn(&T) -> uuid::Uuid) -> Vec<String> {
+            return rows.iter().map(|x| return id(x).to_string()).collect();
+        }
+
+        let tables = self.lock();
+        return Ok(match object_type {
+            FieldExtensionObject::PRODUCT => ids(&tables.products, |x| return x.id),
+            FieldExtensionObject::CATEGORY => ids(&tables.categories, |x| return x.id),
+            FieldExtensionObject::INVENTORY => ids(&tables.inventories, |x| return x.id),
+            FieldExtensionObject::PRICEBOOK => ids(&tables.pricebooks, |x| return x.id),
+            FieldExtensionObject::PRODUCTIMAGE => ids(&tables.images, |x| return x.id),
+            FieldExtensionObject::INVENTORYRECORD => {
+                ids(&tables.inventories_products, |x| return x.id)
+            }
+            FieldExtensionObject::PRICEBOOKRECORD => {
+                ids(&tables.pricebooks_products, |x| return x.id)
+            }
+            FieldExtensionObject::PORTALUSER => ids(&tables.portal_users, |x| return x.id),
+            FieldExtensionObject::CUSTOMER => ids(&tables.customers, |x| return x.id),
+            FieldExtensionObject::CUSTOMOBJECT => ids(
+                &filter(&tables.custom_object_records, |x| {
+                    return custom_object_id.is_none_or(|id| return x.custom_object_id == id);
+                }),
+                |x| return x.id,
+            ),
+        });
+    }
+
+    async fn delete_custom_field_object(
+        &self,
+        object_type: FieldExtensionObject,
+        id: uuid::Uuid,
+    ) -> DbServiceResult<Vec<(FieldExtensionObject, uuid::Uuid)>> {
+        let mut tables = self.lock();
+        let mut removed: Vec<(FieldExtensionObject, uuid::Uuid)> = vec![];
+        match object_type {
+            FieldExtensionObject::PRODUCT => {
+                tables.categories_products.retain(|(_, x)| return *x != id);
+                tables.images.retain(|x| {
+                    if x.product_id == id {
+                        removed.push((FieldExtensionObject::PRODUCTIMAGE, x.id));
+                    }
+                    return x.product_id != id;
+                });
+                tables.inventories_products.retain(|x| {
+                    if x.product_id == id {
+                        removed.push((FieldExtensionObject::INVENTORYRECORD, x.id));
+                    }
+                    return x.product_id != id;
+                });
+                tables.pricebooks_products.retain(|x| {
+                    if x.product_id == id {
+                        removed.push((FieldExtensionObject::PRICEBOOKRECORD, x.id));
+                    }
+                    return x.product_id != id;
+                });
+                tables.products.retain(|x| return x.id != id);
+            }
+            FieldExtensionObject::CATEGORY => tables.categories.retain(|x| return x.id != id),
+            FieldExtensionObject::INVENTORY => {
+                tables.inventories_products.retain(|x| {
+                    if x.inventory_id == id {
+                        removed.push((FieldExtensionObject::INVENTORYRECORD, x.id));
+                    }
+                    return x.inventory_id != id;
+                });
+                tables.inventories.retain(|x| return x.id != id);
+            }
+            FieldExtensionObject::PRICEBOOK => {
+                tables
+                    .customer_groups_pricebooks
+                    .retain(|(_, x)| return *x != id);
+                tables.pricebooks_products.retain(|x| {
+                    if x.pricebook_id == id {
+                        removed.push((FieldExtensionObject::PRICEBOOKRECORD, x.id));
+                    }
+                    return x.pricebook_id != id;
+                });
+                tables.pricebooks.retain(|x| return x.id != id);
+            }
+            FieldExtensionObject::PRODUCTIMAGE => tables.images.retain(|x| return x.id != id),
+            FieldExtensionObject::INVENTORYRECORD => {
+                tables.inventories_products.retain(|x| return x.id != id)
+            }
+            FieldExtensionObject::PRICEBOOKRECORD => {
+                tables.pricebooks_products.retain(|x| return x.id != id)
+            }
+            FieldExtensionObject::PORTALUSER => tables.portal_users.retain(|x| return x.id != id),
+            FieldExtensionObject::CUSTOMER => tables.customers.retain(|x| return x.id != id),
+            FieldExtensionObject::CUSTOMOBJECT => {
+                tables.custom_object_records.retain(|x| return x.id != id)
+            }
+        };
+
+        return Ok(removed);
+    }
+
+    async fn get_custom_object_types(&self) -> DbServiceResult<Vec<CustomObjectType>> {
+        let mut custom_objects = self.lock().custom_objects.clone();
+        custom_objects.sort_by(|x, y| return x.name.cmp(&y.name));
+
+        return Ok(custom_objects);
+    }
+
+    async fn get_custom_object_type(
+        &self,
+        name: &str,
+    ) -> DbServiceResult<Option<CustomObjectType>> {
+        return Ok(find(&self.lock().custom_objects, |x| return x.name == name));
+    }
+
+    async fn create_custom_object_type(
+        &self,
+        payload: &CreateCustomObjectType,
+    ) -> DbServiceResult<CustomObjectType> {
+        let mut tables = self.lock();
+        if tables
+            .custom_objects
+            .iter()
+            .any(|x| return x.name == payload.name)
+        {
+            return Err(unique_violation("_metadata_custom_objects_name_key"));
+        }
+
+        let custom_object = CustomObjectType {
+            id: uuid::Uuid::new_v4(),
+            name: payload.name.clone(),
+            description: payload.description.clone(),
+            created_at: chrono::Utc::now(),
+        };
+        tables.custom_objects.push(custom_object.clone());
+
+        return Ok(custom_object);
+    }
+
+    async fn delete_custom_object_type(&self, id: uuid::Uuid) -> DbServiceResult<()> {
+        let mut tables = self.lock();
+        tables.custom_objects.retain(|x| return x.id != id);
+        tables
+            .custom_fields
+            .retain(|x| return x.custom_object_id != Some(id));
+        tables
+            .custom_object_records
+            .retain(|x| return x.custom_object_id != id);
+
+        return Ok(());
+    }
+
+    async fn get_custom_object_fields(
+        &self,
+        custom_object_id: uuid::Uuid,
+    ) -> DbServiceResult<Vec<FieldExtension>> {
+        let mut fields = filter(&self.lock().custom_fields, |x| {
+            return x.custom_object_id == Some(custom_object_id);
+        });
+        fields.sort_by(|x, y| return x.name.cmp(&y.name));
+
+        return Ok(fields);
+    }
+
+    async fn get_custom_object_records(
+        &self,
+        custom_object_id: uuid::Uuid,
+    ) -> DbServiceResult<Vec<CustomObjectRecord>> {
+        return Ok(filter(&self.lock().custom_object_records, |x| {
+            return x.custom_object_id == custom_object_id;
+        }));
+    }
+
+    async fn get_custom_object_record(
+        &self,
+        custom_object_id: uuid::Uuid,
+        id: &str,
+    ) -> DbServiceResult<Option<CustomObjectRecord>> {
+        return Ok(find(&self.lock().custom_object_records, |x| {
+            return x.custom_object_id == custom_object_id && is_id(&x.id, id);
+        }));
+    }
+
+    async fn create_custom_object_record(
+        &self,
+        custom_object_id: uuid::Uuid,
+    ) -> DbServiceResult<CustomObjectRecord> {
+        let mut tables = self.lock();
+        if !tables
+            .custom_objects
+            .iter()
+            .any(|x| return x.id == custom_object_id)
+        {
+            return Err(foreign_key_violation(
+                "_metadata_custom_object_records_custom_object_id_fkey",
+            ));
+        }
+
+        let now = chrono::Utc::now();
+        let record = CustomObjectRecord {
+            id: uuid::Uuid::new_v4(),
+            custom_object_id,
+            created_at: now,
+            updated_at: now,
+        };
+        tables.custom_object_records.push(record.clone());
+
+        return Ok(record);
+    }
+
+    async fn touch_custom_object_record(
+        &self,
+        id: uuid::Uuid,
+    ) -> DbServiceResult<CustomObjectRecord> {
+        let mut tables = self.lock();
+        let record = match tables
+            .custom_object_records
+            .iter_mut()
+            .find(|x| return x.id == id)
+        {
+            Some(record) => record,
+            None => return Err(sqlx::Error::RowNotFound),
+        };
+
+        record.updated_at = chrono::Utc::now();
+
+        return Ok(record.clone());
+    }
+
+    async fn delete_custom_object_record(&self, id: uuid::Uuid) -> DbServiceResult<()> {
+        self.lock()
+            .custom_object_records
+            .retain(|x| return x.id != id);
+
+        return Ok(());
+    }
+
+    async fn get_shipping_methods(&self) -> DbServiceResult<Vec<ShippingMethod>> {
+        return Ok(self.lock().shipping_methods.clone());
+    }
+
+    async fn get_shipping_method_by_id(&self, id: &str) -> DbServiceResult<Option<ShippingMethod>> {
+        return Ok(find(&self.lock().shipping_methods, |x| {
+            return is_id(&x.id, id);
+        }));
+    }
+
+    async fn get_shipping_method_by_reference(
+        &self,
+        reference: &str,
+    ) -> DbServiceResult<Option<ShippingMethod>> {
+        return Ok(find(&self.lock().shipping_methods, |x| {
+            return x.shipping_method_reference == reference;
+        }));
+    }
+
+    async fn get_shipping_method_costs(
+        &self,
+        id: &str,
+    ) -> DbServiceResult<Vec<ShippingMethodCost>> {
+        return Ok(filter(&self.lock().shipping_methods_costs, |x| {
+            return is_id(&x.shipping_method_id, id);
+        }));
+    }
+
+    async fn get_shipping_method_weight_tiers(
+        &self,
+        id: &str,
+    ) -> DbServiceResult<Vec<ShippingMethodWeightTier>> {
+        let mut tiers = filter(&self.lock().shipping_methods_weight_tiers, |x| {
+            return is_id(&x.shipping_method_id, id);
+        });
+        tiers.sort_by(|x, y| return x.min_weight.cmp(&y.min_weight));
+
+        return Ok(tiers);
+    }
+
+    async fn get_shipping_method_excluded_categories(
+        &self,
+        id: &str,
+    ) -> DbServiceResult<Vec<Category>> {
+        let tables = self.lock();
+        return Ok(join(
+            &tables.shipping_methods_excluded_categories,
+            &tables.categories,
+            id,
+            true,
+            |x| return x.id,
+        ));
+    }
+
+    async fn create_shipping_method(
+        &self,
+        payload: &CreateShippingMethod,
+    ) -> DbServiceResult<ShippingMethod> {
+        let mut tables = self.lock();
+        if tables
+            .shipping_methods
+            .iter()
+            .any(|x| return x.shipping_method_reference == payload.shipping_method_reference)
+        {
+            return Err(unique_violation(
+                "shipping_methods_shipping_method_reference_key",
+            ));
+        }
+
+        // everything is checked before the first insert, like the transaction
+        // of the postgres implementation nothing is kept on a failure
+        for (index, cost) in payload.costs.iter().enumerate() {
+            if payload.costs[..index]
+                .iter()
+                .any(|x| return x.currency_code == cost.currency_code)
+            {
+                return Err(unique_violation(
+                    "shipping_methods_costs_shipping_method_id_currency_code_key",
+                ));
+            }
+        }
+
+        let tiers = payload.weight_tiers.as_deref().unwrap_or_default();
+        for (index, tier) in tiers.iter().enumerate() {
+            if tiers[..index].iter().any(|x| {
+                return x.currency_code == tier.currency_code && x.min_weight == tier.min_weight;
+            }) {
+                return Err(unique_violation(
+                    "shipping_methods_weight_tiers_shipping_method_id_currency_code_min_weight_key",
+                ));
+            }
+        }
+
+        let categories = payload.excluded_categories.as_deref().unwrap_or_default();
+        for (index, category_id) in categories.iter().enumerate() {
+            if !tables
+                .categories
+                .iter()
+                .any(|x| return x.id == *category_id)
+            {
+                return Err(foreign_key_violation(
+                    "shipping_methods_excluded_categories_category_id_fkey",
+                ));
+            }
+            if categories[..index].contains(category_id) {
+                return Err(unique_violation(
+                    "shipping_methods_excluded_categories_shipping_method_id_category_id_key",
+                ));
+            }
+        }
+
+        let shipping_method = ShippingMethod {
+            id: uuid::Uuid::new_v4(),
+            shipping_method_name: payload.shipping_method_name.clone(),
+            shipping_method_description: payload.shipping_method_description.clone(),
+            shipping_method_countries: payload
+                .shipping_method_countries
+                .clone()
+                .unwrap_or_default(),
+            shipping_method_reference: payload.shipping_method_reference.clone(),
+        };
+        tables.shipping_methods.push(shipping_method.clone());
+
+        for cost in &payload.costs {
+            tables.shipping_methods_costs.push(ShippingMethodCost {
+                id: uuid::Uuid::new_v4(),
+                shipping_method_id: shipping_method.id,
+                currency_code: cost.currency_code.clone(),
+                cost: cost.cost,
+                free_shipping_threshold: cost.free_shipping_threshold,
+            });
+        }
+
+        for tier in tiers {
+            tables
+                .shipping_methods_weight_tiers
+                .push(ShippingMethodWeightTier {
+                    id: uuid::Uuid::new_v4(),
+                    shipping_method_id: shipping_method.id,
+                    currency_code: tier.currency_code.clone(),
+                    min_weight: tier.min_weight,
+                    cost: tier.cost,
+                });
+        }
+
+        for category_id in categories {
+            tables
+                .shipping_methods_excluded_categories
+                .push((shipping_method.id, *category_id));
+        }
+
+        return Ok(shipping_method);
+    }
+
+    async fn get_tax_classes(&self) -> DbServiceResult<Vec<TaxClass>> {
+        return Ok(self.lock().tax_classes.clone());
+    }
+
+    async fn get_tax_class_by_id(&self, id: &str) -> DbServiceResult<Option<TaxClass>> {
+        return Ok(find(&self.lock().tax_classes, |x| return is_id(&x.id, id)));
+    }
+
+    async fn get_tax_class_by_reference(
+        &self,
+        reference: &str,
+    ) -> DbServiceResult<Option<TaxClass>> {
+        return Ok(find(&self.lock().tax_classes, |x| {
+            return x.tax_class_reference == reference;
+        }));
+    }
+
+    async fn create_tax_class(&self, payload: &CreateTaxClass) -> DbServiceResult<TaxClass> {
+        let mut tables = self.lock();
+        if tables
+            .tax_classes
+            .iter()
+            .any(|x| return x.tax_class_reference == payload.tax_class_reference)
+        {
+            return Err(unique_violation("tax_classes_tax_class_reference_key"));
+        }
+
+        let tax_class = TaxClass {
+            id: uuid::Uuid::new_v4(),
+            tax_class_name: payload.tax_class_name.clone(),
+            tax_class_reference: payload.tax_class_reference.clone(),
+        };
+        tables.tax_classes.push(tax_class.clone());
+
+        return Ok(tax_class);
+    }
+
+    async fn get_tax_rates(&self, tax_class_id: &str) -> DbServiceResult<Vec<TaxRate>> {
+        return Ok(filter(&self.lock().tax_rates, |x| {
+            return is_id(&x.tax_class_id, tax_class_id);
+        }));
+    }
+
+    async fn get_tax_rate(
+        &self,
+        tax_class_id: &str,
+        country_code: &str,
+        region: Option<&str>,
+    ) -> DbServiceResult<Option<TaxRate>> {
+        return Ok(find(&self.lock().tax_rates, |x| {
+            return is_id(&x.tax_class_id, tax_class_id)
+                && x.country_code.to_uppercase() == country_code.to_uppercase()
+                && x.region.as_ref().map(|x| return x.to_uppercase())
+                    == region.map(|x| return x.to_uppercase());
+        }));
+    }
+
+    async fn create_tax_rate(
+        &self,
+        tax_class_id: &str,
+        payload: &CreateTaxRate,
+    ) -> DbServiceResult<TaxRate> {
+        let tax_class_id = parse_uuid(tax_class_id)?;
+        let mut tables = self.lock();
+        if !tables
+            .tax_classes
+            .iter()
+            .any(|x| return x.id == tax_class_id)
+        {
+            return Err(foreign_key_violation("tax_rates_tax_class_id_fkey"));
+        }
+        if tables.tax_rates.iter().any(|x| {
+            return x.tax_class_id == tax_class_id
+                && x.country_code == payload.country_code
+                && x.region == payload.region;
+        }) {
+            return Err(unique_violation(
+                "tax_rates_tax_class_id_country_code_region_key",
+            ));
+        }
+
+        let tax_rate = TaxRate {
+            id: uuid::Uuid::new_v4(),
+            tax_class_id,
+            country_code: payload.country_code.clone(),
+            region: payload.region.clone(),
+            rate: payload.rate,
+        };
+        tables.tax_rates.push(tax_rate.clone());
+
+        return Ok(tax_rate);
+    }
+
+    async fn get_customers(&self) -> DbServiceResult<Vec<Customer>> {
+        return Ok(self.lock().customers.clone());
+    }
+
+    async fn get_customer(&self, id: &str) -> DbServiceResult<Option<Customer>> {
+        return Ok(find(&self.lock().customers, |x| return is_id(&x.id, id)));
+    }
+
+    async fn get_customer_by_email(&self, email: &str) -> DbServiceResult<Option<Customer>> {
+        return Ok(find(&self.lock().customers, |x| return x.email == email));
+    }
+
+    async fn create_customer(&self, payload: CustomerCreate) -> DbServiceResult<Customer> {
+        let password = match hash_password(&payload.password) {
+            Ok(password) => password,
+            Err(_) => return Err(sqlx::Error::Io(std::io::ErrorKind::InvalidInput.into())),
+        };
+
+        let mut tables = self.lock();
+        if tables
+            .customers
+            .iter()
+            .any(|x| return x.email == payload.email)
+        {
+            return Err(unique_violation("customers_email_key"));
+        }
+
+        let customer = Customer {
+            id: uuid::Uuid::new_v4(),
+            email: payload.email,
+            first_name: payload.first_name,
+            last_name: payload.last_name,
+            phone: payload.phone,
+            password,
+        };
+        tables.customers.push(customer.clone());
+
+        return Ok(customer);
+    }
+
+    async fn update_customer(
+        &self,
+        id: &str,
+        payload: &CustomerUpdate,
+    ) -> DbServiceResult<Customer> {
+        let mut tables = self.lock();
+        let customer = match tables
+            .customers
+            .iter_mut()
+            .find(|x| return is_id(&x.id, id))
+        {
+            Some(customer) => customer,
+            None => return Err(sqlx::Error::RowNotFound),
+        };
+
+        if let Some(first_name) = &payload.first_name {
+            customer.first_name = first_name.clone();
+        }
+        if let Some(last_name) = &payload.last_name {
+            customer.last_name = last_name.clone();
+        }
+        if let Some(phone) = &payload.phone {
+            customer.phone = Some(phone.clone());
+        }
+
+        return Ok(customer.clone());
+    }
+
+    async fn get_customer_addresses(
+        &self,
+        customer_id: &str,
+    ) -> DbServiceResult<Vec<CustomerAddress>> {
+        return Ok(filter(&self.lock().customers_addresses, |x| {
+            return is_id(&x.customer_id, customer_id);
+        }));
+    }
+
+    async fn create_customer_address(
+        &self,
+        customer_id: &str,
+        payload: &CreateCustomerAddress,
+    ) -> DbServiceResult<CustomerAddress> {
+        let customer_id = parse_uuid(customer_id)?;
+        let mut tables = self.lock();
+        if !tables.customers.iter().any(|x| return x.id == customer_id) {
+            return Err(foreign_key_violation(
+                "customers_addresses_customer_id_fkey",
+            ));
+        }
+
+        let is_default = payload.is_default.unwrap_or(false);
+        if is_default {
+            for address in tables
+                .customers_addresses
+                .iter_mut()
+                .filter(|x| return x.customer_id == customer_id)
+            {
+                address.is_default = false;
+            }
+        }
+
+        let address = CustomerAddress {
+            id: uuid::Uuid::new_v4(),
+            customer_id,
+            first_name: payload.first_name.clone(),
+            last_name: payload.last_name.clone(),
+            country_code: payload.address.country_code.clone(),
+            region: payload.address.region.clone(),
+            city: payload.address.city.clone(),
+            postal_code: payload.address.postal_code.clone(),
+            address_line: payload.address.address_line.clone(),
+            is_default,
+        };
+        tables.customers_addresses.push(address.clone());
+
+        return Ok(address);
+    }
+
+    async fn delete_customer_address(
+        &self,
+        customer_id: &str,
+        address_id: &str,
+    ) -> DbServiceResult<bool> {
+        let mut tables = self.lock();
+        let count = tables.customers_addresses.len();
+        tables.customers_addresses.retain(|x| {
+            return !is_id(&x.customer_id, customer_id) || !is_id(&x.id, address_id);
+        });
+
+        return Ok(tables.customers_addresses.len() < count);
+    }
+
+    async fn get_customer_groups(&self) -> DbServiceResult<Vec<CustomerGroup>> {
+        return Ok(self.lock().customer_groups.clone());
+    }
+
+    async fn get_customer_groups_by_type(
+        &self,
+        group_type: CustomerGroupType,
+    ) -> DbServiceResult<Vec<CustomerGroup>> {
+        return Ok(filter(&self.lock().customer_groups, |x| {
+            return x.customer_group_type == group_type;
+        }));
+    }
+
+    async fn get_customer_group_by_id(&self, id: &str) -> DbServiceResult<Option<CustomerGroup>> {
+        return Ok(find(&self.lock().customer_groups, |x| {
+            return is_id(&x.id, id);
+        }));
+    }
+
+    async fn get_customer_group_by_reference(
+        &self,
+        reference: &str,
+    ) -> DbServiceResult<Option<CustomerGroup>> {
+        return Ok(find(&self.lock().customer_groups, |x| {
+            return x.customer_group_reference == reference;
+        }));
+    }
+
+    async fn create_customer_group(
+        &self,
+        payload: &CreateCustomerGroup,
+    ) -> DbServiceResult<CustomerGroup> {
+        let mut tables = self.lock();
+        if tables
+            .customer_groups
+            .iter()
+            .any(|x| return x.customer_group_reference == payload.customer_group_reference)
+        {
+            return Err(unique_violation(
+                "customer_groups_customer_group_reference_key",
+            ));
+        }
+
+        let group = CustomerGroup {
+            id: uuid::Uuid::new_v4(),
+            customer_group_name: payload.customer_group_name.clone(),
+            customer_group_type: payload.customer_group_type,
+            customer_group_reference: payload.customer_group_reference.clone(),
+        };
+        tables.customer_groups.push(group.clone());
+
+        for rule in payload.rules.iter().flatten() {
+            tables.customer_groups_rules.push(CustomerGroupRule {
+                id: uuid::Uuid::new_v4(),
+                customer_group_id: group.id,
+                rule_type: rule.rule_type,
+                rule_value: rule.rule_value.clone(),
+            });
+        }
+
+        return Ok(group);
+    }
+
+    async fn get_customer_group_rules(&self, id: &str) -> DbServiceResult<Vec<CustomerGroupRule>> {
+        return Ok(filter(&self.lock().customer_groups_rules, |x| {
+            return is_id(&x.customer_group_id, id);
+        }));
+    }
+
+    async fn get_customer_group_customers(&self, id: &str) -> DbServiceResult<Vec<Customer>> {
+        let tables = self.lock();
+        return Ok(join(
+            &tables.customer_groups_customers,
+            &tables.customers,
+            id,
+            true,
+            |x| return x.id,
+        ));
+    }
+
+    async fn get_customer_static_groups(
+        &self,
+        customer_id: &str,
+    ) -> DbServiceResult<Vec<CustomerGroup>> {
+        let tables = self.lock();
+        let mut groups = join(
+            &tables.customer_groups_customers,
+            &tables.customer_groups,
+            customer_id,
+            false,
+            |x| return x.id,
+        );
+        groups.retain(|x| return x.customer_group_type == CustomerGroupType::STATIC);
+
+        return Ok(groups);
+    }
+
+    async fn create_customer_group_customer_entries(
+        &self,
+        payload: &AssignCustomersToGroup,
+    ) -> DbServiceResult<()> {
+        let mut tables = self.lock();
+        if !tables
+            .customer_groups
+            .iter()
+            .any(|x| return x.id == payload.customer_group_id)
+        {
+            return Err(foreign_key_violation(
+                "customer_groups_customers_customer_group_id_fkey",
+            ));
+        }
+        if payload
+            .customer_ids
+            .iter()
+            .any(|id| return !tables.customers.iter().any(|x| return x.id == *id))
+        {
+            return Err(foreign_key_violation(
+                "customer_groups_customers_customer_id_fkey",
+            ));
+        }
+
+        for customer_id in &payload.customer_ids {
+            let entry = (payload.customer_group_id, *customer_id);
+            if !tables.customer_groups_customers.contains(&entry) {
+                tables.customer_groups_customers.push(entry);
+            }
+        }
+
+        return Ok(());
+    }
+
+    async fn get_customer_group_pricebooks(&self, id: &str) -> DbServiceResult<Vec<Pricebook>> {
+        let tables = self.lock();
+        return Ok(join(
+            &tables.customer_groups_pricebooks,
+            &tables.pricebooks,
+            id,
+            true,
+            |x| return x.id,
+        ));
+    }
+
+    async fn create_customer_group_pricebook_entries(
+        &self,
+        payload: &AssignPricebooksToGroup,
+    ) -> DbServiceResult<()> {
+        let mut tables = self.lock();
+        if !tables
+            .customer_groups
+            .iter()
+            .any(|x| return x.id == payload.customer_group_id)
+        {
+            return Err(foreign_key_violation(
+                "customer_groups_pricebooks_customer_group_id_fkey",
+            ));
+        }
+        if payload
+            .pricebook_ids
+            .iter()
+            .any(|id| return !tables.pricebooks.iter().any(|x| return x.id == *id))
+        {
+            return Err(foreign_key_violation(
+                "customer_groups_pricebooks_pricebook_id_fkey",
+            ));
+        }
+
+        for pricebook_id in &payload.pricebook_ids {
+            let entry = (payload.customer_group_id, *pricebook_id);
+            if !tables.customer_groups_pricebooks.contains(&entry) {
+                tables.customer_groups_pricebooks.push(entry);
+            }
+        }
+
+        return Ok(());
+    }
+
+    async fn get_product_customer_prices(
+        &self,
+        product_id: &str,
+        currency_code: &str,
+        customer_groups: Vec<uuid::Uuid>,
+    ) -> DbServiceResult<Vec<PricebookRecord>> {
+        let tables = self.lock();
+
+        // pricebooks without a group assignment are public, the rest are only
+        // visible to the members of the groups they are assigned to
+        let is_visible = |pricebook_id: uuid::Uuid| {
+            let mut groups = tables
+                .customer_groups_pricebooks
+                .iter()
+                .filter(|(_, x)| return *x == pricebook_id)
+                .peekable();
+            return groups.peek().is_none()
+                || groups.any(|(x, _)| return customer_groups.contains(x));
+        };
+
+        let mut records = filter(&tables.pricebooks_products, |x| {
+            return is_id(&x.product_id, product_id)
+                && tables.pricebooks.iter().any(|pricebook| {
+                    return pricebook.id == x.pricebook_id
+                        && pricebook.pricebook_currency_code == currency_code;
+                })
+                && is_visible(x.pricebook_id);
+        });
+        records.sort_by(|x, y| return x.price.cmp(&y.price));
+
+        return Ok(records);
+    }
+
+    async fn get_customer_product_lists(
+        &self,
+        customer_id: &str,
+    ) -> DbServiceResult<Vec<ProductList>> {
+        return Ok(filter(&self.lock().product_lists, |x| {
+            return is_id(&x.customer_id, customer_id);
+        }));
+    }
+
+    async fn get_customer_product_list(
+        &self,
+        customer_id: &str,
+        id: &str,
+    ) -> DbServiceResult<Option<ProductList>> {
+        return Ok(find(&self.lock().product_lists, |x| {
+            return is_id(&x.customer_id, customer_id) && is_id(&x.id, id);
+        }));
+    }
+
+    async fn get_product_list_by_share_token(
+        &self,
+        share_token: &str,
+    ) -> DbServiceResult<Option<ProductList>> {
+        return Ok(find(&self.lock().product_lists, |x| {
+            return x.share_token == share_token;
+        }));
+    }
+
+    async fn create_product_list(
+        &self,
+        customer_id: &str,
+        share_token: &str,
+        payload: &CreateProductList,
+    ) -> DbServiceResult<ProductList> {
+        let customer_id = parse_uuid(customer_id)?;
+        let mut tables = self.lock();
+        if !tables.customers.iter().any(|x| return x.id == customer_id) {
+            return Err(foreign_key_violation("product_lists_customer_id_fkey"));
+        }
+        if tables
+            .product_lists
+            .iter()
+            .any(|x| return x.share_token == share_token)
+        {
+            return Err(unique_violation("product_lists_share_token_key"));
+        }
+
+        let product_list = ProductList {
+            id: uuid::Uuid::new_v4(),
+            customer_id,
+            product_list_name: payload.product_list_name.clone(),
+            product_list_type: payload
+                .product_list_type
+                .unwrap_or(ProductListType::WISHLIST),
+            is_public: payload.is_public.unwrap_or(false),
+            share_token: share_token.to_string(),
+        };
+        tables.product_lists.push(product_list.clone());
+
+        return Ok(product_list);
+    }
+
+    async fn update_product_list(
+        &self,
+        customer_id: &str,
+        id: &str,
+        payload: &UpdateProductList,
+    ) -> DbServiceResult<Option<ProductList>> {
+        let mut tables = self.lock();
+        let product_list = tables
+            .product_lists
+            .iter_mut()
+            .find(|x| return is_id(&x.customer_id, customer_id) && is_id(&x.id, id));
+
+        return Ok(product_list.map(|product_list| {
+            if let Some(product_list_name) = &payload.product_list_name {
+                product_list.product_list_name = product_list_name.clone();
+            }
+            if let Some(is_public) = payload.is_public {
+                product_list.is_public = is_public;
+            }
+
+            return product_list.clone();
+        }));
+    }
+
+    async fn delete_product_list(&self, customer_id: &str, id: &str) -> DbServiceResult<bool> {
+        let mut tables = self.lock();
+        let product_list_id = match tables
+            .product_lists
+            .iter()
+            .find(|x| return is_id(&x.customer_id, customer_id) && is_id(&x.id, id))
+        {
+            Some(product_list) => product_list.id,
+            None => return Ok(false),
+        };
+
+        tables
+            .product_lists
+            .retain(|x| return x.id != product_list_id);
+        tables
+            .product_lists_items
+            .retain(|x| return x.product_list_id != product_list_id);
+
+        return Ok(true);
+    }
+
+    async fn get_product_list_items(&self, id: &str) -> DbServiceResult<Vec<ProductListItem>> {
+        let mut items = filter(&self.lock().product_lists_items, |x| {
+            return is_id(&x.product_list_id, id);
+        });
+        items.sort_by_key(|x| return x.priority);
+
+        return Ok(items);
+    }
+
+    async fn put_product_list_item(
+        &self,
+        id: &str,
+        payload: &PutProductListItem,
+    ) -> DbServiceResult<ProductListItem> {
+        let product_list_id = parse_uuid(id)?;
+        let mut tables = self.lock();
+        if !tables
+            .product_lists
+            .iter()
+            .any(|x| return x.id == product_list_id)
+        {
+            return Err(foreign_key_violation(
+                "product_lists_items_product_list_id_fkey",
+            ));
+        }
+        if !tables
+            .products
+            .iter()
+            .any(|x| return x.id == payload.product_id)
+        {
+            return Err(foreign_key_violation("product_lists_items_product_id_fkey"));
+        }
+
+        let quantity = payload.quantity.unwrap_or(1);
+        let priority = payload.priority.unwrap_or(0);
+        if let Some(item) = tables.product_lists_items.iter_mut().find(|x| {
+            return x.product_list_id == product_list_id && x.product_id == payload.product_id;
+        }) {
+            item.quantity = quantity;
+            item.priority = priority;
+            return Ok(item.clone());
+        }
+
+        let item = ProductListItem {
+            id: uuid::Uuid::new_v4(),
+            product_list_id,
+            product_id: payload.product_id,
+            quantity,
+            priority,
+        };
+        tables.product_lists_items.push(item.clone());
+
+        return Ok(item);
+    }
+
+    async fn delete_product_list_item(&self, id: &str, product_id: &str) -> DbServiceResult<bool> {
+        let mut tables = self.lock();
+        let count = tables.product_lists_items.len();
+        tables.product_lists_items.retain(|x| {
+            return !is_id(&x.product_list_id, id) || !is_id(&x.product_id, product_id);
+        });
+
+        return Ok(tables.product_lists_items.len() < count);
+    }
+
+    async fn get_inventory_adjustments(
+        &self,
+        inventory_id: &str,
+    ) -> DbServiceResult<Vec<InventoryAdjustment>> {
+        let mut adjustments = filter(&self.lock().inventory_adjustments, |x| {
+            return is_id(&x.inventory_id, inventory_id);
+        });
+        adjustments.sort_by_key(|x| return x.created_at);
+
+        return Ok(adjustments);
+    }
+
+    async fn get_returns(&self) -> DbServiceResult<Vec<Return>> {
+        let mut returns = self.lock().returns.clone();
+        returns.sort_by_key(|x| return x.created_at);
+
+        return Ok(returns);
+    }
+
+    async fn get_return_by_id(&self, id: &str) -> DbServiceResult<Option<Return>> {
+        return Ok(find(&self.lock().returns, |x| return is_id(&x.id, id)));
+    }
+
+    async fn get_return_by_reference(&self, reference: &str) -> DbServiceResult<Option<Return>> {
+        return Ok(find(&self.lock().returns, |x| {
+            return x.return_reference == reference;
+        }));
+    }
+
+    async fn get_return_lines(&self, id: &str) -> DbServiceResult<Vec<ReturnLine>> {
+        return Ok(filter(&self.lock().returns_lines, |x| {
+            return is_id(&x.return_id, id);
+        }));
+    }
+
+    async fn create_return(&self, payload: &CreateReturn) -> DbServiceResult<Return> {
+        let mut tables = self.lock();
+        if tables
+            .returns
+            .iter()
+            .any(|x| return x.return_reference == payload.return_reference)
+        {
+            return Err(unique_violation("returns_return_reference_key"));
+        }
+        if let Some(customer_id) = payload.customer_id {
+            if !tables.customers.iter().any(|x| return x.id == customer_id) {
+                return Err(foreign_key_violation("returns_customer_id_fkey"));
+            }
+        }
+        if payload.lines.iter().any(|line| {
+            return !tables
+                .products
+                .iter()
+                .any(|x| return x.id == line.product_id);
+        }) {
+            return Err(foreign_key_violation("returns_lines_product_id_fkey"));
+        }
+
+        let rma = Return {
+            id: uuid::Uuid::new_v4(),
+            return_reference: payload.return_reference.clone(),
+            order_reference: payload.order_reference.clone(),
+            currency_code: payload.currency_code.clone(),
+            status: ReturnStatus::AUTHORIZED,
+            refund_amount: None,
+            created_at: chrono::Utc::now(),
+            customer_id: payload.customer_id,
+        };
+        tables.returns.push(rma.clone());
+
+        for line in &payload.lines {
+            tables.returns_lines.push(ReturnLine {
+                id: uuid::Uuid::new_v4(),
+                return_id: rma.id,
+                product_id: line.product_id,
+                order_line_reference: line.order_line_reference.clone(),
+                quantity: line.quantity,
+                unit_price: line.unit_price,
+                reason: line.reason,
+                comment: line.comment.clone(),
+                inspection_outcome: None,
+                restock_inventory_id: None,
+            });
+        }
+
+        return Ok(rma);
+    }
+
+    async fn inspect_return(
+        &self,
+        rma: &Return,
+        payload: &InspectReturn,
+        refund_amount: rust_decimal::Decimal,
+    ) -> DbServiceResult<Return> {
+        let mut tables = self.lock();
+
+        // everything is checked before the first change, like the transaction
+        // of the postgres implementation nothing is kept on a failure
+        for inspected in &payload.lines {
+            if !tables
+                .returns_lines
+                .iter()
+                .any(|x| return x.id == inspected.line_id && x.return_id == rma.id)
+            {
+                return Err(sqlx::Error::RowNotFound);
+            }
+            if let Some(inventory_id) = inspected.inventory_id {
+                if !tables
+                    .inventories
+                    .iter()
+                    .any(|x| return x.id == inventory_id)
+                {
+                    return Err(foreign_key_violation(
+                        "returns_lines_restock_inventory_id_fkey",
+                    ));
+                }
+            } else if inspected.outcome == ReturnInspectionOutcome::RESTOCK {
+                return Err(sqlx::Error::Protocol(
+                    "null value in column \"inventory_id\" violates not-null constraint"
+                        .to_string(),
+                ));
+            }
+        }
+
+        if !tables.returns.iter().any(|x| return x.id == rma.id) {
+            return Err(sqlx::Error::RowNotFound);
+        }
+
+        for inspected in &payload.lines {
+            let line = match tables
+                .returns_lines
+                .iter_mut()
+                .find(|x| return x.id == inspected.line_id && x.return_id == rma.id)
+            {
+                Some(line) => line,
+                None => return Err(sqlx::Error::RowNotFound),
+            };
+
+            line.inspection_outcome = Some(inspected.outcome);
+            line.restock_inventory_id = inspected.inventory_id;
+
+            let (product_id, quantity) = (line.product_id, line.quantity);
+            let inventory_id = match inspected.inventory_id {
+                Some(inventory_id) if inspected.outcome == ReturnInspectionOutcome::RESTOCK => {
+                    inventory_id
+                }
+                _ => continue,
+            };
+
+            tables.inventory_adjustments.push(InventoryAdjustment {
+                id: uuid::Uuid::new_v4(),
+                inventory_id,
+                product_id,
+                quantity,
+                reason: format!("Restock from return '{}'", rma.return_reference),
+                created_at: chrono::Utc::now(),
+            });
+
+            match tables
+                .inventories_products
+                .iter_mut()
+                .find(|x| return x.inventory_id == inventory_id && x.product_id == product_id)
+            {
+                Some(record) => record.allocation += quantity,
+                None => tables.inventories_products.push(ProductInventoryRecord {
+                    id: uuid::Uuid::new_v4(),
+                    product_id,
+                    inventory_id,
+                    allocation: quantity,
+                }),
+            };
+        }
+
+        let rma = match tables.returns.iter_mut().find(|x| return x.id == rma.id) {
+            Some(rma) => rma,
+            None => return Err(sqlx::Error::RowNotFound),
+        };
+        rma.status = ReturnStatus::INSPECTED;
+        rma.refund_amount = Some(refund_amount);
+
+        return Ok(rma.clone());
+    }
+
+    async fn get_signing_keys(&self) -> DbServiceResult<Vec<SigningKey>> {
+        let now = chrono::Utc::now();
+        let mut signing_keys = filter(&self.lock().signing_keys, |x| {
+            return x
+                .expires_at
+                .is_none_or(|expires_at| return expires_at > now);
+        });
+        signing_keys.reverse();
+        signing_keys.sort_by(|x, y| {
+            return y
+                .activates_at
+                .cmp(&x.activates_at)
+                .then(y.created_at.cmp(&x.created_at));
+        });
+
+        return Ok(signing_keys);
+    }
+
+    async fn create_signing_key(
+        &self,
+        algorithm: SigningAlgorithm,
+        private_key: &str,
+        public_key: &str,
+        activates_at: chrono::DateTime<chrono::Utc>,
+    ) -> DbServiceResult<SigningKey> {
+        let mut tables = self.lock();
+
+        // the current keys keep signing until the new one takes over and stay
+        // valid for the tokens they signed until then
+        for signing_key in tables
+            .signing_keys
+            .iter_mut()
+            .filter(|x| return x.retired_at.is_none())
+        {
+            signing_key.retired_at = Some(activates_at);
+            signing_key.expires_at = Some(activates_at + SIGNING_KEY_GRACE_PERIOD);
+        }
+
+        let signing_key = SigningKey {
+            id: uuid::Uuid::new_v4(),
+            algorithm,
+            private_key: private_key.to_string(),
+            public_key: public_key.to_string(),
+            created_at: chrono::Utc::now(),
+            activates_at,
+            retired_at: None,
+            expires_at: None,
+        };
+        tables.signing_keys.push(signing_key.clone());
+
+        return Ok(signing_key);
+    }
+}
@@ -1,31 +1,67 @@
-use argon2::{
-    password_hash::{rand_core::OsRng, PasswordHasher, SaltString},
-    Argon2,
-};
+pub mod memory;
 
 use sqlx::QueryBuilder;
 
-use crate::{models::pricebook::{Pricebook, PricebookRecord}, schemas::category::AssignProductToCategory};
+use crate::models::api_key::ApiKey;
+use crate::models::custom_object::{CustomObjectRecord, CustomObjectType};
+use crate::models::customer::{Customer, CustomerAddress};
+use crate::models::customer_group::{CustomerGroup, CustomerGroupRule, CustomerGroupType};
+use crate::models::inventory::InventoryAdjustment;
+use crate::models::login_attempt::{LoginAttempt, LoginClient, LoginFailureReason, LoginFailures};
+use crate::models::permission::PortalRole;
 use crate::models::product::{Product, ProductImage};
+use crate::models::product_list::{ProductList, ProductListItem, ProductListType};
+use crate::models::returns::{Return, ReturnInspectionOutcome, ReturnLine};
+use crate::models::shipping::{ShippingMethod, ShippingMethodCost, ShippingMethodWeightTier};
+use crate::models::signing_key::{SigningAlgorithm, SigningKey};
+use crate::models::tax::{TaxClass, TaxRate};
 use crate::models::{
     base_extensions::FieldExtensionType,
     inventory::{Inventory, ProductInventoryRecord},
 };
+use crate::schemas::api_key::CreateApiKey;
+use crate::schemas::custom_object::CreateCustomObjectType;
+use crate::schemas::customer::{CreateCustomerAddress, CustomerCreate, CustomerUpdate};
+use crate::schemas::customer_group::{
+    AssignCustomersToGroup, AssignPricebooksToGroup, CreateCustomerGroup,
+};
 use crate::schemas::inventory::{CreateInventory, CreateInventoryRecord};
-use crate::schemas::portal_user::PortalUserCreate;
+use crate::schemas::login_attempt::LoginAttemptQuery;
+use crate::schemas::permission::{AssignPortalRoles, CreatePortalRole};
+use crate::schemas::portal_user::{PortalUserCreate, PortalUserQuery, PortalUserUpdate};
 use crate::schemas::pricebook::{CreatePricebook, CreatePricebookRecord};
 use crate::schemas::product::{CreateProduct, CreateProductImage};
-use crate::{models::portal_user::PortalUser, schemas::base_extensions::CreateCustomFieldEntry};
+use crate::schemas::product_list::{CreateProductList, PutProductListItem, UpdateProductList};
+use crate::schemas::returns::{CreateReturn, InspectReturn};
+use crate::schemas::shipping::CreateShippingMethod;
+use crate::schemas::tax::{CreateTaxClass, CreateTaxRate};
+use crate::services::signing_keys::SIGNING_KEY_GRACE_PERIOD;
+use crate::utils::auth::hash_password;
+use crate::{
+    models::portal_user::{
+        MfaChallenge, PasswordResetToken, PortalUser, PortalUserSession, RecoveryCode,
+    },
+    schemas::base_extensions::CreateCustomFieldEntry,
+};
+use crate::{
+    models::pricebook::{Pricebook, PricebookRecord},
+    schemas::category::AssignProductToCategory,
+};
 use crate::{
     models::{
-        base_extensions::{FieldExtension, FieldExtensionObject},
+        base_extensions::{
+            CustomFieldMigration, CustomFieldMigrationStatus, FieldExtension, FieldExtensionObject,
+            FieldExtensionVersion,
+        },
         category::Category,
     },
-    schemas::base_extensions::CreateCustomField,
+    schemas::base_extensions::{CreateCustomField, MigrateCustomField, UpdateCustomField},
 };
 
 type DbServiceResult<T> = Result<T, sqlx::Error>;
 
+// the postgres database of the server or the in-memory one of the tests
+#[async_trait::async_trait]
 pub trait DbService {
     async fn get_categories(&self) -> DbServiceResult<Vec<Category>>;
 
@@ -41,7 +77,10 @@ pub trait DbService {
 
     async fn get_category_products_by_id(&self, id: &str) -> DbServiceResult<Vec<Product>>;
 
-    async fn create_category_product_entries(&self, payload: &AssignProductToCategory) -> DbServiceResult<()>;
+    async fn create_category_product_entries(
+        &self,
+        payload: &AssignProductToCategory,
+    ) -> DbServiceResult<()>;
 
     async fn get_product(&self, id: &str) -> DbServiceResult<Option<Product>>;
 
@@ -51,14 +90,9 @@ pub trait DbService {
 
     async fn get_product_images(&self, id: &str) -> DbServiceResult<Vec<ProductImage>>;
 
+    // the product and its category assignments are created together
     async fn create_product(&self, payload: &CreateProduct) -> DbServiceResult<Product>;
 
-    async fn create_product_category_assignment(
-        &self,
-        product_id: uuid::Uuid,
-        categories: Vec<uuid::Uuid>,
-    ) -> DbServiceResult<()>;
-
     async fn create_product_image(
         &self,
         id: &str,
@@ -85,7 +119,10 @@ pub trait DbService {
         inventory_id: &str,
     ) -> DbServiceResult<Option<ProductInventoryRecord>>;
 
-    async fn get_product_inventory_records(&self, product_id: &str) -> DbServiceResult<Vec<ProductInventoryRecord>>;
+    async fn get_product_inventory_records(
+        &self,
+        product_id: &str,
+    ) -> DbServiceResult<Vec<ProductInventoryRecord>>;
 
     async fn create_product_inventory_record(
         &self,
@@ -116,7 +153,10 @@ pub trait DbService {
         pricebook_id: &str,
     ) -> DbServiceResult<Option<PricebookRecord>>;
 
-    async fn get_product_pricebooks(&self, product_id: &str) -> DbServiceResult<Vec<PricebookRecord>>;
+    async fn get_product_pricebooks(
+        &self,
+        product_id: &str,
+    ) -> DbServiceResult<Vec<PricebookRecord>>;
 
     async fn get_portal_user(&self, id: &str) -> DbServiceResult<Option<PortalUser>>;
 
@@ -124,9 +164,177 @@ pub trait DbService {
 
     async fn get_portal_user_by_email(&self, email: &str) -> DbServiceResult<Option<PortalUser>>;
 
+    async fn get_portal_user_session(&self, id: &str)
+        -> DbServiceResult<Option<PortalUserSession>>;
+
+    async fn create_portal_user_session(
+        &self,
+        portal_user_id: uuid::Uuid,
+        refresh_token: &str,
+        expires_at: chrono::DateTime<chrono::Utc>,
+    ) -> DbServiceResult<PortalUserSession>;
+
+    // None when the session no longer holds the current refresh token or can
+    // not be used anymore
+    async fn rotate_portal_user_session(
+        &self,
+        id: uuid::Uuid,
+        current_refresh_token: &str,
+        refresh_token: &str,
+        expires_at: chrono::DateTime<chrono::Utc>,
+    ) -> DbServiceResult<Option<PortalUserSession>>;
+
+    async fn revoke_portal_user_session(&self, id: &str) -> DbServiceResult<()>;
+
+    async fn revoke_portal_user_sessions(&self, portal_user_id: &str) -> DbServiceResult<()>;
+
+    async fn get_portal_user_by_session(
+        &self,
+        id: &str,
+        portal_user_id: &str,
+        session_version: i32,
+    ) -> DbServiceResult<Option<PortalUser>>;
+
+    async fn get_portal_users(&self, query: &PortalUserQuery) -> DbServiceResult<Vec<PortalUser>>;
+
+    async fn update_portal_user(
+        &self,
+        id: uuid::Uuid,
+        payload: &PortalUserUpdate,
+    ) -> DbServiceResult<PortalUser>;
+
+    async fn set_portal_user_active(&self, id: uuid::Uuid, active: bool) -> DbServiceResult<()>;
+
+    async fn update_portal_user_password(
+        &self,
+        id: uuid::Uuid,
+        password: &str,
+        keep_session_id: Option<&str>,
+    ) -> DbServiceResult<()>;
+
+    async fn create_password_reset_token(
+        &self,
+        portal_user_id: uuid::Uuid,
+        token: &str,
+        expires_at: chrono::DateTime<chrono::Utc>,
+    ) -> DbServiceResult<PasswordResetToken>;
+
+    async fn get_password_reset_token(
+        &self,
+        id: &str,
+    ) -> DbServiceResult<Option<PasswordResetToken>>;
+
+    async fn use_password_reset_token(
+        &self,
+        id: uuid::Uuid,
+        password: &str,
+    ) -> DbServiceResult<bool>;
+
+    async fn create_login_attempt(
+        &self,
+        email: &str,
+        portal_user_id: Option<uuid::Uuid>,
+        client: &LoginClient,
+        failure_reason: Option<LoginFailureReason>,
+    ) -> DbServiceResult<LoginAttempt>;
+
+    async fn get_login_attempts(
+        &self,
+        query: &LoginAttemptQuery,
+    ) -> DbServiceResult<Vec<LoginAttempt>>;
+
+    async fn get_account_login_failures(&self, email: &str) -> DbServiceResult<LoginFailures>;
+
+    async fn get_ip_address_login_failures(
+        &self,
+        ip_address: &str,
+    ) -> DbServiceResult<LoginFailures>;
+
+    async fn start_portal_user_mfa_enrollment(
+        &self,
+        id: uuid::Uuid,
+        secret: &str,
+    ) -> DbServiceResult<()>;
+
+    async fn enable_portal_user_mfa(
+        &self,
+        id: uuid::Uuid,
+        step: i64,
+        recovery_codes: &[String],
+    ) -> DbServiceResult<()>;
+
+    async fn disable_portal_user_mfa(&self, id: uuid::Uuid) -> DbServiceResult<()>;
+
+    async fn use_portal_user_mfa_step(&self, id: uuid::Uuid, step: i64) -> DbServiceResult<bool>;
+
+    async fn replace_portal_user_recovery_codes(
+        &self,
+        id: uuid::Uuid,
+        recovery_codes: &[String],
+    ) -> DbServiceResult<()>;
+
+    async fn get_portal_user_recovery_codes(
+        &self,
+        id: uuid::Uuid,
+    ) -> DbServiceResult<Vec<RecoveryCode>>;
+
+    async fn use_portal_user_recovery_code(&self, id: uuid::Uuid) -> DbServiceResult<bool>;
+
+    async fn create_mfa_challenge(
+        &self,
+        portal_user_id: uuid::Uuid,
+        token: &str,
+        expires_at: chrono::DateTime<chrono::Utc>,
+    ) -> DbServiceResult<MfaChallenge>;
+
+    async fn get_mfa_challenge(&self, id: &str) -> DbServiceResult<Option<MfaChallenge>>;
+
+    async fn fail_mfa_challenge(&self, id: uuid::Uuid) -> DbServiceResult<()>;
+
+    async fn use_mfa_challenge(&self, id: uuid::Uuid) -> DbServiceResult<bool>;
+
+    async fn get_api_keys(&self) -> DbServiceResult<Vec<ApiKey>>;
+
+    async fn get_api_key(&self, id: &str) -> DbServiceResult<Option<ApiKey>>;
+
+    async fn create_api_key(
+        &self,
+        payload: &CreateApiKey,
+        key_hash: &str,
+        created_by: &str,
+    ) -> DbServiceResult<ApiKey>;
+
+    async fn revoke_api_key(&self, id: &str) -> DbServiceResult<()>;
+
+    async fn touch_api_key(&self, id: uuid::Uuid) -> DbServiceResult<()>;
+
+    async fn get_portal_roles(&self) -> DbServiceResult<Vec<PortalRole>>;
+
+    async fn get_portal_role(&self, id: &str) -> DbServiceResult<Option<PortalRole>>;
+
+    async fn create_portal_role(&self, payload: &CreatePortalRole) -> DbServiceResult<PortalRole>;
+
+    async fn delete_portal_role(&self, id: &str) -> DbServiceResult<()>;
+
+    async fn get_portal_user_roles(&self, portal_user_id: &str)
+        -> DbServiceResult<Vec<PortalRole>>;
+
+    async fn assign_portal_roles(
+        &self,
+        portal_user_id: uuid::Uuid,
+        payload: &AssignPortalRoles,
+    ) -> DbServiceResult<()>;
+
+    async fn get_portal_user_permissions(
+        &self,
+        portal_user_id: &str,
+    ) -> DbServiceResult<Vec<String>>;
+
+    // custom_object_id is only set on the fields of the custom object types
     async fn create_custom_field(
         &self,
         payload: CreateCustomField,
+        custom_object_id: Option<uuid::Uuid>,
     ) -> DbServiceResult<FieldExtension>;
 
     async fn get_custom_field(
@@ -139,6 +347,333 @@ pub trait DbService {
         &self,
         object_type: FieldExtensionObject,
     ) -> DbServiceResult<Vec<FieldExtension>>;
+
+    async fn get_custom_field_by_id(
+        &self,
+        object_type: FieldExtensionObject,
+        id: &str,
+    ) -> DbServiceResult<Option<FieldExtension>>;
+
+    async fn update_custom_field(
+        &self,
+        id: uuid::Uuid,
+        payload: &UpdateCustomField,
+    ) -> DbServiceResult<FieldExtension>;
+
+    async fn delete_custom_field(&self, id: uuid::Uuid) -> DbServiceResult<()>;
+
+    // None when the field was changed since the given version
+    async fn migrate_custom_field(
+        &self,
+        id: uuid::Uuid,
+        version: i32,
+        payload: &MigrateCustomField,
+    ) -> DbServiceResult<Option<FieldExtension>>;
+
+    async fn get_custom_field_versions(
+        &self,
+        custom_field_id: uuid::Uuid,
+    ) -> DbServiceResult<Vec<FieldExtensionVersion>>;
+
+    // None when the field is already being migrated
+    async fn create_custom_field_migration(
+        &self,
+        custom_field_id: uuid::Uuid,
+        from_version: i32,
+    ) -> DbServiceResult<Option<CustomFieldMigration>>;
+
+    // the migrations that were running when the service stopped, they can not
+    // finish anymore
+    async fn fail_running_custom_field_migrations(&self) -> DbServiceResult<u64>;
+
+    async fn finish_custom_field_migration(
+        &self,
+        id: uuid::Uuid,
+        status: CustomFieldMigrationStatus,
+        migrated: i64,
+        dropped: i64,
+        error: Option<String>,
+    ) -> DbServiceResult<CustomFieldMigration>;
+
+    // newest first
+    async fn get_custom_field_migrations(
+        &self,
+        custom_field_id: uuid::Uuid,
+    ) -> DbServiceResult<Vec<CustomFieldMigration>>;
+
+    async fn get_custom_field_migration(
+        &self,
+        custom_field_id: uuid::Uuid,
+        id: &str,
+    ) -> DbServiceResult<Option<CustomFieldMigration>>;
+
+    async fn custom_field_object_exists(
+        &self,
+        object_type: FieldExtensionObject,
+        id: &str,
+    ) -> DbServiceResult<bool>;
+
+    // the ids of every object of the type, the records are limited to one
+    // custom object type when it is given
+    async fn get_custom_field_object_ids(
+        &self,
+        object_type: FieldExtensionObject,
+        custom_object_id: Option<uuid::Uuid>,
+    ) -> DbServiceResult<Vec<String>>;
+
+    // undoes the create of an object when its custom fields could not be stored,
+    // the images and records created for it in the meantime are removed along
+    // with it and returned so their custom fields can be removed as well
+    async fn delete_custom_field_object(
+        &self,
+        object_type: FieldExtensionObject,
+        id: uuid::Uuid,
+    ) -> DbServiceResult<Vec<(FieldExtensionObject, uuid::Uuid)>>;
+
+    async fn get_custom_object_types(&self) -> DbServiceResult<Vec<CustomObjectType>>;
+
+    async fn get_custom_object_type(&self, name: &str)
+        -> DbServiceResult<Option<CustomObjectType>>;
+
+    async fn create_custom_object_type(
+        &self,
+        payload: &CreateCustomObjectType,
+    ) -> DbServiceResult<CustomObjectType>;
+
+    // the fields and records of the type are removed along with it
+    async fn delete_custom_object_type(&self, id: uuid::Uuid) -> DbServiceResult<()>;
+
+    async fn get_custom_object_fields(
+        &self,
+        custom_object_id: uuid::Uuid,
+    ) -> DbServiceResult<Vec<FieldExtension>>;
+
+    async fn get_custom_object_records(
+        &self,
+        custom_object_id: uuid::Uuid,
+    ) -> DbServiceResult<Vec<CustomObjectRecord>>;
+
+    async fn get_custom_object_record(
+        &self,
+        custom_object_id: uuid::Uuid,
+        id: &str,
+    ) -> DbServiceResult<Option<CustomObjectRecord>>;
+
+    async fn create_custom_object_record(
+        &self,
+        custom_object_id: uuid::Uuid,
+    ) -> DbServiceResult<CustomObjectRecord>;
+
+    async fn touch_custom_object_record(
+        &self,
+        id: uuid::Uuid,
+    ) -> DbServiceResult<CustomObjectRecord>;
+
+    async fn delete_custom_object_record(&self, id: uuid::Uuid) -> DbServiceResult<()>;
+
+    async fn get_shipping_methods(&self) -> DbServiceResult<Vec<ShippingMethod>>;
+
+    async fn get_shipping_method_by_id(&self, id: &str) -> DbServiceResult<Option<ShippingMethod>>;
+
+    async fn get_shipping_method_by_reference(
+        &self,
+        reference: &str,
+    ) -> DbServiceResult<Option<ShippingMethod>>;
+
+    async fn get_shipping_method_costs(&self, id: &str)
+        -> DbServiceResult<Vec<ShippingMethodCost>>;
+
+    async fn get_shipping_method_weight_tiers(
+        &self,
+        id: &str,
+    ) -> DbServiceResult<Vec<ShippingMethodWeightTier>>;
+
+    async fn get_shipping_method_excluded_categories(
+        &self,
+        id: &str,
+    ) -> DbServiceResult<Vec<Category>>;
+
+    async fn create_shipping_method(
+        &self,
+        payload: &CreateShippingMethod,
+    ) -> DbServiceResult<ShippingMethod>;
+
+    async fn get_tax_classes(&self) -> DbServiceResult<Vec<TaxClass>>;
+
+    async fn get_tax_class_by_id(&self, id: &str) -> DbServiceResult<Option<TaxClass>>;
+
+    async fn get_tax_class_by_reference(
+        &self,
+        reference: &str,
+    ) -> DbServiceResult<Option<TaxClass>>;
+
+    async fn create_tax_class(&self, payload: &CreateTaxClass) -> DbServiceResult<TaxClass>;
+
+    async fn get_tax_rates(&self, tax_class_id: &str) -> DbServiceResult<Vec<TaxRate>>;
+
+    async fn get_tax_rate(
+        &self,
+        tax_class_id: &str,
+        country_code: &str,
+        region: Option<&str>,
+    ) -> DbServiceResult<Option<TaxRate>>;
+
+    async fn create_tax_rate(
+        &self,
+        tax_class_id: &str,
+        payload: &CreateTaxRate,
+    ) -> DbServiceResult<TaxRate>;
+
+    async fn get_customers(&self) -> DbServiceResult<Vec<Customer>>;
+
+    async fn get_customer(&self, id: &str) -> DbServiceResult<Option<Customer>>;
+
+    async fn get_customer_by_email(&self, email: &str) -> DbServiceResult<Option<Customer>>;
+
+    async fn create_customer(&self, payload: CustomerCreate) -> DbServiceResult<Customer>;
+
+    async fn update_customer(
+        &self,
+        id: &str,
+        payload: &CustomerUpdate,
+    ) -> DbServiceResult<Customer>;
+
+    async fn get_customer_addresses(
+        &self,
+        customer_id: &str,
+    ) -> DbServiceResult<Vec<CustomerAddress>>;
+
+    async fn create_customer_address(
+        &self,
+        customer_id: &str,
+        payload: &CreateCustomerAddress,
+    ) -> DbServiceResult<CustomerAddress>;
+
+    async fn delete_customer_address(
+        &self,
+        customer_id: &str,
+        address_id: &str,
+    ) -> DbServiceResult<bool>;
+
+    async fn get_customer_groups(&self) -> DbServiceResult<Vec<CustomerGroup>>;
+
+    async fn get_customer_groups_by_type(
+        &self,
+        group_type: CustomerGroupType,
+    ) -> DbServiceResult<Vec<CustomerGroup>>;
+
+    async fn get_customer_group_by_id(&self, id: &str) -> DbServiceResult<Option<CustomerGroup>>;
+
+    async fn get_customer_group_by_reference(
+        &self,
+        reference: &str,
+    ) -> DbServiceResult<Option<CustomerGroup>>;
+
+    async fn create_customer_group(
+        &self,
+        payload: &CreateCustomerGroup,
+    ) -> DbServiceResult<CustomerGroup>;
+
+    async fn get_customer_group_rules(&self, id: &str) -> DbServiceResult<Vec<CustomerGroupRule>>;
+
+    async fn get_customer_group_customers(&self, id: &str) -> DbServiceResult<Vec<Customer>>;
+
+    async fn get_customer_static_groups(
+        &self,
+        customer_id: &str,
+    ) -> DbServiceResult<Vec<CustomerGroup>>;
+
+    async fn create_customer_group_customer_entries(
+        &self,
+        payload: &AssignCustomersToGroup,
+    ) -> DbServiceResult<()>;
+
+    async fn get_customer_group_pricebooks(&self, id: &str) -> DbServiceResult<Vec<Pricebook>>;
+
+    async fn create_customer_group_pricebook_entries(
+        &self,
+        payload: &AssignPricebooksToGroup,
+    ) -> DbServiceResult<()>;
+
+    async fn get_product_customer_prices(
+        &self,
+        product_id: &str,
+        currency_code: &str,
+        customer_groups: Vec<uuid::Uuid>,
+    ) -> DbServiceResult<Vec<PricebookRecord>>;
+
+    async fn get_customer_product_lists(
+        &self,
+        customer_id: &str,
+    ) -> DbServiceResult<Vec<ProductList>>;
+
+    async fn get_customer_product_list(
+        &self,
+        customer_id: &str,
+        id: &str,
+    ) -> DbServiceResult<Option<ProductList>>;
+
+    async fn get_product_list_by_share_token(
+        &self,
+        share_token: &str,
+    ) -> DbServiceResult<Option<ProductList>>;
+
+    async fn create_product_list(
+        &self,
+        customer_id: &str,
+        share_token: &str,
+        payload: &CreateProductList,
+    ) -> DbServiceResult<ProductList>;
+
+    async fn update_product_list(
+        &self,
+        customer_id: &str,
+        id: &str,
+        payload: &UpdateProductList,
+    ) -> DbServiceResult<Option<ProductList>>;
+
+    async fn delete_product_list(&self, customer_id: &str, id: &str) -> DbServiceResult<bool>;
+
+    async fn get_product_list_items(&self, id: &str) -> DbServiceResult<Vec<ProductListItem>>;
+
+    async fn put_product_list_item(
+        &self,
+        id: &str,
+        payload: &PutProductListItem,
+    ) -> DbServiceResult<ProductListItem>;
+
+    async fn delete_product_list_item(&self, id: &str, product_id: &str) -> DbServiceResult<bool>;
+
+    async fn get_inventory_adjustments(
+        &self,
+        inventory_id: &str,
+    ) -> DbServiceResult<Vec<InventoryAdjustment>>;
+
+    async fn get_returns(&self) -> DbServiceResult<Vec<Return>>;
+
+    async fn get_return_by_id(&self, id: &str) -> DbServiceResult<Option<Return>>;
+
+    async fn get_return_by_reference(&self, reference: &str) -> DbServiceResult<Option<Return>>;
+
+    async fn get_return_lines(&self, id: &str) -> DbServiceResult<Vec<ReturnLine>>;
+
+    async fn create_return(&self, payload: &CreateReturn) -> DbServiceResult<Return>;
+
+    async fn inspect_return(
+        &self,
+        rma: &Return,
+        payload: &InspectReturn,
+        refund_amount: rust_decimal::Decimal,
+    ) -> DbServiceResult<Return>;
+    async fn get_signing_keys(&self) -> DbServiceResult<Vec<SigningKey>>;
+
+    async fn create_signing_key(
+        &self,
+        algorithm: SigningAlgorithm,
+        private_key: &str,
+        public_key: &str,
+        activates_at: chrono::DateTime<chrono::Utc>,
+    ) -> DbServiceResult<SigningKey>;
 }
 
 pub struct PgDbService {
@@ -151,6 +686,45 @@ impl PgDbService {
     }
 }
 
+// the table holding the objects a custom field can extend
+fn custom_field_object_table(object_type: &FieldExtensionObject) -> &'static str {
+    return match object_type {
+        FieldExtensionObject::PRODUCT => "products",
+        FieldExtensionObject::CATEGORY => "categories",
+        FieldExtensionObject::INVENTORY => "inventories",
+        FieldExtensionObject::PRICEBOOK => "pricebooks",
+        FieldExtensionObject::PRODUCTIMAGE => "images",
+        FieldExtensionObject::INVENTORYRECORD => "inventories_products",
+        FieldExtensionObject::PRICEBOOKRECORD => "pricebooks_products",
+        FieldExtensionObject::PORTALUSER => "portal_users",
+        FieldExtensionObject::CUSTOMER => "customers",
+        FieldExtensionObject::CUSTOMOBJECT => "_metadata_custom_object_records",
+    };
+}
+
+// keeps the definition of the field at its current version
+async fn insert_custom_field_version(
+    connection: &mut sqlx::PgConnection,
+    field: &FieldExtension,
+) -> DbServiceResult<()> {
+    sqlx::query("INSERT INTO _metadata_custom_field_versions (custom_field_id, version, name, description, mandatory, max_len, min_len, allowed_values) VALUES ($1, $2, $3, $4, $5, $6, $7, $8)")
+        .bind(field.id)
+        .bind(field.version)
+        .bind(&field.name)
+        .bind(&field.description)
+        .bind(field.mandatory)
+        .bind(field.max_len)
+        .bind(field.min_len)
+        .bind(&field.allowed_values)
+        .execute(connection)
+        .await?;
+
+    return Ok(());
+}
+
+#[async_trait::async_trait]
+// the futures async_trait wraps the bodies in are returned implicitly
+#[allow(clippy::implicit_return)]
 impl DbService for PgDbService {
     async fn get_categories(&self) -> Result<Vec<Category>, sqlx::Error> {
         return sqlx::query_as::<_, Category>("SELECT * FROM categories")
@@ -195,8 +769,12 @@ impl DbService for PgDbService {
             .await;
     }
 
-    async fn create_category_product_entries(&self, payload: &AssignProductToCategory) -> DbServiceResult<()> {
-        let mut builder = QueryBuilder::new("INSERT INTO categories_products (category_id, product_id)");
+    async fn create_category_product_entries(
+        &self,
+        payload: &AssignProductToCategory,
+    ) -> DbServiceResult<()> {
+        let mut builder =
+            QueryBuilder::new("INSERT INTO categories_products (category_id, product_id)");
 
         builder.push_values(payload.product_ids.iter(), |mut b, uuid| {
             b.push_bind(payload.category_id.clone()).push_bind(uuid);
@@ -240,34 +818,31 @@ impl DbService for PgDbService {
     }
 
     async fn create_product(&self, payload: &CreateProduct) -> DbServiceResult<Product> {
-        return sqlx::query_as::<_, Product>("INSERT INTO products (product_name, product_description, product_color) VALUES ($1, $2, $3) RETURNING *")
+        let mut transaction = self.pool.begin().await?;
+
+        let product = sqlx::query_as::<_, Product>("INSERT INTO products (product_name, product_description, product_color, product_weight, tax_class_id) VALUES ($1, $2, $3, $4, $5) RETURNING *")
             .bind(&payload.product_name)
             .bind(&payload.product_description)
             .bind(&payload.product_color)
-            .fetch_one(&self.pool)
-            .await;
-    }
-
-    async fn create_product_category_assignment(
-        &self,
-        product_id: uuid::Uuid,
-        categories: Vec<uuid::Uuid>,
-    ) -> DbServiceResult<()> {
-        let mut builder =
-            QueryBuilder::new("INSERT INTO categories_products (category_id, product_id)");
-
-        builder.push_values(categories.into_iter(), |mut b, uuid| {
-            b.push_bind(uuid).push_bind(product_id);
-        });
-
-        let query = builder.build();
-
-        let result = query.execute(&self.pool).await;
-        if let Err(error) = result {
-            return Err(error);
+            .bind(payload.product_weight)
+            .bind(payload.tax_class_id)
+            .fetch_one(&mut *transaction)
+            .await?;
+
+        if let Some(categories) = &payload.category_assignments {
+            if !categories.is_empty() {
+                let mut builder =
+                    QueryBuilder::new("INSERT INTO categories_products (category_id, product_id)");
+                builder.push_values(categories.iter(), |mut b, category_id| {
+                    b.push_bind(category_id).push_bind(product.id);
+                });
+                builder.build().execute(&mut *transaction).await?;
+            }
         }
 
-        return Ok(());
+        transaction.commit().await?;
+
+        return Ok(product);
     }
 
     async fn create_product_image(
@@ -341,17 +916,22 @@ impl DbService for PgDbService {
             .fetch_optional(&self.pool).await;
     }
 
-    async fn get_product_inventory_records(&self, product_id: &str) -> DbServiceResult<Vec<ProductInventoryRecord>> {
-        return sqlx::query_as::<_, ProductInventoryRecord>("SELECT * FROM inventories_products WHERE product_id::text = $1")
-            .bind(product_id)
-            .fetch_all(&self.pool)
-            .await;
-    }
-
-    async fn create_product_inventory_record(
+    async fn get_product_inventory_records(
         &self,
-        payload: CreateInventoryRecord,
-    ) -> DbServiceResult<ProductInventoryRecord> {
+        product_id: &str,
+    ) -> DbServiceResult<Vec<ProductInventoryRecord>> {
+        return sqlx::query_as::<_, ProductInventoryRecord>(
+            "SELECT * FROM inventories_products WHERE product_id::text = $1",
+        )
+        .bind(product_id)
+        .fetch_all(&self.pool)
+        .await;
+    }
+
+    async fn create_product_inventory_record(
+        &self,
+        payload: CreateInventoryRecord,
+    ) -> DbServiceResult<ProductInventoryRecord> {
         return sqlx::query_as::<_, ProductInventoryRecord>("INSERT INTO inventories_products (allocation, product_id, inventory_id) VALUES ($1, $2, $3) RETURNING *")
             .bind(payload.allocation)
             .bind(payload.product_id)
@@ -385,17 +965,20 @@ impl DbService for PgDbService {
     }
 
     async fn get_pricebook_records(&self, id: &str) -> DbServiceResult<Vec<PricebookRecord>> {
-        return sqlx::query_as::<_, PricebookRecord>("SELECT * FROM pricebooks_products WHERE pricebook_id::text = $1")
-            .bind(id)
-            .fetch_all(&self.pool)
-            .await;
+        return sqlx::query_as::<_, PricebookRecord>(
+            "SELECT * FROM pricebooks_products WHERE pricebook_id::text = $1",
+        )
+        .bind(id)
+        .fetch_all(&self.pool)
+        .await;
     }
 
     async fn create_pricebook(&self, payload: &CreatePricebook) -> DbServiceResult<Pricebook> {
-        return sqlx::query_as::<_, Pricebook>("INSERT INTO pricebooks (pricebook_name, pricebook_reference, pricebook_currency_code) VALUES ($1, $2, $3) RETURNING *")
+        return sqlx::query_as::<_, Pricebook>("INSERT INTO pricebooks (pricebook_name, pricebook_reference, pricebook_currency_code, pricebook_price_mode) VALUES ($1, $2, $3, $4) RETURNING *")
             .bind(&payload.pricebook_name)
             .bind(&payload.pricebook_reference)
             .bind(&payload.pricebook_currency_code)
+            .bind(payload.pricebook_price_mode.unwrap_or_default())
             .fetch_one(&self.pool).await;
     }
 
@@ -422,11 +1005,16 @@ impl DbService for PgDbService {
             .await;
     }
 
-    async fn get_product_pricebooks(&self, product_id: &str) -> DbServiceResult<Vec<PricebookRecord>> {
-        return sqlx::query_as::<_, PricebookRecord>("SELECT * FROM pricebooks_products WHERE product_id::text = $1")
-            .bind(&product_id)
-            .fetch_all(&self.pool)
-            .await;
+    async fn get_product_pricebooks(
+        &self,
+        product_id: &str,
+    ) -> DbServiceResult<Vec<PricebookRecord>> {
+        return sqlx::query_as::<_, PricebookRecord>(
+            "SELECT * FROM pricebooks_products WHERE product_id::text = $1",
+        )
+        .bind(&product_id)
+        .fetch_all(&self.pool)
+        .await;
     }
 
     async fn get_portal_user(&self, id: &str) -> DbServiceResult<Option<PortalUser>> {
@@ -437,14 +1025,12 @@ impl DbService for PgDbService {
     }
 
     async fn create_portal_user(&self, payload: PortalUserCreate) -> DbServiceResult<PortalUser> {
-        let argon2 = Argon2::default();
-        let salt = SaltString::generate(&mut OsRng);
-        let password_hash = argon2.hash_password(payload.password.as_bytes(), &salt);
+        let password_hash = hash_password(&payload.password);
         if let Err(_) = password_hash {
             return Err(sqlx::Error::Io(std::io::ErrorKind::InvalidInput.into()));
         }
 
-        let hash = password_hash.unwrap().to_string();
+        let hash = password_hash.unwrap();
 
         return sqlx::query_as::<_, PortalUser>("INSERT INTO portal_users (email, first_name, last_name, password, roles) VALUES ($1, $2, $3, $4, $5) RETURNING *")
             .bind(payload.email)
@@ -462,59 +1048,1766 @@ impl DbService for PgDbService {
             .await;
     }
 
-    async fn create_custom_field(
+    async fn get_portal_user_session(
         &self,
-        payload: CreateCustomField,
-    ) -> DbServiceResult<FieldExtension> {
-        let field_type = match payload.custom {
-            CreateCustomFieldEntry::STRING(_) => FieldExtensionType::STRING,
-            CreateCustomFieldEntry::INT => FieldExtensionType::INT,
-        };
+        id: &str,
+    ) -> DbServiceResult<Option<PortalUserSession>> {
+        return sqlx::query_as::<_, PortalUserSession>(
+            "SELECT * FROM portal_users_sessions WHERE id::text = $1",
+        )
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await;
+    }
 
-        let max_len = match &payload.custom {
-            CreateCustomFieldEntry::STRING(fields) => Some(fields.max_len),
-            _ => None,
-        };
+    async fn create_portal_user_session(
+        &self,
+        portal_user_id: uuid::Uuid,
+        refresh_token: &str,
+        expires_at: chrono::DateTime<chrono::Utc>,
+    ) -> DbServiceResult<PortalUserSession> {
+        return sqlx::query_as::<_, PortalUserSession>("INSERT INTO portal_users_sessions (portal_user_id, refresh_token, expires_at) VALUES ($1, $2, $3) RETURNING *")
+            .bind(portal_user_id)
+            .bind(refresh_token)
+            .bind(expires_at)
+            .fetch_one(&self.pool)
+            .await;
+    }
 
-        let min_len = match &payload.custom {
-            CreateCustomFieldEntry::STRING(fields) => Some(fields.min_len),
-            _ => None,
-        };
+    async fn rotate_portal_user_session(
+        &self,
+        id: uuid::Uuid,
+        current_refresh_token: &str,
+        refresh_token: &str,
+        expires_at: chrono::DateTime<chrono::Utc>,
+    ) -> DbServiceResult<Option<PortalUserSession>> {
+        // only one of the requests presenting the same refresh token swaps it
+        return sqlx::query_as::<_, PortalUserSession>("UPDATE portal_users_sessions SET refresh_token = $3, expires_at = $4 WHERE id = $1 AND refresh_token = $2 AND revoked_at IS NULL AND expires_at > now() RETURNING *")
+            .bind(id)
+            .bind(current_refresh_token)
+            .bind(refresh_token)
+            .bind(expires_at)
+            .fetch_optional(&self.pool)
+            .await;
+    }
 
-        return sqlx::query_as::<_, FieldExtension>("INSERT INTO _metadata_custom_fields (object, type, name, description, mandatory, max_len, min_len) VALUES ($1, $2, $3, $4, $5, $6, $7) RETURNING *")
-            .bind(payload.object)
-            .bind(field_type)
-            .bind(payload.base_felds.name)
-            .bind(payload.base_felds.description)
-            .bind(payload.base_felds.mandatory)
-            .bind(max_len)
-            .bind(min_len)
-            .fetch_one(&self.pool).await;
+    async fn revoke_portal_user_session(&self, id: &str) -> DbServiceResult<()> {
+        sqlx::query("UPDATE portal_users_sessions SET revoked_at = now() WHERE id::text = $1 AND revoked_at IS NULL")
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+
+        return Ok(());
     }
 
-    async fn get_custom_field(
+    async fn revoke_portal_user_sessions(&self, portal_user_id: &str) -> DbServiceResult<()> {
+        let mut transaction = self.pool.begin().await?;
+
+        sqlx::query("UPDATE portal_users_sessions SET revoked_at = now() WHERE portal_user_id::text = $1 AND revoked_at IS NULL")
+            .bind(portal_user_id)
+            .execute(&mut *transaction)
+            .await?;
+
+        sqlx::query(
+            "UPDATE portal_users SET session_version = session_version + 1 WHERE id::text = $1",
+        )
+        .bind(portal_user_id)
+        .execute(&mut *transaction)
+        .await?;
+
+        transaction.commit().await?;
+
+        return Ok(());
+    }
+
+    async fn get_portal_user_by_session(
         &self,
-        object_type: FieldExtensionObject,
-        field_name: &str,
-    ) -> DbServiceResult<Option<FieldExtension>> {
-        return sqlx::query_as::<_, FieldExtension>(
-            "SELECT * FROM _metadata_custom_fields WHERE object = $1 AND name = $2",
+        id: &str,
+        portal_user_id: &str,
+        session_version: i32,
+    ) -> DbServiceResult<Option<PortalUser>> {
+        return sqlx::query_as::<_, PortalUser>("SELECT u.* FROM portal_users_sessions s JOIN portal_users u on s.portal_user_id = u.id WHERE s.id::text = $1 AND u.id::text = $2 AND u.session_version = $3 AND u.active AND s.revoked_at IS NULL AND s.expires_at > now()")
+            .bind(id)
+            .bind(portal_user_id)
+            .bind(session_version)
+            .fetch_optional(&self.pool)
+            .await;
+    }
+
+    async fn get_portal_users(&self, query: &PortalUserQuery) -> DbServiceResult<Vec<PortalUser>> {
+        let search = query
+            .search
+            .as_ref()
+            .map(|search| return format!("%{}%", search));
+
+        return sqlx::query_as::<_, PortalUser>("SELECT * FROM portal_users WHERE ($1::text IS NULL OR email ILIKE $1 OR first_name ILIKE $1 OR last_name ILIKE $1) AND ($2::boolean IS NULL OR active = $2) ORDER BY email")
+            .bind(search)
+            .bind(query.active)
+            .fetch_all(&self.pool)
+            .await;
+    }
+
+    async fn update_portal_user(
+        &self,
+        id: uuid::Uuid,
+        payload: &PortalUserUpdate,
+    ) -> DbServiceResult<PortalUser> {
+        return sqlx::query_as::<_, PortalUser>("UPDATE portal_users SET email = COALESCE($2, email), first_name = COALESCE($3, first_name), last_name = COALESCE($4, last_name), roles = COALESCE($5, roles) WHERE id = $1 RETURNING *")
+            .bind(id)
+            .bind(&payload.email)
+            .bind(&payload.first_name)
+            .bind(&payload.last_name)
+            .bind(&payload.roles)
+            .fetch_one(&self.pool)
+            .await;
+    }
+
+    async fn set_portal_user_active(&self, id: uuid::Uuid, active: bool) -> DbServiceResult<()> {
+        let mut transaction = self.pool.begin().await?;
+
+        sqlx::query("UPDATE portal_users SET active = $2 WHERE id = $1")
+            .bind(id)
+            .bind(active)
+            .execute(&mut *transaction)
+            .await?;
+
+        if !active {
+            sqlx::query("UPDATE portal_users_sessions SET revoked_at = now() WHERE portal_user_id = $1 AND revoked_at IS NULL")
+                .bind(id)
+                .execute(&mut *transaction)
+                .await?;
+        }
+
+        transaction.commit().await?;
+
+        return Ok(());
+    }
+
+    async fn update_portal_user_password(
+        &self,
+        id: uuid::Uuid,
+        password: &str,
+        keep_session_id: Option<&str>,
+    ) -> DbServiceResult<()> {
+        let mut transaction = self.pool.begin().await?;
+
+        sqlx::query("UPDATE portal_users SET password = $2 WHERE id = $1")
+            .bind(id)
+            .bind(password)
+            .execute(&mut *transaction)
+            .await?;
+
+        sqlx::query("UPDATE portal_users_sessions SET revoked_at = now() WHERE portal_user_id = $1 AND revoked_at IS NULL AND ($2::text IS NULL OR id::text <> $2)")
+            .bind(id)
+            .bind(keep_session_id)
+            .execute(&mut *transaction)
+            .await?;
+
+        transaction.commit().await?;
+
+        return Ok(());
+    }
+
+    async fn create_password_reset_token(
+        &self,
+        portal_user_id: uuid::Uuid,
+        token: &str,
+        expires_at: chrono::DateTime<chrono::Utc>,
+    ) -> DbServiceResult<PasswordResetToken> {
+        return sqlx::query_as::<_, PasswordResetToken>("INSERT INTO portal_users_password_resets (portal_user_id, token, expires_at) VALUES ($1, $2, $3) RETURNING *")
+            .bind(portal_user_id)
+            .bind(token)
+            .bind(expires_at)
+            .fetch_one(&self.pool)
+            .await;
+    }
+
+    async fn get_password_reset_token(
+        &self,
+        id: &str,
+    ) -> DbServiceResult<Option<PasswordResetToken>> {
+        return sqlx::query_as::<_, PasswordResetToken>(
+            "SELECT * FROM portal_users_password_resets WHERE id::text = $1",
         )
-        .bind(object_type)
-        .bind(field_name)
+        .bind(id)
         .fetch_optional(&self.pool)
         .await;
     }
 
-    async fn get_custom_fields(
+    async fn use_password_reset_token(
         &self,
-        object_type: FieldExtensionObject,
-    ) -> DbServiceResult<Vec<FieldExtension>> {
-        return sqlx::query_as::<_, FieldExtension>(
-            "SELECT * FROM _metadata_custom_fields WHERE object = $1",
+        id: uuid::Uuid,
+        password: &str,
+    ) -> DbServiceResult<bool> {
+        let mut transaction = self.pool.begin().await?;
+
+        // claiming the token and changing the password happen together so a
+        // token can not be used twice by concurrent requests
+        let portal_user_id = sqlx::query_scalar::<_, uuid::Uuid>("UPDATE portal_users_password_resets SET used_at = now() WHERE id = $1 AND used_at IS NULL AND expires_at > now() RETURNING portal_user_id")
+            .bind(id)
+            .fetch_optional(&mut *transaction)
+            .await?;
+
+        let portal_user_id = if let Some(portal_user_id) = portal_user_id {
+            portal_user_id
+        } else {
+            return Ok(false);
+        };
+
+        sqlx::query("UPDATE portal_users SET password = $2 WHERE id = $1")
+            .bind(portal_user_id)
+            .bind(password)
+            .execute(&mut *transaction)
+            .await?;
+
+        sqlx::query("UPDATE portal_users_password_resets SET used_at = now() WHERE portal_user_id = $1 AND used_at IS NULL")
+            .bind(portal_user_id)
+            .execute(&mut *transaction)
+            .await?;
+
+        sqlx::query("UPDATE portal_users_sessions SET revoked_at = now() WHERE portal_user_id = $1 AND revoked_at IS NULL")
+            .bind(portal_user_id)
+            .execute(&mut *transaction)
+            .await?;
+
+        transaction.commit().await?;
+
+        return Ok(true);
+    }
+
+    async fn create_login_attempt(
+        &self,
+        email: &str,
+        portal_user_id: Option<uuid::Uuid>,
+        client: &LoginClient,
+        failure_reason: Option<LoginFailureReason>,
+    ) -> DbServiceResult<LoginAttempt> {
+        return sqlx::query_as::<_, LoginAttempt>("INSERT INTO portal_users_login_attempts (email, portal_user_id, ip_address, user_agent, successful, failure_reason) VALUES ($1, $2, $3, $4, $5, $6) RETURNING *")
+            .bind(email)
+            .bind(portal_user_id)
+            .bind(&client.ip_address)
+            .bind(&client.user_agent)
+            .bind(failure_reason.is_none())
+            .bind(failure_reason)
+            .fetch_one(&self.pool)
+            .await;
+    }
+
+    async fn get_login_attempts(
+        &self,
+        query: &LoginAttemptQuery,
+    ) -> DbServiceResult<Vec<LoginAttempt>> {
+        return sqlx::query_as::<_, LoginAttempt>("SELECT * FROM portal_users_login_attempts WHERE ($1::text IS NULL OR email = $1) AND ($2::text IS NULL OR ip_address = $2) AND ($3::boolean IS NULL OR successful = $3) ORDER BY created_at DESC LIMIT $4")
+            .bind(&query.email)
+            .bind(&query.ip_address)
+            .bind(query.successful)
+            .bind(query.limit.unwrap_or(100))
+            .fetch_all(&self.pool)
+            .await;
+    }
+
+    async fn get_account_login_failures(&self, email: &str) -> DbServiceResult<LoginFailures> {
+        // failures since the last successful sign-in of the account, attempts
+        // turned away because of a lockout are not counted
+        return sqlx::query_as::<_, LoginFailures>("SELECT count(*) AS count, max(created_at) AS last_attempt_at FROM portal_users_login_attempts WHERE email = $1 AND NOT successful AND failure_reason <> 'LOCKED' AND created_at > now() - interval '1 day' AND created_at > COALESCE((SELECT max(created_at) FROM portal_users_login_attempts WHERE email = $1 AND successful), '-infinity')")
+            .bind(email)
+            .fetch_one(&self.pool)
+            .await;
+    }
+
+    async fn get_ip_address_login_failures(
+        &self,
+        ip_address: &str,
+    ) -> DbServiceResult<LoginFailures> {
+        return sqlx::query_as::<_, LoginFailures>("SELECT count(*) AS count, max(created_at) AS last_attempt_at FROM portal_users_login_attempts WHERE ip_address = $1 AND NOT successful AND failure_reason <> 'LOCKED' AND created_at > now() - interval '1 hour'")
+            .bind(ip_address)
+            .fetch_one(&self.pool)
+            .await;
+    }
+
+    async fn start_portal_user_mfa_enrollment(
+        &self,
+        id: uuid::Uuid,
+        secret: &str,
+    ) -> DbServiceResult<()> {
+        sqlx::query("UPDATE portal_users SET mfa_secret = $2, mfa_enabled = false, mfa_last_step = NULL WHERE id = $1")
+            .bind(id)
+            .bind(secret)
+            .execute(&self.pool)
+            .await?;
+
+        return Ok(());
+    }
+
+    async fn enable_portal_user_mfa(
+        &self,
+        id: uuid::Uuid,
+        step: i64,
+        recovery_codes: &[String],
+    ) -> DbServiceResult<()> {
+        let mut transaction = self.pool.begin().await?;
+
+        sqlx::query("UPDATE portal_users SET mfa_enabled = true, mfa_last_step = $2 WHERE id = $1")
+            .bind(id)
+            .bind(step)
+            .execute(&mut *transaction)
+            .await?;
+
+        sqlx::query("DELETE FROM portal_users_recovery_codes WHERE portal_user_id = $1")
+            .bind(id)
+            .execute(&mut *transaction)
+            .await?;
+
+        let mut query_builder =
+            QueryBuilder::new("INSERT INTO portal_users_recovery_codes (portal_user_id, code) ");
+        query_builder.push_values(recovery_codes.iter(), |mut b, code| {
+            b.push_bind(id).push_bind(code);
+        });
+        query_builder.build().execute(&mut *transaction).await?;
+
+        transaction.commit().await?;
+
+        return Ok(());
+    }
+
+    async fn disable_portal_user_mfa(&self, id: uuid::Uuid) -> DbServiceResult<()> {
+        let mut transaction = self.pool.begin().await?;
+
+        sqlx::query("UPDATE portal_users SET mfa_secret = NULL, mfa_enabled = false, mfa_last_step = NULL WHERE id = $1")
+            .bind(id)
+            .execute(&mut *transaction)
+            .await?;
+
+        sqlx::query("DELETE FROM portal_users_recovery_codes WHERE portal_user_id = $1")
+            .bind(id)
+            .execute(&mut *transaction)
+            .await?;
+
+        transaction.commit().await?;
+
+        return Ok(());
+    }
+
+    async fn use_portal_user_mfa_step(&self, id: uuid::Uuid, step: i64) -> DbServiceResult<bool> {
+        let result = sqlx::query("UPDATE portal_users SET mfa_last_step = $2 WHERE id = $1 AND (mfa_last_step IS NULL OR mfa_last_step < $2)")
+            .bind(id)
+            .bind(step)
+            .execute(&self.pool)
+            .await?;
+
+        return Ok(result.rows_affected() == 1);
+    }
+
+    async fn replace_portal_user_recovery_codes(
+        &self,
+        id: uuid::Uuid,
+        recovery_codes: &[String],
+    ) -> DbServiceResult<()> {
+        let mut transaction = self.pool.begin().await?;
+
+        sqlx::query("DELETE FROM portal_users_recovery_codes WHERE portal_user_id = $1")
+            .bind(id)
+            .execute(&mut *transaction)
+            .await?;
+
+        let mut query_builder =
+            QueryBuilder::new("INSERT INTO portal_users_recovery_codes (portal_user_id, code) ");
+        query_builder.push_values(recovery_codes.iter(), |mut b, code| {
+            b.push_bind(id).push_bind(code);
+        });
+        query_builder.build().execute(&mut *transaction).await?;
+
+        transaction.commit().await?;
+
+        return Ok(());
+    }
+
+    async fn get_portal_user_recovery_codes(
+        &self,
+        id: uuid::Uuid,
+    ) -> DbServiceResult<Vec<RecoveryCode>> {
+        return sqlx::query_as::<_, RecoveryCode>("SELECT * FROM portal_users_recovery_codes WHERE portal_user_id = $1 AND used_at IS NULL")
+            .bind(id)
+            .fetch_all(&self.pool)
+            .await;
+    }
+
+    async fn use_portal_user_recovery_code(&self, id: uuid::Uuid) -> DbServiceResult<bool> {
+        let result = sqlx::query("UPDATE portal_users_recovery_codes SET used_at = now() WHERE id = $1 AND used_at IS NULL")
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+
+        return Ok(result.rows_affected() == 1);
+    }
+
+    async fn create_mfa_challenge(
+        &self,
+        portal_user_id: uuid::Uuid,
+        token: &str,
+        expires_at: chrono::DateTime<chrono::Utc>,
+    ) -> DbServiceResult<MfaChallenge> {
+        return sqlx::query_as::<_, MfaChallenge>("INSERT INTO portal_users_mfa_challenges (portal_user_id, token, expires_at) VALUES ($1, $2, $3) RETURNING *")
+            .bind(portal_user_id)
+            .bind(token)
+            .bind(expires_at)
+            .fetch_one(&self.pool)
+            .await;
+    }
+
+    async fn get_mfa_challenge(&self, id: &str) -> DbServiceResult<Option<MfaChallenge>> {
+        return sqlx::query_as::<_, MfaChallenge>(
+            "SELECT * FROM portal_users_mfa_challenges WHERE id::text = $1",
         )
-        .bind(object_type)
-        .fetch_all(&self.pool)
+        .bind(id)
+        .fetch_optional(&self.pool)
         .await;
     }
+
+    async fn fail_mfa_challenge(&self, id: uuid::Uuid) -> DbServiceResult<()> {
+        sqlx::query("UPDATE portal_users_mfa_challenges SET attempts = attempts + 1 WHERE id = $1")
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+
+        return Ok(());
+    }
+
+    async fn use_mfa_challenge(&self, id: uuid::Uuid) -> DbServiceResult<bool> {
+        let result = sqlx::query("UPDATE portal_users_mfa_challenges SET used_at = now() WHERE id = $1 AND used_at IS NULL AND expires_at > now()")
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+
+        return Ok(result.rows_affected() == 1);
+    }
+
+    async fn get_api_keys(&self) -> DbServiceResult<Vec<ApiKey>> {
+        return sqlx::query_as::<_, ApiKey>("SELECT * FROM api_keys ORDER BY created_at DESC")
+            .fetch_all(&self.pool)
+            .await;
+    }
+
+    async fn get_api_key(&self, id: &str) -> DbServiceResult<Option<ApiKey>> {
+        return sqlx::query_as::<_, ApiKey>("SELECT * FROM api_keys WHERE id::text = $1")
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await;
+    }
+
+    async fn create_api_key(
+        &self,
+        payload: &CreateApiKey,
+        key_hash: &str,
+        created_by: &str,
+    ) -> DbServiceResult<ApiKey> {
+        return sqlx::query_as::<_, ApiKey>("INSERT INTO api_keys (name, key_hash, scopes, expires_at, created_by) VALUES ($1, $2, $3, $4, (SELECT id FROM portal_users WHERE id::text = $5)) RETURNING *")
+            .bind(&payload.name)
+            .bind(key_hash)
+            .bind(&payload.scopes)
+            .bind(payload.expires_at)
+            .bind(created_by)
+            .fetch_one(&self.pool)
+            .await;
+    }
+
+    async fn revoke_api_key(&self, id: &str) -> DbServiceResult<()> {
+        sqlx::query(
+            "UPDATE api_keys SET revoked_at = now() WHERE id::text = $1 AND revoked_at IS NULL",
+        )
+        .bind(id)
+        .execute(&self.pool)
+        .await?;
+
+        return Ok(());
+    }
+
+    async fn touch_api_key(&self, id: uuid::Uuid) -> DbServiceResult<()> {
+        sqlx::query("UPDATE api_keys SET last_used_at = now() WHERE id = $1")
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+
+        return Ok(());
+    }
+
+    async fn get_portal_roles(&self) -> DbServiceResult<Vec<PortalRole>> {
+        return sqlx::query_as::<_, PortalRole>("SELECT * FROM portal_roles ORDER BY name")
+            .fetch_all(&self.pool)
+            .await;
+    }
+
+    async fn get_portal_role(&self, id: &str) -> DbServiceResult<Option<PortalRole>> {
+        return sqlx::query_as::<_, PortalRole>("SELECT * FROM portal_roles WHERE id::text = $1")
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await;
+    }
+
+    async fn create_portal_role(&self, payload: &CreatePortalRole) -> DbServiceResult<PortalRole> {
+        return sqlx::query_as::<_, PortalRole>(
+            "INSERT INTO portal_roles (name, permissions) VALUES ($1, $2) RETURNING *",
+        )
+        .bind(&payload.name)
+        .bind(&payload.permissions)
+        .fetch_one(&self.pool)
+        .await;
+    }
+
+    async fn delete_portal_role(&self, id: &str) -> DbServiceResult<()> {
+        sqlx::query("DELETE FROM portal_roles WHERE id::text = $1")
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+
+        return Ok(());
+    }
+
+    async fn get_portal_user_roles(
+        &self,
+        portal_user_id: &str,
+    ) -> DbServiceResult<Vec<PortalRole>> {
+        return sqlx::query_as::<_, PortalRole>("SELECT r.* FROM portal_roles r JOIN portal_users_portal_roles ur ON r.id = ur.portal_role_id WHERE ur.portal_user_id::text = $1 ORDER BY r.name")
+            .bind(portal_user_id)
+            .fetch_all(&self.pool)
+            .await;
+    }
+
+    async fn assign_portal_roles(
+        &self,
+        portal_user_id: uuid::Uuid,
+        payload: &AssignPortalRoles,
+    ) -> DbServiceResult<()> {
+        let mut transaction = self.pool.begin().await?;
+
+        sqlx::query("DELETE FROM portal_users_portal_roles WHERE portal_user_id = $1")
+            .bind(portal_user_id)
+            .execute(&mut *transaction)
+            .await?;
+
+        if !payload.role_ids.is_empty() {
+            let mut query_builder = QueryBuilder::new(
+                "INSERT INTO portal_users_portal_roles (portal_user_id, portal_role_id) ",
+            );
+            query_builder.push_values(payload.role_ids.iter(), |mut b, role_id| {
+                b.push_bind(portal_user_id).push_bind(role_id);
+            });
+            query_builder.build().execute(&mut *transaction).await?;
+        }
+
+        transaction.commit().await?;
+
+        return Ok(());
+    }
+
+    async fn get_portal_user_permissions(
+        &self,
+        portal_user_id: &str,
+    ) -> DbServiceResult<Vec<String>> {
+        return sqlx::query_scalar::<_, String>("SELECT DISTINCT unnest(r.permissions) FROM portal_roles r JOIN portal_users_portal_roles ur ON r.id = ur.portal_role_id WHERE ur.portal_user_id::text = $1")
+            .bind(portal_user_id)
+            .fetch_all(&self.pool)
+            .await;
+    }
+
+    async fn create_custom_field(
+        &self,
+        payload: CreateCustomField,
+        custom_object_id: Option<uuid::Uuid>,
+    ) -> DbServiceResult<FieldExtension> {
+        let field_type = match payload.custom {
+            CreateCustomFieldEntry::STRING(_) => FieldExtensionType::STRING,
+            CreateCustomFieldEntry::INT => FieldExtensionType::INT,
+            CreateCustomFieldEntry::BOOLEAN => FieldExtensionType::BOOLEAN,
+            CreateCustomFieldEntry::DECIMAL(_) => FieldExtensionType::DECIMAL,
+            CreateCustomFieldEntry::DATE => FieldExtensionType::DATE,
+            CreateCustomFieldEntry::DATETIME => FieldExtensionType::DATETIME,
+            CreateCustomFieldEntry::ENUM(_) => FieldExtensionType::ENUM,
+            CreateCustomFieldEntry::SET(_) => FieldExtensionType::SET,
+            CreateCustomFieldEntry::REFERENCE(_) => FieldExtensionType::REFERENCE,
+            CreateCustomFieldEntry::JSON => FieldExtensionType::JSON,
+        };
+
+        let max_len = match &payload.custom {
+            CreateCustomFieldEntry::STRING(fields) => Some(fields.max_len),
+            _ => None,
+        };
+
+        let min_len = match &payload.custom {
+            CreateCustomFieldEntry::STRING(fields) => Some(fields.min_len),
+            _ => None,
+        };
+
+        let scale = match &payload.custom {
+            CreateCustomFieldEntry::DECIMAL(fields) => fields.scale,
+            _ => None,
+        };
+
+        let allowed_values = match &payload.custom {
+            CreateCustomFieldEntry::ENUM(fields) => Some(fields.allowed_values.clone()),
+            CreateCustomFieldEntry::SET(fields) => fields.allowed_values.clone(),
+            _ => None,
+        };
+
+        let reference_object = match &payload.custom {
+            CreateCustomFieldEntry::REFERENCE(fields) => Some(fields.reference_object.clone()),
+            _ => None,
+        };
+
+        let mut transaction = self.pool.begin().await?;
+
+        let field = sqlx::query_as::<_, FieldExtension>("INSERT INTO _metadata_custom_fields (object, type, name, description, mandatory, max_len, min_len, scale, allowed_values, reference_object, custom_object_id) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11) RETURNING *")
+            .bind(payload.object)
+            .bind(field_type)
+            .bind(payload.base_felds.name)
+            .bind(payload.base_felds.description)
+            .bind(payload.base_felds.mandatory)
+            .bind(max_len)
+            .bind(min_len)
+            .bind(scale)
+            .bind(allowed_values)
+            .bind(reference_object)
+            .bind(custom_object_id)
+            .fetch_one(&mut *transaction).await?;
+
+        insert_custom_field_version(&mut transaction, &field).await?;
+
+        transaction.commit().await?;
+
+        return Ok(field);
+    }
+
+    async fn get_custom_field(
+        &self,
+        object_type: FieldExtensionObject,
+        field_name: &str,
+    ) -> DbServiceResult<Option<FieldExtension>> {
+        return sqlx::query_as::<_, FieldExtension>(
+            "SELECT * FROM _metadata_custom_fields WHERE object = $1 AND name = $2",
+        )
+        .bind(object_type)
+        .bind(field_name)
+        .fetch_optional(&self.pool)
+        .await;
+    }
+
+    async fn get_custom_fields(
+        &self,
+        object_type: FieldExtensionObject,
+    ) -> DbServiceResult<Vec<FieldExtension>> {
+        return sqlx::query_as::<_, FieldExtension>(
+            "SELECT * FROM _metadata_custom_fields WHERE object = $1",
+        )
+        .bind(object_type)
+        .fetch_all(&self.pool)
+        .await;
+    }
+
+    async fn get_custom_field_by_id(
+        &self,
+        object_type: FieldExtensionObject,
+        id: &str,
+    ) -> DbServiceResult<Option<FieldExtension>> {
+        return sqlx::query_as::<_, FieldExtension>(
+            "SELECT * FROM _metadata_custom_fields WHERE object = $1 AND id::text = $2",
+        )
+        .bind(object_type)
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await;
+    }
+
+    async fn update_custom_field(
+        &self,
+        id: uuid::Uuid,
+        payload: &UpdateCustomField,
+    ) -> DbServiceResult<FieldExtension> {
+        let mut transaction = self.pool.begin().await?;
+
+        let field = sqlx::query_as::<_, FieldExtension>("UPDATE _metadata_custom_fields SET description = COALESCE($2, description), mandatory = COALESCE($3, mandatory), max_len = COALESCE($4, max_len), min_len = COALESCE($5, min_len), version = version + 1 WHERE id = $1 RETURNING *")
+            .bind(id)
+            .bind(&payload.description)
+            .bind(payload.mandatory)
+            .bind(payload.max_len)
+            .bind(payload.min_len)
+            .fetch_one(&mut *transaction)
+            .await?;
+
+        insert_custom_field_version(&mut transaction, &field).await?;
+
+        transaction.commit().await?;
+
+        return Ok(field);
+    }
+
+    async fn delete_custom_field(&self, id: uuid::Uuid) -> DbServiceResult<()> {
+        sqlx::query("DELETE FROM _metadata_custom_fields WHERE id = $1")
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+
+        return Ok(());
+    }
+
+    async fn migrate_custom_field(
+        &self,
+        id: uuid::Uuid,
+        version: i32,
+        payload: &MigrateCustomField,
+    ) -> DbServiceResult<Option<FieldExtension>> {
+        let mut transaction = self.pool.begin().await?;
+
+        let field = sqlx::query_as::<_, FieldExtension>("UPDATE _metadata_custom_fields SET name = COALESCE($3, name), description = COALESCE($4, description), mandatory = COALESCE($5, mandatory), max_len = COALESCE($6, max_len), min_len = COALESCE($7, min_len), allowed_values = COALESCE($8, allowed_values), version = version + 1 WHERE id = $1 AND version = $2 RETURNING *")
+            .bind(id)
+            .bind(version)
+            .bind(&payload.name)
+            .bind(&payload.description)
+            .bind(payload.mandatory)
+            .bind(payload.max_len)
+            .bind(payload.min_len)
+            .bind(&payload.allowed_values)
+            .fetch_optional(&mut *transaction)
+            .await?;
+
+        let field = match field {
+            Some(field) => field,
+            None => return Ok(None),
+        };
+
+        insert_custom_field_version(&mut transaction, &field).await?;
+
+        transaction.commit().await?;
+
+        return Ok(Some(field));
+    }
+
+    async fn get_custom_field_versions(
+        &self,
+        custom_field_id: uuid::Uuid,
+    ) -> DbServiceResult<Vec<FieldExtensionVersion>> {
+        return sqlx::query_as::<_, FieldExtensionVersion>(
+            "SELECT * FROM _metadata_custom_field_versions WHERE custom_field_id = $1 ORDER BY version",
+        )
+        .bind(custom_field_id)
+        .fetch_all(&self.pool)
+        .await;
+    }
+
+    async fn create_custom_field_migration(
+        &self,
+        custom_field_id: uuid::Uuid,
+        from_version: i32,
+    ) -> DbServiceResult<Option<CustomFieldMigration>> {
+        return sqlx::query_as::<_, CustomFieldMigration>("INSERT INTO _metadata_custom_field_migrations (custom_field_id, from_version, to_version) VALUES ($1, $2, $3) ON CONFLICT (custom_field_id) WHERE status = 'RUNNING' DO NOTHING RETURNING *")
+            .bind(custom_field_id)
+            .bind(from_version)
+            .bind(from_version + 1)
+            .fetch_optional(&self.pool)
+            .await;
+    }
+
+    async fn fail_running_custom_field_migrations(&self) -> DbServiceResult<u64> {
+        let result = sqlx::query("UPDATE _metadata_custom_field_migrations SET status = 'FAILED', error = 'The service stopped while the migration was running', finished_at = now() WHERE status = 'RUNNING'")
+            .execute(&self.pool)
+            .await?;
+
+        return Ok(result.rows_affected());
+    }
+
+    async fn finish_custom_field_migration(
+        &self,
+        id: uuid::Uuid,
+        status: CustomFieldMigrationStatus,
+        migrated: i64,
+        dropped: i64,
+        error: Option<String>,
+    ) -> DbServiceResult<CustomFieldMigration> {
+        return sqlx::query_as::<_, CustomFieldMigration>("UPDATE _metadata_custom_field_migrations SET status = $2, migrated = $3, dropped = $4, error = $5, finished_at = now() WHERE id = $1 RETURNING *")
+            .bind(id)
+            .bind(status)
+            .bind(migrated)
+            .bind(dropped)
+            .bind(error)
+            .fetch_one(&self.pool)
+            .await;
+    }
+
+    async fn get_custom_field_migrations(
+        &self,
+        custom_field_id: uuid::Uuid,
+    ) -> DbServiceResult<Vec<CustomFieldMigration>> {
+        return sqlx::query_as::<_, CustomFieldMigration>(
+            "SELECT * FROM _metadata_custom_field_migrations WHERE custom_field_id = $1 ORDER BY created_at DESC",
+        )
+        .bind(custom_field_id)
+        .fetch_all(&self.pool)
+        .await;
+    }
+
+    async fn get_custom_field_migration(
+        &self,
+        custom_field_id: uuid::Uuid,
+        id: &str,
+    ) -> DbServiceResult<Option<CustomFieldMigration>> {
+        return sqlx::query_as::<_, CustomFieldMigration>(
+            "SELECT * FROM _metadata_custom_field_migrations WHERE custom_field_id = $1 AND id::text = $2",
+        )
+        .bind(custom_field_id)
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await;
+    }
+
+    async fn custom_field_object_exists(
+        &self,
+        object_type: FieldExtensionObject,
+        id: &str,
+    ) -> DbServiceResult<bool> {
+        let table = custom_field_object_table(&object_type);
+
+        return sqlx::query_scalar::<_, bool>(&format!(
+            "SELECT EXISTS (SELECT 1 FROM {} WHERE id::text = $1)",
+            table
+        ))
+        .bind(id)
+        .fetch_one(&self.pool)
+        .await;
+    }
+
+    async fn get_custom_field_object_ids(
+        &self,
+        object_type: FieldExtensionObject,
+        custom_object_id: Option<uuid::Uuid>,
+    ) -> DbServiceResult<Vec<String>> {
+        if let Some(custom_object_id) = custom_object_id {
+            return sqlx::query_scalar::<_, String>(
+                "SELECT id::text FROM _metadata_custom_object_records WHERE custom_object_id = $1",
+            )
+            .bind(custom_object_id)
+            .fetch_all(&self.pool)
+            .await;
+        }
+
+        return sqlx::query_scalar::<_, String>(&format!(
+            "SELECT id::text FROM {}",
+            custom_field_object_table(&object_type)
+        ))
+        .fetch_all(&self.pool)
+        .await;
+    }
+
+    async fn delete_custom_field_object(
+        &self,
+        object_type: FieldExtensionObject,
+        id: uuid::Uuid,
+    ) -> DbServiceResult<Vec<(FieldExtensionObject, uuid::Uuid)>> {
+        let table = custom_field_object_table(&object_type);
+
+        // the tables with rows that can point at the object, along with the
+        // object type of the rows when they can have custom fields themselves
+        let dependents: &[(&str, &str, Option<FieldExtensionObject>)] = match object_type {
+            FieldExtensionObject::PRODUCT => &[
+                ("categories_products", "product_id", None),
+                (
+                    "images",
+                    "product_id",
+                    Some(FieldExtensionObject::PRODUCTIMAGE),
+                ),
+                (
+                    "inventories_products",
+                    "product_id",
+                    Some(FieldExtensionObject::INVENTORYRECORD),
+                ),
+                (
+                    "pricebooks_products",
+                    "product_id",
+                    Some(FieldExtensionObject::PRICEBOOKRECORD),
+                ),
+            ],
+            FieldExtensionObject::INVENTORY => &[(
+                "inventories_products",
+                "inventory_id",
+                Some(FieldExtensionObject::INVENTORYRECORD),
+            )],
+            FieldExtensionObject::PRICEBOOK => &[
+                ("customer_groups_pricebooks", "pricebook_id", None),
+                (
+                    "pricebooks_products",
+                    "pricebook_id",
+                    Some(FieldExtensionObject::PRICEBOOKRECORD),
+                ),
+            ],
+            _ => &[],
+        };
+
+        let mut transaction = self.pool.begin().await?;
+        let mut removed: Vec<(FieldExtensionObject, uuid::Uuid)> = vec![];
+
+        for (dependent_table, column, dependent_object) in dependents {
+            let ids = sqlx::query_scalar::<_, uuid::Uuid>(&format!(
+                "DELETE FROM {} WHERE {} = $1 RETURNING id",
+                dependent_table, column
+            ))
+            .bind(id)
+            .fetch_all(&mut *transaction)
+            .await?;
+
+            if let Some(dependent_object) = dependent_object {
+                removed.extend(
+                    ids.into_iter()
+                        .map(|x| return (dependent_object.clone(), x)),
+                );
+            }
+        }
+
+        sqlx::query(&format!("DELETE FROM {} WHERE id = $1", table))
+            .bind(id)
+            .execute(&mut *transaction)
+            .await?;
+
+        transaction.commit().await?;
+
+        return Ok(removed);
+    }
+
+    async fn get_shipping_methods(&self) -> DbServiceResult<Vec<ShippingMethod>> {
+        return sqlx::query_as::<_, ShippingMethod>("SELECT * FROM shipping_methods")
+            .fetch_all(&self.pool)
+            .await;
+    }
+
+    async fn get_shipping_method_by_id(&self, id: &str) -> DbServiceResult<Option<ShippingMethod>> {
+        return sqlx::query_as::<_, ShippingMethod>(
+            "SELECT * FROM shipping_methods WHERE id::text = $1",
+        )
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await;
+    }
+
+    async fn get_shipping_method_by_reference(
+        &self,
+        reference: &str,
+    ) -> DbServiceResult<Option<ShippingMethod>> {
+        return sqlx::query_as::<_, ShippingMethod>(
+            "SELECT * FROM shipping_methods WHERE shipping_method_reference = $1",
+        )
+        .bind(reference)
+        .fetch_optional(&self.pool)
+        .await;
+    }
+
+    async fn get_shipping_method_costs(
+        &self,
+        id: &str,
+    ) -> DbServiceResult<Vec<ShippingMethodCost>> {
+        return sqlx::query_as::<_, ShippingMethodCost>(
+            "SELECT * FROM shipping_methods_costs WHERE shipping_method_id::text = $1",
+        )
+        .bind(id)
+        .fetch_all(&self.pool)
+        .await;
+    }
+
+    async fn get_shipping_method_weight_tiers(
+        &self,
+        id: &str,
+    ) -> DbServiceResult<Vec<ShippingMethodWeightTier>> {
+        return sqlx::query_as::<_, ShippingMethodWeightTier>("SELECT * FROM shipping_methods_weight_tiers WHERE shipping_method_id::text = $1 ORDER BY min_weight")
+            .bind(id)
+            .fetch_all(&self.pool)
+            .await;
+    }
+
+    async fn get_shipping_method_excluded_categories(
+        &self,
+        id: &str,
+    ) -> DbServiceResult<Vec<Category>> {
+        return sqlx::query_as::<_, Category>("SELECT c.* FROM shipping_methods_excluded_categories sec JOIN categories c on sec.category_id = c.id WHERE sec.shipping_method_id::text = $1")
+            .bind(id)
+            .fetch_all(&self.pool)
+            .await;
+    }
+
+    async fn create_shipping_method(
+        &self,
+        payload: &CreateShippingMethod,
+    ) -> DbServiceResult<ShippingMethod> {
+        let mut transaction = self.pool.begin().await?;
+
+        let shipping_method = sqlx::query_as::<_, ShippingMethod>("INSERT INTO shipping_methods (shipping_method_name, shipping_method_description, shipping_method_reference, shipping_method_countries) VALUES ($1, $2, $3, $4) RETURNING *")
+            .bind(&payload.shipping_method_name)
+            .bind(&payload.shipping_method_description)
+            .bind(&payload.shipping_method_reference)
+            .bind(payload.shipping_method_countries.clone().unwrap_or_default())
+            .fetch_one(&mut *transaction)
+            .await?;
+
+        let mut costs_builder = QueryBuilder::new(
+            "INSERT INTO shipping_methods_costs (shipping_method_id, currency_code, cost, free_shipping_threshold)",
+        );
+        costs_builder.push_values(payload.costs.iter(), |mut b, cost| {
+            b.push_bind(shipping_method.id)
+                .push_bind(&cost.currency_code)
+                .push_bind(cost.cost)
+                .push_bind(cost.free_shipping_threshold);
+        });
+        costs_builder.build().execute(&mut *transaction).await?;
+
+        if let Some(tiers) = &payload.weight_tiers {
+            if !tiers.is_empty() {
+                let mut tiers_builder = QueryBuilder::new(
+                    "INSERT INTO shipping_methods_weight_tiers (shipping_method_id, currency_code, min_weight, cost)",
+                );
+                tiers_builder.push_values(tiers.iter(), |mut b, tier| {
+                    b.push_bind(shipping_method.id)
+                        .push_bind(&tier.currency_code)
+                        .push_bind(tier.min_weight)
+                        .push_bind(tier.cost);
+                });
+                tiers_builder.build().execute(&mut *transaction).await?;
+            }
+        }
+
+        if let Some(categories) = &payload.excluded_categories {
+            if !categories.is_empty() {
+                let mut categories_builder = QueryBuilder::new(
+                    "INSERT INTO shipping_methods_excluded_categories (shipping_method_id, category_id)",
+                );
+                categories_builder.push_values(categories.iter(), |mut b, category_id| {
+                    b.push_bind(shipping_method.id).push_bind(category_id);
+                });
+                categories_builder
+                    .build()
+                    .execute(&mut *transaction)
+                    .await?;
+            }
+        }
+
+        transaction.commit().await?;
+
+        return Ok(shipping_method);
+    }
+
+    async fn get_tax_classes(&self) -> DbServiceResult<Vec<TaxClass>> {
+        return sqlx::query_as::<_, TaxClass>("SELECT * FROM tax_classes")
+            .fetch_all(&self.pool)
+            .await;
+    }
+
+    async fn get_tax_class_by_id(&self, id: &str) -> DbServiceResult<Option<TaxClass>> {
+        return sqlx::query_as::<_, TaxClass>("SELECT * FROM tax_classes WHERE id::text = $1")
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await;
+    }
+
+    async fn get_tax_class_by_reference(
+        &self,
+        reference: &str,
+    ) -> DbServiceResult<Option<TaxClass>> {
+        return sqlx::query_as::<_, TaxClass>(
+            "SELECT * FROM tax_classes WHERE tax_class_reference = $1",
+        )
+        .bind(reference)
+        .fetch_optional(&self.pool)
+        .await;
+    }
+
+    async fn create_tax_class(&self, payload: &CreateTaxClass) -> DbServiceResult<TaxClass> {
+        return sqlx::query_as::<_, TaxClass>("INSERT INTO tax_classes (tax_class_name, tax_class_reference) VALUES ($1, $2) RETURNING *")
+            .bind(&payload.tax_class_name)
+            .bind(&payload.tax_class_reference)
+            .fetch_one(&self.pool)
+            .await;
+    }
+
+    async fn get_tax_rates(&self, tax_class_id: &str) -> DbServiceResult<Vec<TaxRate>> {
+        return sqlx::query_as::<_, TaxRate>(
+            "SELECT * FROM tax_rates WHERE tax_class_id::text = $1",
+        )
+        .bind(tax_class_id)
+        .fetch_all(&self.pool)
+        .await;
+    }
+
+    async fn get_tax_rate(
+        &self,
+        tax_class_id: &str,
+        country_code: &str,
+        region: Option<&str>,
+    ) -> DbServiceResult<Option<TaxRate>> {
+        return sqlx::query_as::<_, TaxRate>("SELECT * FROM tax_rates WHERE tax_class_id::text = $1 AND upper(country_code) = upper($2) AND upper(region) IS NOT DISTINCT FROM upper($3)")
+            .bind(tax_class_id)
+            .bind(country_code)
+            .bind(region)
+            .fetch_optional(&self.pool)
+            .await;
+    }
+
+    async fn create_tax_rate(
+        &self,
+        tax_class_id: &str,
+        payload: &CreateTaxRate,
+    ) -> DbServiceResult<TaxRate> {
+        return sqlx::query_as::<_, TaxRate>("INSERT INTO tax_rates (tax_class_id, country_code, region, rate) VALUES ($1::uuid, $2, $3, $4) RETURNING *")
+            .bind(tax_class_id)
+            .bind(&payload.country_code)
+            .bind(&payload.region)
+            .bind(payload.rate)
+            .fetch_one(&self.pool)
+            .await;
+    }
+
+    async fn get_customers(&self) -> DbServiceResult<Vec<Customer>> {
+        return sqlx::query_as::<_, Customer>("SELECT * FROM customers")
+            .fetch_all(&self.pool)
+            .await;
+    }
+
+    async fn get_customer(&self, id: &str) -> DbServiceResult<Option<Customer>> {
+        return sqlx::query_as::<_, Customer>("SELECT * FROM customers WHERE id::text = $1")
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await;
+    }
+
+    async fn get_customer_by_email(&self, email: &str) -> DbServiceResult<Option<Customer>> {
+        return sqlx::query_as::<_, Customer>("SELECT * FROM customers WHERE email = $1")
+            .bind(email)
+            .fetch_optional(&self.pool)
+            .await;
+    }
+
+    async fn create_customer(&self, payload: CustomerCreate) -> DbServiceResult<Customer> {
+        let password_hash = hash_password(&payload.password);
+        if password_hash.is_err() {
+            return Err(sqlx::Error::Io(std::io::ErrorKind::InvalidInput.into()));
+        }
+
+        return sqlx::query_as::<_, Customer>("INSERT INTO customers (email, first_name, last_name, phone, password) VALUES ($1, $2, $3, $4, $5) RETURNING *")
+            .bind(payload.email)
+            .bind(payload.first_name)
+            .bind(payload.last_name)
+            .bind(payload.phone)
+            .bind(password_hash.unwrap())
+            .fetch_one(&self.pool)
+            .await;
+    }
+
+    async fn update_customer(
+        &self,
+        id: &str,
+        payload: &CustomerUpdate,
+    ) -> DbServiceResult<Customer> {
+        return sqlx::query_as::<_, Customer>("UPDATE customers SET first_name = COALESCE($2, first_name), last_name = COALESCE($3, last_name), phone = COALESCE($4, phone) WHERE id::text = $1 RETURNING *")
+            .bind(id)
+            .bind(&payload.first_name)
+            .bind(&payload.last_name)
+            .bind(&payload.phone)
+            .fetch_one(&self.pool)
+            .await;
+    }
+
+    async fn get_customer_addresses(
+        &self,
+        customer_id: &str,
+    ) -> DbServiceResult<Vec<CustomerAddress>> {
+        return sqlx::query_as::<_, CustomerAddress>(
+            "SELECT * FROM customers_addresses WHERE customer_id::text = $1",
+        )
+        .bind(customer_id)
+        .fetch_all(&self.pool)
+        .await;
+    }
+
+    async fn create_customer_address(
+        &self,
+        customer_id: &str,
+        payload: &CreateCustomerAddress,
+    ) -> DbServiceResult<CustomerAddress> {
+        let mut transaction = self.pool.begin().await?;
+        let is_default = payload.is_default.unwrap_or(false);
+
+        if is_default {
+            sqlx::query(
+                "UPDATE customers_addresses SET is_default = false WHERE customer_id::text = $1",
+            )
+            .bind(customer_id)
+            .execute(&mut *transaction)
+            .await?;
+        }
+
+        let address = sqlx::query_as::<_, CustomerAddress>("INSERT INTO customers_addresses (customer_id, first_name, last_name, country_code, region, city, postal_code, address_line, is_default) VALUES ($1::uuid, $2, $3, $4, $5, $6, $7, $8, $9) RETURNING *")
+            .bind(customer_id)
+            .bind(&payload.first_name)
+            .bind(&payload.last_name)
+            .bind(&payload.address.country_code)
+            .bind(&payload.address.region)
+            .bind(&payload.address.city)
+            .bind(&payload.address.postal_code)
+            .bind(&payload.address.address_line)
+            .bind(is_default)
+            .fetch_one(&mut *transaction)
+            .await?;
+
+        transaction.commit().await?;
+
+        return Ok(address);
+    }
+
+    async fn delete_customer_address(
+        &self,
+        customer_id: &str,
+        address_id: &str,
+    ) -> DbServiceResult<bool> {
+        let result = sqlx::query(
+            "DELETE FROM customers_addresses WHERE customer_id::text = $1 AND id::text = $2",
+        )
+        .bind(customer_id)
+        .bind(address_id)
+        .execute(&self.pool)
+        .await?;
+
+        return Ok(result.rows_affected() > 0);
+    }
+
+    async fn get_customer_groups(&self) -> DbServiceResult<Vec<CustomerGroup>> {
+        return sqlx::query_as::<_, CustomerGroup>("SELECT * FROM customer_groups")
+            .fetch_all(&self.pool)
+            .await;
+    }
+
+    async fn get_customer_groups_by_type(
+        &self,
+        group_type: CustomerGroupType,
+    ) -> DbServiceResult<Vec<CustomerGroup>> {
+        return sqlx::query_as::<_, CustomerGroup>(
+            "SELECT * FROM customer_groups WHERE customer_group_type = $1",
+        )
+        .bind(group_type)
+        .fetch_all(&self.pool)
+        .await;
+    }
+
+    async fn get_customer_group_by_id(&self, id: &str) -> DbServiceResult<Option<CustomerGroup>> {
+        return sqlx::query_as::<_, CustomerGroup>(
+            "SELECT * FROM customer_groups WHERE id::text = $1",
+        )
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await;
+    }
+
+    async fn get_customer_group_by_reference(
+        &self,
+        reference: &str,
+    ) -> DbServiceResult<Option<CustomerGroup>> {
+        return sqlx::query_as::<_, CustomerGroup>(
+            "SELECT * FROM customer_groups WHERE customer_group_reference = $1",
+        )
+        .bind(reference)
+        .fetch_optional(&self.pool)
+        .await;
+    }
+
+    async fn create_customer_group(
+        &self,
+        payload: &CreateCustomerGroup,
+    ) -> DbServiceResult<CustomerGroup> {
+        let mut transaction = self.pool.begin().await?;
+
+        let group = sqlx::query_as::<_, CustomerGroup>("INSERT INTO customer_groups (customer_group_name, customer_group_reference, customer_group_type) VALUES ($1, $2, $3) RETURNING *")
+            .bind(&payload.customer_group_name)
+            .bind(&payload.customer_group_reference)
+            .bind(payload.customer_group_type)
+            .fetch_one(&mut *transaction)
+            .await?;
+
+        if let Some(rules) = &payload.rules {
+            if !rules.is_empty() {
+                let mut builder = QueryBuilder::new(
+                    "INSERT INTO customer_groups_rules (customer_group_id, rule_type, rule_value)",
+                );
+                builder.push_values(rules.iter(), |mut b, rule| {
+                    b.push_bind(group.id)
+                        .push_bind(rule.rule_type)
+                        .push_bind(&rule.rule_value);
+                });
+                builder.build().execute(&mut *transaction).await?;
+            }
+        }
+
+        transaction.commit().await?;
+
+        return Ok(group);
+    }
+
+    async fn get_customer_group_rules(&self, id: &str) -> DbServiceResult<Vec<CustomerGroupRule>> {
+        return sqlx::query_as::<_, CustomerGroupRule>(
+            "SELECT * FROM customer_groups_rules WHERE customer_group_id::text = $1",
+        )
+        .bind(id)
+        .fetch_all(&self.pool)
+        .await;
+    }
+
+    async fn get_customer_group_customers(&self, id: &str) -> DbServiceResult<Vec<Customer>> {
+        return sqlx::query_as::<_, Customer>("SELECT c.* FROM customer_groups_customers cgc JOIN customers c on cgc.customer_id = c.id WHERE cgc.customer_group_id::text = $1")
+            .bind(id)
+            .fetch_all(&self.pool)
+            .await;
+    }
+
+    async fn get_customer_static_groups(
+        &self,
+        customer_id: &str,
+    ) -> DbServiceResult<Vec<CustomerGroup>> {
+        return sqlx::query_as::<_, CustomerGroup>("SELECT cg.* FROM customer_groups_customers cgc JOIN customer_groups cg on cgc.customer_group_id = cg.id WHERE cgc.customer_id::text = $1 AND cg.customer_group_type = 'STATIC'")
+            .bind(customer_id)
+            .fetch_all(&self.pool)
+            .await;
+    }
+
+    async fn create_customer_group_customer_entries(
+        &self,
+        payload: &AssignCustomersToGroup,
+    ) -> DbServiceResult<()> {
+        let mut builder = QueryBuilder::new(
+            "INSERT INTO customer_groups_customers (customer_group_id, customer_id)",
+        );
+        builder.push_values(payload.customer_ids.iter(), |mut b, customer_id| {
+            b.push_bind(payload.customer_group_id)
+                .push_bind(customer_id);
+        });
+        builder.push(" ON CONFLICT DO NOTHING");
+        builder.build().execute(&self.pool).await?;
+
+        return Ok(());
+    }
+
+    async fn get_customer_group_pricebooks(&self, id: &str) -> DbServiceResult<Vec<Pricebook>> {
+        return sqlx::query_as::<_, Pricebook>("SELECT p.* FROM customer_groups_pricebooks cgp JOIN pricebooks p on cgp.pricebook_id = p.id WHERE cgp.customer_group_id::text = $1")
+            .bind(id)
+            .fetch_all(&self.pool)
+            .await;
+    }
+
+    async fn create_customer_group_pricebook_entries(
+        &self,
+        payload: &AssignPricebooksToGroup,
+    ) -> DbServiceResult<()> {
+        let mut builder = QueryBuilder::new(
+            "INSERT INTO customer_groups_pricebooks (customer_group_id, pricebook_id)",
+        );
+        builder.push_values(payload.pricebook_ids.iter(), |mut b, pricebook_id| {
+            b.push_bind(payload.customer_group_id)
+                .push_bind(pricebook_id);
+        });
+        builder.push(" ON CONFLICT DO NOTHING");
+        builder.build().execute(&self.pool).await?;
+
+        return Ok(());
+    }
+
+    async fn get_product_customer_prices(
+        &self,
+        product_id: &str,
+        currency_code: &str,
+        customer_groups: Vec<uuid::Uuid>,
+    ) -> DbServiceResult<Vec<PricebookRecord>> {
+        // pricebooks without a group assignment are public, the rest are only
+        // visible to the members of the groups they are assigned to
+        return sqlx::query_as::<_, PricebookRecord>("SELECT pp.* FROM pricebooks_products pp JOIN pricebooks p on pp.pricebook_id = p.id WHERE pp.product_id::text = $1 AND p.pricebook_currency_code = $2 AND (NOT EXISTS (SELECT 1 FROM customer_groups_pricebooks cgp WHERE cgp.pricebook_id = p.id) OR EXISTS (SELECT 1 FROM customer_groups_pricebooks cgp WHERE cgp.pricebook_id = p.id AND cgp.customer_group_id = ANY($3))) ORDER BY pp.price")
+            .bind(product_id)
+            .bind(currency_code)
+            .bind(customer_groups)
+            .fetch_all(&self.pool)
+            .await;
+    }
+
+    async fn get_customer_product_lists(
+        &self,
+        customer_id: &str,
+    ) -> DbServiceResult<Vec<ProductList>> {
+        return sqlx::query_as::<_, ProductList>(
+            "SELECT * FROM product_lists WHERE customer_id::text = $1",
+        )
+        .bind(customer_id)
+        .fetch_all(&self.pool)
+        .await;
+    }
+
+    async fn get_customer_product_list(
+        &self,
+        customer_id: &str,
+        id: &str,
+    ) -> DbServiceResult<Option<ProductList>> {
+        return sqlx::query_as::<_, ProductList>(
+            "SELECT * FROM product_lists WHERE customer_id::text = $1 AND id::text = $2",
+        )
+        .bind(customer_id)
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await;
+    }
+
+    async fn get_product_list_by_share_token(
+        &self,
+        share_token: &str,
+    ) -> DbServiceResult<Option<ProductList>> {
+        return sqlx::query_as::<_, ProductList>(
+            "SELECT * FROM product_lists WHERE share_token = $1",
+        )
+        .bind(share_token)
+        .fetch_optional(&self.pool)
+        .await;
+    }
+
+    async fn create_product_list(
+        &self,
+        customer_id: &str,
+        share_token: &str,
+        payload: &CreateProductList,
+    ) -> DbServiceResult<ProductList> {
+        return sqlx::query_as::<_, ProductList>("INSERT INTO product_lists (customer_id, product_list_name, product_list_type, is_public, share_token) VALUES ($1::uuid, $2, $3, $4, $5) RETURNING *")
+            .bind(customer_id)
+            .bind(&payload.product_list_name)
+            .bind(payload.product_list_type.unwrap_or(ProductListType::WISHLIST))
+            .bind(payload.is_public.unwrap_or(false))
+            .bind(share_token)
+            .fetch_one(&self.pool)
+            .await;
+    }
+
+    async fn update_product_list(
+        &self,
+        customer_id: &str,
+        id: &str,
+        payload: &UpdateProductList,
+    ) -> DbServiceResult<Option<ProductList>> {
+        return sqlx::query_as::<_, ProductList>("UPDATE product_lists SET product_list_name = COALESCE($3, product_list_name), is_public = COALESCE($4, is_public) WHERE customer_id::text = $1 AND id::text = $2 RETURNING *")
+            .bind(customer_id)
+            .bind(id)
+            .bind(&payload.product_list_name)
+            .bind(payload.is_public)
+            .fetch_optional(&self.pool)
+            .await;
+    }
+
+    async fn delete_product_list(&self, customer_id: &str, id: &str) -> DbServiceResult<bool> {
+        let result =
+            sqlx::query("DELETE FROM product_lists WHERE customer_id::text = $1 AND id::text = $2")
+                .bind(customer_id)
+                .bind(id)
+                .execute(&self.pool)
+                .await?;
+
+        return Ok(result.rows_affected() > 0);
+    }
+
+    async fn get_product_list_items(&self, id: &str) -> DbServiceResult<Vec<ProductListItem>> {
+        return sqlx::query_as::<_, ProductListItem>(
+            "SELECT * FROM product_lists_items WHERE product_list_id::text = $1 ORDER BY priority",
+        )
+        .bind(id)
+        .fetch_all(&self.pool)
+        .await;
+    }
+
+    async fn put_product_list_item(
+        &self,
+        id: &str,
+        payload: &PutProductListItem,
+    ) -> DbServiceResult<ProductListItem> {
+        return sqlx::query_as::<_, ProductListItem>("INSERT INTO product_lists_items (product_list_id, product_id, quantity, priority) VALUES ($1::uuid, $2, $3, $4) ON CONFLICT (product_list_id, product_id) DO UPDATE SET quantity = EXCLUDED.quantity, priority = EXCLUDED.priority RETURNING *")
+            .bind(id)
+            .bind(payload.product_id)
+            .bind(payload.quantity.unwrap_or(1))
+            .bind(payload.priority.unwrap_or(0))
+            .fetch_one(&self.pool)
+            .await;
+    }
+
+    async fn delete_product_list_item(&self, id: &str, product_id: &str) -> DbServiceResult<bool> {
+        let result = sqlx::query(
+            "DELETE FROM product_lists_items WHERE product_list_id::text = $1 AND product_id::text = $2",
+        )
+        .bind(id)
+        .bind(product_id)
+        .execute(&self.pool)
+        .await?;
+
+        return Ok(result.rows_affected() > 0);
+    }
+
+    async fn get_inventory_adjustments(
+        &self,
+        inventory_id: &str,
+    ) -> DbServiceResult<Vec<InventoryAdjustment>> {
+        return sqlx::query_as::<_, InventoryAdjustment>(
+            "SELECT * FROM inventory_adjustments WHERE inventory_id::text = $1 ORDER BY created_at",
+        )
+        .bind(inventory_id)
+        .fetch_all(&self.pool)
+        .await;
+    }
+
+    async fn get_returns(&self) -> DbServiceResult<Vec<Return>> {
+        return sqlx::query_as::<_, Return>("SELECT * FROM returns ORDER BY created_at")
+            .fetch_all(&self.pool)
+            .await;
+    }
+
+    async fn get_return_by_id(&self, id: &str) -> DbServiceResult<Option<Return>> {
+        return sqlx::query_as::<_, Return>("SELECT * FROM returns WHERE id::text = $1")
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await;
+    }
+
+    async fn get_return_by_reference(&self, reference: &str) -> DbServiceResult<Option<Return>> {
+        return sqlx::query_as::<_, Return>("SELECT * FROM returns WHERE return_reference = $1")
+            .bind(reference)
+            .fetch_optional(&self.pool)
+            .await;
+    }
+
+    async fn get_return_lines(&self, id: &str) -> DbServiceResult<Vec<ReturnLine>> {
+        return sqlx::query_as::<_, ReturnLine>(
+            "SELECT * FROM returns_lines WHERE return_id::text = $1",
+        )
+        .bind(id)
+        .fetch_all(&self.pool)
+        .await;
+    }
+
+    async fn create_return(&self, payload: &CreateReturn) -> DbServiceResult<Return> {
+        let mut transaction = self.pool.begin().await?;
+
+        let rma = sqlx::query_as::<_, Return>("INSERT INTO returns (return_reference, order_reference, currency_code, customer_id) VALUES ($1, $2, $3, $4) RETURNING *")
+            .bind(&payload.return_reference)
+            .bind(&payload.order_reference)
+            .bind(&payload.currency_code)
+            .bind(payload.customer_id)
+            .fetch_one(&mut *transaction)
+            .await?;
+
+        let mut builder = QueryBuilder::new("INSERT INTO returns_lines (return_id, product_id, order_line_reference, quantity, unit_price, reason, comment)");
+        builder.push_values(payload.lines.iter(), |mut b, line| {
+            b.push_bind(rma.id)
+                .push_bind(line.product_id)
+                .push_bind(&line.order_line_reference)
+                .push_bind(line.quantity)
+                .push_bind(line.unit_price)
+                .push_bind(line.reason)
+                .push_bind(&line.comment);
+        });
+        builder.build().execute(&mut *transaction).await?;
+
+        transaction.commit().await?;
+
+        return Ok(rma);
+    }
+
+    async fn inspect_return(
+        &self,
+        rma: &Return,
+        payload: &InspectReturn,
+        refund_amount: rust_decimal::Decimal,
+    ) -> DbServiceResult<Return> {
+        let mut transaction = self.pool.begin().await?;
+
+        for inspected in &payload.lines {
+            let line = sqlx::query_as::<_, ReturnLine>("UPDATE returns_lines SET inspection_outcome = $1, restock_inventory_id = $2 WHERE id = $3 AND return_id = $4 RETURNING *")
+                .bind(inspected.outcome)
+                .bind(inspected.inventory_id)
+                .bind(inspected.line_id)
+                .bind(rma.id)
+                .fetch_one(&mut *transaction)
+                .await?;
+
+            if inspected.outcome != ReturnInspectionOutcome::RESTOCK {
+                continue;
+            }
+
+            sqlx::query("INSERT INTO inventory_adjustments (inventory_id, product_id, quantity, reason) VALUES ($1, $2, $3, $4)")
+                .bind(inspected.inventory_id)
+                .bind(line.product_id)
+                .bind(line.quantity)
+                .bind(format!("Restock from return '{}'", rma.return_reference))
+                .execute(&mut *transaction)
+                .await?;
+
+            sqlx::query("INSERT INTO inventories_products (inventory_id, product_id, allocation) VALUES ($1, $2, $3) ON CONFLICT (inventory_id, product_id) DO UPDATE SET allocation = inventories_products.allocation + EXCLUDED.allocation")
+                .bind(inspected.inventory_id)
+                .bind(line.product_id)
+                .bind(line.quantity)
+                .execute(&mut *transaction)
+                .await?;
+        }
+
+        let rma = sqlx::query_as::<_, Return>(
+            "UPDATE returns SET status = 'INSPECTED', refund_amount = $2 WHERE id = $1 RETURNING *",
+        )
+        .bind(rma.id)
+        .bind(refund_amount)
+        .fetch_one(&mut *transaction)
+        .await?;
+
+        transaction.commit().await?;
+
+        return Ok(rma);
+    }
+
+    async fn get_signing_keys(&self) -> DbServiceResult<Vec<SigningKey>> {
+        return sqlx::query_as::<_, SigningKey>("SELECT * FROM signing_keys WHERE expires_at IS NULL OR expires_at > now() ORDER BY activates_at DESC, created_at DESC")
+            .fetch_all(&self.pool)
+            .await;
+    }
+
+    async fn create_signing_key(
+        &self,
+        algorithm: SigningAlgorithm,
+        private_key: &str,
+        public_key: &str,
+        activates_at: chrono::DateTime<chrono::Utc>,
+    ) -> DbServiceResult<SigningKey> {
+        let mut transaction = self.pool.begin().await?;
+
+        // the current keys keep signing until the new one takes over and stay
+        // valid for the tokens they signed until then
+        sqlx::query(
+            "UPDATE signing_keys SET retired_at = $1, expires_at = $2 WHERE retired_at IS NULL",
+        )
+        .bind(activates_at)
+        .bind(activates_at + SIGNING_KEY_GRACE_PERIOD)
+        .execute(&mut *transaction)
+        .await?;
+
+        let signing_key = sqlx::query_as::<_, SigningKey>("INSERT INTO signing_keys (algorithm, private_key, public_key, activates_at) VALUES ($1, $2, $3, $4) RETURNING *")
+            .bind(algorithm)
+            .bind(private_key)
+            .bind(public_key)
+            .bind(activates_at)
+            .fetch_one(&mut *transaction)
+            .await?;
+
+        transaction.commit().await?;
+
+        return Ok(signing_key);
+    }
+
+    async fn get_custom_object_types(&self) -> DbServiceResult<Vec<CustomObjectType>> {
+        return sqlx::query_as::<_, CustomObjectType>(
+            "SELECT * FROM _metadata_custom_objects ORDER BY name",
+        )
+        .fetch_all(&self.pool)
+        .await;
+    }
+
+    async fn get_custom_object_type(
+        &self,
+        name: &str,
+    ) -> DbServiceResult<Option<CustomObjectType>> {
+        return sqlx::query_as::<_, CustomObjectType>(
+            "SELECT * FROM _metadata_custom_objects WHERE name = $1",
+        )
+        .bind(name)
+        .fetch_optional(&self.pool)
+        .await;
+    }
+
+    async fn create_custom_object_type(
+        &self,
+        payload: &CreateCustomObjectType,
+    ) -> DbServiceResult<CustomObjectType> {
+        return sqlx::query_as::<_, CustomObjectType>(
+            "INSERT INTO _metadata_custom_objects (name, description) VALUES ($1, $2) RETURNING *",
+        )
+        .bind(&payload.name)
+        .bind(&payload.description)
+        .fetch_one(&self.pool)
+        .await;
+    }
+
+    async fn delete_custom_object_type(&self, id: uuid::Uuid) -> DbServiceResult<()> {
+        sqlx::query("DELETE FROM _metadata_custom_objects WHERE id = $1")
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+
+        return Ok(());
+    }
+
+    async fn get_custom_object_fields(
+        &self,
+        custom_object_id: uuid::Uuid,
+    ) -> DbServiceResult<Vec<FieldExtension>> {
+        return sqlx::query_as::<_, FieldExtension>(
+            "SELECT * FROM _metadata_custom_fields WHERE custom_object_id = $1 ORDER BY name",
+        )
+        .bind(custom_object_id)
+        .fetch_all(&self.pool)
+        .await;
+    }
+
+    async fn get_custom_object_records(
+        &self,
+        custom_object_id: uuid::Uuid,
+    ) -> DbServiceResult<Vec<CustomObjectRecord>> {
+        return sqlx::query_as::<_, CustomObjectRecord>(
+            "SELECT * FROM _metadata_custom_object_records WHERE custom_object_id = $1 ORDER BY created_at",
+        )
+        .bind(custom_object_id)
+        .fetch_all(&self.pool)
+        .await;
+    }
+
+    async fn get_custom_object_record(
+        &self,
+        custom_object_id: uuid::Uuid,
+        id: &str,
+    ) -> DbServiceResult<Option<CustomObjectRecord>> {
+        return sqlx::query_as::<_, CustomObjectRecord>(
+            "SELECT * FROM _metadata_custom_object_records WHERE custom_object_id = $1 AND id::text = $2",
+        )
+        .bind(custom_object_id)
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await;
+    }
+
+    async fn create_custom_object_record(
+        &self,
+        custom_object_id: uuid::Uuid,
+    ) -> DbServiceResult<CustomObjectRecord> {
+        return sqlx::query_as::<_, CustomObjectRecord>(
+            "INSERT INTO _metadata_custom_object_records (custom_object_id) VALUES ($1) RETURNING *",
+        )
+        .bind(custom_object_id)
+        .fetch_one(&self.pool)
+        .await;
+    }
+
+    async fn touch_custom_object_record(
+        &self,
+        id: uuid::Uuid,
+    ) -> DbServiceResult<CustomObjectRecord> {
+        return sqlx::query_as::<_, CustomObjectRecord>(
+            "UPDATE _metadata_custom_object_records SET updated_at = now() WHERE id = $1 RETURNING *",
+        )
+        .bind(id)
+        .fetch_one(&self.pool)
+        .await;
+    }
+
+    async fn delete_custom_object_record(&self, id: uuid::Uuid) -> DbServiceResult<()> {
+        sqlx::query("DELETE FROM _metadata_custom_object_records WHERE id = $1")
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+
+        return Ok(());
+    }
 }
@@ -0,0 +1,106 @@
+use std::sync::{Arc, Mutex};
+
+use super::{LogLevel, Logger, LoggerResult};
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct MemoryLogEntry {
+    pub file: String,
+    pub category: String,
+    pub level: String,
+    pub message: String,
+}
+
+// Keeps the logs instead of writing them to ./logs, the clones share the
+// entries so a test can hand one to the state and read the logs with another.
+#[derive(Clone, Default)]
+pub struct MemoryLogger {
+    entries: Arc<Mutex<Vec<MemoryLogEntry>>>,
+}
+
+impl MemoryLogger {
+    pub fn new() -> Self {
+        return Self::default();
+    }
+
+    pub fn entries(&self) -> Vec<MemoryLogEntry> {
+        return self
+            .entries
+            .lock()
+            .expect("The in-memory logs were poisoned!")
+            .clone();
+    }
+
+    fn push(&self, level: &str, file: &str, category: &str, message: &str) -> LoggerResult {
+        self.entries
+            .lock()
+            .expect("The in-memory logs were poisoned!")
+            .push(MemoryLogEntry {
+                file: file.to_string(),
+                category: category.to_lowercase(),
+                level: level.to_string(),
+                message: message.to_string(),
+            });
+
+        return Ok(());
+    }
+}
+
+impl Logger for MemoryLogger {
+    fn log(&self, level: LogLevel, message: &str) -> LoggerResult {
+        return self.category_log(level, "default", message);
+    }
+
+    fn info(&self, message: &str) -> LoggerResult {
+        return self.category_info("default", message);
+    }
+
+    fn warn(&self, message: &str) -> LoggerResult {
+        return self.category_warn("default", message);
+    }
+
+    fn error(&self, message: &str) -> LoggerResult {
+        return self.category_error("default", message);
+    }
+
+    fn category_log(&self, level: LogLevel, category: &str, message: &str) -> LoggerResult {
+        return self.file_category_log(level, "commercyfy-core", category, message);
+    }
+
+    fn category_info(&self, category: &str, message: &str) -> LoggerResult {
+        return self.file_category_info("commercyfy-core", category, message);
+    }
+
+    fn category_warn(&self, category: &str, message: &str) -> LoggerResult {
+        return self.file_category_warn("commercyfy-core", category, message);
+    }
+
+    fn category_error(&self, category: &str, message: &str) -> LoggerResult {
+        return self.file_category_error("commercyfy-core", category, message);
+    }
+
+    fn file_category_log(
+        &self,
+        level: LogLevel,
+        file: &str,
+        category: &str,
+        message: &str,
+    ) -> LoggerResult {
+        return match level {
+            LogLevel::INFO => self.file_category_info(file, category, message),
+            LogLevel::WARN => self.file_category_warn(file, category, message),
+            LogLevel::ERROR => self.file_category_error(file, category, message),
+        };
+    }
+
+    fn file_category_info(&self, file: &str, category: &str, message: &str) -> LoggerResult {
+        return self.push("INFO", file, category, message);
+    }
+
+    fn file_category_warn(&self, file: &str, category: &str, message: &str) -> LoggerResult {
+        return self.push("WARN", file, category, message);
+    }
+
+    fn file_category_error(&self, file: &str, category: &str, message: &str) -> LoggerResult {
+        return self.push("ERROR", file, category, message);
+    }
+}
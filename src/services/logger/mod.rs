@@ -1,3 +1,5 @@
+pub mod memory;
+
 use std::{fs::OpenOptions, io::Write};
 
 pub type LoggerResult = std::io::Result<()>;
@@ -25,6 +27,7 @@ pub trait Logger {
     fn file_category_error(&self, file: &str, category: &str, message: &str) -> LoggerResult;
 }
 
+#[derive(Default)]
 pub struct GenericLogger {}
 impl GenericLogger {
     pub fn new() -> Self {
@@ -0,0 +1,69 @@
+use std::io::Write;
+
+pub type MailerResult = Result<(), String>;
+
+pub struct Mail {
+    pub to: String,
+    pub subject: String,
+    pub body: String,
+}
+
+#[allow(async_fn_in_trait)]
+pub trait Mailer {
+    async fn send(&self, mail: Mail) -> MailerResult;
+}
+
+// Development mailer, every mail is written as a plain text file in the output
+// directory or printed to stdout when there is none.
+pub struct FileMailer {
+    output_dir: Option<String>,
+}
+
+impl FileMailer {
+    pub fn new(output_dir: Option<String>) -> Self {
+        return FileMailer { output_dir };
+    }
+
+    fn format_mail(&self, mail: &Mail) -> String {
+        return format!(
+            "Date: {}\nTo: {}\nSubject: {}\n\n{}\n",
+            chrono::Utc::now().to_rfc2822(),
+            mail.to,
+            mail.subject,
+            mail.body
+        );
+    }
+}
+
+impl Mailer for FileMailer {
+    async fn send(&self, mail: Mail) -> MailerResult {
+        let content = self.format_mail(&mail);
+
+        let output_dir = if let Some(output_dir) = &self.output_dir {
+            output_dir
+        } else {
+            print!("{}", content);
+            return Ok(());
+        };
+
+        if let Err(err) = std::fs::create_dir_all(output_dir) {
+            return Err(err.to_string());
+        }
+
+        let file_name = format!(
+            "{}/{}-{}.eml",
+            output_dir,
+            chrono::Utc::now().format("%Y%m%d%H%M%S%f"),
+            mail.to.replace(['/', '\\'], "_")
+        );
+
+        let mut file = match std::fs::File::create(file_name) {
+            Ok(file) => file,
+            Err(err) => return Err(err.to_string()),
+        };
+
+        return file
+            .write_all(content.as_bytes())
+            .map_err(|err| return err.to_string());
+    }
+}
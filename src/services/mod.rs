@@ -1,4 +1,6 @@
 pub mod db;
+pub mod logger;
+pub mod mailer;
 pub mod role_validation;
+pub mod signing_keys;
 pub mod unstructureddb;
-pub mod logger;
@@ -1,46 +1,42 @@
-use crate::models::portal_user::{JWTClaims, PortalUsersRoles};
+use crate::models::{permission::*, portal_user::PortalUsersRoles};
 
 pub trait RoleService {
-    fn validate_role(&self, claims: &JWTClaims, role: PortalUsersRoles) -> Result<(), String>;
-    fn validate_admin(&self, claims: &JWTClaims) -> Result<(), String>;
-    fn _validate_editor(&self, claims: &JWTClaims) -> Result<(), String>;
-    fn _validate_reader(&self, claims: &JWTClaims) -> Result<(), String>;
-    fn validate_any(&self, claims: &JWTClaims, roles: Vec<PortalUsersRoles>) -> Result<(), String>;
+    fn get_role_permissions(&self, role: &PortalUsersRoles) -> Vec<&'static str>;
 }
 
 #[derive(Default)]
 pub struct RoleValidation {}
 
 impl RoleService for RoleValidation {
-    fn validate_role(&self, claims: &JWTClaims, role: PortalUsersRoles) -> Result<(), String> {
-        let checked_role = claims.roles.iter().find(|x| **x == role);
-
-        if let None = checked_role {
-            return Err("Not enough permissions".to_string());
-        }
-
-        return Ok(());
-    }
-
-    fn validate_admin(&self, claims: &JWTClaims) -> Result<(), String> {
-        return self.validate_role(claims, PortalUsersRoles::ADMIN);
-    }
-
-    fn _validate_editor(&self, claims: &JWTClaims) -> Result<(), String> {
-        return self.validate_role(claims, PortalUsersRoles::EDITOR);
-    }
-
-    fn _validate_reader(&self, claims: &JWTClaims) -> Result<(), String> {
-        return self.validate_role(claims, PortalUsersRoles::READER);
-    }
-
-    fn validate_any(&self, claims: &JWTClaims, roles: Vec<PortalUsersRoles>) -> Result<(), String> {
-        let has_role = claims.roles.iter().any(|x| roles.contains(x));
-
-        if has_role {
-            return Ok(());
-        }
-
-        return Err("Not enough permissions".to_string());
+    fn get_role_permissions(&self, role: &PortalUsersRoles) -> Vec<&'static str> {
+        return match role {
+            PortalUsersRoles::READER => vec![
+                CATEGORY_READ,
+                PRODUCT_READ,
+                INVENTORY_READ,
+                PRICEBOOK_READ,
+                CUSTOMER_READ,
+                CUSTOMER_GROUP_READ,
+                RETURN_READ,
+                SHIPPING_READ,
+                TAX_READ,
+                EXTENSION_READ,
+                CUSTOM_OBJECT_READ,
+            ],
+            PortalUsersRoles::EDITOR => vec![
+                CATEGORY_WRITE,
+                PRODUCT_WRITE,
+                INVENTORY_WRITE,
+                INVENTORY_ADJUST,
+                PRICEBOOK_WRITE,
+                CUSTOMER_WRITE,
+                CUSTOMER_GROUP_WRITE,
+                RETURN_WRITE,
+                RETURN_INSPECT,
+                SHIPPING_WRITE,
+                CUSTOM_OBJECT_WRITE,
+            ],
+            PortalUsersRoles::ADMIN => ALL_PERMISSIONS.to_vec(),
+        };
     }
 }
@@ -0,0 +1,404 @@
+use std::sync::RwLock;
+
+use argon2::password_hash::rand_core::OsRng;
+use base64::{
+    engine::general_purpose::{STANDARD, URL_SAFE_NO_PAD},
+    Engine,
+};
+use jsonwebtoken::{
+    jwk::{
+        AlgorithmParameters, CommonParameters, EllipticCurve, Jwk, JwkSet, KeyAlgorithm,
+        OctetKeyPairParameters, OctetKeyPairType, PublicKeyUse, RSAKeyParameters, RSAKeyType,
+    },
+    Algorithm, DecodingKey, EncodingKey, Header, Validation,
+};
+use ring::{
+    aead::{Aad, LessSafeKey, Nonce, UnboundKey, AES_256_GCM, NONCE_LEN},
+    rand::SecureRandom,
+    signature::{Ed25519KeyPair, KeyPair},
+};
+use rsa::{pkcs1::EncodeRsaPrivateKey, traits::PublicKeyParts, RsaPrivateKey};
+
+use crate::models::signing_key::{SigningAlgorithm, SigningKey};
+
+pub type SigningKeysResult<T> = Result<T, String>;
+
+const RSA_KEY_BITS: usize = 2048;
+
+// the private keys are stored as this prefix followed by the base64 of the
+// nonce and the sealed DER, the keys stored before they were encrypted have
+// no prefix
+const SEALED_KEY_PREFIX: &str = "aes-256-gcm:";
+
+// every instance reloads the keys this often, a rotated key is only activated
+// once all of them had the chance to pick it up
+pub const SIGNING_KEY_REFRESH_INTERVAL: std::time::Duration =
+    std::time::Duration::from_secs(60 * 5);
+pub const SIGNING_KEY_PUBLISH_DELAY: chrono::TimeDelta = chrono::TimeDelta::minutes(15);
+
+// retired keys stay published and accepted until the tokens they signed ran
+// out, the longest lived one is the storefront token with a day
+pub const SIGNING_KEY_GRACE_PERIOD: chrono::TimeDelta = chrono::TimeDelta::days(2);
+
+struct LoadedKey {
+    kid: String,
+    algorithm: Algorithm,
+    signing_algorithm: SigningAlgorithm,
+    encoding_key: EncodingKey,
+    decoding_key: DecodingKey,
+    jwk: Jwk,
+    created_at: chrono::DateTime<chrono::Utc>,
+    activates_at: chrono::DateTime<chrono::Utc>,
+    retired_at: Option<chrono::DateTime<chrono::Utc>>,
+    expires_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+impl LoadedKey {
+    fn is_active(&self) -> bool {
+        let now = chrono::Utc::now();
+        return self.activates_at <= now
+            && self
+                .retired_at
+                .is_none_or(|retired_at| return retired_at > now);
+    }
+
+    fn is_expired(&self) -> bool {
+        return self
+            .expires_at
+            .is_some_and(|expires_at| return expires_at <= chrono::Utc::now());
+    }
+}
+
+// Keeps the signing keys in memory, they are loaded from the database on start
+// and reloaded by the rotation task so every instance picks up a rotation.
+// Without an algorithm the tokens keep being signed with the shared HS256
+// secret, with one the secret (when set) is only used to accept the tokens
+// issued before the switch, until `secret_accepted_until` or else until the
+// first key was active for the grace period. The private keys are stored
+// encrypted with `encryption_key`.
+pub struct SigningKeys {
+    algorithm: Option<SigningAlgorithm>,
+    secret: Option<String>,
+    secret_accepted_until: Option<chrono::DateTime<chrono::Utc>>,
+    encryption_key: Option<[u8; 32]>,
+    rotation_interval: chrono::TimeDelta,
+    keys: RwLock<Vec<LoadedKey>>,
+}
+
+impl SigningKeys {
+    pub fn new(
+        algorithm: Option<SigningAlgorithm>,
+        secret: Option<String>,
+        secret_accepted_until: Option<chrono::DateTime<chrono::Utc>>,
+        encryption_key: Option<[u8; 32]>,
+        rotation_interval: chrono::TimeDelta,
+    ) -> Self {
+        return SigningKeys {
+            algorithm,
+            secret,
+            secret_accepted_until,
+            encryption_key,
+            rotation_interval,
+            keys: RwLock::new(vec![]),
+        };
+    }
+
+    pub fn algorithm(&self) -> Option<SigningAlgorithm> {
+        return self.algorithm;
+    }
+
+    pub fn has_keys(&self) -> SigningKeysResult<bool> {
+        let keys = self.keys.read().map_err(|err| return err.to_string())?;
+        return Ok(!keys.is_empty());
+    }
+
+    // the keys are expected newest first
+    pub fn load(&self, keys: &[SigningKey]) -> SigningKeysResult<()> {
+        let mut loaded = vec![];
+        for key in keys {
+            loaded.push(self.load_key(key)?);
+        }
+
+        let mut cache = self.keys.write().map_err(|err| return err.to_string())?;
+        *cache = loaded;
+
+        return Ok(());
+    }
+
+    fn get_encryption_key(&self) -> SigningKeysResult<LessSafeKey> {
+        let encryption_key = match &self.encryption_key {
+            Some(encryption_key) => encryption_key,
+            None => return Err("JWT_KEY_ENCRYPTION_KEY is not configured".to_string()),
+        };
+
+        let unbound_key =
+            UnboundKey::new(&AES_256_GCM, encryption_key).map_err(|err| return err.to_string())?;
+
+        return Ok(LessSafeKey::new(unbound_key));
+    }
+
+    // encrypts a private key returned by generate_key to the form it is stored in
+    pub fn seal_private_key(&self, private_key: &str) -> SigningKeysResult<String> {
+        let encryption_key = self.get_encryption_key()?;
+
+        let mut nonce = [0u8; NONCE_LEN];
+        ring::rand::SystemRandom::new()
+            .fill(&mut nonce)
+            .map_err(|err| return err.to_string())?;
+
+        let mut sealed = private_key.as_bytes().to_vec();
+        encryption_key
+            .seal_in_place_append_tag(
+                Nonce::assume_unique_for_key(nonce),
+                Aad::empty(),
+                &mut sealed,
+            )
+            .map_err(|err| return err.to_string())?;
+
+        let mut stored = nonce.to_vec();
+        stored.extend(sealed);
+
+        return Ok(format!("{}{}", SEALED_KEY_PREFIX, STANDARD.encode(stored)));
+    }
+
+    fn open_private_key(&self, stored: &str) -> SigningKeysResult<String> {
+        let sealed = if let Some(sealed) = stored.strip_prefix(SEALED_KEY_PREFIX) {
+            sealed
+        } else {
+            return Ok(stored.to_string());
+        };
+
+        let encryption_key = self.get_encryption_key()?;
+        let mut sealed = STANDARD
+            .decode(sealed)
+            .map_err(|err| return err.to_string())?;
+        if sealed.len() < NONCE_LEN {
+            return Err("The signing key is not a sealed key".to_string());
+        }
+
+        let mut nonce = [0u8; NONCE_LEN];
+        nonce.copy_from_slice(&sealed[..NONCE_LEN]);
+
+        let private_key = encryption_key
+            .open_in_place(
+                Nonce::assume_unique_for_key(nonce),
+                Aad::empty(),
+                &mut sealed[NONCE_LEN..],
+            )
+            .map_err(|_err| {
+                return "The signing key could not be decrypted with JWT_KEY_ENCRYPTION_KEY"
+                    .to_string();
+            })?;
+
+        return String::from_utf8(private_key.to_vec()).map_err(|err| return err.to_string());
+    }
+
+    fn load_key(&self, key: &SigningKey) -> SigningKeysResult<LoadedKey> {
+        let der = STANDARD
+            .decode(self.open_private_key(&key.private_key)?)
+            .map_err(|err| return err.to_string())?;
+
+        let (algorithm, key_algorithm, encoding_key) = match key.algorithm {
+            SigningAlgorithm::RS256 => (
+                Algorithm::RS256,
+                KeyAlgorithm::RS256,
+                EncodingKey::from_rsa_der(&der),
+            ),
+            SigningAlgorithm::EDDSA => (
+                Algorithm::EdDSA,
+                KeyAlgorithm::EdDSA,
+                EncodingKey::from_ed_der(&der),
+            ),
+        };
+
+        let mut jwk =
+            serde_json::from_str::<Jwk>(&key.public_key).map_err(|err| return err.to_string())?;
+        jwk.common = CommonParameters {
+            public_key_use: Some(PublicKeyUse::Signature),
+            key_algorithm: Some(key_algorithm),
+            key_id: Some(key.id.to_string()),
+            ..Default::default()
+        };
+
+        let decoding_key = DecodingKey::from_jwk(&jwk).map_err(|err| return err.to_string())?;
+
+        return Ok(LoadedKey {
+            kid: key.id.to_string(),
+            algorithm,
+            signing_algorithm: key.algorithm,
+            encoding_key,
+            decoding_key,
+            jwk,
+            created_at: key.created_at,
+            activates_at: key.activates_at,
+            retired_at: key.retired_at,
+            expires_at: key.expires_at,
+        });
+    }
+
+    // a new key is due when the newest one is not for the configured algorithm
+    // or older than the rotation interval
+    pub fn needs_rotation(&self) -> SigningKeysResult<bool> {
+        let algorithm = if let Some(algorithm) = self.algorithm {
+            algorithm
+        } else {
+            return Ok(false);
+        };
+
+        let keys = self.keys.read().map_err(|err| return err.to_string())?;
+
+        return Ok(match keys.first() {
+            Some(key) => {
+                key.signing_algorithm != algorithm
+                    || key.created_at + self.rotation_interval <= chrono::Utc::now()
+            }
+            None => true,
+        });
+    }
+
+    pub fn encode<T: serde::Serialize>(&self, claims: &T) -> SigningKeysResult<String> {
+        if self.algorithm.is_none() {
+            let secret = self.get_secret()?;
+            return jsonwebtoken::encode(
+                &Header::default(),
+                claims,
+                &EncodingKey::from_secret(secret.as_bytes()),
+            )
+            .map_err(|err| return err.to_string());
+        }
+
+        let keys = self.keys.read().map_err(|err| return err.to_string())?;
+        let key = if let Some(key) = keys.iter().find(|key| return key.is_active()) {
+            key
+        } else {
+            return Err("There is no active signing key".to_string());
+        };
+
+        let mut header = Header::new(key.algorithm);
+        header.kid = Some(key.kid.clone());
+
+        return jsonwebtoken::encode(&header, claims, &key.encoding_key)
+            .map_err(|err| return err.to_string());
+    }
+
+    pub fn decode<T: serde::de::DeserializeOwned>(
+        &self,
+        token: &str,
+        audience: &str,
+    ) -> SigningKeysResult<T> {
+        let header = jsonwebtoken::decode_header(token).map_err(|err| return err.to_string())?;
+
+        let keys = self.keys.read().map_err(|err| return err.to_string())?;
+        let (algorithm, decoding_key) = match header.kid {
+            Some(kid) => match keys
+                .iter()
+                .find(|key| return key.kid == kid && !key.is_expired())
+            {
+                Some(key) => (key.algorithm, key.decoding_key.clone()),
+                None => return Err("Unknown signing key".to_string()),
+            },
+            None if self.accepts_secret(&keys) => (
+                Algorithm::HS256,
+                DecodingKey::from_secret(self.get_secret()?.as_bytes()),
+            ),
+            None => {
+                return Err(
+                    "Tokens signed with the shared secret are no longer accepted".to_string(),
+                )
+            }
+        };
+
+        let mut validation = Validation::new(algorithm);
+        validation.set_audience(&[audience]);
+
+        return jsonwebtoken::decode::<T>(token, &decoding_key, &validation)
+            .map(|data| return data.claims)
+            .map_err(|err| return err.to_string());
+    }
+
+    // every key that may still have signed a valid token
+    pub fn jwks(&self) -> SigningKeysResult<JwkSet> {
+        let keys = self.keys.read().map_err(|err| return err.to_string())?;
+
+        return Ok(JwkSet {
+            keys: keys
+                .iter()
+                .filter(|key| return !key.is_expired())
+                .map(|key| return key.jwk.clone())
+                .collect(),
+        });
+    }
+
+    // the tokens signed with the secret before the switch to an algorithm ran
+    // out once the first key was active for the grace period, the keys that
+    // expired since are not loaded but a later key only activated after them
+    fn accepts_secret(&self, keys: &[LoadedKey]) -> bool {
+        if self.algorithm.is_none() {
+            return true;
+        }
+
+        let deadline = self.secret_accepted_until.or_else(|| {
+            return keys
+                .iter()
+                .map(|key| return key.activates_at)
+                .min()
+                .map(|activates_at| return activates_at + SIGNING_KEY_GRACE_PERIOD);
+        });
+
+        return deadline.is_none_or(|deadline| return chrono::Utc::now() < deadline);
+    }
+
+    fn get_secret(&self) -> SigningKeysResult<&str> {
+        return match &self.secret {
+            Some(secret) => Ok(secret),
+            None => Err("JWT_TOKEN_SECRET is not configured".to_string()),
+        };
+    }
+}
+
+// returns the private key as base64 DER and the public key as a JWK, in the
+// form they are stored in, generating an RSA key takes a while so this is best
+// run on a blocking thread
+pub fn generate_key(algorithm: SigningAlgorithm) -> SigningKeysResult<(String, String)> {
+    let (der, params) = match algorithm {
+        SigningAlgorithm::RS256 => {
+            let private_key = RsaPrivateKey::new(&mut OsRng, RSA_KEY_BITS)
+                .map_err(|err| return err.to_string())?;
+            let der = private_key
+                .to_pkcs1_der()
+                .map_err(|err| return err.to_string())?;
+
+            (
+                der.as_bytes().to_vec(),
+                AlgorithmParameters::RSA(RSAKeyParameters {
+                    key_type: RSAKeyType::RSA,
+                    n: URL_SAFE_NO_PAD.encode(private_key.n().to_bytes_be()),
+                    e: URL_SAFE_NO_PAD.encode(private_key.e().to_bytes_be()),
+                }),
+            )
+        }
+        SigningAlgorithm::EDDSA => {
+            let rng = ring::rand::SystemRandom::new();
+            let der = Ed25519KeyPair::generate_pkcs8(&rng).map_err(|err| return err.to_string())?;
+            let key_pair =
+                Ed25519KeyPair::from_pkcs8(der.as_ref()).map_err(|err| return err.to_string())?;
+
+            (
+                der.as_ref().to_vec(),
+                AlgorithmParameters::OctetKeyPair(OctetKeyPairParameters {
+                    key_type: OctetKeyPairType::OctetKeyPair,
+                    curve: EllipticCurve::Ed25519,
+                    x: URL_SAFE_NO_PAD.encode(key_pair.public_key().as_ref()),
+                }),
+            )
+        }
+    };
+
+    let jwk = Jwk {
+        common: CommonParameters::default(),
+        algorithm: params,
+    };
+    let public_key = serde_json::to_string(&jwk).map_err(|err| return err.to_string())?;
+
+    return Ok((STANDARD.encode(der), public_key));
+}
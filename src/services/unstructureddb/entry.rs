@@ -1,11 +1,20 @@
-#[derive(serde::Serialize, serde::Deserialize, Debug)]
+// Decimals are kept as strings so they keep their precision, the filters
+// compare them as decimals. Dates and datetimes are ISO 8601 strings in UTC,
+// enums and references strings and sets lists. The order matters when reading
+// the entries back, a stored decimal reads back as a string and only the
+// decimals stored as numbers by older versions read back as decimals.
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq)]
 #[serde(untagged)]
 pub enum UnstructuredEntryType {
-    STRING(String),
+    BOOLEAN(bool),
     INT(i64),
+    STRING(String),
+    DECIMAL(rust_decimal::Decimal),
+    LIST(Vec<String>),
+    JSON(serde_json::Value),
 }
 
-#[derive(serde::Serialize, serde::Deserialize, Debug)]
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
 pub struct UnstructuredEntry {
     pub extr_ref: String,
     pub field_name: String,
@@ -0,0 +1,293 @@
+use rust_decimal::Decimal;
+use std::{
+    cmp::Ordering,
+    str::FromStr,
+    sync::{
+        atomic::{AtomicBool, Ordering as AtomicOrdering},
+        Mutex,
+    },
+};
+
+use super::{
+    entry::{UnstructuredEntry, UnstructuredEntryType},
+    query::UnstructuredFilter,
+    UnstructuredDb, UnstructuredDbCountResult, UnstructuredDbObjectResult,
+    UnstructuredDbRefsResult, UnstructuredDbResult,
+};
+use crate::models::base_extensions::FieldExtensionObject;
+
+struct MemoryEntry {
+    object: FieldExtensionObject,
+    entry: UnstructuredEntry,
+}
+
+// Keeps the entries in a list for the tests and demos, the filters behave like
+// the ones of mongodb. The writes and deletes can be made to fail to test what
+// happens when mongodb is not reachable.
+#[derive(Default)]
+pub struct MemoryUnstructuredDb {
+    entries: Mutex<Vec<MemoryEntry>>,
+    failing_writes: AtomicBool,
+    failing_deletes: AtomicBool,
+}
+
+impl MemoryUnstructuredDb {
+    pub fn new() -> Self {
+        return Self::default();
+    }
+
+    pub fn fail_writes(&self, failing: bool) {
+        self.failing_writes.store(failing, AtomicOrdering::SeqCst);
+    }
+
+    fn check_writes(&self) -> UnstructuredDbResult {
+        if self.failing_writes.load(AtomicOrdering::SeqCst) {
+            return Err("The in-memory unstructured db is set to fail the writes".to_string());
+        }
+
+        return Ok(());
+    }
+
+    pub fn fail_deletes(&self, failing: bool) {
+        self.failing_deletes.store(failing, AtomicOrdering::SeqCst);
+    }
+
+    fn check_deletes(&self) -> UnstructuredDbResult {
+        if self.failing_deletes.load(AtomicOrdering::SeqCst) {
+            return Err("The in-memory unstructured db is set to fail the deletes".to_string());
+        }
+
+        return Ok(());
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Vec<MemoryEntry>> {
+        return self
+            .entries
+            .lock()
+            .expect("The in-memory unstructured db was poisoned!");
+    }
+
+    fn collect<F>(&self, object: FieldExtensionObject, predicate: F) -> Vec<UnstructuredEntry>
+    where
+        F: Fn(&UnstructuredEntry) -> bool,
+    {
+        return self
+            .lock()
+            .iter()
+            .filter(|x| return x.object == object && predicate(&x.entry))
+            .map(|x| return x.entry.clone())
+            .collect();
+    }
+
+    fn remove<F>(&self, object: FieldExtensionObject, predicate: F)
+    where
+        F: Fn(&UnstructuredEntry) -> bool,
+    {
+        self.lock()
+            .retain(|x| return x.object != object || !predicate(&x.entry));
+    }
+}
+
+#[async_trait::async_trait]
+// the futures async_trait wraps the bodies in are returned implicitly
+#[allow(clippy::implicit_return)]
+impl UnstructuredDb for MemoryUnstructuredDb {
+    async fn put_custom_fields(
+        &self,
+        object: FieldExtensionObject,
+        entry: Vec<UnstructuredEntry>,
+    ) -> UnstructuredDbResult {
+        self.check_writes()?;
+
+        let mut entries = self.lock();
+        for entry in entry {
+            entries.push(MemoryEntry {
+                object: object.clone(),
+                entry,
+            });
+        }
+
+        return Ok(());
+    }
+
+    async fn get_custom_fields(
+        &self,
+        object: FieldExtensionObject,
+        extr_ref: &str,
+    ) -> UnstructuredDbObjectResult {
+        return Ok(self.collect(object, |x| return x.extr_ref == extr_ref));
+    }
+
+    async fn get_objects_custom_fields(
+        &self,
+        object: FieldExtensionObject,
+        extr_refs: &[String],
+    ) -> UnstructuredDbObjectResult {
+        return Ok(self.collect(object, |x| return extr_refs.contains(&x.extr_ref)));
+    }
+
+    async fn upsert_custom_fields(
+        &self,
+        object: FieldExtensionObject,
+        entries: Vec<UnstructuredEntry>,
+    ) -> UnstructuredDbResult {
+        self.check_writes()?;
+
+        let mut stored = self.lock();
+        for entry in entries {
+            let mut found = false;
+            for existing in stored.iter_mut().filter(|x| {
+                return x.object == object
+                    && x.entry.extr_ref == entry.extr_ref
+                    && x.entry.field_name == entry.field_name;
+            }) {
+                existing.entry.value = entry.value.clone();
+                found = true;
+            }
+
+            if !found {
+                stored.push(MemoryEntry {
+                    object: object.clone(),
+                    entry,
+                });
+            }
+        }
+
+        return Ok(());
+    }
+
+    async fn delete_custom_fields(
+        &self,
+        object: FieldExtensionObject,
+        extr_ref: &str,
+        field_names: &[String],
+    ) -> UnstructuredDbResult {
+        self.check_deletes()?;
+        self.remove(object, |x| {
+            return x.extr_ref == extr_ref && field_names.contains(&x.field_name);
+        });
+
+        return Ok(());
+    }
+
+    async fn get_custom_field_values(
+        &self,
+        object: FieldExtensionObject,
+        field_name: &str,
+    ) -> UnstructuredDbObjectResult {
+        return Ok(self.collect(object, |x| return x.field_name == field_name));
+    }
+
+    async fn find_custom_field_refs(
+        &self,
+        object: FieldExtensionObject,
+        field_name: &str,
+        filter: &UnstructuredFilter,
+    ) -> UnstructuredDbRefsResult {
+        let entries = self.collect(object, |x| {
+            return x.field_name == field_name && matches_filter(&x.value, filter);
+        });
+
+        let mut refs: Vec<String> = Vec::new();
+        for entry in entries {
+            if !refs.contains(&entry.extr_ref) {
+                refs.push(entry.extr_ref);
+            }
+        }
+
+        return Ok(refs);
+    }
+
+    async fn count_custom_field_values(
+        &self,
+        object: FieldExtensionObject,
+        field_name: &str,
+    ) -> UnstructuredDbCountResult {
+        return Ok(self
+            .collect(object, |x| return x.field_name == field_name)
+            .len() as u64);
+    }
+
+    async fn delete_custom_field_values(
+        &self,
+        object: FieldExtensionObject,
+        field_name: &str,
+    ) -> UnstructuredDbResult {
+        self.check_deletes()?;
+        self.remove(object, |x| return x.field_name == field_name);
+
+        return Ok(());
+    }
+
+    async fn delete_custom_field_values_of(
+        &self,
+        object: FieldExtensionObject,
+        field_name: &str,
+        extr_refs: &[String],
+    ) -> UnstructuredDbResult {
+        self.check_deletes()?;
+        self.remove(object, |x| {
+            return x.field_name == field_name && extr_refs.contains(&x.extr_ref);
+        });
+
+        return Ok(());
+    }
+}
+
+// an equal value also matches the lists containing it and the ranges only
+// compare numbers with numbers and strings with strings
+fn matches_filter(value: &UnstructuredEntryType, filter: &UnstructuredFilter) -> bool {
+    return match filter {
+        UnstructuredFilter::EQ(expected) => {
+            value == expected
+                || match (value, expected) {
+                    (UnstructuredEntryType::LIST(list), UnstructuredEntryType::STRING(x)) => {
+                        list.contains(x)
+                    }
+                    _ => compare(value, expected) == Some(Ordering::Equal),
+                }
+        }
+        UnstructuredFilter::GT(expected) => compare(value, expected) == Some(Ordering::Greater),
+        UnstructuredFilter::GTE(expected) => matches!(
+            compare(value, expected),
+            Some(Ordering::Greater | Ordering::Equal)
+        ),
+        UnstructuredFilter::LT(expected) => compare(value, expected) == Some(Ordering::Less),
+        UnstructuredFilter::LTE(expected) => matches!(
+            compare(value, expected),
+            Some(Ordering::Less | Ordering::Equal)
+        ),
+        UnstructuredFilter::CONTAINS(expected) => {
+            let expected = expected.to_lowercase();
+            match value {
+                UnstructuredEntryType::STRING(x) => x.to_lowercase().contains(&expected),
+                UnstructuredEntryType::LIST(list) => list
+                    .iter()
+                    .any(|x| return x.to_lowercase().contains(&expected)),
+                _ => false,
+            }
+        }
+        UnstructuredFilter::EXISTS => true,
+    };
+}
+
+// the decimals are compared as decimals, also when they were read back as
+// strings
+fn as_decimal(value: &UnstructuredEntryType) -> Option<Decimal> {
+    return match value {
+        UnstructuredEntryType::INT(x) => Some(Decimal::from(*x)),
+        UnstructuredEntryType::DECIMAL(x) => Some(*x),
+        UnstructuredEntryType::STRING(x) => Decimal::from_str(x).ok(),
+        _ => None,
+    };
+}
+
+fn compare(value: &UnstructuredEntryType, other: &UnstructuredEntryType) -> Option<Ordering> {
+    return match (value, other) {
+        (UnstructuredEntryType::INT(x), UnstructuredEntryType::INT(y)) => Some(x.cmp(y)),
+        (UnstructuredEntryType::STRING(x), UnstructuredEntryType::STRING(y)) => Some(x.cmp(y)),
+        (_, UnstructuredEntryType::DECIMAL(y)) => as_decimal(value).map(|x| return x.cmp(y)),
+        (UnstructuredEntryType::DECIMAL(x), _) => as_decimal(other).map(|y| return x.cmp(&y)),
+        _ => None,
+    };
+}
@@ -1,13 +1,24 @@
 pub mod entry;
+pub mod memory;
+pub mod postgres;
+pub mod query;
 
-use self::entry::UnstructuredEntry;
+use self::{
+    entry::{UnstructuredEntry, UnstructuredEntryType},
+    query::UnstructuredFilter,
+};
 use crate::models::base_extensions::FieldExtensionObject;
 use futures::TryStreamExt;
-use mongodb::bson::doc;
+use mongodb::{bson::doc, IndexModel};
 
 pub type UnstructuredDbResult = Result<(), String>;
 pub type UnstructuredDbObjectResult = Result<Vec<UnstructuredEntry>, String>;
+pub type UnstructuredDbCountResult = Result<u64, String>;
+pub type UnstructuredDbRefsResult = Result<Vec<String>, String>;
 
+// mongodb or the postgres table picked at startup with UNSTRUCTURED_DB, or the
+// in-memory one of the tests
+#[async_trait::async_trait]
 pub trait UnstructuredDb {
     async fn put_custom_fields(
         &self,
@@ -20,6 +31,62 @@ pub trait UnstructuredDb {
         object: FieldExtensionObject,
         extr_ref: &str,
     ) -> UnstructuredDbObjectResult;
+
+    // the entries of several objects at once
+    async fn get_objects_custom_fields(
+        &self,
+        object: FieldExtensionObject,
+        extr_refs: &[String],
+    ) -> UnstructuredDbObjectResult;
+
+    // sets the value of every entry, creating the ones that do not exist yet
+    async fn upsert_custom_fields(
+        &self,
+        object: FieldExtensionObject,
+        entries: Vec<UnstructuredEntry>,
+    ) -> UnstructuredDbResult;
+
+    async fn delete_custom_fields(
+        &self,
+        object: FieldExtensionObject,
+        extr_ref: &str,
+        field_names: &[String],
+    ) -> UnstructuredDbResult;
+
+    async fn get_custom_field_values(
+        &self,
+        object: FieldExtensionObject,
+        field_name: &str,
+    ) -> UnstructuredDbObjectResult;
+
+    // the refs of the objects with a value of the field that matches the filter
+    async fn find_custom_field_refs(
+        &self,
+        object: FieldExtensionObject,
+        field_name: &str,
+        filter: &UnstructuredFilter,
+    ) -> UnstructuredDbRefsResult;
+
+    async fn count_custom_field_values(
+        &self,
+        object: FieldExtensionObject,
+        field_name: &str,
+    ) -> UnstructuredDbCountResult;
+
+    async fn delete_custom_field_values(
+        &self,
+        object: FieldExtensionObject,
+        field_name: &str,
+    ) -> UnstructuredDbResult;
+
+    // the custom object types share a collection, their fields only own the
+    // values of their records
+    async fn delete_custom_field_values_of(
+        &self,
+        object: FieldExtensionObject,
+        field_name: &str,
+        extr_refs: &[String],
+    ) -> UnstructuredDbResult;
 }
 
 pub struct MongoDb {
@@ -36,6 +103,12 @@ impl MongoDb {
                 "categories".to_string(),
                 "inventories".to_string(),
                 "pricebooks".to_string(),
+                "product_images".to_string(),
+                "inventory_records".to_string(),
+                "pricebook_records".to_string(),
+                "portal_users".to_string(),
+                "customers".to_string(),
+                "custom_objects".to_string(),
             ],
         };
     }
@@ -60,6 +133,23 @@ impl MongoDb {
             println!("Created unstructured db collection '{name}'");
         }
 
+        // creating an index that already exists is a no-op
+        for name in &self.required_collections {
+            let collection = self.db.collection::<mongodb::bson::Document>(name);
+            let indexes = vec![
+                IndexModel::builder()
+                    .keys(doc! { "extr_ref": 1, "field_name": 1 })
+                    .build(),
+                IndexModel::builder()
+                    .keys(doc! { "field_name": 1, "value": 1 })
+                    .build(),
+            ];
+
+            if let Err(err) = collection.create_indexes(indexes, None).await {
+                return Err(err.to_string());
+            }
+        }
+
         return Ok(());
     }
 
@@ -72,9 +162,18 @@ impl MongoDb {
             FieldExtensionObject::CATEGORY => self.db.collection("categories"),
             FieldExtensionObject::INVENTORY => self.db.collection("inventories"),
             FieldExtensionObject::PRICEBOOK => self.db.collection("pricebooks"),
+            FieldExtensionObject::PRODUCTIMAGE => self.db.collection("product_images"),
+            FieldExtensionObject::INVENTORYRECORD => self.db.collection("inventory_records"),
+            FieldExtensionObject::PRICEBOOKRECORD => self.db.collection("pricebook_records"),
+            FieldExtensionObject::PORTALUSER => self.db.collection("portal_users"),
+            FieldExtensionObject::CUSTOMER => self.db.collection("customers"),
+            FieldExtensionObject::CUSTOMOBJECT => self.db.collection("custom_objects"),
         };
     }
 }
+#[async_trait::async_trait]
+// the futures async_trait wraps the bodies in are returned implicitly
+#[allow(clippy::implicit_return)]
 impl UnstructuredDb for MongoDb {
     async fn put_custom_fields(
         &self,
@@ -111,4 +210,237 @@ impl UnstructuredDb for MongoDb {
             Err(err) => return Err(err.to_string()),
         };
     }
+
+    async fn get_objects_custom_fields(
+        &self,
+        object: FieldExtensionObject,
+        extr_refs: &[String],
+    ) -> UnstructuredDbObjectResult {
+        let collection = self.get_collection(object);
+
+        let collection_cursor = match collection
+            .find(doc! { "extr_ref": { "$in": extr_refs } }, None)
+            .await
+        {
+            Ok(cursor) => cursor,
+            Err(err) => return Err(err.to_string()),
+        };
+
+        return match collection_cursor
+            .try_collect::<Vec<UnstructuredEntry>>()
+            .await
+        {
+            Ok(fields) => Ok(fields),
+            Err(err) => Err(err.to_string()),
+        };
+    }
+
+    async fn upsert_custom_fields(
+        &self,
+        object: FieldExtensionObject,
+        entries: Vec<UnstructuredEntry>,
+    ) -> UnstructuredDbResult {
+        let collection = self.get_collection(object);
+
+        for entry in entries {
+            let value = match mongodb::bson::to_bson(&entry.value) {
+                Ok(value) => value,
+                Err(err) => return Err(err.to_string()),
+            };
+
+            // update_many also brings entries that were duplicated by earlier
+            // inserts to the same value
+            let options = mongodb::options::UpdateOptions::builder()
+                .upsert(true)
+                .build();
+            if let Err(err) = collection
+                .update_many(
+                    doc! { "extr_ref": &entry.extr_ref, "field_name": &entry.field_name },
+                    doc! { "$set": { "value": value } },
+                    options,
+                )
+                .await
+            {
+                return Err(err.to_string());
+            }
+        }
+
+        return Ok(());
+    }
+
+    async fn delete_custom_fields(
+        &self,
+        object: FieldExtensionObject,
+        extr_ref: &str,
+        field_names: &[String],
+    ) -> UnstructuredDbResult {
+        let collection = self.get_collection(object);
+
+        if let Err(err) = collection
+            .delete_many(
+                doc! { "extr_ref": extr_ref, "field_name": { "$in": field_names } },
+                None,
+            )
+            .await
+        {
+            return Err(err.to_string());
+        }
+
+        return Ok(());
+    }
+
+    async fn find_custom_field_refs(
+        &self,
+        object: FieldExtensionObject,
+        field_name: &str,
+        filter: &UnstructuredFilter,
+    ) -> UnstructuredDbRefsResult {
+        let collection = self.get_collection(object);
+
+        let to_bson = |value: &UnstructuredEntryType| {
+            return mongodb::bson::to_bson(value).map_err(|err| return err.to_string());
+        };
+
+        // the decimals are stored as strings and compared as decimals, the ones
+        // stored as numbers by older versions are compared along with them
+        let decimal_condition = |operator: &str, value: &rust_decimal::Decimal| {
+            let decimal = doc! {
+                "$convert": { "input": "$value", "to": "decimal", "onError": null, "onNull": null }
+            };
+
+            return doc! {
+                "$expr": {
+                    "$and": [
+                        { "$ne": [decimal.clone(), null] },
+                        { operator: [decimal, { "$toDecimal": value.to_string() }] },
+                    ]
+                }
+            };
+        };
+
+        let condition = match filter {
+            UnstructuredFilter::EQ(UnstructuredEntryType::DECIMAL(value)) => {
+                decimal_condition("$eq", value)
+            }
+            UnstructuredFilter::GT(UnstructuredEntryType::DECIMAL(value)) => {
+                decimal_condition("$gt", value)
+            }
+            UnstructuredFilter::GTE(UnstructuredEntryType::DECIMAL(value)) => {
+                decimal_condition("$gte", value)
+            }
+            UnstructuredFilter::LT(UnstructuredEntryType::DECIMAL(value)) => {
+                decimal_condition("$lt", value)
+            }
+            UnstructuredFilter::LTE(UnstructuredEntryType::DECIMAL(value)) => {
+                decimal_condition("$lte", value)
+            }
+            UnstructuredFilter::EQ(value) => doc! { "value": to_bson(value)? },
+            UnstructuredFilter::GT(value) => doc! { "value": { "$gt": to_bson(value)? } },
+            UnstructuredFilter::GTE(value) => doc! { "value": { "$gte": to_bson(value)? } },
+            UnstructuredFilter::LT(value) => doc! { "value": { "$lt": to_bson(value)? } },
+            UnstructuredFilter::LTE(value) => doc! { "value": { "$lte": to_bson(value)? } },
+            UnstructuredFilter::CONTAINS(value) => doc! {
+                "value": { "$regex": escape_regex(value), "$options": "i" }
+            },
+            UnstructuredFilter::EXISTS => doc! {},
+        };
+
+        let mut query = doc! { "field_name": field_name };
+        query.extend(condition);
+
+        let refs = match collection.distinct("extr_ref", query, None).await {
+            Ok(refs) => refs,
+            Err(err) => return Err(err.to_string()),
+        };
+
+        return Ok(refs
+            .into_iter()
+            .filter_map(|x| return x.as_str().map(|x| return x.to_string()))
+            .collect());
+    }
+
+    async fn get_custom_field_values(
+        &self,
+        object: FieldExtensionObject,
+        field_name: &str,
+    ) -> UnstructuredDbObjectResult {
+        let collection = self.get_collection(object);
+
+        let collection_cursor = match collection
+            .find(doc! { "field_name": field_name }, None)
+            .await
+        {
+            Ok(cursor) => cursor,
+            Err(err) => return Err(err.to_string()),
+        };
+
+        return collection_cursor
+            .try_collect::<Vec<UnstructuredEntry>>()
+            .await
+            .map_err(|err| return err.to_string());
+    }
+
+    async fn count_custom_field_values(
+        &self,
+        object: FieldExtensionObject,
+        field_name: &str,
+    ) -> UnstructuredDbCountResult {
+        let collection = self.get_collection(object);
+
+        return collection
+            .count_documents(doc! { "field_name": field_name }, None)
+            .await
+            .map_err(|err| return err.to_string());
+    }
+
+    async fn delete_custom_field_values(
+        &self,
+        object: FieldExtensionObject,
+        field_name: &str,
+    ) -> UnstructuredDbResult {
+        let collection = self.get_collection(object);
+
+        if let Err(err) = collection
+            .delete_many(doc! { "field_name": field_name }, None)
+            .await
+        {
+            return Err(err.to_string());
+        }
+
+        return Ok(());
+    }
+
+    async fn delete_custom_field_values_of(
+        &self,
+        object: FieldExtensionObject,
+        field_name: &str,
+        extr_refs: &[String],
+    ) -> UnstructuredDbResult {
+        let collection = self.get_collection(object);
+
+        if let Err(err) = collection
+            .delete_many(
+                doc! { "field_name": field_name, "extr_ref": { "$in": extr_refs } },
+                None,
+            )
+            .await
+        {
+            return Err(err.to_string());
+        }
+
+        return Ok(());
+    }
+}
+
+// the value is matched literally
+fn escape_regex(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for char in value.chars() {
+        if !char.is_alphanumeric() && !char.is_whitespace() {
+            escaped.push('\\');
+        }
+        escaped.push(char);
+    }
+
+    return escaped;
 }
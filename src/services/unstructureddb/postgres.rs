@@ -0,0 +1,308 @@
+use super::{
+    entry::{UnstructuredEntry, UnstructuredEntryType},
+    query::UnstructuredFilter,
+    UnstructuredDb, UnstructuredDbCountResult, UnstructuredDbObjectResult,
+    UnstructuredDbRefsResult, UnstructuredDbResult,
+};
+use crate::models::base_extensions::FieldExtensionObject;
+use sqlx::types::Json;
+
+// The decimals are stored as strings and compared as numbers, the ones stored
+// as numbers by older versions are compared along with them. Values that are
+// not a number are left out instead of failing the cast.
+const DECIMAL_VALUE: &str = "CASE WHEN value #>> '{}' ~ '^-?[0-9]+(\\.[0-9]+)?([eE][-+]?[0-9]+)?$' THEN (value #>> '{}')::numeric END";
+
+#[derive(sqlx::FromRow)]
+struct UnstructuredRow {
+    extr_ref: String,
+    field_name: String,
+    value: Json<UnstructuredEntryType>,
+}
+
+impl From<UnstructuredRow> for UnstructuredEntry {
+    fn from(row: UnstructuredRow) -> Self {
+        return Self {
+            extr_ref: row.extr_ref,
+            field_name: row.field_name,
+            value: row.value.0,
+        };
+    }
+}
+
+// Keeps the entries of every object in the `_unstructured_entries` table, the
+// values are stored as JSONB in the same form they have in mongodb.
+pub struct PgUnstructuredDb {
+    pool: sqlx::Pool<sqlx::Postgres>,
+}
+
+impl PgUnstructuredDb {
+    pub fn new(pool: sqlx::Pool<sqlx::Postgres>) -> Self {
+        return Self { pool };
+    }
+
+    async fn fetch_entries(
+        &self,
+        query: sqlx::query::QueryAs<
+            '_,
+            sqlx::Postgres,
+            UnstructuredRow,
+            sqlx::postgres::PgArguments,
+        >,
+    ) -> UnstructuredDbObjectResult {
+        return match query.fetch_all(&self.pool).await {
+            Ok(rows) => Ok(rows.into_iter().map(UnstructuredEntry::from).collect()),
+            Err(err) => Err(err.to_string()),
+        };
+    }
+
+    async fn insert_entries(
+        &self,
+        object: FieldExtensionObject,
+        entries: Vec<UnstructuredEntry>,
+        upsert: bool,
+    ) -> UnstructuredDbResult {
+        let mut extr_refs = Vec::with_capacity(entries.len());
+        let mut field_names = Vec::with_capacity(entries.len());
+        let mut values = Vec::with_capacity(entries.len());
+        for entry in entries {
+            extr_refs.push(entry.extr_ref);
+            field_names.push(entry.field_name);
+            values.push(Json(entry.value));
+        }
+
+        let conflict = if upsert {
+            "ON CONFLICT (object, extr_ref, field_name) DO UPDATE SET value = EXCLUDED.value"
+        } else {
+            ""
+        };
+
+        if let Err(err) = sqlx::query(&format!(
+            "INSERT INTO _unstructured_entries (object, extr_ref, field_name, value)
+            SELECT $1, * FROM UNNEST($2::varchar[], $3::varchar[], $4::jsonb[]) {conflict}"
+        ))
+        .bind(object)
+        .bind(extr_refs)
+        .bind(field_names)
+        .bind(values)
+        .execute(&self.pool)
+        .await
+        {
+            return Err(err.to_string());
+        }
+
+        return Ok(());
+    }
+}
+
+#[async_trait::async_trait]
+// the futures async_trait wraps the bodies in are returned implicitly
+#[allow(clippy::implicit_return)]
+impl UnstructuredDb for PgUnstructuredDb {
+    async fn put_custom_fields(
+        &self,
+        object: FieldExtensionObject,
+        entry: Vec<UnstructuredEntry>,
+    ) -> UnstructuredDbResult {
+        return self.insert_entries(object, entry, false).await;
+    }
+
+    async fn get_custom_fields(
+        &self,
+        object: FieldExtensionObject,
+        extr_ref: &str,
+    ) -> UnstructuredDbObjectResult {
+        return self
+            .fetch_entries(
+                sqlx::query_as(
+                    "SELECT extr_ref, field_name, value FROM _unstructured_entries WHERE object = $1 AND extr_ref = $2",
+                )
+                .bind(object)
+                .bind(extr_ref),
+            )
+            .await;
+    }
+
+    async fn get_objects_custom_fields(
+        &self,
+        object: FieldExtensionObject,
+        extr_refs: &[String],
+    ) -> UnstructuredDbObjectResult {
+        return self
+            .fetch_entries(
+                sqlx::query_as(
+                    "SELECT extr_ref, field_name, value FROM _unstructured_entries WHERE object = $1 AND extr_ref = ANY($2)",
+                )
+                .bind(object)
+                .bind(extr_refs),
+            )
+            .await;
+    }
+
+    async fn upsert_custom_fields(
+        &self,
+        object: FieldExtensionObject,
+        entries: Vec<UnstructuredEntry>,
+    ) -> UnstructuredDbResult {
+        return self.insert_entries(object, entries, true).await;
+    }
+
+    async fn delete_custom_fields(
+        &self,
+        object: FieldExtensionObject,
+        extr_ref: &str,
+        field_names: &[String],
+    ) -> UnstructuredDbResult {
+        if let Err(err) = sqlx::query(
+            "DELETE FROM _unstructured_entries WHERE object = $1 AND extr_ref = $2 AND field_name = ANY($3)",
+        )
+        .bind(object)
+        .bind(extr_ref)
+        .bind(field_names)
+        .execute(&self.pool)
+        .await
+        {
+            return Err(err.to_string());
+        }
+
+        return Ok(());
+    }
+
+    async fn get_custom_field_values(
+        &self,
+        object: FieldExtensionObject,
+        field_name: &str,
+    ) -> UnstructuredDbObjectResult {
+        return self
+            .fetch_entries(
+                sqlx::query_as(
+                    "SELECT extr_ref, field_name, value FROM _unstructured_entries WHERE object = $1 AND field_name = $2",
+                )
+                .bind(object)
+                .bind(field_name),
+            )
+            .await;
+    }
+
+    async fn find_custom_field_refs(
+        &self,
+        object: FieldExtensionObject,
+        field_name: &str,
+        filter: &UnstructuredFilter,
+    ) -> UnstructuredDbRefsResult {
+        let decimal_condition = |operator: &str| {
+            return format!("AND {DECIMAL_VALUE} {operator} ($3 #>> '{{}}')::numeric");
+        };
+
+        // like in mongodb an equal value also matches the lists containing it
+        // and the ranges only match the values of the same type
+        let condition = match filter {
+            UnstructuredFilter::EQ(UnstructuredEntryType::DECIMAL(_)) => decimal_condition("="),
+            UnstructuredFilter::GT(UnstructuredEntryType::DECIMAL(_)) => decimal_condition(">"),
+            UnstructuredFilter::GTE(UnstructuredEntryType::DECIMAL(_)) => decimal_condition(">="),
+            UnstructuredFilter::LT(UnstructuredEntryType::DECIMAL(_)) => decimal_condition("<"),
+            UnstructuredFilter::LTE(UnstructuredEntryType::DECIMAL(_)) => decimal_condition("<="),
+            UnstructuredFilter::EQ(_) => {
+                "AND (value = $3 OR (jsonb_typeof(value) = 'array' AND value @> jsonb_build_array($3)))".to_string()
+            }
+            UnstructuredFilter::GT(_) => "AND jsonb_typeof(value) = jsonb_typeof($3) AND value > $3".to_string(),
+            UnstructuredFilter::GTE(_) => "AND jsonb_typeof(value) = jsonb_typeof($3) AND value >= $3".to_string(),
+            UnstructuredFilter::LT(_) => "AND jsonb_typeof(value) = jsonb_typeof($3) AND value < $3".to_string(),
+            UnstructuredFilter::LTE(_) => "AND jsonb_typeof(value) = jsonb_typeof($3) AND value <= $3".to_string(),
+            UnstructuredFilter::CONTAINS(_) => {
+                "AND jsonb_typeof(value) = 'string' AND value #>> '{}' ILIKE $3".to_string()
+            }
+            UnstructuredFilter::EXISTS => String::new(),
+        };
+
+        let sql = format!(
+            "SELECT DISTINCT extr_ref FROM _unstructured_entries WHERE object = $1 AND field_name = $2 {condition}"
+        );
+        let query = sqlx::query_scalar::<_, String>(&sql)
+            .bind(object)
+            .bind(field_name);
+        let query = match filter {
+            UnstructuredFilter::EQ(value)
+            | UnstructuredFilter::GT(value)
+            | UnstructuredFilter::GTE(value)
+            | UnstructuredFilter::LT(value)
+            | UnstructuredFilter::LTE(value) => query.bind(Json(value)),
+            UnstructuredFilter::CONTAINS(value) => query.bind(format!("%{}%", escape_like(value))),
+            UnstructuredFilter::EXISTS => query,
+        };
+
+        return query
+            .fetch_all(&self.pool)
+            .await
+            .map_err(|err| return err.to_string());
+    }
+
+    async fn count_custom_field_values(
+        &self,
+        object: FieldExtensionObject,
+        field_name: &str,
+    ) -> UnstructuredDbCountResult {
+        return match sqlx::query_scalar::<_, i64>(
+            "SELECT COUNT(*) FROM _unstructured_entries WHERE object = $1 AND field_name = $2",
+        )
+        .bind(object)
+        .bind(field_name)
+        .fetch_one(&self.pool)
+        .await
+        {
+            Ok(count) => Ok(count as u64),
+            Err(err) => Err(err.to_string()),
+        };
+    }
+
+    async fn delete_custom_field_values(
+        &self,
+        object: FieldExtensionObject,
+        field_name: &str,
+    ) -> UnstructuredDbResult {
+        if let Err(err) =
+            sqlx::query("DELETE FROM _unstructured_entries WHERE object = $1 AND field_name = $2")
+                .bind(object)
+                .bind(field_name)
+                .execute(&self.pool)
+                .await
+        {
+            return Err(err.to_string());
+        }
+
+        return Ok(());
+    }
+
+    async fn delete_custom_field_values_of(
+        &self,
+        object: FieldExtensionObject,
+        field_name: &str,
+        extr_refs: &[String],
+    ) -> UnstructuredDbResult {
+        if let Err(err) = sqlx::query(
+            "DELETE FROM _unstructured_entries WHERE object = $1 AND field_name = $2 AND extr_ref = ANY($3)",
+        )
+        .bind(object)
+        .bind(field_name)
+        .bind(extr_refs)
+        .execute(&self.pool)
+        .await
+        {
+            return Err(err.to_string());
+        }
+
+        return Ok(());
+    }
+}
+
+// the value is matched literally
+fn escape_like(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for char in value.chars() {
+        if matches!(char, '\\' | '%' | '_') {
+            escaped.push('\\');
+        }
+        escaped.push(char);
+    }
+
+    return escaped;
+}
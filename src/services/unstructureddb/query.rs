@@ -0,0 +1,14 @@
+use super::entry::UnstructuredEntryType;
+
+// A predicate on the value of a single custom field, the values are already in
+// the form they are stored in.
+pub enum UnstructuredFilter {
+    EQ(UnstructuredEntryType),
+    GT(UnstructuredEntryType),
+    GTE(UnstructuredEntryType),
+    LT(UnstructuredEntryType),
+    LTE(UnstructuredEntryType),
+    // case insensitive substring of a string value
+    CONTAINS(String),
+    EXISTS,
+}
@@ -0,0 +1,76 @@
+use std::time::{self, Duration, SystemTime};
+
+use argon2::{
+    password_hash::{
+        rand_core::{OsRng, RngCore},
+        SaltString,
+    },
+    Argon2, PasswordHash, PasswordHasher, PasswordVerifier,
+};
+
+pub fn hash_password(password: &str) -> Result<String, String> {
+    let salt = SaltString::generate(&mut OsRng);
+    return match Argon2::default().hash_password(password.as_bytes(), &salt) {
+        Ok(hash) => Ok(hash.to_string()),
+        Err(err) => Err(err.to_string()),
+    };
+}
+
+pub fn verify_password(hash: &str, password: &str) -> Result<bool, String> {
+    let parsed_hash = match PasswordHash::new(hash) {
+        Ok(hash) => hash,
+        Err(err) => return Err(err.to_string()),
+    };
+
+    return Ok(Argon2::default()
+        .verify_password(password.as_bytes(), &parsed_hash)
+        .is_ok());
+}
+
+// Checked instead of the stored hash when no account has the email, so unknown
+// emails take as long as wrong passwords and the timing does not tell which
+// accounts exist. It uses the same argon2 parameters as hash_password.
+pub const DUMMY_PASSWORD_HASH: &str =
+    "$argon2id$v=19$m=19456,t=2,p=1$1aHG5zfDxlcxj+cajanzUQ$6B0nKsAj4uC84oKSH9CR4lZWbxDSh2CuvmBST3wKnUc";
+
+pub fn verify_dummy_password(password: &str) {
+    let _ = verify_password(DUMMY_PASSWORD_HASH, password);
+}
+
+pub fn get_token_expiration(valid_for: Duration) -> Result<u64, String> {
+    let expiration = match SystemTime::now().checked_add(valid_for) {
+        Some(expiration) => expiration,
+        None => return Err("Internal server error".to_string()),
+    };
+
+    return match expiration.duration_since(time::UNIX_EPOCH) {
+        Ok(exp) => Ok(exp.as_secs()),
+        Err(_err) => Err("Internal server error".to_string()),
+    };
+}
+
+// Opaque random tokens (share links, refresh and reset tokens), hex encoded.
+pub fn generate_bytes(bytes: usize) -> Vec<u8> {
+    let mut buffer = vec![0u8; bytes];
+    OsRng.fill_bytes(&mut buffer);
+
+    return buffer;
+}
+
+pub fn generate_token(bytes: usize) -> String {
+    return generate_bytes(bytes)
+        .iter()
+        .map(|b| return format!("{:02x}", b))
+        .collect();
+}
+
+// Opaque credentials are handed out as '<id>.<secret>', only the argon2 hash of
+// the secret is stored next to the id.
+pub fn split_credential(credential: &str) -> Option<(&str, &str)> {
+    let (id, secret) = credential.split_once('.')?;
+    if id.is_empty() || secret.is_empty() {
+        return None;
+    }
+
+    return Some((id, secret));
+}
@@ -0,0 +1,403 @@
+use crate::{
+    models::base_extensions::{
+        CustomFieldMigration, CustomFieldMigrationStatus, FieldExtension, FieldExtensionType,
+    },
+    schemas::base_extensions::{CustomFieldViolationAction, MigrateCustomField},
+    services::unstructureddb::entry::{UnstructuredEntry, UnstructuredEntryType},
+    utils::custom_fields::read_custom_field,
+    CommercyfyState,
+};
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
+
+#[derive(serde::Serialize)]
+pub struct CustomFieldValueChange {
+    pub extr_ref: String,
+    pub from: UnstructuredEntryType,
+    pub to: UnstructuredEntryType,
+}
+
+#[derive(serde::Serialize)]
+pub struct CustomFieldValueViolation {
+    pub extr_ref: String,
+    // None when the object has no value for a field that becomes mandatory
+    pub value: Option<UnstructuredEntryType>,
+    pub error: String,
+}
+
+// What a migration does with the stored values of a field, it is returned by
+// the dry-run and planned again when the migration runs.
+#[derive(serde::Serialize)]
+pub struct CustomFieldMigrationReport {
+    pub name: String,
+    pub from_version: i32,
+    pub to_version: i32,
+
+    // the number of stored values
+    pub values: usize,
+
+    pub transformed: Vec<CustomFieldValueChange>,
+    pub dropped: Vec<CustomFieldValueViolation>,
+
+    // the values that do not fit, the migration is refused while there are any
+    pub violations: Vec<CustomFieldValueViolation>,
+
+    // every value that is kept, as it is stored after the migration
+    #[serde(skip)]
+    kept: Vec<UnstructuredEntry>,
+}
+
+// the definition the field would have after the change
+fn change_field(
+    field: &FieldExtension,
+    payload: &MigrateCustomField,
+) -> Result<FieldExtension, String> {
+    let is_string = matches!(field.r#type, FieldExtensionType::STRING);
+    let has_allowed_values = matches!(
+        field.r#type,
+        FieldExtensionType::ENUM | FieldExtensionType::SET
+    );
+
+    if (payload.max_len.is_some() || payload.min_len.is_some()) && !is_string {
+        return Err("'max_len' and 'min_len' are only available on string fields".to_string());
+    }
+
+    if payload.allowed_values.is_some() && !has_allowed_values {
+        return Err("'allowed_values' is only available on enum and set fields".to_string());
+    }
+
+    if payload.value_map.is_some() && !is_string && !has_allowed_values {
+        return Err("'value_map' is only available on string, enum and set fields".to_string());
+    }
+
+    if payload.on_violation == Some(CustomFieldViolationAction::TRUNCATE) && !is_string {
+        return Err("'truncate' is only available on string fields".to_string());
+    }
+
+    let mut proposed = field.clone();
+    if let Some(name) = &payload.name {
+        proposed.name = name.clone();
+    }
+    if let Some(description) = &payload.description {
+        proposed.description = Some(description.clone());
+    }
+    if let Some(mandatory) = payload.mandatory {
+        proposed.mandatory = mandatory;
+    }
+    if let Some(max_len) = payload.max_len {
+        proposed.max_len = Some(max_len);
+    }
+    if let Some(min_len) = payload.min_len {
+        proposed.min_len = Some(min_len);
+    }
+    if let Some(allowed_values) = &payload.allowed_values {
+        proposed.allowed_values = Some(allowed_values.clone());
+    }
+
+    if let (Some(max_len), Some(min_len)) = (proposed.max_len, proposed.min_len) {
+        if min_len > max_len {
+            return Err("'min_len' can not be greater than 'max_len'".to_string());
+        }
+    }
+
+    if matches!(field.r#type, FieldExtensionType::ENUM)
+        && proposed
+            .allowed_values
+            .as_ref()
+            .is_some_and(|x| return x.is_empty())
+    {
+        return Err("'allowed_values' is mandatory for enum fields".to_string());
+    }
+
+    return Ok(proposed);
+}
+
+// the items of a set that end up the same are only kept once
+fn map_value(value: serde_json::Value, value_map: &HashMap<String, String>) -> serde_json::Value {
+    return match value {
+        serde_json::Value::String(value) => {
+            serde_json::Value::String(value_map.get(&value).cloned().unwrap_or(value))
+        }
+        serde_json::Value::Array(items) => {
+            let mut mapped: Vec<serde_json::Value> = vec![];
+            for item in items {
+                let item = map_value(item, value_map);
+                if !mapped.contains(&item) {
+                    mapped.push(item);
+                }
+            }
+
+            serde_json::Value::Array(mapped)
+        }
+        value => value,
+    };
+}
+
+fn truncate_value(value: serde_json::Value, max_len: Option<i64>) -> serde_json::Value {
+    return match (value, max_len) {
+        (serde_json::Value::String(value), Some(max_len)) => {
+            serde_json::Value::String(value.chars().take(max_len.max(0) as usize).collect())
+        }
+        (value, _) => value,
+    };
+}
+
+pub async fn plan_custom_field_migration(
+    state: &CommercyfyState,
+    field: &FieldExtension,
+    payload: &MigrateCustomField,
+) -> Result<CustomFieldMigrationReport, String> {
+    let proposed = change_field(field, payload)?;
+    let on_violation = payload.on_violation.unwrap_or_default();
+
+    // the fields of the custom object types only own the values of the records
+    // of their type
+    let becomes_mandatory = proposed.mandatory && !field.mandatory;
+    let object_ids = if field.custom_object_id.is_some() || becomes_mandatory {
+        match state
+            .db_service
+            .get_custom_field_object_ids(field.object.clone(), field.custom_object_id)
+            .await
+        {
+            Ok(object_ids) => object_ids.into_iter().collect::<HashSet<String>>(),
+            Err(err) => return Err(err.to_string()),
+        }
+    } else {
+        HashSet::new()
+    };
+
+    let mut entries = state
+        .unstructureddb
+        .get_custom_field_values(field.object.clone(), &field.name)
+        .await?;
+    if field.custom_object_id.is_some() {
+        entries.retain(|x| return object_ids.contains(&x.extr_ref));
+    }
+    entries.sort_by(|a, b| return a.extr_ref.cmp(&b.extr_ref));
+    let extr_refs = entries
+        .iter()
+        .map(|x| return x.extr_ref.clone())
+        .collect::<HashSet<String>>();
+
+    let mut report = CustomFieldMigrationReport {
+        name: proposed.name.clone(),
+        from_version: field.version,
+        to_version: field.version + 1,
+        values: entries.len(),
+        transformed: vec![],
+        dropped: vec![],
+        violations: vec![],
+        kept: vec![],
+    };
+
+    for entry in entries {
+        // the values are compared in their stored form, a decimal reads back as
+        // a string
+        let stored = match serde_json::to_value(&entry.value) {
+            Ok(stored) => stored,
+            Err(err) => return Err(err.to_string()),
+        };
+        let mut value = stored.clone();
+
+        if let Some(value_map) = &payload.value_map {
+            value = map_value(value, value_map);
+        }
+
+        if on_violation == CustomFieldViolationAction::TRUNCATE {
+            value = truncate_value(value, proposed.max_len);
+        }
+
+        let value = match read_custom_field(&proposed, &value) {
+            Ok(value) => value,
+            Err(error) => {
+                let violation = CustomFieldValueViolation {
+                    extr_ref: entry.extr_ref,
+                    value: Some(entry.value),
+                    error,
+                };
+
+                match on_violation {
+                    CustomFieldViolationAction::DROP => report.dropped.push(violation),
+                    _ => report.violations.push(violation),
+                };
+                continue;
+            }
+        };
+
+        if serde_json::to_value(&value).ok().as_ref() != Some(&stored) {
+            report.transformed.push(CustomFieldValueChange {
+                extr_ref: entry.extr_ref.clone(),
+                from: entry.value,
+                to: value.clone(),
+            });
+        }
+
+        report.kept.push(UnstructuredEntry {
+            extr_ref: entry.extr_ref,
+            field_name: proposed.name.clone(),
+            value,
+        });
+    }
+
+    // the objects without a value can not be fixed by dropping or truncating,
+    // a field only becomes mandatory once all of them have one
+    if becomes_mandatory {
+        let mut missing = object_ids
+            .into_iter()
+            .filter(|x| return !extr_refs.contains(x))
+            .collect::<Vec<String>>();
+        missing.sort();
+
+        for extr_ref in missing {
+            report.violations.push(CustomFieldValueViolation {
+                extr_ref,
+                value: None,
+                error: format!("'{}' is mandatory", proposed.name),
+            });
+        }
+    }
+
+    return Ok(report);
+}
+
+// Values are written under the new name before the old ones are removed and the
+// definition only changes once the values were moved, so a migration that
+// failed half way can be run again.
+async fn migrate_custom_field_values(
+    state: &CommercyfyState,
+    field: &FieldExtension,
+    payload: &MigrateCustomField,
+) -> Result<(i64, i64), String> {
+    let current = match state
+        .db_service
+        .get_custom_field_by_id(field.object.clone(), &field.id.to_string())
+        .await
+    {
+        Ok(current) => current,
+        Err(err) => return Err(err.to_string()),
+    };
+    if current.is_none_or(|x| return x.version != field.version) {
+        return Err(format!(
+            "'{}' was changed since version {}",
+            field.name, field.version
+        ));
+    }
+
+    let report = plan_custom_field_migration(state, field, payload).await?;
+    if !report.violations.is_empty() {
+        return Err(format!(
+            "{} existing value(s) of '{}' do not fit the change",
+            report.violations.len(),
+            field.name
+        ));
+    }
+
+    let renamed = report.name != field.name;
+    let entries = if renamed {
+        report.kept
+    } else {
+        report
+            .transformed
+            .into_iter()
+            .map(|change| {
+                return UnstructuredEntry {
+                    extr_ref: change.extr_ref,
+                    field_name: field.name.clone(),
+                    value: change.to,
+                };
+            })
+            .collect::<Vec<UnstructuredEntry>>()
+    };
+    let migrated = entries.len() as i64;
+    let dropped = report
+        .dropped
+        .into_iter()
+        .map(|violation| return violation.extr_ref)
+        .collect::<Vec<String>>();
+
+    // only the values that were planned are removed under the old name, a value
+    // written while the migration runs is not lost with them
+    let mut removed = dropped.clone();
+    if renamed {
+        removed.extend(entries.iter().map(|x| return x.extr_ref.clone()));
+    }
+
+    if !entries.is_empty() {
+        state
+            .unstructureddb
+            .upsert_custom_fields(field.object.clone(), entries)
+            .await?;
+    }
+
+    if !removed.is_empty() {
+        state
+            .unstructureddb
+            .delete_custom_field_values_of(field.object.clone(), &field.name, &removed)
+            .await?;
+    }
+
+    return match state
+        .db_service
+        .migrate_custom_field(field.id, field.version, payload)
+        .await
+    {
+        Ok(Some(_field)) => Ok((migrated, dropped.len() as i64)),
+        Ok(None) => Err(format!(
+            "'{}' was changed while it was migrated",
+            field.name
+        )),
+        Err(err) => Err(err.to_string()),
+    };
+}
+
+// runs in the background, the outcome is kept on the migration
+pub async fn run_custom_field_migration(
+    state: Arc<CommercyfyState>,
+    field: FieldExtension,
+    payload: MigrateCustomField,
+    migration: CustomFieldMigration,
+) {
+    let outcome = match migrate_custom_field_values(&state, &field, &payload).await {
+        Ok((migrated, dropped)) => {
+            state
+                .db_service
+                .finish_custom_field_migration(
+                    migration.id,
+                    CustomFieldMigrationStatus::COMPLETED,
+                    migrated,
+                    dropped,
+                    None,
+                )
+                .await
+        }
+        Err(err) => {
+            let _ = state.logger.category_error(
+                "custom-fields",
+                &format!("The migration of '{}' failed: {}", field.name, err),
+            );
+
+            state
+                .db_service
+                .finish_custom_field_migration(
+                    migration.id,
+                    CustomFieldMigrationStatus::FAILED,
+                    0,
+                    0,
+                    Some(err),
+                )
+                .await
+        }
+    };
+
+    if let Err(err) = outcome {
+        let _ = state.logger.category_error(
+            "custom-fields",
+            &format!(
+                "Could not store the outcome of the migration '{}': {}",
+                migration.id, err
+            ),
+        );
+    }
+}
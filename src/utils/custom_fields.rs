@@ -1,59 +1,631 @@
 use crate::{
-    models::base_extensions::FieldExtensionObject,
-    schemas::base_extensions::{ObjectCustomField, ObjectCustomFields},
+    models::base_extensions::{FieldExtension, FieldExtensionObject, FieldExtensionType},
+    routes::FieldError,
+    schemas::base_extensions::ObjectCustomFields,
     services::{
-        db::DbService,
         unstructureddb::entry::{UnstructuredEntry, UnstructuredEntryType},
-        unstructureddb::UnstructuredDb,
+        unstructureddb::query::UnstructuredFilter,
     },
     CommercyfyState,
 };
-use std::sync::Arc;
+use axum::http::StatusCode;
+use rust_decimal::Decimal;
+use std::{
+    collections::{HashMap, HashSet},
+    str::FromStr,
+    sync::Arc,
+};
 
-pub async fn create_custom_fields(
-    state: Arc<CommercyfyState>,
-    extr_ref: String,
-    object: FieldExtensionObject,
-    custom_fields: &ObjectCustomFields,
-) -> Result<(), String> {
-    if let Some(custom_fields) = custom_fields {
-        if custom_fields.is_empty() {
-            return Ok(());
+pub struct CustomFieldValues {
+    pub values: Vec<(String, UnstructuredEntryType)>,
+    pub errors: Vec<FieldError>,
+}
+
+// The objects of a list that pass the custom field filters of the query. When
+// no filter selects objects every object is included, the excluded ones are
+// those with a field that is required not to be set.
+pub struct CustomFieldMatches {
+    included: Option<HashSet<String>>,
+    excluded: HashSet<String>,
+    pub errors: Vec<FieldError>,
+}
+
+impl CustomFieldMatches {
+    pub fn matches(&self, extr_ref: &str) -> bool {
+        return !self.excluded.contains(extr_ref)
+            && self
+                .included
+                .as_ref()
+                .is_none_or(|included| return included.contains(extr_ref));
+    }
+}
+
+fn read_string_field(field: &FieldExtension, value: &str) -> Result<UnstructuredEntryType, String> {
+    let len = value.chars().count() as i64;
+
+    if field.mandatory && value.is_empty() {
+        return Err("The field is mandatory and can not be empty".to_string());
+    }
+
+    if let Some(min_len) = field.min_len {
+        if len < min_len {
+            return Err(format!("Should be at least {} characters long", min_len));
         }
+    }
 
-        let mut unstructured_entries: Vec<UnstructuredEntry> = vec![];
-        for (key, value) in custom_fields {
-            let custom_field = match state.db_service.get_custom_field(object.clone(), key).await {
-                Ok(custom_field) => custom_field,
-                Err(err) => return Err(err.to_string()),
+    if let Some(max_len) = field.max_len {
+        if len > max_len {
+            return Err(format!("Should be at most {} characters long", max_len));
+        }
+    }
+
+    return Ok(UnstructuredEntryType::STRING(value.to_string()));
+}
+
+fn read_decimal_field(
+    field: &FieldExtension,
+    value: &serde_json::Value,
+) -> Result<UnstructuredEntryType, String> {
+    let decimal = match value {
+        serde_json::Value::Number(number) => Decimal::from_str(&number.to_string()),
+        serde_json::Value::String(string) => Decimal::from_str(string),
+        _ => return Err("Should be a decimal".to_string()),
+    };
+
+    let decimal = match decimal {
+        Ok(decimal) => decimal.normalize(),
+        Err(_err) => return Err("Should be a decimal".to_string()),
+    };
+
+    if let Some(scale) = field.scale {
+        if decimal.scale() > scale as u32 {
+            return Err(format!("Should have at most {} decimal places", scale));
+        }
+    }
+
+    return Ok(UnstructuredEntryType::DECIMAL(decimal));
+}
+
+fn read_allowed_value(field: &FieldExtension, value: &str) -> Result<(), String> {
+    return match &field.allowed_values {
+        Some(allowed_values) if !allowed_values.iter().any(|x| return x == value) => Err(format!(
+            "'{}' is not one of {}",
+            value,
+            allowed_values.join(", ")
+        )),
+        _ => Ok(()),
+    };
+}
+
+// converts the value to the form it is stored in, the error is the reason the
+// value does not fit the field
+pub fn read_custom_field(
+    field: &FieldExtension,
+    value: &serde_json::Value,
+) -> Result<UnstructuredEntryType, String> {
+    if value.is_null() {
+        return Err("Should not be null".to_string());
+    }
+
+    return match field.r#type {
+        FieldExtensionType::STRING => match value.as_str() {
+            Some(string) => read_string_field(field, string),
+            None => Err("Should be a string".to_string()),
+        },
+        FieldExtensionType::INT => match value.as_i64() {
+            Some(integer) => Ok(UnstructuredEntryType::INT(integer)),
+            None => Err("Should be an integer".to_string()),
+        },
+        FieldExtensionType::BOOLEAN => match value.as_bool() {
+            Some(boolean) => Ok(UnstructuredEntryType::BOOLEAN(boolean)),
+            None => Err("Should be a boolean".to_string()),
+        },
+        FieldExtensionType::DECIMAL => read_decimal_field(field, value),
+        FieldExtensionType::DATE => {
+            match value
+                .as_str()
+                .and_then(|x| return chrono::NaiveDate::parse_from_str(x, "%Y-%m-%d").ok())
+            {
+                Some(date) => Ok(UnstructuredEntryType::STRING(
+                    date.format("%Y-%m-%d").to_string(),
+                )),
+                None => Err("Should be a date formatted as YYYY-MM-DD".to_string()),
+            }
+        }
+        FieldExtensionType::DATETIME => {
+            match value
+                .as_str()
+                .and_then(|x| return chrono::DateTime::parse_from_rfc3339(x).ok())
+            {
+                Some(datetime) => Ok(UnstructuredEntryType::STRING(
+                    datetime
+                        .with_timezone(&chrono::Utc)
+                        .to_rfc3339_opts(chrono::SecondsFormat::Millis, true),
+                )),
+                None => Err("Should be an RFC 3339 datetime".to_string()),
+            }
+        }
+        FieldExtensionType::ENUM => match value.as_str() {
+            Some(string) => {
+                read_allowed_value(field, string)?;
+                Ok(UnstructuredEntryType::STRING(string.to_string()))
+            }
+            None => Err("Should be a string".to_string()),
+        },
+        FieldExtensionType::SET => {
+            let items = if let Some(items) = value.as_array() {
+                items
+            } else {
+                return Err("Should be a list of strings".to_string());
             };
 
-            if let None = custom_field {
-                return Err(format!("Custom field with name '{}', does not exist", key));
+            let mut values: Vec<String> = vec![];
+            for item in items {
+                let item = if let Some(item) = item.as_str() {
+                    item
+                } else {
+                    return Err("Should be a list of strings".to_string());
+                };
+
+                read_allowed_value(field, item)?;
+
+                if values.iter().any(|x| return x == item) {
+                    return Err(format!("'{}' is listed more than once", item));
+                }
+
+                values.push(item.to_string());
+            }
+
+            if field.mandatory && values.is_empty() {
+                return Err("The field is mandatory and can not be empty".to_string());
+            }
+
+            Ok(UnstructuredEntryType::LIST(values))
+        }
+        FieldExtensionType::REFERENCE => {
+            match value
+                .as_str()
+                .and_then(|x| return uuid::Uuid::parse_str(x).ok())
+            {
+                Some(id) => Ok(UnstructuredEntryType::STRING(id.to_string())),
+                None => Err("Should be the id of the referenced object".to_string()),
             }
+        }
+        FieldExtensionType::JSON => Ok(UnstructuredEntryType::JSON(value.clone())),
+    };
+}
+
+// Checks the values against the field definitions of the object and converts
+// them to the form they are stored in. Every field that is not valid is
+// reported, an error is only returned when the definitions could not be
+// loaded. Mandatory fields are only required to be present when all the fields
+// of the object are written, not when some of them are changed.
+pub async fn validate_custom_fields(
+    state: &CommercyfyState,
+    object: FieldExtensionObject,
+    custom_fields: &ObjectCustomFields,
+    require_mandatory: bool,
+) -> Result<CustomFieldValues, String> {
+    let definitions = match state.db_service.get_custom_fields(object).await {
+        Ok(definitions) => definitions,
+        Err(err) => return Err(err.to_string()),
+    };
+
+    return check_custom_fields(state, &definitions, custom_fields, require_mandatory).await;
+}
+
+// validate_custom_fields against the given definitions, the custom object
+// types have their own
+pub async fn check_custom_fields(
+    state: &CommercyfyState,
+    definitions: &[FieldExtension],
+    custom_fields: &ObjectCustomFields,
+    require_mandatory: bool,
+) -> Result<CustomFieldValues, String> {
+    let mut values: Vec<(String, UnstructuredEntryType)> = vec![];
+    let mut errors: Vec<FieldError> = vec![];
+
+    if let Some(custom_fields) = custom_fields {
+        for (key, value) in custom_fields {
+            let definition =
+                if let Some(definition) = definitions.iter().find(|x| return &x.name == key) {
+                    definition
+                } else {
+                    errors.push(FieldError {
+                        field: key.to_string(),
+                        error: "The custom field does not exist".to_string(),
+                    });
+                    continue;
+                };
 
-            let custom_value = match value {
-                ObjectCustomField::STRING(string) => {
-                    UnstructuredEntryType::STRING(string.to_owned())
+            let value = match read_custom_field(definition, value) {
+                Ok(value) => value,
+                Err(error) => {
+                    errors.push(FieldError {
+                        field: key.to_string(),
+                        error,
+                    });
+                    continue;
                 }
-                ObjectCustomField::INT(integer) => UnstructuredEntryType::INT(integer.to_owned()),
             };
 
-            unstructured_entries.push(UnstructuredEntry {
+            if let (Some(reference_object), UnstructuredEntryType::STRING(id)) =
+                (&definition.reference_object, &value)
+            {
+                match state
+                    .db_service
+                    .custom_field_object_exists(reference_object.clone(), id)
+                    .await
+                {
+                    Ok(true) => {}
+                    Ok(false) => {
+                        errors.push(FieldError {
+                            field: key.to_string(),
+                            error: format!("There is no object with id '{}'", id),
+                        });
+                        continue;
+                    }
+                    Err(err) => return Err(err.to_string()),
+                };
+            }
+
+            values.push((key.to_string(), value));
+        }
+    }
+
+    if require_mandatory {
+        for definition in definitions.iter().filter(|x| return x.mandatory) {
+            let present = custom_fields
+                .as_ref()
+                .is_some_and(|x| return x.contains_key(&definition.name));
+
+            if !present {
+                errors.push(FieldError {
+                    field: definition.name.clone(),
+                    error: "The field is mandatory".to_string(),
+                });
+            }
+        }
+    }
+
+    errors.sort_by(|a, b| return a.field.cmp(&b.field));
+
+    return Ok(CustomFieldValues { values, errors });
+}
+
+pub async fn get_object_custom_fields(
+    state: &CommercyfyState,
+    object: FieldExtensionObject,
+    extr_ref: &str,
+) -> Result<HashMap<String, UnstructuredEntryType>, String> {
+    let entries = state
+        .unstructureddb
+        .get_custom_fields(object, extr_ref)
+        .await?;
+
+    return Ok(entries
+        .into_iter()
+        .map(|entry| return (entry.field_name, entry.value))
+        .collect());
+}
+
+// Stores the custom fields of an object that was just created, expects the
+// values returned by validate_custom_fields. The object and its values live in
+// different stores, so when the values can not be stored the ones already
+// written and the object are removed again and the create fails as a whole.
+// When that fails as well the create is reported as not undone, with what was
+// left behind in the logs.
+pub async fn create_custom_fields(
+    state: Arc<CommercyfyState>,
+    id: uuid::Uuid,
+    object: FieldExtensionObject,
+    custom_fields: Vec<(String, UnstructuredEntryType)>,
+) -> Result<(), (StatusCode, String)> {
+    if custom_fields.is_empty() {
+        return Ok(());
+    }
+
+    let extr_ref = id.to_string();
+    let field_names = custom_fields
+        .iter()
+        .map(|(field_name, _value)| return field_name.clone())
+        .collect::<Vec<String>>();
+    let unstructured_entries = custom_fields
+        .into_iter()
+        .map(|(field_name, value)| {
+            return UnstructuredEntry {
                 extr_ref: extr_ref.clone(),
-                field_name: key.to_string(),
-                value: custom_value,
-            });
+                field_name,
+                value,
+            };
+        })
+        .collect::<Vec<UnstructuredEntry>>();
+
+    let error = match state
+        .unstructureddb
+        .put_custom_fields(object.clone(), unstructured_entries)
+        .await
+    {
+        Ok(()) => return Ok(()),
+        Err(err) => err.to_string(),
+    };
+
+    let mut undone = true;
+
+    if let Err(err) = state
+        .unstructureddb
+        .delete_custom_fields(object.clone(), &extr_ref, &field_names)
+        .await
+    {
+        undone = false;
+        let _ = state.logger.category_error(
+            "custom-fields",
+            &format!(
+                "Could not remove the custom fields of '{}' after they failed to be stored: {}",
+                extr_ref, err
+            ),
+        );
+    }
+
+    let dependents = match state
+        .db_service
+        .delete_custom_field_object(object, id)
+        .await
+    {
+        Ok(dependents) => dependents,
+        Err(err) => {
+            undone = false;
+            let _ = state.logger.category_error(
+                "custom-fields",
+                &format!(
+                    "Could not remove '{}' after its custom fields failed to be stored: {}",
+                    extr_ref, err
+                ),
+            );
+            vec![]
         }
+    };
 
-        if let Err(err) = state
+    // the images and records that were added to the object in the meantime
+    for (dependent_object, dependent_id) in dependents {
+        let dependent_ref = dependent_id.to_string();
+        let removed = match state
             .unstructureddb
-            .put_custom_fields(object, unstructured_entries)
+            .get_custom_fields(dependent_object.clone(), &dependent_ref)
             .await
         {
-            return Err(err.to_string());
+            Ok(entries) => {
+                let field_names = entries
+                    .into_iter()
+                    .map(|entry| return entry.field_name)
+                    .collect::<Vec<String>>();
+                state
+                    .unstructureddb
+                    .delete_custom_fields(dependent_object, &dependent_ref, &field_names)
+                    .await
+            }
+            Err(err) => Err(err),
+        };
+
+        if let Err(err) = removed {
+            undone = false;
+            let _ = state.logger.category_error(
+                "custom-fields",
+                &format!(
+                    "Could not remove the custom fields of '{}' after '{}' was removed: {}",
+                    dependent_ref, extr_ref, err
+                ),
+            );
         }
     }
 
-    return Ok(());
+    if !undone {
+        return Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!(
+                "The custom fields could not be stored and the create could not be undone, '{}' has to be removed by hand: {}",
+                extr_ref, error
+            ),
+        ));
+    }
+
+    return Err((
+        StatusCode::BAD_REQUEST,
+        format!(
+            "The custom fields could not be stored, so nothing was created: {}",
+            error
+        ),
+    ));
+}
+
+// converts a query parameter to the form the values of the field are stored in
+fn read_filter_value(field: &FieldExtension, value: &str) -> Result<UnstructuredEntryType, String> {
+    return match field.r#type {
+        FieldExtensionType::STRING
+        | FieldExtensionType::ENUM
+        | FieldExtensionType::SET
+        | FieldExtensionType::REFERENCE => Ok(UnstructuredEntryType::STRING(value.to_string())),
+        FieldExtensionType::INT => match value.parse::<i64>() {
+            Ok(integer) => Ok(UnstructuredEntryType::INT(integer)),
+            Err(_err) => Err("Should be an integer".to_string()),
+        },
+        FieldExtensionType::BOOLEAN => match value.parse::<bool>() {
+            Ok(boolean) => Ok(UnstructuredEntryType::BOOLEAN(boolean)),
+            Err(_err) => Err("Should be a boolean".to_string()),
+        },
+        FieldExtensionType::DECIMAL => match Decimal::from_str(value) {
+            Ok(decimal) => Ok(UnstructuredEntryType::DECIMAL(decimal.normalize())),
+            Err(_err) => Err("Should be a decimal".to_string()),
+        },
+        FieldExtensionType::DATE | FieldExtensionType::DATETIME => {
+            read_custom_field(field, &serde_json::Value::String(value.to_string()))
+        }
+        FieldExtensionType::JSON => {
+            Err("JSON fields can only be filtered on being set".to_string())
+        }
+    };
+}
+
+// Reads one `cf.<name>` or `cf.<name>[<operator>]` query parameter, the
+// boolean is false when the objects with the field set are to be excluded.
+fn read_custom_field_filter(
+    field: &FieldExtension,
+    operator: &str,
+    value: &str,
+) -> Result<(UnstructuredFilter, bool), String> {
+    let ordered = matches!(
+        field.r#type,
+        FieldExtensionType::INT
+            | FieldExtensionType::DECIMAL
+            | FieldExtensionType::DATE
+            | FieldExtensionType::DATETIME
+    );
+
+    return match operator {
+        "eq" => Ok((
+            UnstructuredFilter::EQ(read_filter_value(field, value)?),
+            true,
+        )),
+        "gt" | "gte" | "lt" | "lte" if !ordered => {
+            Err("Only numbers and dates can be filtered by range".to_string())
+        }
+        "gt" => Ok((
+            UnstructuredFilter::GT(read_filter_value(field, value)?),
+            true,
+        )),
+        "gte" => Ok((
+            UnstructuredFilter::GTE(read_filter_value(field, value)?),
+            true,
+        )),
+        "lt" => Ok((
+            UnstructuredFilter::LT(read_filter_value(field, value)?),
+            true,
+        )),
+        "lte" => Ok((
+            UnstructuredFilter::LTE(read_filter_value(field, value)?),
+            true,
+        )),
+        "contains" => match field.r#type {
+            FieldExtensionType::STRING => {
+                Ok((UnstructuredFilter::CONTAINS(value.to_string()), true))
+            }
+            FieldExtensionType::SET => Ok((
+                UnstructuredFilter::EQ(UnstructuredEntryType::STRING(value.to_string())),
+                true,
+            )),
+            _ => Err("Only strings and sets can be filtered by contents".to_string()),
+        },
+        "exists" => match value.parse::<bool>() {
+            Ok(exists) => Ok((UnstructuredFilter::EXISTS, exists)),
+            Err(_err) => Err("Should be a boolean".to_string()),
+        },
+        _ => Err(format!(
+            "'{}' is not one of eq, gt, gte, lt, lte, contains, exists",
+            operator
+        )),
+    };
+}
+
+// Finds the objects that pass the custom field filters among the query
+// parameters, the parameters that are not filters are ignored. All of the
+// filters have to pass and every filter that is not valid is reported.
+pub async fn find_custom_field_matches(
+    state: &CommercyfyState,
+    object: FieldExtensionObject,
+    params: &HashMap<String, String>,
+) -> Result<CustomFieldMatches, String> {
+    if !params.keys().any(|x| return x.starts_with("cf.")) {
+        return match_custom_fields(state, object, &[], params).await;
+    }
+
+    let definitions = match state.db_service.get_custom_fields(object.clone()).await {
+        Ok(definitions) => definitions,
+        Err(err) => return Err(err.to_string()),
+    };
+
+    return match_custom_fields(state, object, &definitions, params).await;
+}
+
+// find_custom_field_matches against the given definitions, the custom object
+// types have their own
+pub async fn match_custom_fields(
+    state: &CommercyfyState,
+    object: FieldExtensionObject,
+    definitions: &[FieldExtension],
+    params: &HashMap<String, String>,
+) -> Result<CustomFieldMatches, String> {
+    let mut matches = CustomFieldMatches {
+        included: None,
+        excluded: HashSet::new(),
+        errors: vec![],
+    };
+
+    let filters = params
+        .iter()
+        .filter_map(|(key, value)| return Some((key, key.strip_prefix("cf.")?, value)))
+        .collect::<Vec<(&String, &str, &String)>>();
+
+    for (key, filter, value) in filters {
+        let (name, operator) = match filter.split_once('[') {
+            Some((name, operator)) => match operator.strip_suffix(']') {
+                Some(operator) => (name, operator),
+                None => {
+                    matches.errors.push(FieldError {
+                        field: key.to_string(),
+                        error: "The filter should be formatted as cf.<name>[<operator>]"
+                            .to_string(),
+                    });
+                    continue;
+                }
+            },
+            None => (filter, "eq"),
+        };
+
+        let definition =
+            if let Some(definition) = definitions.iter().find(|x| return x.name == name) {
+                definition
+            } else {
+                matches.errors.push(FieldError {
+                    field: key.to_string(),
+                    error: "The custom field does not exist".to_string(),
+                });
+                continue;
+            };
+
+        let (filter, include) = match read_custom_field_filter(definition, operator, value) {
+            Ok(filter) => filter,
+            Err(error) => {
+                matches.errors.push(FieldError {
+                    field: key.to_string(),
+                    error,
+                });
+                continue;
+            }
+        };
+
+        if !matches.errors.is_empty() {
+            continue;
+        }
+
+        let refs = state
+            .unstructureddb
+            .find_custom_field_refs(object.clone(), name, &filter)
+            .await?
+            .into_iter()
+            .collect::<HashSet<String>>();
+
+        if !include {
+            matches.excluded.extend(refs);
+            continue;
+        }
+
+        matches.included = Some(match matches.included {
+            Some(included) => included.intersection(&refs).cloned().collect(),
+            None => refs,
+        });
+    }
+
+    matches.errors.sort_by(|a, b| return a.field.cmp(&b.field));
+
+    return Ok(matches);
 }
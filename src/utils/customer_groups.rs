@@ -0,0 +1,87 @@
+use std::sync::Arc;
+
+use crate::{
+    models::{
+        customer::{Customer, CustomerAddress},
+        customer_group::{
+            CustomerGroup, CustomerGroupRule, CustomerGroupRuleType, CustomerGroupType,
+        },
+    },
+    CommercyfyState,
+};
+
+pub fn matches_rule(
+    rule: &CustomerGroupRule,
+    customer: &Customer,
+    addresses: &[CustomerAddress],
+) -> bool {
+    let value = rule.rule_value.clone().unwrap_or_default().to_lowercase();
+
+    return match rule.rule_type {
+        CustomerGroupRuleType::REGISTERED => true,
+        CustomerGroupRuleType::DOMAIN => customer
+            .email
+            .to_lowercase()
+            .ends_with(&format!("@{}", value.trim_start_matches('@'))),
+        CustomerGroupRuleType::COUNTRY => addresses
+            .iter()
+            .any(|address| return address.country_code.to_lowercase() == value),
+    };
+}
+
+// Resolves every group the customer belongs to, static groups come from the
+// explicit membership and dynamic ones are evaluated against the current data.
+pub async fn get_customer_groups(
+    state: Arc<CommercyfyState>,
+    customer: &Customer,
+) -> Result<Vec<CustomerGroup>, String> {
+    let customer_id = customer.id.to_string();
+
+    let mut groups = match state
+        .db_service
+        .get_customer_static_groups(&customer_id)
+        .await
+    {
+        Ok(groups) => groups,
+        Err(err) => return Err(err.to_string()),
+    };
+
+    let dynamic_groups = match state
+        .db_service
+        .get_customer_groups_by_type(CustomerGroupType::DYNAMIC)
+        .await
+    {
+        Ok(groups) => groups,
+        Err(err) => return Err(err.to_string()),
+    };
+
+    if dynamic_groups.is_empty() {
+        return Ok(groups);
+    }
+
+    let addresses = match state.db_service.get_customer_addresses(&customer_id).await {
+        Ok(addresses) => addresses,
+        Err(err) => return Err(err.to_string()),
+    };
+
+    for group in dynamic_groups {
+        let rules = match state
+            .db_service
+            .get_customer_group_rules(&group.id.to_string())
+            .await
+        {
+            Ok(rules) => rules,
+            Err(err) => return Err(err.to_string()),
+        };
+
+        if !rules.is_empty()
+            && rules
+                .iter()
+                .all(|rule| return matches_rule(rule, customer, &addresses))
+        {
+            groups.push(group);
+        }
+    }
+
+    return Ok(groups);
+}
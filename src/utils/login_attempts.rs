@@ -0,0 +1,90 @@
+use std::net::SocketAddr;
+
+use axum::http::{header, HeaderMap};
+
+use crate::{
+    models::login_attempt::{LoginClient, LoginFailures},
+    CommercyfyState,
+};
+
+const ACCOUNT_LOCKOUT_THRESHOLD: i64 = 5;
+const IP_ADDRESS_LOCKOUT_THRESHOLD: i64 = 20;
+const LOCKOUT_BASE: chrono::TimeDelta = chrono::TimeDelta::seconds(30);
+const LOCKOUT_MAX: chrono::TimeDelta = chrono::TimeDelta::hours(1);
+
+// The address of the connection is used unless the server runs behind a proxy
+// that is trusted to set X-Forwarded-For.
+pub fn get_login_client(address: &SocketAddr, headers: &HeaderMap) -> LoginClient {
+    let trust_proxy = std::env::var("TRUST_PROXY_HEADERS").is_ok_and(|x| return x == "true");
+
+    let forwarded_for = headers
+        .get("x-forwarded-for")
+        .and_then(|value| return value.to_str().ok())
+        .and_then(|value| return value.split(',').next())
+        .map(|value| return value.trim().to_string())
+        .filter(|value| return !value.is_empty());
+
+    let ip_address = match forwarded_for {
+        Some(forwarded_for) if trust_proxy => forwarded_for,
+        _ => address.ip().to_string(),
+    };
+
+    let user_agent = headers
+        .get(header::USER_AGENT)
+        .and_then(|value| return value.to_str().ok())
+        .map(|value| return value.to_string());
+
+    return LoginClient {
+        ip_address,
+        user_agent,
+    };
+}
+
+// Once the threshold is reached every further failure doubles the lockout,
+// starting from the base and capped at the max.
+fn get_lockout_end(
+    failures: &LoginFailures,
+    threshold: i64,
+) -> Option<chrono::DateTime<chrono::Utc>> {
+    if failures.count < threshold {
+        return None;
+    }
+
+    let last_attempt_at = failures.last_attempt_at?;
+    let exponent = (failures.count - threshold).min(16) as u32;
+    let lockout = (LOCKOUT_BASE * 2_i32.pow(exponent)).min(LOCKOUT_MAX);
+
+    let lockout_end = last_attempt_at + lockout;
+    if lockout_end <= chrono::Utc::now() {
+        return None;
+    }
+
+    return Some(lockout_end);
+}
+
+// Returns when the sign-in lockout for the account or the address ends, if
+// either is locked out right now.
+pub async fn get_login_lockout(
+    state: &CommercyfyState,
+    email: &str,
+    client: &LoginClient,
+) -> Result<Option<chrono::DateTime<chrono::Utc>>, String> {
+    let account_failures = match state.db_service.get_account_login_failures(email).await {
+        Ok(failures) => failures,
+        Err(err) => return Err(err.to_string()),
+    };
+
+    let ip_address_failures = match state
+        .db_service
+        .get_ip_address_login_failures(&client.ip_address)
+        .await
+    {
+        Ok(failures) => failures,
+        Err(err) => return Err(err.to_string()),
+    };
+
+    let account_lockout = get_lockout_end(&account_failures, ACCOUNT_LOCKOUT_THRESHOLD);
+    let ip_address_lockout = get_lockout_end(&ip_address_failures, IP_ADDRESS_LOCKOUT_THRESHOLD);
+
+    return Ok(account_lockout.max(ip_address_lockout));
+}
@@ -0,0 +1,74 @@
+use totp_rs::{Algorithm, Secret, TOTP};
+
+use super::auth::{generate_bytes, generate_token};
+
+const MFA_ISSUER: &str = "Commercyfy";
+const TOTP_DIGITS: usize = 6;
+const TOTP_STEP: u64 = 30;
+const RECOVERY_CODES_COUNT: usize = 10;
+
+// base32 encoded, the format authenticator apps expect
+pub fn generate_mfa_secret() -> String {
+    return Secret::Raw(generate_bytes(20)).to_encoded().to_string();
+}
+
+fn get_totp(secret: &str, account: &str) -> Result<TOTP, String> {
+    let secret = match Secret::Encoded(secret.to_string()).to_bytes() {
+        Ok(secret) => secret,
+        Err(err) => return Err(err.to_string()),
+    };
+
+    return TOTP::new(
+        Algorithm::SHA1,
+        TOTP_DIGITS,
+        1,
+        TOTP_STEP,
+        secret,
+        Some(MFA_ISSUER.to_string()),
+        account.to_string(),
+    )
+    .map_err(|err| return err.to_string());
+}
+
+pub fn get_provisioning_uri(secret: &str, account: &str) -> Result<String, String> {
+    return Ok(get_totp(secret, account)?.get_url());
+}
+
+// The code is checked against the previous, current and next time step to
+// allow for clock drift. Returns the matching step, steps up to and including
+// the last used one are rejected so a code can not be replayed.
+pub fn verify_totp_code(
+    secret: &str,
+    account: &str,
+    code: &str,
+    last_step: Option<i64>,
+) -> Result<Option<i64>, String> {
+    let totp = get_totp(secret, account)?;
+
+    let now = match std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH) {
+        Ok(now) => now.as_secs(),
+        Err(err) => return Err(err.to_string()),
+    };
+
+    let current_step = (now / TOTP_STEP) as i64;
+    for step in [current_step - 1, current_step, current_step + 1] {
+        if last_step.is_some_and(|last_step| return step <= last_step) {
+            continue;
+        }
+
+        if totp.generate(step as u64 * TOTP_STEP) == code {
+            return Ok(Some(step));
+        }
+    }
+
+    return Ok(None);
+}
+
+pub fn generate_recovery_codes() -> Vec<String> {
+    return (0..RECOVERY_CODES_COUNT)
+        .map(|_| {
+            let code = generate_token(5);
+            return format!("{}-{}", &code[..5], &code[5..]);
+        })
+        .collect();
+}
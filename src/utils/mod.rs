@@ -1 +1,11 @@
+pub mod auth;
+pub mod custom_field_migrations;
 pub mod custom_fields;
+pub mod customer_groups;
+pub mod login_attempts;
+pub mod mfa;
+pub mod permissions;
+pub mod returns;
+pub mod shipping;
+pub mod signing_keys;
+pub mod tax;
@@ -0,0 +1,75 @@
+use axum::http::StatusCode;
+
+use crate::{
+    models::portal_user::{JWTClaims, PortalUser, PortalUsersRoles},
+    services::role_validation::RoleService,
+    CommercyfyState,
+};
+
+// A role is held through the role itself or through all of its permissions,
+// so a custom role with the same permissions counts as holding it.
+pub fn holds_role(state: &CommercyfyState, claims: &JWTClaims, role: &PortalUsersRoles) -> bool {
+    return claims.roles.contains(role)
+        || state
+            .role_service
+            .get_role_permissions(role)
+            .iter()
+            .all(|permission| return holds_permission(claims, permission));
+}
+
+pub fn holds_permission(claims: &JWTClaims, permission: &str) -> bool {
+    return claims.permissions.iter().any(|x| return x == permission);
+}
+
+// the caller can only grant the roles they hold
+pub fn check_grants_roles(
+    state: &CommercyfyState,
+    claims: &JWTClaims,
+    roles: &[PortalUsersRoles],
+) -> Result<(), (StatusCode, String)> {
+    if roles
+        .iter()
+        .all(|role| return holds_role(state, claims, role))
+    {
+        return Ok(());
+    }
+
+    return Err((
+        StatusCode::FORBIDDEN,
+        "'roles' can only contain the roles you hold".to_string(),
+    ));
+}
+
+// A portal user can only be changed, reset or deactivated by a caller holding
+// every role and permission of the user, otherwise a lower privileged caller
+// could take over a more privileged account.
+pub async fn check_manages_portal_user(
+    state: &CommercyfyState,
+    claims: &JWTClaims,
+    portal_user: &PortalUser,
+) -> Result<(), (StatusCode, String)> {
+    let permissions = match state
+        .db_service
+        .get_portal_user_permissions(&portal_user.id.to_string())
+        .await
+    {
+        Ok(permissions) => permissions,
+        Err(err) => return Err((StatusCode::BAD_REQUEST, err.to_string())),
+    };
+
+    let exceeds_caller = !portal_user
+        .roles
+        .iter()
+        .all(|role| return holds_role(state, claims, role))
+        || !permissions
+            .iter()
+            .all(|permission| return holds_permission(claims, permission));
+    if exceeds_caller {
+        return Err((
+            StatusCode::FORBIDDEN,
+            "The portal user holds roles or permissions you do not hold".to_string(),
+        ));
+    }
+
+    return Ok(());
+}
@@ -0,0 +1,40 @@
+use rust_decimal::Decimal;
+
+use crate::{
+    models::returns::{ReturnInspectionOutcome, ReturnLine},
+    schemas::returns::InspectReturn,
+};
+
+// Every line of the return needs exactly one outcome, restocked and discarded
+// lines are refunded at the price they were sold for, rejected ones are not.
+pub fn calculate_refund(
+    lines: &[ReturnLine],
+    inspection: &InspectReturn,
+) -> Result<Decimal, String> {
+    if inspection.lines.len() != lines.len() {
+        return Err("Every return line should have exactly one inspection outcome".to_string());
+    }
+
+    let mut refund = Decimal::ZERO;
+    for line in lines {
+        let outcome = match inspection
+            .lines
+            .iter()
+            .find(|inspected| return inspected.line_id == line.id)
+        {
+            Some(inspected) => inspected.outcome,
+            None => {
+                return Err(format!(
+                    "Return line '{}' has no inspection outcome",
+                    line.id
+                ))
+            }
+        };
+
+        if outcome != ReturnInspectionOutcome::REJECT {
+            refund += line.unit_price * Decimal::from(line.quantity);
+        }
+    }
+
+    return Ok(refund);
+}
@@ -0,0 +1,155 @@
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use rust_decimal::Decimal;
+
+use crate::{
+    models::shipping::{ApplicableShippingMethod, ShippingMethodCost, ShippingMethodWeightTier},
+    schemas::basket::Basket,
+    CommercyfyState,
+};
+
+// The free shipping threshold wins over everything, otherwise the heaviest tier
+// the basket reaches replaces the base cost of the method.
+pub fn calculate_shipping_cost(
+    cost: &ShippingMethodCost,
+    tiers: &[ShippingMethodWeightTier],
+    subtotal: Decimal,
+    weight: Decimal,
+) -> Decimal {
+    if let Some(threshold) = cost.free_shipping_threshold {
+        if subtotal >= threshold {
+            return Decimal::ZERO;
+        }
+    }
+
+    let tier = tiers
+        .iter()
+        .filter(|tier| return tier.currency_code == cost.currency_code && tier.min_weight <= weight)
+        .max_by(|a, b| return a.min_weight.cmp(&b.min_weight));
+
+    return match tier {
+        Some(tier) => tier.cost,
+        None => cost.cost,
+    };
+}
+
+pub async fn get_applicable_shipping_methods(
+    state: Arc<CommercyfyState>,
+    payload: &Basket,
+) -> Result<Vec<ApplicableShippingMethod>, String> {
+    let pricebook_id = payload.pricebook_id.to_string();
+    let pricebook = match state.db_service.get_pricebook_by_id(&pricebook_id).await {
+        Ok(Some(pricebook)) => pricebook,
+        Ok(None) => {
+            return Err(format!(
+                "Pricebook with id '{}' does not exist",
+                pricebook_id
+            ))
+        }
+        Err(err) => return Err(err.to_string()),
+    };
+
+    let mut subtotal = Decimal::ZERO;
+    let mut weight = Decimal::ZERO;
+    let mut basket_categories: HashSet<uuid::Uuid> = HashSet::new();
+
+    for item in &payload.items {
+        let product_id = item.product_id.to_string();
+        let product = match state.db_service.get_product(&product_id).await {
+            Ok(Some(product)) => product,
+            Ok(None) => return Err(format!("Product with id '{}' does not exist", product_id)),
+            Err(err) => return Err(err.to_string()),
+        };
+
+        let record = match state
+            .db_service
+            .get_product_pricebook_record(&product_id, &pricebook_id)
+            .await
+        {
+            Ok(Some(record)) => record,
+            Ok(None) => {
+                return Err(format!(
+                    "Product with id '{}' has no price in pricebook '{}'",
+                    product_id, pricebook.pricebook_reference
+                ))
+            }
+            Err(err) => return Err(err.to_string()),
+        };
+
+        let quantity = Decimal::from(item.quantity);
+        subtotal += record.price * quantity;
+        weight += product.product_weight.unwrap_or_default() * quantity;
+
+        match state.db_service.get_product_categories(&product_id).await {
+            Ok(categories) => basket_categories.extend(categories.into_iter().map(|c| return c.id)),
+            Err(err) => return Err(err.to_string()),
+        };
+    }
+
+    let shipping_methods = match state.db_service.get_shipping_methods().await {
+        Ok(methods) => methods,
+        Err(err) => return Err(err.to_string()),
+    };
+
+    let country_code = payload.address.country_code.to_uppercase();
+    let mut applicable: Vec<ApplicableShippingMethod> = vec![];
+    for method in shipping_methods {
+        let ships_to_country = method.shipping_method_countries.is_empty()
+            || method
+                .shipping_method_countries
+                .iter()
+                .any(|country| return country.to_uppercase() == country_code);
+        if !ships_to_country {
+            continue;
+        }
+
+        let method_id = method.id.to_string();
+        let costs = match state.db_service.get_shipping_method_costs(&method_id).await {
+            Ok(costs) => costs,
+            Err(err) => return Err(err.to_string()),
+        };
+
+        let cost = match costs
+            .iter()
+            .find(|cost| return cost.currency_code == pricebook.pricebook_currency_code)
+        {
+            Some(cost) => cost,
+            None => continue,
+        };
+
+        let excluded_categories = match state
+            .db_service
+            .get_shipping_method_excluded_categories(&method_id)
+            .await
+        {
+            Ok(categories) => categories,
+            Err(err) => return Err(err.to_string()),
+        };
+        if excluded_categories
+            .iter()
+            .any(|category| return basket_categories.contains(&category.id))
+        {
+            continue;
+        }
+
+        let tiers = match state
+            .db_service
+            .get_shipping_method_weight_tiers(&method_id)
+            .await
+        {
+            Ok(tiers) => tiers,
+            Err(err) => return Err(err.to_string()),
+        };
+
+        applicable.push(ApplicableShippingMethod {
+            id: method.id,
+            shipping_method_name: method.shipping_method_name,
+            shipping_method_reference: method.shipping_method_reference,
+            currency_code: cost.currency_code.clone(),
+            cost: calculate_shipping_cost(cost, &tiers, subtotal, weight),
+        });
+    }
+
+    return Ok(applicable);
+}
@@ -0,0 +1,92 @@
+use crate::{
+    models::signing_key::SigningKey,
+    services::signing_keys::{
+        generate_key, SIGNING_KEY_PUBLISH_DELAY, SIGNING_KEY_REFRESH_INTERVAL,
+    },
+    CommercyfyState,
+};
+
+pub async fn reload_signing_keys(state: &CommercyfyState) -> Result<(), String> {
+    let keys = match state.db_service.get_signing_keys().await {
+        Ok(keys) => keys,
+        Err(err) => return Err(err.to_string()),
+    };
+
+    return state.signing_keys.load(&keys);
+}
+
+// Creates a new key when one is due or when forced. The first key is active
+// right away, later ones only after the publish delay.
+pub async fn rotate_signing_keys(
+    state: &CommercyfyState,
+    force: bool,
+) -> Result<Option<SigningKey>, String> {
+    reload_signing_keys(state).await?;
+
+    let algorithm = if let Some(algorithm) = state.signing_keys.algorithm() {
+        algorithm
+    } else {
+        return Err(
+            "Tokens are signed with the shared secret, there are no keys to rotate".to_string(),
+        );
+    };
+
+    if !force && !state.signing_keys.needs_rotation()? {
+        return Ok(None);
+    }
+
+    let (private_key, public_key) =
+        match tokio::task::spawn_blocking(move || return generate_key(algorithm)).await {
+            Ok(key) => key?,
+            Err(err) => return Err(err.to_string()),
+        };
+    let private_key = state.signing_keys.seal_private_key(&private_key)?;
+
+    let activates_at = if state.signing_keys.has_keys()? {
+        chrono::Utc::now() + SIGNING_KEY_PUBLISH_DELAY
+    } else {
+        chrono::Utc::now()
+    };
+
+    let signing_key = match state
+        .db_service
+        .create_signing_key(algorithm, &private_key, &public_key, activates_at)
+        .await
+    {
+        Ok(signing_key) => signing_key,
+        Err(err) => return Err(err.to_string()),
+    };
+
+    reload_signing_keys(state).await?;
+
+    return Ok(Some(signing_key));
+}
+
+// Runs for the lifetime of the server, reloading the keys so rotations done by
+// other instances are picked up and rotating them when they are due.
+pub async fn run_signing_key_rotation(state: std::sync::Arc<CommercyfyState>) {
+    let mut interval = tokio::time::interval(SIGNING_KEY_REFRESH_INTERVAL);
+
+    loop {
+        interval.tick().await;
+
+        match rotate_signing_keys(&state, false).await {
+            Ok(Some(signing_key)) => {
+                let _ = state.logger.category_info(
+                    "signing-keys",
+                    &format!(
+                        "Created signing key '{}', active from {}",
+                        signing_key.id, signing_key.activates_at
+                    ),
+                );
+            }
+            Ok(None) => {}
+            Err(err) => {
+                let _ = state.logger.category_error(
+                    "signing-keys",
+                    &format!("Could not rotate the signing keys: {}", err),
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,148 @@
+use std::sync::Arc;
+
+use rust_decimal::{Decimal, RoundingStrategy};
+
+use crate::{
+    models::{
+        pricebook::PricebookPriceMode,
+        tax::{TaxCalculation, TaxLine},
+    },
+    schemas::{address::Address, basket::Basket},
+    CommercyfyState,
+};
+
+fn round_amount(amount: Decimal) -> Decimal {
+    return amount.round_dp_with_strategy(2, RoundingStrategy::MidpointAwayFromZero);
+}
+
+pub fn calculate_line_tax(
+    product_id: uuid::Uuid,
+    quantity: i32,
+    unit_price: Decimal,
+    tax_rate: Decimal,
+    price_mode: PricebookPriceMode,
+) -> TaxLine {
+    let line_total = unit_price * Decimal::from(quantity);
+
+    let (net_total, tax_total, gross_total) = match price_mode {
+        PricebookPriceMode::NET => {
+            let tax = round_amount(line_total * tax_rate);
+            (line_total, tax, line_total + tax)
+        }
+        PricebookPriceMode::GROSS => {
+            let net = round_amount(line_total / (Decimal::ONE + tax_rate));
+            (net, line_total - net, line_total)
+        }
+    };
+
+    return TaxLine {
+        product_id,
+        quantity,
+        unit_price,
+        tax_rate,
+        net_total,
+        tax_total,
+        gross_total,
+    };
+}
+
+// A rate defined for the exact region wins over the country wide one, products
+// without a tax class or without a rate for the destination are not taxed.
+pub async fn get_tax_rate(
+    state: Arc<CommercyfyState>,
+    tax_class_id: Option<uuid::Uuid>,
+    address: &Address,
+) -> Result<Decimal, String> {
+    let tax_class_id = match tax_class_id {
+        Some(id) => id.to_string(),
+        None => return Ok(Decimal::ZERO),
+    };
+
+    if let Some(region) = &address.region {
+        match state
+            .db_service
+            .get_tax_rate(&tax_class_id, &address.country_code, Some(region))
+            .await
+        {
+            Ok(Some(rate)) => return Ok(rate.rate),
+            Ok(None) => {}
+            Err(err) => return Err(err.to_string()),
+        };
+    }
+
+    return match state
+        .db_service
+        .get_tax_rate(&tax_class_id, &address.country_code, None)
+        .await
+    {
+        Ok(Some(rate)) => Ok(rate.rate),
+        Ok(None) => Ok(Decimal::ZERO),
+        Err(err) => Err(err.to_string()),
+    };
+}
+
+pub async fn calculate_basket_tax(
+    state: Arc<CommercyfyState>,
+    payload: &Basket,
+) -> Result<TaxCalculation, String> {
+    let pricebook_id = payload.pricebook_id.to_string();
+    let pricebook = match state.db_service.get_pricebook_by_id(&pricebook_id).await {
+        Ok(Some(pricebook)) => pricebook,
+        Ok(None) => {
+            return Err(format!(
+                "Pricebook with id '{}' does not exist",
+                pricebook_id
+            ))
+        }
+        Err(err) => return Err(err.to_string()),
+    };
+
+    let mut calculation = TaxCalculation {
+        currency_code: pricebook.pricebook_currency_code,
+        price_mode: pricebook.pricebook_price_mode,
+        lines: vec![],
+        net_total: Decimal::ZERO,
+        tax_total: Decimal::ZERO,
+        gross_total: Decimal::ZERO,
+    };
+
+    for item in &payload.items {
+        let product_id = item.product_id.to_string();
+        let product = match state.db_service.get_product(&product_id).await {
+            Ok(Some(product)) => product,
+            Ok(None) => return Err(format!("Product with id '{}' does not exist", product_id)),
+            Err(err) => return Err(err.to_string()),
+        };
+
+        let record = match state
+            .db_service
+            .get_product_pricebook_record(&product_id, &pricebook_id)
+            .await
+        {
+            Ok(Some(record)) => record,
+            Ok(None) => {
+                return Err(format!(
+                    "Product with id '{}' has no price in pricebook '{}'",
+                    product_id, pricebook.pricebook_reference
+                ))
+            }
+            Err(err) => return Err(err.to_string()),
+        };
+
+        let tax_rate = get_tax_rate(state.clone(), product.tax_class_id, &payload.address).await?;
+        let line = calculate_line_tax(
+            product.id,
+            item.quantity,
+            record.price,
+            tax_rate,
+            calculation.price_mode,
+        );
+
+        calculation.net_total += line.net_total;
+        calculation.tax_total += line.tax_total;
+        calculation.gross_total += line.gross_total;
+        calculation.lines.push(line);
+    }
+
+    return Ok(calculation);
+}
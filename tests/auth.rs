@@ -0,0 +1,723 @@
+mod common;
+
+use axum::http::{Method, StatusCode};
+use commercyfy_core::{
+    models::{
+        permission::{PORTAL_USER_WRITE, ROLE_WRITE},
+        portal_user::{PortalUsersRoles, PORTAL_JWT_AUDIENCE},
+        signing_key::{SigningAlgorithm, SigningKey},
+    },
+    services::signing_keys::{generate_key, SigningKeys},
+    utils::auth::{verify_password, DUMMY_PASSWORD_HASH},
+};
+use common::{TestApp, PASSWORD};
+use serde_json::json;
+
+#[tokio::test]
+async fn requires_a_token() {
+    let app = TestApp::new();
+
+    let (status, _body) = app.request(Method::GET, "/categories", None, None).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    let (status, _body) = app.get("/categories", "not-a-token").await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn checks_the_permissions_of_the_roles() {
+    let app = TestApp::new();
+    let token = app
+        .token_for("reader@commercyfy.test", vec![PortalUsersRoles::READER])
+        .await;
+
+    let (status, body) = app.get("/categories", &token).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+
+    let (status, _body) = app
+        .post(
+            "/categories",
+            &token,
+            json!({
+                "category_name": "Socks",
+                "category_description": null,
+                "category_reference": "socks",
+                "custom_fields": null
+            }),
+        )
+        .await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    let (status, _body) = app.get("/portal/users", &token).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+}
+
+#[tokio::test]
+async fn records_the_sign_in_attempts() {
+    let app = TestApp::new();
+    let token = app.admin_token().await;
+
+    let (status, body) = app.signin("admin@commercyfy.test", "wrong-password").await;
+    assert_eq!(status, StatusCode::BAD_REQUEST, "{}", body);
+
+    let (status, body) = app.signin("nobody@commercyfy.test", PASSWORD).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST, "{}", body);
+
+    let (status, body) = app
+        .get("/portal/login-attempts?email=admin@commercyfy.test", &token)
+        .await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+
+    // newest first, the sign-in of the admin token comes last
+    let attempts = body.as_array().expect("The attempts are not a list!");
+    assert_eq!(attempts.len(), 2);
+    assert_eq!(attempts[0]["successful"], false);
+    assert_eq!(attempts[0]["failure_reason"], "PASSWORD");
+    assert_eq!(attempts[1]["successful"], true);
+}
+
+#[tokio::test]
+async fn revoked_sessions_can_not_be_used() {
+    let app = TestApp::new();
+    let token = app.admin_token().await;
+
+    let (status, body) = app.get("/categories", &token).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+
+    let (status, body) = app.post("/portal/signout", &token, json!({})).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+
+    let (status, _body) = app.get("/categories", &token).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn deactivated_users_can_not_sign_in() {
+    let app = TestApp::new();
+    let token = app.admin_token().await;
+    let editor_id = app
+        .create_portal_user("editor@commercyfy.test", vec![PortalUsersRoles::EDITOR])
+        .await;
+
+    let (status, body) = app
+        .post(
+            &format!("/portal/user/{}/deactivate", editor_id),
+            &token,
+            json!({}),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+
+    let (status, body) = app.signin("editor@commercyfy.test", PASSWORD).await;
+    assert_eq!(status, StatusCode::FORBIDDEN, "{}", body);
+}
+
+#[tokio::test]
+async fn api_keys_of_deactivated_users_are_rejected() {
+    let app = TestApp::new();
+    let token = app.admin_token().await;
+    let operator_id = app
+        .create_portal_user("operator@commercyfy.test", vec![PortalUsersRoles::ADMIN])
+        .await;
+    let (status, body) = app.signin("operator@commercyfy.test", PASSWORD).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    let operator_token = body["jwt"].as_str().unwrap_or_default().to_string();
+
+    let (status, body) = app
+        .post(
+            "/portal/api-keys",
+            &operator_token,
+            json!({ "name": "automation", "scopes": ["READER"], "expires_at": null }),
+        )
+        .await;
+    assert_eq!(status, StatusCode::CREATED, "{}", body);
+    let api_key = body["key"].as_str().unwrap_or_default().to_string();
+
+    let (status, body) = app
+        .request_with_api_key(Method::GET, "/categories", &api_key, None)
+        .await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+
+    let (status, body) = app
+        .post(
+            &format!("/portal/user/{}/deactivate", operator_id),
+            &token,
+            json!({}),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+
+    let (status, _body) = app
+        .request_with_api_key(Method::GET, "/categories", &api_key, None)
+        .await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn writes_the_submitted_logs() {
+    let app = TestApp::new();
+    let token = app.admin_token().await;
+
+    let (status, body) = app
+        .post(
+            "/logs",
+            &token,
+            json!({ "level": "warn", "message": "Low stock", "category": "Inventory", "file": null }),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+
+    let entries = app.logger.entries();
+    let entry = entries.last().expect("Nothing was logged!");
+    assert_eq!(entry.file, "commercyfy-core");
+    assert_eq!(entry.category, "inventory");
+    assert_eq!(entry.level, "WARN");
+    assert_eq!(entry.message, "Low stock");
+}
+
+#[tokio::test]
+async fn refresh_tokens_can_only_be_used_once() {
+    let app = TestApp::new();
+    app.create_portal_user("admin@commercyfy.test", vec![PortalUsersRoles::ADMIN])
+        .await;
+    let (status, body) = app.signin("admin@commercyfy.test", PASSWORD).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    let refresh_token = body["refresh_token"].clone();
+
+    let (status, body) = app
+        .request(
+            Method::POST,
+            "/portal/token/refresh",
+            None,
+            Some(json!({ "refresh_token": refresh_token })),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+
+    let (status, body) = app
+        .request(
+            Method::POST,
+            "/portal/token/refresh",
+            None,
+            Some(json!({ "refresh_token": refresh_token })),
+        )
+        .await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED, "{}", body);
+}
+
+#[tokio::test]
+async fn only_one_rotation_of_a_refresh_token_succeeds() {
+    let app = TestApp::new();
+    app.create_portal_user("admin@commercyfy.test", vec![PortalUsersRoles::ADMIN])
+        .await;
+    let (status, body) = app.signin("admin@commercyfy.test", PASSWORD).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    let session_id = body["refresh_token"]
+        .as_str()
+        .and_then(|x| return x.split('.').next())
+        .expect("The sign-in did not return a refresh token!")
+        .to_string();
+
+    // two requests that both verified the same refresh token
+    let session = app
+        .state
+        .db_service
+        .get_portal_user_session(&session_id)
+        .await
+        .expect("Could not load the session!")
+        .expect("The session was not found!");
+    let expires_at = chrono::Utc::now() + chrono::TimeDelta::days(1);
+
+    let first = app
+        .state
+        .db_service
+        .rotate_portal_user_session(session.id, &session.refresh_token, "first", expires_at)
+        .await
+        .expect("Could not rotate the session!");
+    assert!(first.is_some());
+
+    let second = app
+        .state
+        .db_service
+        .rotate_portal_user_session(session.id, &session.refresh_token, "second", expires_at)
+        .await
+        .expect("Could not rotate the session!");
+    assert!(second.is_none());
+}
+
+#[tokio::test]
+async fn api_keys_can_not_exceed_the_roles_of_their_creator() {
+    let app = TestApp::new();
+    let token = app.admin_token().await;
+    let manager_id = app
+        .create_portal_user("keys@commercyfy.test", vec![PortalUsersRoles::READER])
+        .await;
+
+    let (status, body) = app
+        .post(
+            "/portal/roles",
+            &token,
+            json!({ "name": "key-manager", "permissions": ["api_key:write"] }),
+        )
+        .await;
+    assert_eq!(status, StatusCode::CREATED, "{}", body);
+    let role_id = body["id"].clone();
+
+    let (status, body) = app
+        .put(
+            &format!("/portal/user/{}/roles", manager_id),
+            &token,
+            json!({ "role_ids": [role_id] }),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+
+    let (status, body) = app.signin("keys@commercyfy.test", PASSWORD).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    let manager_token = body["jwt"].as_str().unwrap_or_default().to_string();
+
+    let (status, body) = app
+        .post(
+            "/portal/api-keys",
+            &manager_token,
+            json!({ "name": "escalated", "scopes": ["ADMIN"], "expires_at": null }),
+        )
+        .await;
+    assert_eq!(status, StatusCode::FORBIDDEN, "{}", body);
+
+    let (status, body) = app
+        .post(
+            "/portal/api-keys",
+            &manager_token,
+            json!({ "name": "reporting", "scopes": ["READER"], "expires_at": null }),
+        )
+        .await;
+    assert_eq!(status, StatusCode::CREATED, "{}", body);
+}
+
+#[tokio::test]
+async fn api_keys_can_not_create_api_keys() {
+    let app = TestApp::new();
+    let token = app.admin_token().await;
+
+    let (status, body) = app
+        .post(
+            "/portal/api-keys",
+            &token,
+            json!({ "name": "automation", "scopes": ["ADMIN"], "expires_at": null }),
+        )
+        .await;
+    assert_eq!(status, StatusCode::CREATED, "{}", body);
+    let api_key = body["key"].as_str().unwrap_or_default().to_string();
+
+    let (status, body) = app
+        .request_with_api_key(
+            Method::POST,
+            "/portal/api-keys",
+            &api_key,
+            Some(json!({ "name": "nested", "scopes": ["READER"], "expires_at": null })),
+        )
+        .await;
+    assert_eq!(status, StatusCode::FORBIDDEN, "{}", body);
+}
+
+#[tokio::test]
+async fn portal_user_writers_can_not_set_roles_without_role_write() {
+    let app = TestApp::new();
+    let token = app
+        .token_with_permissions(
+            "manager@commercyfy.test",
+            vec![PortalUsersRoles::READER],
+            &[PORTAL_USER_WRITE],
+        )
+        .await;
+    let reader_id = app
+        .create_portal_user("reader@commercyfy.test", vec![PortalUsersRoles::READER])
+        .await;
+
+    let (status, body) = app
+        .put(
+            &format!("/portal/user/{}", reader_id),
+            &token,
+            json!({ "roles": ["ADMIN"] }),
+        )
+        .await;
+    assert_eq!(status, StatusCode::FORBIDDEN, "{}", body);
+
+    let (status, body) = app
+        .put(
+            &format!("/portal/user/{}", reader_id),
+            &token,
+            json!({ "roles": ["READER"] }),
+        )
+        .await;
+    assert_eq!(status, StatusCode::FORBIDDEN, "{}", body);
+
+    let (status, body) = app
+        .post(
+            "/portal/user",
+            &token,
+            json!({
+                "email": "new-admin@commercyfy.test",
+                "first_name": "New",
+                "last_name": "Admin",
+                "password": PASSWORD,
+                "roles": ["ADMIN"],
+            }),
+        )
+        .await;
+    assert_eq!(status, StatusCode::FORBIDDEN, "{}", body);
+
+    let (status, body) = app
+        .put(
+            &format!("/portal/user/{}", reader_id),
+            &token,
+            json!({ "first_name": "Renamed" }),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+}
+
+#[tokio::test]
+async fn role_writers_can_only_grant_the_roles_they_hold() {
+    let app = TestApp::new();
+    let token = app
+        .token_with_permissions(
+            "manager@commercyfy.test",
+            vec![PortalUsersRoles::READER],
+            &[PORTAL_USER_WRITE, ROLE_WRITE],
+        )
+        .await;
+    let reader_id = app
+        .create_portal_user("reader@commercyfy.test", vec![PortalUsersRoles::READER])
+        .await;
+
+    let (status, body) = app
+        .put(
+            &format!("/portal/user/{}", reader_id),
+            &token,
+            json!({ "roles": ["ADMIN"] }),
+        )
+        .await;
+    assert_eq!(status, StatusCode::FORBIDDEN, "{}", body);
+
+    let (status, body) = app
+        .post(
+            "/portal/user",
+            &token,
+            json!({
+                "email": "new-admin@commercyfy.test",
+                "first_name": "New",
+                "last_name": "Admin",
+                "password": PASSWORD,
+                "roles": ["ADMIN"],
+            }),
+        )
+        .await;
+    assert_eq!(status, StatusCode::FORBIDDEN, "{}", body);
+
+    let (status, body) = app
+        .put(
+            &format!("/portal/user/{}", reader_id),
+            &token,
+            json!({ "roles": ["READER"] }),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+
+    let (status, body) = app
+        .post(
+            "/portal/user",
+            &token,
+            json!({
+                "email": "new-reader@commercyfy.test",
+                "first_name": "New",
+                "last_name": "Reader",
+                "password": PASSWORD,
+                "roles": ["READER"],
+            }),
+        )
+        .await;
+    assert_eq!(status, StatusCode::CREATED, "{}", body);
+}
+
+#[tokio::test]
+async fn portal_user_writers_can_not_take_over_more_privileged_accounts() {
+    let app = TestApp::new();
+    let token = app
+        .token_with_permissions(
+            "manager@commercyfy.test",
+            vec![PortalUsersRoles::READER],
+            &[PORTAL_USER_WRITE],
+        )
+        .await;
+    let admin_id = app
+        .create_portal_user("admin@commercyfy.test", vec![PortalUsersRoles::ADMIN])
+        .await;
+    let reader_id = app
+        .create_portal_user("reader@commercyfy.test", vec![PortalUsersRoles::READER])
+        .await;
+
+    let (status, body) = app
+        .post(
+            &format!("/portal/user/{}/password", admin_id),
+            &token,
+            json!({ "password": "An0ther-Secret!" }),
+        )
+        .await;
+    assert_eq!(status, StatusCode::FORBIDDEN, "{}", body);
+
+    let (status, body) = app
+        .put(
+            &format!("/portal/user/{}", admin_id),
+            &token,
+            json!({ "email": "taken-over@commercyfy.test" }),
+        )
+        .await;
+    assert_eq!(status, StatusCode::FORBIDDEN, "{}", body);
+
+    let (status, body) = app
+        .post(
+            &format!("/portal/user/{}/deactivate", admin_id),
+            &token,
+            json!({}),
+        )
+        .await;
+    assert_eq!(status, StatusCode::FORBIDDEN, "{}", body);
+
+    let (status, body) = app.signin("admin@commercyfy.test", PASSWORD).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+
+    let (status, body) = app
+        .post(
+            &format!("/portal/user/{}/password", reader_id),
+            &token,
+            json!({ "password": "An0ther-Secret!" }),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+
+    let (status, body) = app
+        .post(
+            &format!("/portal/user/{}/deactivate", reader_id),
+            &token,
+            json!({}),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+}
+
+#[tokio::test]
+async fn credential_and_role_routes_are_bound_to_the_permissions_of_the_caller() {
+    let app = TestApp::new();
+    let admin_token = app.admin_token().await;
+    let token = app
+        .token_with_permissions(
+            "manager@commercyfy.test",
+            vec![PortalUsersRoles::READER],
+            &[PORTAL_USER_WRITE, ROLE_WRITE],
+        )
+        .await;
+    let admin_id = app
+        .create_portal_user("other-admin@commercyfy.test", vec![PortalUsersRoles::ADMIN])
+        .await;
+    let reader_id = app
+        .create_portal_user("reader@commercyfy.test", vec![PortalUsersRoles::READER])
+        .await;
+
+    let (status, body) = app
+        .post(
+            &format!("/portal/user/{}/mfa/reset", admin_id),
+            &token,
+            json!({}),
+        )
+        .await;
+    assert_eq!(status, StatusCode::FORBIDDEN, "{}", body);
+
+    let (status, body) = app
+        .post(
+            &format!("/portal/user/{}/sessions/revoke", admin_id),
+            &token,
+            json!({}),
+        )
+        .await;
+    assert_eq!(status, StatusCode::FORBIDDEN, "{}", body);
+
+    let (status, body) = app
+        .post(
+            "/portal/roles",
+            &admin_token,
+            json!({ "name": "key-manager", "permissions": ["api_key:write"] }),
+        )
+        .await;
+    assert_eq!(status, StatusCode::CREATED, "{}", body);
+    let role_id = body["id"].clone();
+
+    let (status, body) = app
+        .put(
+            &format!("/portal/user/{}/roles", reader_id),
+            &token,
+            json!({ "role_ids": [role_id] }),
+        )
+        .await;
+    assert_eq!(status, StatusCode::FORBIDDEN, "{}", body);
+
+    let (status, body) = app
+        .post(
+            &format!("/portal/user/{}/sessions/revoke", reader_id),
+            &token,
+            json!({}),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+}
+
+#[tokio::test]
+async fn api_keys_lose_the_roles_their_creator_loses() {
+    let app = TestApp::new();
+    let admin_token = app.admin_token().await;
+    let creator_id = app
+        .create_portal_user("creator@commercyfy.test", vec![PortalUsersRoles::ADMIN])
+        .await;
+    let (status, body) = app.signin("creator@commercyfy.test", PASSWORD).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    let creator_token = body["jwt"].as_str().unwrap_or_default().to_string();
+
+    let (status, body) = app
+        .post(
+            "/portal/api-keys",
+            &creator_token,
+            json!({ "name": "automation", "scopes": ["ADMIN"], "expires_at": null }),
+        )
+        .await;
+    assert_eq!(status, StatusCode::CREATED, "{}", body);
+    let api_key = body["key"].as_str().unwrap_or_default().to_string();
+
+    let (status, body) = app
+        .request_with_api_key(Method::GET, "/portal/users", &api_key, None)
+        .await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+
+    let (status, body) = app
+        .put(
+            &format!("/portal/user/{}", creator_id),
+            &admin_token,
+            json!({ "roles": ["READER"] }),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+
+    let (status, body) = app
+        .request_with_api_key(Method::GET, "/portal/users", &api_key, None)
+        .await;
+    assert_eq!(status, StatusCode::FORBIDDEN, "{}", body);
+
+    let (status, body) = app
+        .request_with_api_key(Method::GET, "/categories", &api_key, None)
+        .await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+}
+
+fn secret_signed_token() -> String {
+    let secret_keys = SigningKeys::new(
+        None,
+        Some("integration-tests-secret".to_string()),
+        None,
+        None,
+        chrono::TimeDelta::days(30),
+    );
+
+    return secret_keys
+        .encode(&json!({
+            "sub": "someone",
+            "aud": PORTAL_JWT_AUDIENCE,
+            "exp": (chrono::Utc::now() + chrono::TimeDelta::hours(1)).timestamp(),
+        }))
+        .unwrap();
+}
+
+fn eddsa_keys(secret_accepted_until: chrono::DateTime<chrono::Utc>) -> SigningKeys {
+    return SigningKeys::new(
+        Some(SigningAlgorithm::EDDSA),
+        Some("integration-tests-secret".to_string()),
+        Some(secret_accepted_until),
+        Some([7; 32]),
+        chrono::TimeDelta::days(30),
+    );
+}
+
+#[tokio::test]
+async fn stops_accepting_secret_signed_tokens_after_the_switch() {
+    let token = secret_signed_token();
+
+    let keys = eddsa_keys(chrono::Utc::now() + chrono::TimeDelta::hours(1));
+    assert!(keys
+        .decode::<serde_json::Value>(&token, PORTAL_JWT_AUDIENCE)
+        .is_ok());
+
+    let keys = eddsa_keys(chrono::Utc::now() - chrono::TimeDelta::hours(1));
+    assert!(keys
+        .decode::<serde_json::Value>(&token, PORTAL_JWT_AUDIENCE)
+        .is_err());
+}
+
+#[tokio::test]
+async fn stores_the_private_keys_encrypted() {
+    let keys = eddsa_keys(chrono::Utc::now());
+    let (private_key, public_key) = generate_key(SigningAlgorithm::EDDSA).unwrap();
+
+    let sealed = keys.seal_private_key(&private_key).unwrap();
+    assert!(!sealed.contains(&private_key));
+
+    let now = chrono::Utc::now();
+    keys.load(&[SigningKey {
+        id: uuid::Uuid::new_v4(),
+        algorithm: SigningAlgorithm::EDDSA,
+        private_key: sealed.clone(),
+        public_key,
+        created_at: now,
+        activates_at: now,
+        retired_at: None,
+        expires_at: None,
+    }])
+    .unwrap();
+
+    let token = keys
+        .encode(&json!({
+            "sub": "someone",
+            "aud": PORTAL_JWT_AUDIENCE,
+            "exp": (now + chrono::TimeDelta::hours(1)).timestamp(),
+        }))
+        .unwrap();
+    let claims = keys
+        .decode::<serde_json::Value>(&token, PORTAL_JWT_AUDIENCE)
+        .unwrap();
+    assert_eq!(claims["sub"], "someone");
+
+    // another encryption key cannot read the stored key
+    let other_keys = SigningKeys::new(
+        Some(SigningAlgorithm::EDDSA),
+        None,
+        None,
+        Some([8; 32]),
+        chrono::TimeDelta::days(30),
+    );
+    assert!(other_keys
+        .load(&[SigningKey {
+            id: uuid::Uuid::new_v4(),
+            algorithm: SigningAlgorithm::EDDSA,
+            private_key: sealed,
+            public_key: String::new(),
+            created_at: now,
+            activates_at: now,
+            retired_at: None,
+            expires_at: None,
+        }])
+        .is_err());
+}
+
+#[tokio::test]
+async fn unknown_emails_are_checked_against_a_valid_hash() {
+    // an unparsable hash would return before argon2 runs and give the timing away
+    assert_eq!(verify_password(DUMMY_PASSWORD_HASH, PASSWORD), Ok(false));
+}
@@ -0,0 +1,142 @@
+mod common;
+
+use axum::http::StatusCode;
+use common::{created_id, TestApp};
+use serde_json::json;
+
+#[tokio::test]
+async fn creates_categories_and_products() {
+    let app = TestApp::new();
+    let token = app.admin_token().await;
+
+    let (status, body) = app
+        .post(
+            "/categories",
+            &token,
+            json!({
+                "category_name": "Shoes",
+                "category_description": "Everything to walk in",
+                "category_reference": "shoes",
+                "custom_fields": null
+            }),
+        )
+        .await;
+    assert_eq!(status, StatusCode::CREATED, "{}", body);
+    let category_id = created_id(&body);
+
+    let (status, body) = app
+        .post(
+            "/product",
+            &token,
+            json!({
+                "product_name": "Running shoe",
+                "product_description": "Light and fast",
+                "product_color": "red",
+                "product_weight": "0.4",
+                "tax_class_id": null,
+                "category_assignments": [category_id],
+                "custom_fields": null
+            }),
+        )
+        .await;
+    assert_eq!(status, StatusCode::CREATED, "{}", body);
+    let product_id = created_id(&body);
+
+    let (status, body) = app.get(&format!("/product/{}", product_id), &token).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(body["product_name"], "Running shoe");
+
+    // the category is found by its reference as well
+    let (status, body) = app.get("/categories/shoes", &token).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(body["id"], category_id);
+    assert_eq!(body["products"][0]["id"], product_id);
+
+    let (status, body) = app.get("/products", &token).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(body.as_array().map(|x| return x.len()), Some(1));
+}
+
+#[tokio::test]
+async fn rejects_duplicate_references() {
+    let app = TestApp::new();
+    let token = app.admin_token().await;
+    let category = json!({
+        "category_name": "Hats",
+        "category_description": null,
+        "category_reference": "hats",
+        "custom_fields": null
+    });
+
+    let (status, body) = app.post("/categories", &token, category.clone()).await;
+    assert_eq!(status, StatusCode::CREATED, "{}", body);
+
+    let (status, body) = app.post("/categories", &token, category).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST, "{}", body);
+
+    let (status, body) = app.get("/categories", &token).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(body.as_array().map(|x| return x.len()), Some(1));
+}
+
+#[tokio::test]
+async fn rejects_products_with_unknown_categories() {
+    let app = TestApp::new();
+    let token = app.admin_token().await;
+
+    let (status, body) = app
+        .post(
+            "/product",
+            &token,
+            json!({
+                "product_name": "Lost shoe",
+                "product_description": "Nowhere to be found",
+                "product_color": null,
+                "product_weight": null,
+                "tax_class_id": null,
+                "category_assignments": [uuid::Uuid::new_v4()],
+                "custom_fields": null
+            }),
+        )
+        .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST, "{}", body);
+
+    // the product is rolled back with the assignments
+    let (status, body) = app.get("/products", &token).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(body.as_array().map(|x| return x.len()), Some(0));
+}
+
+#[tokio::test]
+async fn finds_tax_rates_regardless_of_the_case() {
+    let app = TestApp::new();
+    let token = app.admin_token().await;
+
+    let (status, body) = app
+        .post(
+            "/tax/class",
+            &token,
+            json!({ "tax_class_name": "Standard", "tax_class_reference": "standard" }),
+        )
+        .await;
+    assert_eq!(status, StatusCode::CREATED, "{}", body);
+    let tax_class_id = created_id(&body);
+
+    let (status, body) = app
+        .post(
+            &format!("/tax/class/{}/rate", tax_class_id),
+            &token,
+            json!({ "country_code": "CA", "region": "QC", "rate": "0.14975" }),
+        )
+        .await;
+    assert_eq!(status, StatusCode::CREATED, "{}", body);
+
+    let rate = app
+        .state
+        .db_service
+        .get_tax_rate(&tax_class_id, "ca", Some("qc"))
+        .await
+        .expect("Could not load the tax rate!")
+        .expect("The tax rate was not found!");
+    assert_eq!(rate.rate.to_string(), "0.14975");
+}
@@ -0,0 +1,267 @@
+// every test file only uses some of the helpers
+#![allow(dead_code)]
+
+use std::{net::SocketAddr, sync::Arc};
+
+use axum::{
+    body::Body,
+    extract::ConnectInfo,
+    http::{header, Method, Request, StatusCode},
+    Router,
+};
+use commercyfy_core::{
+    create_router,
+    models::portal_user::PortalUsersRoles,
+    schemas::{
+        permission::{AssignPortalRoles, CreatePortalRole},
+        portal_user::PortalUserCreate,
+    },
+    services::{
+        db::memory::MemoryDbService, logger::memory::MemoryLogger, mailer::FileMailer,
+        role_validation::RoleValidation, signing_keys::SigningKeys,
+        unstructureddb::memory::MemoryUnstructuredDb,
+    },
+    CommercyfyState,
+};
+use serde_json::Value;
+use tower::ServiceExt;
+
+pub const PASSWORD: &str = "Sup3r-Secret!";
+
+// The router with the in-memory services, the state, the logger and the
+// unstructured db stay reachable so the tests can seed data, read what was
+// logged and make the writes fail.
+pub struct TestApp {
+    pub router: Router,
+    pub state: Arc<CommercyfyState>,
+    pub logger: MemoryLogger,
+    pub unstructureddb: Arc<MemoryUnstructuredDb>,
+}
+
+impl TestApp {
+    pub fn new() -> Self {
+        let logger = MemoryLogger::new();
+        let unstructureddb = Arc::new(MemoryUnstructuredDb::new());
+        let mailer_dir =
+            std::env::temp_dir().join(format!("commercyfy-mails-{}", uuid::Uuid::new_v4()));
+        let state = Arc::new(CommercyfyState {
+            db_service: Arc::new(MemoryDbService::new()),
+            role_service: RoleValidation::default(),
+            unstructureddb: unstructureddb.clone(),
+            logger: Box::new(logger.clone()),
+            mailer: FileMailer::new(Some(mailer_dir.to_string_lossy().to_string())),
+            signing_keys: SigningKeys::new(
+                None,
+                Some("integration-tests-secret".to_string()),
+                None,
+                None,
+                chrono::TimeDelta::days(30),
+            ),
+        });
+
+        return Self {
+            router: create_router(state.clone()),
+            state,
+            logger,
+            unstructureddb,
+        };
+    }
+
+    pub async fn request(
+        &self,
+        method: Method,
+        uri: &str,
+        token: Option<&str>,
+        body: Option<Value>,
+    ) -> (StatusCode, Value) {
+        let authorization = token.map(|token| return format!("Bearer {}", token));
+
+        return self.send(method, uri, authorization, body).await;
+    }
+
+    // authenticates with an api key instead of a signed in user
+    pub async fn request_with_api_key(
+        &self,
+        method: Method,
+        uri: &str,
+        api_key: &str,
+        body: Option<Value>,
+    ) -> (StatusCode, Value) {
+        let authorization = Some(format!("ApiKey {}", api_key));
+
+        return self.send(method, uri, authorization, body).await;
+    }
+
+    async fn send(
+        &self,
+        method: Method,
+        uri: &str,
+        authorization: Option<String>,
+        body: Option<Value>,
+    ) -> (StatusCode, Value) {
+        let mut request = Request::builder().method(method).uri(uri);
+        if let Some(authorization) = authorization {
+            request = request.header(header::AUTHORIZATION, authorization);
+        }
+
+        let mut request = match body {
+            Some(body) => request
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(Body::from(body.to_string())),
+            None => request.body(Body::empty()),
+        }
+        .expect("Could not build the request!");
+
+        // the sign-in routes read the address of the client for the audit log
+        request
+            .extensions_mut()
+            .insert(ConnectInfo(SocketAddr::from(([127, 0, 0, 1], 4000))));
+
+        let response = self
+            .router
+            .clone()
+            .oneshot(request)
+            .await
+            .expect("The router could not handle the request!");
+
+        let status = response.status();
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .expect("Could not read the response body!");
+        let body = if bytes.is_empty() {
+            Value::Null
+        } else {
+            serde_json::from_slice(&bytes)
+                .unwrap_or(Value::String(String::from_utf8_lossy(&bytes).to_string()))
+        };
+
+        return (status, body);
+    }
+
+    pub async fn get(&self, uri: &str, token: &str) -> (StatusCode, Value) {
+        return self.request(Method::GET, uri, Some(token), None).await;
+    }
+
+    pub async fn post(&self, uri: &str, token: &str, body: Value) -> (StatusCode, Value) {
+        return self
+            .request(Method::POST, uri, Some(token), Some(body))
+            .await;
+    }
+
+    pub async fn put(&self, uri: &str, token: &str, body: Value) -> (StatusCode, Value) {
+        return self
+            .request(Method::PUT, uri, Some(token), Some(body))
+            .await;
+    }
+
+    pub async fn delete(&self, uri: &str, token: &str) -> (StatusCode, Value) {
+        return self.request(Method::DELETE, uri, Some(token), None).await;
+    }
+
+    // portal users are seeded directly, the api needs a signed in user to
+    // create them
+    pub async fn create_portal_user(
+        &self,
+        email: &str,
+        roles: Vec<PortalUsersRoles>,
+    ) -> uuid::Uuid {
+        let portal_user = self
+            .state
+            .db_service
+            .create_portal_user(PortalUserCreate {
+                email: email.to_string(),
+                first_name: "Test".to_string(),
+                last_name: "User".to_string(),
+                password: PASSWORD.to_string(),
+                roles,
+                custom_fields: None,
+            })
+            .await
+            .expect("Could not create the portal user!");
+
+        return portal_user.id;
+    }
+
+    pub async fn signin(&self, email: &str, password: &str) -> (StatusCode, Value) {
+        return self
+            .request(
+                Method::POST,
+                "/portal/signin",
+                None,
+                Some(serde_json::json!({ "email": email, "password": password })),
+            )
+            .await;
+    }
+
+    pub async fn token_for(&self, email: &str, roles: Vec<PortalUsersRoles>) -> String {
+        self.create_portal_user(email, roles).await;
+
+        let (status, body) = self.signin(email, PASSWORD).await;
+        assert_eq!(status, StatusCode::OK, "{}", body);
+
+        return body["jwt"]
+            .as_str()
+            .expect("The sign-in did not return a token!")
+            .to_string();
+    }
+
+    // a portal user holding the permissions through a custom role on top of
+    // the built-in roles
+    pub async fn token_with_permissions(
+        &self,
+        email: &str,
+        roles: Vec<PortalUsersRoles>,
+        permissions: &[&str],
+    ) -> String {
+        let portal_user_id = self.create_portal_user(email, roles).await;
+        let role = self
+            .state
+            .db_service
+            .create_portal_role(&CreatePortalRole {
+                name: format!("{}-role", email),
+                permissions: permissions.iter().map(|x| return x.to_string()).collect(),
+            })
+            .await
+            .expect("Could not create the portal role!");
+        self.state
+            .db_service
+            .assign_portal_roles(
+                portal_user_id,
+                &AssignPortalRoles {
+                    role_ids: vec![role.id],
+                },
+            )
+            .await
+            .expect("Could not assign the portal role!");
+
+        let (status, body) = self.signin(email, PASSWORD).await;
+        assert_eq!(status, StatusCode::OK, "{}", body);
+
+        return body["jwt"]
+            .as_str()
+            .expect("The sign-in did not return a token!")
+            .to_string();
+    }
+
+    // stands in for mongodb being unreachable
+    pub fn fail_unstructured_writes(&self, failing: bool) {
+        self.unstructureddb.fail_writes(failing);
+    }
+
+    pub fn fail_unstructured_deletes(&self, failing: bool) {
+        self.unstructureddb.fail_deletes(failing);
+    }
+
+    pub async fn admin_token(&self) -> String {
+        return self
+            .token_for("admin@commercyfy.test", vec![PortalUsersRoles::ADMIN])
+            .await;
+    }
+}
+
+pub fn created_id(body: &Value) -> String {
+    return body["id"]
+        .as_str()
+        .expect("The response did not contain the id of the created entry!")
+        .to_string();
+}
@@ -0,0 +1,830 @@
+mod common;
+
+use axum::http::StatusCode;
+use commercyfy_core::models::base_extensions::FieldExtensionObject;
+use common::{created_id, TestApp};
+use serde_json::{json, Value};
+
+async fn create_product(app: &TestApp, token: &str, name: &str, custom_fields: Value) -> String {
+    let (status, body) = app
+        .post(
+            "/product",
+            token,
+            json!({
+                "product_name": name,
+                "product_description": "A product with custom fields",
+                "product_color": null,
+                "product_weight": null,
+                "tax_class_id": null,
+                "category_assignments": null,
+                "custom_fields": custom_fields
+            }),
+        )
+        .await;
+    assert_eq!(status, StatusCode::CREATED, "{}", body);
+
+    return created_id(&body);
+}
+
+async fn create_extensions(app: &TestApp, token: &str) {
+    let (status, body) = app
+        .post(
+            "/extensions",
+            token,
+            json!({
+                "$object": "product",
+                "$type": "int",
+                "name": "stock_level",
+                "description": null,
+                "mandatory": true
+            }),
+        )
+        .await;
+    assert_eq!(status, StatusCode::CREATED, "{}", body);
+
+    let (status, body) = app
+        .post(
+            "/extensions",
+            token,
+            json!({
+                "$object": "product",
+                "$type": "string",
+                "name": "material",
+                "description": "What the product is made of",
+                "mandatory": false,
+                "max_len": 20,
+                "min_len": null
+            }),
+        )
+        .await;
+    assert_eq!(status, StatusCode::CREATED, "{}", body);
+}
+
+#[tokio::test]
+async fn stores_and_validates_custom_fields() {
+    let app = TestApp::new();
+    let token = app.admin_token().await;
+    create_extensions(&app, &token).await;
+
+    let (status, body) = app.get("/extensions/product", &token).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(body.as_array().map(|x| return x.len()), Some(2));
+
+    let product_id = create_product(
+        &app,
+        &token,
+        "Wool sweater",
+        json!({ "stock_level": 12, "material": "wool" }),
+    )
+    .await;
+
+    let (status, body) = app.get(&format!("/product/{}", product_id), &token).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(body["custom_fields"]["stock_level"], 12);
+    assert_eq!(body["custom_fields"]["material"], "wool");
+
+    // the mandatory field is missing and the string is too long
+    let (status, body) = app
+        .post(
+            "/product",
+            &token,
+            json!({
+                "product_name": "Broken sweater",
+                "product_description": "Not valid",
+                "product_color": null,
+                "product_weight": null,
+                "tax_class_id": null,
+                "category_assignments": null,
+                "custom_fields": { "material": "a material with a very long name" }
+            }),
+        )
+        .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST, "{}", body);
+
+    let (status, body) = app.get("/products", &token).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(body.as_array().map(|x| return x.len()), Some(1));
+}
+
+#[tokio::test]
+async fn updates_and_deletes_custom_field_values() {
+    let app = TestApp::new();
+    let token = app.admin_token().await;
+    create_extensions(&app, &token).await;
+
+    let product_id =
+        create_product(&app, &token, "Cotton shirt", json!({ "stock_level": 3 })).await;
+
+    let (status, body) = app
+        .put(
+            &format!("/product/{}/custom-fields", product_id),
+            &token,
+            json!({ "stock_level": 4, "material": "cotton" }),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(body["stock_level"], 4);
+    assert_eq!(body["material"], "cotton");
+
+    let (status, body) = app
+        .delete(
+            &format!("/product/{}/custom-fields/material", product_id),
+            &token,
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert!(body.get("material").is_none(), "{}", body);
+    assert_eq!(body["stock_level"], 4);
+}
+
+#[tokio::test]
+async fn filters_products_by_custom_fields() {
+    let app = TestApp::new();
+    let token = app.admin_token().await;
+    create_extensions(&app, &token).await;
+
+    let wool = create_product(
+        &app,
+        &token,
+        "Wool socks",
+        json!({ "stock_level": 40, "material": "Merino wool" }),
+    )
+    .await;
+    let linen = create_product(
+        &app,
+        &token,
+        "Linen trousers",
+        json!({ "stock_level": 5, "material": "linen" }),
+    )
+    .await;
+
+    let (status, body) = app.get("/products?cf.stock_level[gte]=10", &token).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(body.as_array().map(|x| return x.len()), Some(1));
+    assert_eq!(body[0]["id"], wool);
+
+    let (status, body) = app.get("/products?cf.material[contains]=LIN", &token).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(body.as_array().map(|x| return x.len()), Some(1));
+    assert_eq!(body[0]["id"], linen);
+
+    let (status, body) = app.get("/products?cf.material[gt]=linen", &token).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST, "{}", body);
+}
+
+#[tokio::test]
+async fn keeps_the_precision_of_decimals() {
+    let app = TestApp::new();
+    let token = app.admin_token().await;
+
+    let (status, body) = app
+        .post(
+            "/extensions",
+            &token,
+            json!({
+                "$object": "product",
+                "$type": "decimal",
+                "name": "weight",
+                "description": null,
+                "mandatory": false,
+                "scale": null
+            }),
+        )
+        .await;
+    assert_eq!(status, StatusCode::CREATED, "{}", body);
+
+    let heavy = create_product(
+        &app,
+        &token,
+        "Anvil",
+        json!({ "weight": "12345678901234567.89" }),
+    )
+    .await;
+    let light = create_product(&app, &token, "Feather", json!({ "weight": 9.5 })).await;
+
+    let (status, body) = app.get(&format!("/product/{}", heavy), &token).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(body["custom_fields"]["weight"], "12345678901234567.89");
+
+    let (status, body) = app
+        .get("/products?cf.weight[gt]=12345678901234567.88", &token)
+        .await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(body.as_array().map(|x| return x.len()), Some(1));
+    assert_eq!(body[0]["id"], heavy);
+
+    let (status, body) = app
+        .get("/products?cf.weight[gt]=12345678901234567.89", &token)
+        .await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(body.as_array().map(|x| return x.len()), Some(0));
+
+    let (status, body) = app.get("/products?cf.weight[lt]=10", &token).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(body.as_array().map(|x| return x.len()), Some(1));
+    assert_eq!(body[0]["id"], light);
+
+    let (status, body) = app.get("/products?cf.weight=9.50", &token).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(body.as_array().map(|x| return x.len()), Some(1));
+}
+
+#[tokio::test]
+async fn keeps_fields_whose_values_could_not_be_deleted() {
+    let app = TestApp::new();
+    let token = app.admin_token().await;
+    create_extensions(&app, &token).await;
+    let field_id = created_id(&get_extension(&app, &token, "material").await);
+    let product_id = create_product(
+        &app,
+        &token,
+        "Wool sweater",
+        json!({ "stock_level": 1, "material": "wool" }),
+    )
+    .await;
+
+    let (status, body) = app
+        .delete(&format!("/extensions/product/{}", field_id), &token)
+        .await;
+    assert_eq!(status, StatusCode::CONFLICT, "{}", body);
+
+    app.fail_unstructured_deletes(true);
+    let (status, body) = app
+        .delete(
+            &format!("/extensions/product/{}?force=true", field_id),
+            &token,
+        )
+        .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST, "{}", body);
+    get_extension(&app, &token, "material").await;
+
+    app.fail_unstructured_deletes(false);
+    let (status, body) = app
+        .delete(
+            &format!("/extensions/product/{}?force=true", field_id),
+            &token,
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+
+    let (status, body) = app.get(&format!("/product/{}", product_id), &token).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert!(body["custom_fields"].get("material").is_none(), "{}", body);
+}
+
+#[tokio::test]
+async fn rolls_back_creates_when_custom_fields_fail() {
+    let app = TestApp::new();
+    let token = app.admin_token().await;
+    create_extensions(&app, &token).await;
+
+    let (status, body) = app
+        .post(
+            "/categories",
+            &token,
+            json!({
+                "category_name": "Knitwear",
+                "category_description": null,
+                "category_reference": "knitwear",
+                "custom_fields": null
+            }),
+        )
+        .await;
+    assert_eq!(status, StatusCode::CREATED, "{}", body);
+    let category_id = created_id(&body);
+
+    let product = json!({
+        "product_name": "Wool cardigan",
+        "product_description": "Warm and soft",
+        "product_color": null,
+        "product_weight": null,
+        "tax_class_id": null,
+        "category_assignments": [category_id],
+        "custom_fields": { "stock_level": 7, "material": "wool" }
+    });
+
+    app.fail_unstructured_writes(true);
+    let (status, body) = app.post("/product", &token, product.clone()).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST, "{}", body);
+
+    let (status, body) = app.get("/products", &token).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(body.as_array().map(|x| return x.len()), Some(0));
+
+    let (status, body) = app.get("/categories/knitwear", &token).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(body["products"].as_array().map(|x| return x.len()), Some(0));
+
+    app.fail_unstructured_writes(false);
+    let (status, body) = app.post("/product", &token, product).await;
+    assert_eq!(status, StatusCode::CREATED, "{}", body);
+    let product_id = created_id(&body);
+
+    let (status, body) = app.get(&format!("/product/{}", product_id), &token).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(body["custom_fields"]["material"], "wool");
+}
+
+#[tokio::test]
+async fn reports_creates_that_could_not_be_undone() {
+    let app = TestApp::new();
+    let token = app.admin_token().await;
+    create_extensions(&app, &token).await;
+
+    app.fail_unstructured_writes(true);
+    app.fail_unstructured_deletes(true);
+    let (status, body) = app
+        .post(
+            "/product",
+            &token,
+            json!({
+                "product_name": "Wool cardigan",
+                "product_description": "Warm and soft",
+                "product_color": null,
+                "product_weight": null,
+                "tax_class_id": null,
+                "category_assignments": null,
+                "custom_fields": { "stock_level": 7, "material": "wool" }
+            }),
+        )
+        .await;
+    assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR, "{}", body);
+
+    let entries = app.logger.entries();
+    assert!(entries
+        .iter()
+        .any(|x| return x.category == "custom-fields" && x.level == "ERROR"));
+}
+
+#[tokio::test]
+async fn removes_the_records_of_an_undone_create() {
+    let app = TestApp::new();
+    let token = app.admin_token().await;
+    let product_id = create_product(&app, &token, "Wool cardigan", json!(null)).await;
+
+    let (status, body) = app
+        .post(
+            &format!("/product/{}/images", product_id),
+            &token,
+            json!({ "src": "/cardigan.png", "srcset": null, "alt": null, "custom_fields": null }),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    let image_id = created_id(&body);
+
+    let removed = app
+        .state
+        .db_service
+        .delete_custom_field_object(
+            FieldExtensionObject::PRODUCT,
+            uuid::Uuid::parse_str(&product_id).expect("Not a uuid!"),
+        )
+        .await
+        .expect("Could not remove the product!");
+    assert_eq!(removed.len(), 1);
+    assert_eq!(removed[0].1.to_string(), image_id);
+
+    let (status, _body) = app.get(&format!("/product/{}", product_id), &token).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn keeps_references_free_after_a_failed_create() {
+    let app = TestApp::new();
+    let token = app.admin_token().await;
+
+    let (status, body) = app
+        .post(
+            "/extensions",
+            &token,
+            json!({
+                "$object": "category",
+                "$type": "boolean",
+                "name": "featured",
+                "description": null,
+                "mandatory": false
+            }),
+        )
+        .await;
+    assert_eq!(status, StatusCode::CREATED, "{}", body);
+
+    let category = json!({
+        "category_name": "Outlet",
+        "category_description": null,
+        "category_reference": "outlet",
+        "custom_fields": { "featured": true }
+    });
+
+    app.fail_unstructured_writes(true);
+    let (status, body) = app.post("/categories", &token, category.clone()).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST, "{}", body);
+
+    // the reference can be used again since the category was removed
+    app.fail_unstructured_writes(false);
+    let (status, body) = app.post("/categories", &token, category).await;
+    assert_eq!(status, StatusCode::CREATED, "{}", body);
+
+    let (status, body) = app.get("/categories/outlet", &token).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(body["custom_fields"]["featured"], true);
+}
+
+async fn get_extension(app: &TestApp, token: &str, name: &str) -> Value {
+    let (status, body) = app.get("/extensions/product", token).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+
+    return body
+        .as_array()
+        .and_then(|x| return x.iter().find(|field| return field["name"] == name))
+        .cloned()
+        .expect("The field was not found!");
+}
+
+// the migrations run in the background
+async fn wait_for_migration(
+    app: &TestApp,
+    token: &str,
+    field_id: &str,
+    migration_id: &str,
+) -> Value {
+    for _ in 0..100 {
+        let (status, body) = app
+            .get(
+                &format!(
+                    "/extensions/product/{}/migrations/{}",
+                    field_id, migration_id
+                ),
+                token,
+            )
+            .await;
+        assert_eq!(status, StatusCode::OK, "{}", body);
+
+        if body["status"] != "RUNNING" {
+            return body;
+        }
+
+        tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+    }
+
+    panic!("The migration did not finish!");
+}
+
+#[tokio::test]
+async fn versions_field_definitions() {
+    let app = TestApp::new();
+    let token = app.admin_token().await;
+    create_extensions(&app, &token).await;
+    let field_id = created_id(&get_extension(&app, &token, "material").await);
+
+    let (status, body) = app
+        .put(
+            &format!("/extensions/product/{}", field_id),
+            &token,
+            json!({ "description": "The main material", "mandatory": null, "max_len": null, "min_len": null }),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(body["version"], 2);
+
+    let (status, body) = app
+        .get(
+            &format!("/extensions/product/{}/versions", field_id),
+            &token,
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(body.as_array().map(|x| return x.len()), Some(2));
+    assert_eq!(body[0]["description"], "What the product is made of");
+    assert_eq!(body[1]["description"], "The main material");
+}
+
+#[tokio::test]
+async fn reports_values_that_do_not_fit_a_change() {
+    let app = TestApp::new();
+    let token = app.admin_token().await;
+    create_extensions(&app, &token).await;
+    let field_id = created_id(&get_extension(&app, &token, "material").await);
+
+    create_product(
+        &app,
+        &token,
+        "Wool sweater",
+        json!({ "stock_level": 1, "material": "wool" }),
+    )
+    .await;
+    let blend = create_product(
+        &app,
+        &token,
+        "Blended sweater",
+        json!({ "stock_level": 1, "material": "merino wool blend" }),
+    )
+    .await;
+
+    let (status, body) = app
+        .post(
+            &format!("/extensions/product/{}/migrations/dry-run", field_id),
+            &token,
+            json!({ "max_len": 5 }),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(body["values"], 2);
+    assert_eq!(body["from_version"], 1);
+    assert_eq!(body["violations"][0]["extr_ref"], blend);
+    assert_eq!(
+        body["transformed"].as_array().map(|x| return x.len()),
+        Some(0)
+    );
+
+    let (status, body) = app
+        .post(
+            &format!("/extensions/product/{}/migrations/dry-run", field_id),
+            &token,
+            json!({ "max_len": 5, "on_violation": "truncate" }),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(
+        body["violations"].as_array().map(|x| return x.len()),
+        Some(0)
+    );
+    assert_eq!(body["transformed"][0]["from"], "merino wool blend");
+    assert_eq!(body["transformed"][0]["to"], "merin");
+
+    // nothing is migrated while values do not fit
+    let (status, body) = app
+        .post(
+            &format!("/extensions/product/{}/migrations", field_id),
+            &token,
+            json!({ "max_len": 5 }),
+        )
+        .await;
+    assert_eq!(status, StatusCode::CONFLICT, "{}", body);
+
+    let (status, body) = app
+        .post(
+            &format!("/extensions/product/{}/migrations", field_id),
+            &token,
+            json!({ "max_len": 5, "version": 3 }),
+        )
+        .await;
+    assert_eq!(status, StatusCode::CONFLICT, "{}", body);
+
+    let (status, body) = app.get(&format!("/product/{}", blend), &token).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(body["custom_fields"]["material"], "merino wool blend");
+    assert_eq!(get_extension(&app, &token, "material").await["version"], 1);
+}
+
+#[tokio::test]
+async fn migrates_the_values_of_renamed_fields() {
+    let app = TestApp::new();
+    let token = app.admin_token().await;
+    create_extensions(&app, &token).await;
+    let field_id = created_id(&get_extension(&app, &token, "material").await);
+
+    let wool = create_product(
+        &app,
+        &token,
+        "Wool sweater",
+        json!({ "stock_level": 1, "material": "wool" }),
+    )
+    .await;
+    let blend = create_product(
+        &app,
+        &token,
+        "Blended sweater",
+        json!({ "stock_level": 1, "material": "merino wool blend" }),
+    )
+    .await;
+
+    let (status, body) = app
+        .post(
+            &format!("/extensions/product/{}/migrations", field_id),
+            &token,
+            json!({ "name": "fabric", "max_len": 5, "on_violation": "truncate", "version": 1 }),
+        )
+        .await;
+    assert_eq!(status, StatusCode::ACCEPTED, "{}", body);
+    let migration_id = created_id(&body);
+
+    let migration = wait_for_migration(&app, &token, &field_id, &migration_id).await;
+    assert_eq!(migration["status"], "COMPLETED", "{}", migration);
+    assert_eq!(migration["migrated"], 2);
+    assert_eq!(migration["to_version"], 2);
+
+    let (status, body) = app.get(&format!("/product/{}", wool), &token).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(body["custom_fields"]["fabric"], "wool");
+    assert!(body["custom_fields"].get("material").is_none(), "{}", body);
+
+    let (status, body) = app.get(&format!("/product/{}", blend), &token).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(body["custom_fields"]["fabric"], "merin");
+
+    let field = get_extension(&app, &token, "fabric").await;
+    assert_eq!(field["version"], 2);
+    assert_eq!(field["max_len"], 5);
+
+    let (status, body) = app
+        .get(
+            &format!("/extensions/product/{}/versions", field_id),
+            &token,
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(body[0]["name"], "material");
+    assert_eq!(body[1]["name"], "fabric");
+}
+
+#[tokio::test]
+async fn drops_the_values_that_do_not_fit() {
+    let app = TestApp::new();
+    let token = app.admin_token().await;
+    create_extensions(&app, &token).await;
+    let field_id = created_id(&get_extension(&app, &token, "material").await);
+
+    let wool = create_product(
+        &app,
+        &token,
+        "Wool sweater",
+        json!({ "stock_level": 1, "material": "wool" }),
+    )
+    .await;
+    let blend = create_product(
+        &app,
+        &token,
+        "Blended sweater",
+        json!({ "stock_level": 1, "material": "merino wool blend" }),
+    )
+    .await;
+
+    let (status, body) = app
+        .post(
+            &format!("/extensions/product/{}/migrations", field_id),
+            &token,
+            json!({ "max_len": 5, "value_map": { "wool": "Wool" }, "on_violation": "drop" }),
+        )
+        .await;
+    assert_eq!(status, StatusCode::ACCEPTED, "{}", body);
+    let migration_id = created_id(&body);
+
+    let migration = wait_for_migration(&app, &token, &field_id, &migration_id).await;
+    assert_eq!(migration["status"], "COMPLETED", "{}", migration);
+    assert_eq!(migration["migrated"], 1);
+    assert_eq!(migration["dropped"], 1);
+
+    let (status, body) = app.get(&format!("/product/{}", wool), &token).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(body["custom_fields"]["material"], "Wool");
+
+    let (status, body) = app.get(&format!("/product/{}", blend), &token).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert!(body["custom_fields"].get("material").is_none(), "{}", body);
+
+    let (status, body) = app
+        .get(
+            &format!("/extensions/product/{}/migrations", field_id),
+            &token,
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(body.as_array().map(|x| return x.len()), Some(1));
+}
+
+#[tokio::test]
+async fn runs_one_migration_of_a_field_at_a_time() {
+    let app = TestApp::new();
+    let token = app.admin_token().await;
+    create_extensions(&app, &token).await;
+    let field = get_extension(&app, &token, "material").await;
+    let field_id = uuid::Uuid::parse_str(&created_id(&field)).expect("Not a uuid!");
+
+    let running = app
+        .state
+        .db_service
+        .create_custom_field_migration(field_id, 1)
+        .await
+        .expect("Could not create the migration!");
+    assert!(running.is_some());
+
+    let second = app
+        .state
+        .db_service
+        .create_custom_field_migration(field_id, 1)
+        .await
+        .expect("Could not create the migration!");
+    assert!(second.is_none());
+
+    let (status, body) = app
+        .post(
+            &format!("/extensions/product/{}/migrations", field_id),
+            &token,
+            json!({ "max_len": 10 }),
+        )
+        .await;
+    assert_eq!(status, StatusCode::CONFLICT, "{}", body);
+
+    // what happens at boot, the job of a stopped service is not left running
+    let failed = app
+        .state
+        .db_service
+        .fail_running_custom_field_migrations()
+        .await
+        .expect("Could not fail the running migrations!");
+    assert_eq!(failed, 1);
+
+    let (status, body) = app
+        .post(
+            &format!("/extensions/product/{}/migrations", field_id),
+            &token,
+            json!({ "max_len": 10 }),
+        )
+        .await;
+    assert_eq!(status, StatusCode::ACCEPTED, "{}", body);
+}
+
+#[tokio::test]
+async fn reports_the_objects_missing_a_field_that_becomes_mandatory() {
+    let app = TestApp::new();
+    let token = app.admin_token().await;
+    create_extensions(&app, &token).await;
+    let field_id = created_id(&get_extension(&app, &token, "material").await);
+
+    create_product(
+        &app,
+        &token,
+        "Wool sweater",
+        json!({ "stock_level": 1, "material": "wool" }),
+    )
+    .await;
+    let plain = create_product(&app, &token, "Plain sweater", json!({ "stock_level": 1 })).await;
+
+    let (status, body) = app
+        .post(
+            &format!("/extensions/product/{}/migrations/dry-run", field_id),
+            &token,
+            json!({ "mandatory": true, "on_violation": "drop" }),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(
+        body["violations"].as_array().map(|x| return x.len()),
+        Some(1)
+    );
+    assert_eq!(body["violations"][0]["extr_ref"], plain);
+    assert_eq!(body["violations"][0]["value"], Value::Null);
+
+    let (status, body) = app
+        .post(
+            &format!("/extensions/product/{}/migrations", field_id),
+            &token,
+            json!({ "mandatory": true }),
+        )
+        .await;
+    assert_eq!(status, StatusCode::CONFLICT, "{}", body);
+    assert_eq!(
+        get_extension(&app, &token, "material").await["mandatory"],
+        false
+    );
+}
+
+#[tokio::test]
+async fn refuses_updates_that_existing_values_do_not_fit() {
+    let app = TestApp::new();
+    let token = app.admin_token().await;
+    create_extensions(&app, &token).await;
+    let field_id = created_id(&get_extension(&app, &token, "material").await);
+
+    create_product(
+        &app,
+        &token,
+        "Blended sweater",
+        json!({ "stock_level": 1, "material": "merino wool blend" }),
+    )
+    .await;
+    create_product(&app, &token, "Plain sweater", json!({ "stock_level": 1 })).await;
+
+    let (status, body) = app
+        .put(
+            &format!("/extensions/product/{}", field_id),
+            &token,
+            json!({ "description": null, "mandatory": true, "max_len": null, "min_len": null }),
+        )
+        .await;
+    assert_eq!(status, StatusCode::CONFLICT, "{}", body);
+
+    let (status, body) = app
+        .put(
+            &format!("/extensions/product/{}", field_id),
+            &token,
+            json!({ "description": null, "mandatory": null, "max_len": 5, "min_len": null }),
+        )
+        .await;
+    assert_eq!(status, StatusCode::CONFLICT, "{}", body);
+
+    let field = get_extension(&app, &token, "material").await;
+    assert_eq!(field["version"], 1);
+    assert_eq!(field["mandatory"], false);
+    assert_eq!(field["max_len"], 20);
+}
@@ -0,0 +1,128 @@
+mod common;
+
+use axum::http::StatusCode;
+use commercyfy_core::models::portal_user::PortalUsersRoles;
+use common::{created_id, TestApp};
+use serde_json::json;
+
+async fn create_store_type(app: &TestApp, token: &str) {
+    let (status, body) = app
+        .post(
+            "/custom-objects",
+            token,
+            json!({ "name": "store", "description": "The physical stores" }),
+        )
+        .await;
+    assert_eq!(status, StatusCode::CREATED, "{}", body);
+
+    let (status, body) = app
+        .post(
+            "/custom-objects/store/fields",
+            token,
+            json!({
+                "$type": "string",
+                "name": "city",
+                "description": null,
+                "mandatory": true,
+                "max_len": null,
+                "min_len": null
+            }),
+        )
+        .await;
+    assert_eq!(status, StatusCode::CREATED, "{}", body);
+}
+
+#[tokio::test]
+async fn manages_the_records_of_a_type() {
+    let app = TestApp::new();
+    let token = app.admin_token().await;
+    create_store_type(&app, &token).await;
+
+    let (status, body) = app
+        .post(
+            "/custom-objects/store/records",
+            &token,
+            json!({ "custom_fields": { "city": "Sofia" } }),
+        )
+        .await;
+    assert_eq!(status, StatusCode::CREATED, "{}", body);
+    let record_id = created_id(&body);
+
+    let (status, body) = app
+        .put(
+            &format!("/custom-objects/store/records/{}", record_id),
+            &token,
+            json!({ "city": "Plovdiv" }),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+
+    let (status, body) = app
+        .get(
+            &format!("/custom-objects/store/records/{}", record_id),
+            &token,
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(body["custom_fields"]["city"], "Plovdiv");
+
+    let (status, body) = app
+        .delete(
+            &format!("/custom-objects/store/records/{}", record_id),
+            &token,
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+
+    let (status, body) = app.get("/custom-objects/store/records", &token).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(body.as_array().map(|x| return x.len()), Some(0));
+}
+
+#[tokio::test]
+async fn validates_the_records() {
+    let app = TestApp::new();
+    let token = app.admin_token().await;
+    create_store_type(&app, &token).await;
+
+    let (status, body) = app
+        .post(
+            "/custom-objects/store/records",
+            &token,
+            json!({ "custom_fields": {} }),
+        )
+        .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST, "{}", body);
+
+    let (status, body) = app
+        .post(
+            "/custom-objects/warehouse/records",
+            &token,
+            json!({ "custom_fields": { "city": "Varna" } }),
+        )
+        .await;
+    assert_eq!(status, StatusCode::NOT_FOUND, "{}", body);
+}
+
+#[tokio::test]
+async fn checks_the_permissions_of_the_records() {
+    let app = TestApp::new();
+    let admin_token = app.admin_token().await;
+    create_store_type(&app, &admin_token).await;
+
+    let token = app
+        .token_for("reader@commercyfy.test", vec![PortalUsersRoles::READER])
+        .await;
+
+    let (status, body) = app.get("/custom-objects/store/records", &token).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+
+    let (status, body) = app
+        .post(
+            "/custom-objects/store/records",
+            &token,
+            json!({ "custom_fields": { "city": "Burgas" } }),
+        )
+        .await;
+    assert_eq!(status, StatusCode::FORBIDDEN, "{}", body);
+}
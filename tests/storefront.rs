@@ -0,0 +1,134 @@
+mod common;
+
+use axum::http::{Method, StatusCode};
+use common::{created_id, TestApp, PASSWORD};
+use serde_json::json;
+
+async fn customer_token(app: &TestApp, email: &str) -> String {
+    let (status, body) = app
+        .request(
+            Method::POST,
+            "/storefront/customer/register",
+            None,
+            Some(json!({
+                "email": email,
+                "first_name": "Jane",
+                "last_name": "Doe",
+                "phone": null,
+                "password": PASSWORD
+            })),
+        )
+        .await;
+    assert_eq!(status, StatusCode::CREATED, "{}", body);
+
+    let (status, body) = app
+        .request(
+            Method::POST,
+            "/storefront/customer/signin",
+            None,
+            Some(json!({ "email": email, "password": PASSWORD })),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+
+    return body["jwt"]
+        .as_str()
+        .expect("The sign-in did not return a token!")
+        .to_string();
+}
+
+#[tokio::test]
+async fn registers_and_signs_in_customers() {
+    let app = TestApp::new();
+    let token = customer_token(&app, "jane@commercyfy.test").await;
+
+    let (status, body) = app.get("/storefront/customer/profile", &token).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(body["email"], "jane@commercyfy.test");
+    assert!(body.get("password").is_none(), "{}", body);
+
+    // the customer tokens do not open the portal
+    let (status, _body) = app.get("/categories", &token).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    let (status, body) = app
+        .request(
+            Method::POST,
+            "/storefront/customer/signin",
+            None,
+            Some(json!({ "email": "jane@commercyfy.test", "password": "wrong-password" })),
+        )
+        .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST, "{}", body);
+}
+
+#[tokio::test]
+async fn shares_product_lists() {
+    let app = TestApp::new();
+    let admin_token = app.admin_token().await;
+    let (status, body) = app
+        .post(
+            "/product",
+            &admin_token,
+            json!({
+                "product_name": "Travel mug",
+                "product_description": "Keeps the coffee warm",
+                "product_color": null,
+                "product_weight": null,
+                "tax_class_id": null,
+                "category_assignments": null,
+                "custom_fields": null
+            }),
+        )
+        .await;
+    assert_eq!(status, StatusCode::CREATED, "{}", body);
+    let product_id = created_id(&body);
+
+    let token = customer_token(&app, "john@commercyfy.test").await;
+    let (status, body) = app
+        .post(
+            "/storefront/lists",
+            &token,
+            json!({ "product_list_name": "Birthday", "product_list_type": null, "is_public": true }),
+        )
+        .await;
+    assert_eq!(status, StatusCode::CREATED, "{}", body);
+    let list_id = created_id(&body);
+
+    let (status, body) = app
+        .put(
+            &format!("/storefront/lists/{}/items", list_id),
+            &token,
+            json!({ "product_id": product_id, "quantity": 2, "priority": null }),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+
+    let (status, body) = app
+        .get(&format!("/storefront/lists/{}", list_id), &token)
+        .await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(body["items"][0]["quantity"], 2);
+    let share_token = body["share_token"]
+        .as_str()
+        .expect("The list has no share token!")
+        .to_string();
+
+    let (status, body) = app
+        .request(
+            Method::GET,
+            &format!("/storefront/shared/lists/{}", share_token),
+            None,
+            None,
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(body["id"], list_id);
+
+    // the lists of one customer are not visible to the others
+    let other_token = customer_token(&app, "other@commercyfy.test").await;
+    let (status, body) = app
+        .get(&format!("/storefront/lists/{}", list_id), &other_token)
+        .await;
+    assert_eq!(status, StatusCode::NOT_FOUND, "{}", body);
+}